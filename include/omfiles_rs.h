@@ -0,0 +1,108 @@
+#ifndef OMFILES_RS_H
+#define OMFILES_RS_H
+
+/* Generated with cbindgen from src/capi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define OM_RS_OK 0
+
+#define OM_RS_ERROR -1
+
+/**
+ * Opaque handle of an opened variable
+ */
+typedef struct OmRsReader OmRsReader;
+
+/**
+ * Open the file at `path` with mmap. Returns NULL on error.
+ *
+ * # Safety
+ * `path` must be a NUL terminated string.
+ */
+struct OmRsReader *om_rs_open(const char *path);
+
+/**
+ * Open the file at `path` with positional reads and keep up to
+ * `max_blocks` blocks of `block_size` bytes in memory. Returns NULL on error.
+ *
+ * # Safety
+ * `path` must be a NUL terminated string.
+ */
+struct OmRsReader *om_rs_open_cached(const char *path, uint64_t block_size, uint64_t max_blocks);
+
+/**
+ * Cache up to `max_bytes` of decoded index (LUT) entries, see `OmFileReader::enable_lut_cache`.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ */
+int32_t om_rs_enable_lut_cache(struct OmRsReader *reader, uint64_t max_bytes);
+
+/**
+ * Number of dimensions of the variable, 0 if `reader` is NULL.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ */
+uint64_t om_rs_dimension_count(const struct OmRsReader *reader);
+
+/**
+ * Copy the dimensions of the variable into `dimensions`, which has room for
+ * `capacity` values.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ * `dimensions` must point to at least `capacity` values.
+ */
+int32_t om_rs_get_dimensions(const struct OmRsReader *reader,
+                             uint64_t *dimensions,
+                             uint64_t capacity);
+
+/**
+ * Copy the chunk dimensions of the variable into `chunks`, which has room
+ * for `capacity` values.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ * `chunks` must point to at least `capacity` values.
+ */
+int32_t om_rs_get_chunk_dimensions(const struct OmRsReader *reader,
+                                   uint64_t *chunks,
+                                   uint64_t capacity);
+
+/**
+ * Read `count[i]` elements starting at `offset[i]` in all `dimension_count`
+ * dimensions of a float variable into `out` in row-major order. `out` must
+ * have room for `out_len` values, which has to be the product of `count`.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ * `offset` and `count` must point to `dimension_count` values and `out` to
+ * `out_len` values.
+ */
+int32_t om_rs_read_f32(const struct OmRsReader *reader,
+                       const uint64_t *offset,
+                       const uint64_t *count,
+                       uint64_t dimension_count,
+                       float *out,
+                       uint64_t out_len);
+
+/**
+ * Close a reader. NULL is ignored.
+ *
+ * # Safety
+ * `reader` must be a handle returned by `om_rs_open` that was not closed.
+ */
+void om_rs_close(struct OmRsReader *reader);
+
+/**
+ * Message of the last error on the calling thread. The string is valid
+ * until the next call into this library on the same thread.
+ */
+const char *om_rs_last_error(void);
+
+#endif  /* OMFILES_RS_H */
@@ -0,0 +1,173 @@
+//! Read om files stored inside zip or tar archives without extracting them.
+//! The archive is memory mapped and the entry is read in place, so only
+//! uncompressed entries are supported: zip entries with the `Stored` method
+//! and regular files in uncompressed tar archives.
+
+use crate::backend::backends::{IoSizes, OmFileReaderBackend};
+use crate::backend::mmapfile::{MmapFile, Mode};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A reader backend for `count` bytes of another backend starting at `offset`,
+/// e.g. one entry of an archive.
+pub struct RangeBackend<Backend: OmFileReaderBackend> {
+    backend: Backend,
+    offset: u64,
+    count: u64,
+}
+
+impl<Backend: OmFileReaderBackend> RangeBackend<Backend> {
+    pub fn new(backend: Backend, offset: u64, count: u64) -> Result<Self, OmFilesRsError> {
+        if offset
+            .checked_add(count)
+            .is_none_or(|end| end > backend.count() as u64)
+        {
+            return Err(OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: format!(
+                    "Range of {} bytes at offset {} exceeds the length {}",
+                    count,
+                    offset,
+                    backend.count()
+                ),
+            });
+        }
+        Ok(Self {
+            backend,
+            offset,
+            count,
+        })
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Start of the range in the wrapped backend
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn check_range(&self, offset: u64, count: u64) -> Result<(), OmFilesRsError> {
+        if offset.checked_add(count).is_none_or(|end| end > self.count) {
+            return Err(OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: format!(
+                    "Read of {} bytes at offset {} exceeds the length {}",
+                    count, offset, self.count
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReaderBackend for RangeBackend<Backend> {
+    fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        self.backend.needs_prefetch()
+    }
+
+    fn prefetch_data(&self, offset: usize, count: usize) {
+        self.backend
+            .prefetch_data(self.offset as usize + offset, count);
+    }
+
+    fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        self.backend.pre_read(self.offset as usize + offset, count)
+    }
+
+    fn preferred_io_sizes(&self) -> IoSizes {
+        self.backend.preferred_io_sizes()
+    }
+
+    fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
+        self.check_range(offset, count)?;
+        self.backend.get_bytes(self.offset + offset, count)
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        self.check_range(offset, count)?;
+        self.backend.get_bytes_owned(self.offset + offset, count)
+    }
+}
+
+fn open_archive(path: &Path) -> Result<File, OmFilesRsError> {
+    File::open(path).map_err(|e| OmFilesRsError::CannotOpenFile {
+        filename: path.display().to_string(),
+        errno: e.raw_os_error().unwrap_or(0),
+        error: e.to_string(),
+    })
+}
+
+fn archive_error(error: impl std::fmt::Display) -> OmFilesRsError {
+    OmFilesRsError::FileReaderError {
+        errno: 0,
+        error: error.to_string(),
+    }
+}
+
+/// Memory map `file` and read `count` bytes at `offset` as om file
+fn open_entry(
+    file: File,
+    offset: u64,
+    count: u64,
+) -> Result<OmFileReader<RangeBackend<MmapFile>>, OmFilesRsError> {
+    let mmap =
+        MmapFile::new(file, Mode::ReadOnly).map_err(|e| OmFilesRsError::FileReaderError {
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+    OmFileReader::new(Arc::new(RangeBackend::new(mmap, offset, count)?))
+}
+
+impl OmFileReader<RangeBackend<MmapFile>> {
+    /// Open the om file `entry` of the zip archive at `zip_path`. The entry
+    /// must be stored without compression, e.g. with `zip -0`.
+    pub fn from_zip_entry(zip_path: impl AsRef<Path>, entry: &str) -> Result<Self, OmFilesRsError> {
+        let file = open_archive(zip_path.as_ref())?;
+        let (offset, count) = {
+            let mut archive = zip::ZipArchive::new(&file).map_err(archive_error)?;
+            let zip_file = archive.by_name(entry).map_err(|e| match e {
+                zip::result::ZipError::FileNotFound => {
+                    OmFilesRsError::ArchiveEntryNotFound(entry.to_string())
+                }
+                e => archive_error(e),
+            })?;
+            if zip_file.compression() != zip::CompressionMethod::Stored || zip_file.encrypted() {
+                return Err(OmFilesRsError::CompressedArchiveEntry(entry.to_string()));
+            }
+            (zip_file.data_start(), zip_file.size())
+        };
+        open_entry(file, offset, count)
+    }
+
+    /// Open the om file `entry` of the uncompressed tar archive at `tar_path`.
+    pub fn from_tar_entry(tar_path: impl AsRef<Path>, entry: &str) -> Result<Self, OmFilesRsError> {
+        let file = open_archive(tar_path.as_ref())?;
+        let (offset, count) = {
+            let mut archive = tar::Archive::new(&file);
+            let mut found = None;
+            for tar_entry in archive.entries_with_seek().map_err(archive_error)? {
+                let tar_entry = tar_entry.map_err(archive_error)?;
+                if tar_entry.path().map_err(archive_error)? != Path::new(entry) {
+                    continue;
+                }
+                if !tar_entry.header().entry_type().is_file() {
+                    return Err(OmFilesRsError::CompressedArchiveEntry(entry.to_string()));
+                }
+                found = Some((tar_entry.raw_file_position(), tar_entry.size()));
+                break;
+            }
+            found.ok_or_else(|| OmFilesRsError::ArchiveEntryNotFound(entry.to_string()))?
+        };
+        open_entry(file, offset, count)
+    }
+}
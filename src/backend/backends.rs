@@ -1,15 +1,23 @@
-use crate::backend::mmapfile::{MAdvice, MmapFile, MmapType};
+use crate::backend::mmapfile::{MmapFile, MmapType};
 use crate::core::c_defaults::{c_error_string, new_data_read, new_index_read};
 use crate::core::data_types::OmFileArrayDataType;
 use crate::errors::OmFilesRsError;
+use crate::io::batch_reader::chunk_decode_error;
+use crate::io::read_stats::ReadStats;
+use crate::utils::block_on;
 use ndarray::ArrayD;
 use om_file_format_sys::{
     om_decoder_decode_chunks, om_decoder_next_data_read, om_decoder_next_index_read, OmDecoder_t,
     OmError_t_ERROR_OK,
 };
+use std::any::Any;
+use std::borrow::Cow;
 use std::fs::File;
+use std::future::Future;
 use std::io::{Seek, SeekFrom, Write};
 use std::os::raw::c_void;
+use std::pin::Pin;
+use std::sync::Arc;
 
 pub trait OmFileWriterBackend {
     fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError>;
@@ -17,9 +25,30 @@ pub trait OmFileWriterBackend {
     fn synchronize(&self) -> Result<(), OmFilesRsError>;
 }
 
+/// IO parameters for reading compressed data. Requests that are at most
+/// `io_size_merge` bytes apart are merged as long as the merged request does
+/// not exceed `io_size_max` bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IoSizes {
+    pub io_size_max: u64,
+    pub io_size_merge: u64,
+}
+
+impl Default for IoSizes {
+    fn default() -> Self {
+        Self {
+            io_size_max: 65536,
+            io_size_merge: 512,
+        }
+    }
+}
+
 /// A trait for reading byte data from different storage backends.
 /// Provides methods for reading bytes either by reference or as owned data,
 /// as well as functions for prefetching and pre-reading data.
+///
+/// The trait is dyn compatible. `Box<dyn OmFileReaderBackend + Send + Sync>`
+/// implements it as well, so the backend of a reader can be chosen at runtime.
 pub trait OmFileReaderBackend {
     /// Length in bytes
     fn count(&self) -> usize;
@@ -27,6 +56,23 @@ pub trait OmFileReaderBackend {
     fn prefetch_data(&self, offset: usize, count: usize);
     fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError>;
 
+    /// IO parameters used by the reader if the caller does not specify them.
+    /// Backends with expensive requests should prefer fewer, larger reads.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        IoSizes::default()
+    }
+
+    /// Owner of the memory of `get_bytes` if slices returned by it stay valid
+    /// and unchanged for as long as the owner is alive, e.g. for memory maps
+    /// and files in memory. `OmFileReader` then keeps variable metadata as a
+    /// view into that memory instead of copying it. The owner may be dropped
+    /// on any thread, hence `Send + Sync`. Readers rely on this for memory
+    /// safety, only return an owner if the memory is never freed, moved or
+    /// modified while it is alive.
+    fn stable_bytes_owner(self: Arc<Self>) -> Option<Arc<dyn Any + Send + Sync>> {
+        None
+    }
+
     /// Returns a reference to a slice of bytes from the backend, starting at `offset` and reading `count` bytes.
     /// At least one of `get_bytes` or `get_bytes_owned` must be implemented.
     fn get_bytes(&self, _offset: u64, _count: u64) -> Result<&[u8], OmFilesRsError> {
@@ -50,6 +96,7 @@ pub trait OmFileReaderBackend {
     ) -> Result<&'a [u8], OmFilesRsError>
     where
         F: FnOnce() -> Result<&'a [u8], OmFilesRsError>,
+        Self: Sized,
     {
         match e {
             OmFilesRsError::NotImplementedError(_) => fallback_fn(),
@@ -57,17 +104,63 @@ pub trait OmFileReaderBackend {
         }
     }
 
+    /// Returns bytes from the backend, using `get_bytes_owned` if it is implemented
+    /// and falling back to a borrowed slice from `get_bytes` otherwise.
+    fn get_bytes_with_fallback(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> Result<Cow<'_, [u8]>, OmFilesRsError> {
+        match self.get_bytes_owned(offset, count) {
+            Ok(data) => Ok(Cow::Owned(data)),
+            Err(OmFilesRsError::NotImplementedError(_)) => {
+                self.get_bytes(offset, count).map(Cow::Borrowed)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     fn decode<OmType: OmFileArrayDataType>(
         &self,
         decoder: &OmDecoder_t,
         into: &mut ArrayD<OmType>,
         chunk_buffer: &mut [u8],
-    ) -> Result<(), OmFilesRsError> {
-        #[allow(unused_mut)]
-        let mut into = into
+    ) -> Result<(), OmFilesRsError>
+    where
+        Self: Sized,
+    {
+        self.decode_with_stats(decoder, into, chunk_buffer, &mut ReadStats::default())
+    }
+
+    /// Like `decode`, but accumulates request and decode counters in `stats`.
+    fn decode_with_stats<OmType: OmFileArrayDataType>(
+        &self,
+        decoder: &OmDecoder_t,
+        into: &mut ArrayD<OmType>,
+        chunk_buffer: &mut [u8],
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError>
+    where
+        Self: Sized,
+    {
+        let into = into
             .as_slice_mut()
             .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+        self.decode_flat_with_stats(decoder, into, chunk_buffer, stats)
+    }
 
+    /// Like `decode_with_stats`, but decodes into a flat buffer laid out
+    /// like the cube dimensions of the decoder.
+    fn decode_flat_with_stats<OmType: OmFileArrayDataType>(
+        &self,
+        decoder: &OmDecoder_t,
+        into: &mut [OmType],
+        chunk_buffer: &mut [u8],
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError>
+    where
+        Self: Sized,
+    {
         let mut index_read = new_index_read(decoder);
         unsafe {
             // Loop over index blocks and read index data
@@ -80,6 +173,7 @@ pub trait OmFileReaderBackend {
                         self.get_bytes(index_read.offset, index_read.count)
                     })?,
                 };
+                stats.record_index_read(index_read.count);
 
                 let mut data_read = new_data_read(&index_read);
 
@@ -101,23 +195,26 @@ pub trait OmFileReaderBackend {
                             self.get_bytes(data_read.offset, data_read.count)
                         })?,
                     };
-
-                    if !om_decoder_decode_chunks(
-                        decoder,
-                        data_read.chunkIndex,
-                        data_data.as_ptr() as *const c_void,
-                        data_read.count,
-                        into.as_mut_ptr() as *mut c_void,
-                        chunk_buffer.as_mut_ptr() as *mut c_void,
-                        &mut error,
-                    ) {
-                        let error_string = c_error_string(error);
-                        return Err(OmFilesRsError::DecoderError(error_string));
+                    stats.record_data_read(&data_read);
+
+                    if !stats.time_decode(&data_read, || {
+                        om_decoder_decode_chunks(
+                            decoder,
+                            data_read.chunkIndex,
+                            data_data.as_ptr() as *const c_void,
+                            data_read.count,
+                            into.as_mut_ptr() as *mut c_void,
+                            chunk_buffer.as_mut_ptr() as *mut c_void,
+                            &mut error,
+                        )
+                    }) {
+                        return Err(chunk_decode_error(error, &data_read));
                     }
                 }
                 if error != OmError_t_ERROR_OK {
                     let error_string = c_error_string(error);
-                    return Err(OmFilesRsError::DecoderError(error_string));
+                    return Err(OmFilesRsError::DecoderError(error_string)
+                        .context(format!("index block at offset {}", index_read.offset)));
                 }
             }
         }
@@ -125,7 +222,183 @@ pub trait OmFileReaderBackend {
     }
 }
 
-fn map_io_error(e: std::io::Error) -> OmFilesRsError {
+impl<Backend: OmFileReaderBackend + ?Sized> OmFileReaderBackend for Box<Backend> {
+    fn count(&self) -> usize {
+        (**self).count()
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        (**self).needs_prefetch()
+    }
+
+    fn prefetch_data(&self, offset: usize, count: usize) {
+        (**self).prefetch_data(offset, count)
+    }
+
+    fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        (**self).pre_read(offset, count)
+    }
+
+    fn preferred_io_sizes(&self) -> IoSizes {
+        (**self).preferred_io_sizes()
+    }
+
+    fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
+        (**self).get_bytes(offset, count)
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        (**self).get_bytes_owned(offset, count)
+    }
+}
+
+impl<Backend: OmFileWriterBackend + ?Sized> OmFileWriterBackend for Box<Backend> {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError> {
+        (**self).write(data)
+    }
+
+    fn write_at(&mut self, data: &[u8], offset: usize) -> Result<(), OmFilesRsError> {
+        (**self).write_at(data, offset)
+    }
+
+    fn synchronize(&self) -> Result<(), OmFilesRsError> {
+        (**self).synchronize()
+    }
+}
+
+/// Asynchronous counterpart of `OmFileReaderBackend` for backends where
+/// requests take a long time to complete, like object storage or io_uring.
+pub trait OmFileReaderBackendAsync {
+    /// Length in bytes
+    fn count_async(&self) -> usize;
+
+    /// IO parameters used by the reader if the caller does not specify them.
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        IoSizes::default()
+    }
+
+    /// Returns an owned Vec<u8> containing bytes from the backend, starting at `offset` and reading `count` bytes.
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send;
+}
+
+/// Dyn compatible version of `OmFileReaderBackendAsync` with a boxed future.
+/// It is implemented for every asynchronous backend, and `Box<dyn
+/// DynOmFileReaderBackendAsync + Send + Sync>` implements
+/// `OmFileReaderBackendAsync`, so the backend can be chosen at runtime.
+pub trait DynOmFileReaderBackendAsync {
+    fn count_dyn(&self) -> usize;
+
+    fn preferred_io_sizes_dyn(&self) -> IoSizes;
+
+    fn get_bytes_dyn(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send + '_>>;
+}
+
+impl<Backend: OmFileReaderBackendAsync> DynOmFileReaderBackendAsync for Backend {
+    fn count_dyn(&self) -> usize {
+        self.count_async()
+    }
+
+    fn preferred_io_sizes_dyn(&self) -> IoSizes {
+        self.preferred_io_sizes_async()
+    }
+
+    fn get_bytes_dyn(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send + '_>> {
+        Box::pin(self.get_bytes_async(offset, count))
+    }
+}
+
+impl<Backend: DynOmFileReaderBackendAsync + ?Sized> OmFileReaderBackendAsync for Box<Backend> {
+    fn count_async(&self) -> usize {
+        (**self).count_dyn()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        (**self).preferred_io_sizes_dyn()
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        (**self).get_bytes_dyn(offset, count)
+    }
+}
+
+/// Adapter to use an asynchronous backend with the synchronous `OmFileReader`.
+/// Every request blocks the calling thread until the future completes.
+pub struct BlockingAdapter<Backend: OmFileReaderBackendAsync> {
+    backend: Backend,
+}
+
+impl<Backend: OmFileReaderBackendAsync> BlockingAdapter<Backend> {
+    pub fn new(backend: Backend) -> Self {
+        Self { backend }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+}
+
+impl<Backend: OmFileReaderBackendAsync> OmFileReaderBackend for BlockingAdapter<Backend> {
+    fn count(&self) -> usize {
+        self.backend.count_async()
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        false
+    }
+
+    fn prefetch_data(&self, _offset: usize, _count: usize) {
+        // No-op, every request is fetched on demand
+    }
+
+    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+        // No-op, every request is fetched on demand
+        Ok(())
+    }
+
+    fn preferred_io_sizes(&self) -> IoSizes {
+        self.backend.preferred_io_sizes_async()
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        block_on(self.backend.get_bytes_async(offset, count))
+    }
+}
+
+/// Byte range of a request to a backend of `size` bytes. Offsets read from
+/// corrupted files can be arbitrary, so this never overflows or panics.
+pub(crate) fn checked_range(
+    offset: u64,
+    count: u64,
+    size: usize,
+) -> Result<std::ops::Range<usize>, OmFilesRsError> {
+    match offset.checked_add(count) {
+        Some(end) if end <= size as u64 => Ok(offset as usize..end as usize),
+        _ => Err(OmFilesRsError::OffsetAndCountExceedDimension {
+            offset,
+            count,
+            dimension: size as u64,
+        }),
+    }
+}
+
+pub(crate) fn map_io_error(e: std::io::Error) -> OmFilesRsError {
     OmFilesRsError::FileWriterError {
         errno: e.raw_os_error().unwrap_or(0),
         error: e.to_string(),
@@ -170,6 +443,58 @@ impl OmFileWriterBackend for File {
     }
 }
 
+/// Sink that can only be written sequentially, like a pipe or a socket.
+/// Use it with `OmFileWriter` through `StreamingBackend`.
+pub trait StreamingWriterBackend {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError>;
+    fn flush(&mut self) -> Result<(), OmFilesRsError>;
+}
+
+impl<W: Write> StreamingWriterBackend for W {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError> {
+        self.write_all(data).map_err(|e| map_io_error(e))
+    }
+
+    fn flush(&mut self) -> Result<(), OmFilesRsError> {
+        Write::flush(self).map_err(|e| map_io_error(e))
+    }
+}
+
+/// Writer backend for a `StreamingWriterBackend`. Om files are written front
+/// to back, so everything except `write_at` works. Data is flushed to the
+/// sink after every write of the buffered writer.
+pub struct StreamingBackend<Backend: StreamingWriterBackend> {
+    backend: Backend,
+}
+
+impl<Backend: StreamingWriterBackend> StreamingBackend<Backend> {
+    pub fn new(backend: Backend) -> Self {
+        Self { backend }
+    }
+
+    pub fn into_inner(self) -> Backend {
+        self.backend
+    }
+}
+
+impl<Backend: StreamingWriterBackend> OmFileWriterBackend for StreamingBackend<Backend> {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError> {
+        self.backend.write(data)?;
+        self.backend.flush()
+    }
+
+    fn write_at(&mut self, _data: &[u8], _offset: usize) -> Result<(), OmFilesRsError> {
+        Err(OmFilesRsError::NotImplementedError(
+            "Streaming backends can only append data".to_string(),
+        ))
+    }
+
+    fn synchronize(&self) -> Result<(), OmFilesRsError> {
+        // Data is flushed on every write
+        Ok(())
+    }
+}
+
 impl OmFileReaderBackend for MmapFile {
     fn count(&self) -> usize {
         self.data.len()
@@ -180,7 +505,9 @@ impl OmFileReaderBackend for MmapFile {
     }
 
     fn prefetch_data(&self, offset: usize, count: usize) {
-        self.prefetch_data_advice(offset, count, MAdvice::WillNeed);
+        if let Some(advice) = self.prefetch_advice() {
+            self.prefetch_data_advice(offset, count, advice);
+        }
     }
 
     fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
@@ -188,8 +515,13 @@ impl OmFileReaderBackend for MmapFile {
         Ok(())
     }
 
+    /// The mapping is only unmapped when the backend is dropped
+    fn stable_bytes_owner(self: Arc<Self>) -> Option<Arc<dyn Any + Send + Sync>> {
+        Some(self)
+    }
+
     fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
-        let index_range = (offset as usize)..(offset + count) as usize;
+        let index_range = checked_range(offset, count, self.count())?;
         match self.data {
             MmapType::ReadOnly(ref mmap) => Ok(&mmap[index_range]),
             MmapType::ReadWrite(ref mmap_mut) => Ok(&mmap_mut[index_range]),
@@ -197,6 +529,8 @@ impl OmFileReaderBackend for MmapFile {
     }
 }
 
+/// Growable in-memory file. Files in memory that are only read can also use
+/// `Arc<[u8]>` or `bytes::Bytes` (`bytes` feature) as backend without copying.
 #[derive(Debug)]
 pub struct InMemoryBackend {
     data: Vec<u8>,
@@ -206,6 +540,42 @@ impl InMemoryBackend {
     pub fn new(data: Vec<u8>) -> Self {
         Self { data }
     }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl AsRef<[u8]> for InMemoryBackend {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<Vec<u8>> for InMemoryBackend {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<bytes::Bytes> for InMemoryBackend {
+    /// Does not copy if `bytes` is the only reference to its buffer
+    fn from(bytes: bytes::Bytes) -> Self {
+        Self::new(bytes.into())
+    }
 }
 
 impl OmFileWriterBackend for &mut InMemoryBackend {
@@ -227,26 +597,56 @@ impl OmFileWriterBackend for &mut InMemoryBackend {
     }
 }
 
-impl OmFileReaderBackend for InMemoryBackend {
-    fn count(&self) -> usize {
-        self.data.len()
-    }
+/// Reader backends for types that hold the whole file in memory
+macro_rules! impl_in_memory_reader_backend {
+    ($($t:ty),*) => {
+        $(
+            impl OmFileReaderBackend for $t {
+                fn count(&self) -> usize {
+                    AsRef::<[u8]>::as_ref(self).len()
+                }
 
-    fn needs_prefetch(&self) -> bool {
-        false
-    }
+                fn needs_prefetch(&self) -> bool {
+                    false
+                }
 
-    fn prefetch_data(&self, _offset: usize, _count: usize) {
-        // No-op for in-memory backend
-    }
+                fn prefetch_data(&self, _offset: usize, _count: usize) {
+                    // No-op for in-memory backend
+                }
 
-    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
-        // No-op for in-memory backend
-        Ok(())
-    }
+                fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+                    // No-op for in-memory backend
+                    Ok(())
+                }
 
-    fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
-        let index_range = (offset as usize)..(offset + count) as usize;
-        Ok(&self.data[index_range])
-    }
+                /// The buffer is only read and is never reallocated
+                fn stable_bytes_owner(self: Arc<Self>) -> Option<Arc<dyn Any + Send + Sync>> {
+                    Some(self)
+                }
+
+                fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
+                    let data = AsRef::<[u8]>::as_ref(self);
+                    Ok(&data[checked_range(offset, count, data.len())?])
+                }
+            }
+
+            impl OmFileReaderBackendAsync for $t {
+                fn count_async(&self) -> usize {
+                    AsRef::<[u8]>::as_ref(self).len()
+                }
+
+                fn get_bytes_async(
+                    &self,
+                    offset: u64,
+                    count: u64,
+                ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+                    std::future::ready(self.get_bytes(offset, count).map(|data| data.to_vec()))
+                }
+            }
+        )*
+    };
 }
+
+impl_in_memory_reader_backend!(InMemoryBackend, Arc<[u8]>);
+#[cfg(feature = "bytes")]
+impl_in_memory_reader_backend!(bytes::Bytes);
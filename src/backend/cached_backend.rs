@@ -0,0 +1,255 @@
+use crate::backend::backends::{
+    checked_range, IoSizes, OmFileReaderBackend, OmFileReaderBackendAsync,
+};
+use crate::errors::OmFilesRsError;
+use crate::utils::divide_rounded_up;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Counters describing how effective the block cache is.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStatistics {
+    /// Number of block lookups served from the cache
+    pub hits: u64,
+    /// Number of block lookups that had to be fetched from the inner backend
+    pub misses: u64,
+    /// Number of blocks removed to stay within the configured capacity
+    pub evictions: u64,
+    /// Number of blocks currently held in the cache
+    pub cached_blocks: u64,
+    /// Bytes requested from the inner backend
+    pub bytes_fetched: u64,
+}
+
+struct CacheState {
+    /// Block index -> (block data, last access tick)
+    blocks: HashMap<u64, (Arc<[u8]>, u64)>,
+    /// Last access tick -> block index, the first entry is the least recently used block
+    lru: BTreeMap<u64, u64>,
+    tick: u64,
+    statistics: CacheStatistics,
+}
+
+/// A reader backend that keeps recently used fixed-size blocks of another
+/// backend in memory. Useful for slow or remote backends where the LUT and
+/// hot chunks are read repeatedly. Works for synchronous and asynchronous
+/// backends.
+pub struct CachedBackend<Backend> {
+    backend: Backend,
+    block_size: u64,
+    max_blocks: usize,
+    state: Mutex<CacheState>,
+}
+
+impl<Backend> CachedBackend<Backend> {
+    /// Cache up to `max_blocks` blocks of `block_size` bytes of `backend`.
+    /// Zero values are raised to 1.
+    pub fn new(backend: Backend, block_size: u64, max_blocks: usize) -> Self {
+        Self {
+            backend,
+            block_size: block_size.max(1),
+            max_blocks: max_blocks.max(1),
+            state: Mutex::new(CacheState {
+                blocks: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                statistics: CacheStatistics::default(),
+            }),
+        }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    pub fn statistics(&self) -> CacheStatistics {
+        self.state.lock().unwrap().statistics
+    }
+
+    pub fn reset_statistics(&self) {
+        let mut state = self.state.lock().unwrap();
+        let cached_blocks = state.statistics.cached_blocks;
+        state.statistics = CacheStatistics {
+            cached_blocks,
+            ..Default::default()
+        };
+    }
+
+    /// Remove all cached blocks
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.clear();
+        state.lru.clear();
+        state.statistics.cached_blocks = 0;
+    }
+
+    /// Return a cached block and mark it as recently used
+    fn lookup(&self, block_index: u64) -> Option<Arc<[u8]>> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some((data, last_used)) = state.blocks.get_mut(&block_index) {
+            let data = data.clone();
+            let previous = std::mem::replace(last_used, tick);
+            state.lru.remove(&previous);
+            state.lru.insert(tick, block_index);
+            state.statistics.hits += 1;
+            return Some(data);
+        }
+        state.statistics.misses += 1;
+        None
+    }
+
+    /// Byte range of a block in a backend of `len` bytes
+    fn block_range(&self, block_index: u64, len: u64) -> (u64, u64) {
+        let offset = block_index * self.block_size;
+        (offset, self.block_size.min(len - offset))
+    }
+
+    /// Store a block fetched from the inner backend and evict the least
+    /// recently used blocks beyond the capacity
+    fn insert(&self, block_index: u64, data: Arc<[u8]>) {
+        let mut state = self.state.lock().unwrap();
+        state.statistics.bytes_fetched += data.len() as u64;
+        if state.blocks.contains_key(&block_index) {
+            // Another thread fetched the same block in the meantime
+            return;
+        }
+        while state.blocks.len() >= self.max_blocks {
+            let Some((_, evicted)) = state.lru.pop_first() else {
+                break;
+            };
+            state.blocks.remove(&evicted);
+            state.statistics.evictions += 1;
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.blocks.insert(block_index, (data, tick));
+        state.lru.insert(tick, block_index);
+        state.statistics.cached_blocks = state.blocks.len() as u64;
+    }
+}
+
+impl<Backend: OmFileReaderBackend> CachedBackend<Backend> {
+    /// Return a block from the cache or fetch it from the inner backend.
+    fn get_block(&self, block_index: u64) -> Result<Arc<[u8]>, OmFilesRsError> {
+        if let Some(data) = self.lookup(block_index) {
+            return Ok(data);
+        }
+        // Fetch without holding the lock, so slow backends do not block other readers
+        let (offset, count) = self.block_range(block_index, self.backend.count() as u64);
+        let data: Arc<[u8]> = self.backend.get_bytes_with_fallback(offset, count)?.into();
+        self.insert(block_index, data.clone());
+        Ok(data)
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReaderBackend for CachedBackend<Backend> {
+    fn count(&self) -> usize {
+        self.backend.count()
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        self.backend.needs_prefetch()
+    }
+
+    fn prefetch_data(&self, offset: usize, count: usize) {
+        self.backend.prefetch_data(offset, count)
+    }
+
+    fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        // Warm up the cache
+        let range = checked_range(offset as u64, count as u64, self.backend.count())?;
+        if range.is_empty() {
+            return Ok(());
+        }
+        let first_block = range.start as u64 / self.block_size;
+        let last_block = divide_rounded_up(range.end, self.block_size as usize) as u64;
+        for block_index in first_block..last_block {
+            self.get_block(block_index)?;
+        }
+        Ok(())
+    }
+
+    /// Gaps within a block are fetched anyway, so they never cost an extra request.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        let inner = self.backend.preferred_io_sizes();
+        IoSizes {
+            io_size_max: inner.io_size_max.max(self.block_size),
+            io_size_merge: inner.io_size_merge.max(self.block_size),
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        let end = checked_range(offset, count, self.backend.count())?.end as u64;
+        let mut result = Vec::with_capacity(count as usize);
+        let mut position = offset;
+        while position < end {
+            let block_index = position / self.block_size;
+            let block_start = block_index * self.block_size;
+            let block = self.get_block(block_index)?;
+            let from = (position - block_start) as usize;
+            let to = ((end - block_start) as usize).min(block.len());
+            result.extend_from_slice(&block[from..to]);
+            position = block_start + to as u64;
+        }
+        Ok(result)
+    }
+}
+
+impl<Backend: OmFileReaderBackendAsync + Sync> CachedBackend<Backend> {
+    /// Return a block from the cache or fetch it from the inner backend.
+    async fn get_block_async(&self, block_index: u64) -> Result<Arc<[u8]>, OmFilesRsError> {
+        if let Some(data) = self.lookup(block_index) {
+            return Ok(data);
+        }
+        let (offset, count) = self.block_range(block_index, self.backend.count_async() as u64);
+        let data: Arc<[u8]> = self.backend.get_bytes_async(offset, count).await?.into();
+        self.insert(block_index, data.clone());
+        Ok(data)
+    }
+
+    async fn get_bytes_cached(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        let end = checked_range(offset, count, self.backend.count_async())?.end as u64;
+        let mut result = Vec::with_capacity(count as usize);
+        let mut position = offset;
+        while position < end {
+            let block_index = position / self.block_size;
+            let block_start = block_index * self.block_size;
+            let block = self.get_block_async(block_index).await?;
+            let from = (position - block_start) as usize;
+            let to = ((end - block_start) as usize).min(block.len());
+            result.extend_from_slice(&block[from..to]);
+            position = block_start + to as u64;
+        }
+        Ok(result)
+    }
+}
+
+impl<Backend: OmFileReaderBackendAsync + Sync> OmFileReaderBackendAsync for CachedBackend<Backend> {
+    fn count_async(&self) -> usize {
+        self.backend.count_async()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        let inner = self.backend.preferred_io_sizes_async();
+        IoSizes {
+            io_size_max: inner.io_size_max.max(self.block_size),
+            io_size_merge: inner.io_size_merge.max(self.block_size),
+        }
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        self.get_bytes_cached(offset, count)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::backend::backends::{checked_range, IoSizes, OmFileReaderBackend};
+use crate::backend::pread::map_read_error;
+use crate::errors::OmFilesRsError;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+
+/// Default alignment of offsets, lengths and buffers. 4096 satisfies the
+/// logical block size of common file systems and devices.
+const DEFAULT_ALIGNMENT: usize = 4096;
+
+/// Reader backend that opens the file with `O_DIRECT` and bypasses the page
+/// cache. Useful if the application keeps its own cache, e.g. a decoded chunk
+/// cache, and data should not be cached twice. Throughput is predictable and
+/// independent of memory pressure, but every request goes to the device.
+///
+/// Requests are widened to aligned blocks, read into an aligned buffer and
+/// the requested range is copied out. Not all file systems support
+/// `O_DIRECT`, e.g. tmpfs, in which case opening fails with `EINVAL`.
+///
+/// Reads block the calling thread, so this is only a backend of the
+/// synchronous `OmFileReader`.
+pub struct DirectIoBackend {
+    file: File,
+    file_size: usize,
+    alignment: usize,
+}
+
+impl DirectIoBackend {
+    pub fn from_path(path: &str) -> Result<Self, OmFilesRsError> {
+        Self::with_alignment(path, DEFAULT_ALIGNMENT)
+    }
+
+    /// Open `path` with a custom `alignment`. It must be a power of two and a
+    /// multiple of the logical block size of the device.
+    pub fn with_alignment(path: &str, alignment: usize) -> Result<Self, OmFilesRsError> {
+        if !alignment.is_power_of_two() {
+            return Err(OmFilesRsError::InvalidConfiguration(format!(
+                "alignment {} must be a power of two",
+                alignment
+            )));
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)
+            .map_err(|e| OmFilesRsError::CannotOpenFile {
+                filename: path.to_string(),
+                errno: e.raw_os_error().unwrap_or(0),
+                error: e.to_string(),
+            })?;
+        let file_size = file.metadata().map_err(map_read_error)?.len() as usize;
+        Ok(Self {
+            file,
+            file_size,
+            alignment,
+        })
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// The underlying file handle
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+}
+
+impl OmFileReaderBackend for DirectIoBackend {
+    fn count(&self) -> usize {
+        self.file_size
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        false
+    }
+
+    fn prefetch_data(&self, _offset: usize, _count: usize) {
+        // No-op, the page cache is bypassed
+    }
+
+    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+        // No-op, the page cache is bypassed
+        Ok(())
+    }
+
+    /// Every request reads whole aligned blocks from the device, so gaps
+    /// within a block are free.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        IoSizes {
+            io_size_max: 1024 * 1024,
+            io_size_merge: self.alignment as u64,
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        checked_range(offset, count, self.file_size)?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let alignment = self.alignment as u64;
+        let start = offset / alignment * alignment;
+        let end = (offset + count).div_ceil(alignment) * alignment;
+        let length = (end - start) as usize;
+
+        // Over-allocate and use the aligned part of the allocation
+        let mut buffer = vec![0u8; length + self.alignment];
+        let buffer_start = buffer.as_ptr().align_offset(self.alignment);
+        let aligned = &mut buffer[buffer_start..buffer_start + length];
+
+        // The last block of the file may be shorter than the alignment
+        let available = (self.file_size as u64 - start).min(length as u64) as usize;
+        let mut read = 0;
+        while read < available {
+            match self
+                .file
+                .read_at(&mut aligned[read..], start + read as u64)
+                .map_err(map_read_error)?
+            {
+                0 => {
+                    return Err(OmFilesRsError::FileReaderError {
+                        errno: 0,
+                        error: "Unexpected end of file".to_string(),
+                    })
+                }
+                n => read += n,
+            }
+        }
+        let begin = (offset - start) as usize;
+        Ok(aligned[begin..begin + count as usize].to_vec())
+    }
+}
@@ -0,0 +1,289 @@
+use crate::backend::backends::{IoSizes, OmFileReaderBackend, OmFileWriterBackend};
+use crate::errors::OmFilesRsError;
+use crate::utils::divide_rounded_up;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+
+const MAGIC: [u8; 4] = *b"OMEC";
+/// Magic number followed by the block size as little endian u32
+const HEADER_SIZE: u64 = 8;
+const NONCE_SIZE: u64 = 12;
+const TAG_SIZE: u64 = 16;
+
+/// A backend that stores data encrypted with AES-256-GCM in fixed-size
+/// blocks. Every block carries its own random nonce and authentication tag,
+/// so reads only need to decrypt the blocks they touch.
+///
+/// The block index and a flag for the last block are authenticated as well,
+/// which detects reordered or truncated files.
+///
+/// When writing, data is buffered until a block is complete. `finalize` has
+/// to be called after the `OmFileWriter` is done, to encrypt the last block.
+pub struct EncryptedBackend<Backend> {
+    backend: Backend,
+    cipher: Aes256Gcm,
+    block_size: u64,
+    /// Number of plaintext bytes
+    count: u64,
+    /// Plaintext that is not encrypted yet
+    pending: Vec<u8>,
+    /// Number of blocks written to the inner backend
+    blocks_written: u64,
+    finalized: bool,
+}
+
+impl<Backend> EncryptedBackend<Backend> {
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+
+    pub fn into_inner(self) -> Backend {
+        self.backend
+    }
+
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    fn stored_block_size(&self) -> u64 {
+        NONCE_SIZE + self.block_size + TAG_SIZE
+    }
+
+    fn number_of_blocks(&self) -> u64 {
+        // An empty file still stores one (empty) last block
+        divide_rounded_up(self.count as usize, self.block_size as usize).max(1) as u64
+    }
+
+    /// Block index and last block flag are authenticated with every block
+    fn associated_data(block_index: u64, is_last: bool) -> [u8; 9] {
+        let mut aad = [0; 9];
+        aad[..8].copy_from_slice(&block_index.to_le_bytes());
+        aad[8] = is_last as u8;
+        aad
+    }
+
+    /// Range of the stored file with the blocks that contain plaintext bytes `offset..offset+count`
+    fn stored_range(&self, offset: u64, count: u64) -> (u64, u64) {
+        let first_block = offset / self.block_size;
+        let last_block =
+            divide_rounded_up((offset + count) as usize, self.block_size as usize) as u64;
+        let start = HEADER_SIZE + first_block * self.stored_block_size();
+        let end = if last_block >= self.number_of_blocks() {
+            HEADER_SIZE + self.count + self.number_of_blocks() * (NONCE_SIZE + TAG_SIZE)
+        } else {
+            HEADER_SIZE + last_block * self.stored_block_size()
+        };
+        (start, end)
+    }
+}
+
+impl<Backend: OmFileWriterBackend> EncryptedBackend<Backend> {
+    /// Write an encrypted file to `backend` with `key` and blocks of `block_size` bytes.
+    pub fn create(
+        mut backend: Backend,
+        key: &[u8; 32],
+        block_size: u32,
+    ) -> Result<Self, OmFilesRsError> {
+        if block_size == 0 {
+            return Err(OmFilesRsError::InvalidConfiguration(
+                "block_size must be larger than 0".to_string(),
+            ));
+        }
+        let mut header = [0; HEADER_SIZE as usize];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..].copy_from_slice(&block_size.to_le_bytes());
+        backend.write(&header)?;
+        Ok(Self {
+            backend,
+            cipher: Aes256Gcm::new(key.into()),
+            block_size: block_size as u64,
+            count: 0,
+            pending: Vec::new(),
+            blocks_written: 0,
+            finalized: false,
+        })
+    }
+
+    /// Encrypt the remaining data as last block. Further writes fail.
+    pub fn finalize(&mut self) -> Result<(), OmFilesRsError> {
+        if self.finalized {
+            return Ok(());
+        }
+        let block = std::mem::take(&mut self.pending);
+        self.write_block(&block, true)?;
+        self.finalized = true;
+        self.backend.synchronize()
+    }
+
+    fn write_block(&mut self, block: &[u8], is_last: bool) -> Result<(), OmFilesRsError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = Self::associated_data(self.blocks_written, is_last);
+        let payload = Payload {
+            msg: block,
+            aad: &aad,
+        };
+        let ciphertext =
+            self.cipher
+                .encrypt(&nonce, payload)
+                .map_err(|e| OmFilesRsError::FileWriterError {
+                    errno: 0,
+                    error: e.to_string(),
+                })?;
+        self.backend.write(&nonce)?;
+        self.backend.write(&ciphertext)?;
+        self.blocks_written += 1;
+        Ok(())
+    }
+}
+
+impl<Backend: OmFileWriterBackend> OmFileWriterBackend for &mut EncryptedBackend<Backend> {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError> {
+        if self.finalized {
+            return Err(OmFilesRsError::FileWriterError {
+                errno: 0,
+                error: "Encrypted backend is already finalized".to_string(),
+            });
+        }
+        self.pending.extend_from_slice(data);
+        self.count += data.len() as u64;
+        // Keep at least one byte pending, because the last block is only known on `finalize`
+        let block_size = self.block_size as usize;
+        let full_blocks = self.pending.len().saturating_sub(1) / block_size;
+        if full_blocks > 0 {
+            let pending = std::mem::take(&mut self.pending);
+            for block in pending[..full_blocks * block_size].chunks(block_size) {
+                self.write_block(block, false)?;
+            }
+            self.pending = pending[full_blocks * block_size..].to_vec();
+        }
+        Ok(())
+    }
+
+    fn write_at(&mut self, data: &[u8], offset: usize) -> Result<(), OmFilesRsError> {
+        // Only data that is not encrypted yet can be modified
+        let pending_start = (self.blocks_written * self.block_size) as usize;
+        if self.finalized || offset < pending_start || offset + data.len() > self.count as usize {
+            return Err(OmFilesRsError::NotImplementedError(
+                "Encrypted backend cannot modify blocks that are already encrypted".to_string(),
+            ));
+        }
+        let start = offset - pending_start;
+        self.pending[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn synchronize(&self) -> Result<(), OmFilesRsError> {
+        self.backend.synchronize()
+    }
+}
+
+impl<Backend: OmFileReaderBackend> EncryptedBackend<Backend> {
+    /// Open an encrypted file in `backend` that was written with `key`.
+    pub fn open(backend: Backend, key: &[u8; 32]) -> Result<Self, OmFilesRsError> {
+        let stored = backend.count() as u64;
+        if stored < HEADER_SIZE + NONCE_SIZE + TAG_SIZE {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        let header = backend.get_bytes_with_fallback(0, HEADER_SIZE)?;
+        if header[..4] != MAGIC {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        let block_size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+        if block_size == 0 {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        // All blocks are full, except the last one
+        let stored_block_size = NONCE_SIZE + block_size + TAG_SIZE;
+        let number_of_blocks =
+            divide_rounded_up((stored - HEADER_SIZE) as usize, stored_block_size as usize) as u64;
+        let count = stored - HEADER_SIZE - number_of_blocks * (NONCE_SIZE + TAG_SIZE);
+        let last_block_size = stored - HEADER_SIZE - (number_of_blocks - 1) * stored_block_size;
+        if last_block_size < NONCE_SIZE + TAG_SIZE {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        Ok(Self {
+            backend,
+            cipher: Aes256Gcm::new(key.into()),
+            block_size,
+            count,
+            pending: Vec::new(),
+            blocks_written: number_of_blocks,
+            finalized: true,
+        })
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReaderBackend for EncryptedBackend<Backend> {
+    fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        self.backend.needs_prefetch()
+    }
+
+    fn prefetch_data(&self, offset: usize, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let (start, end) = self.stored_range(offset as u64, count as u64);
+        self.backend
+            .prefetch_data(start as usize, (end - start) as usize)
+    }
+
+    fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        if count == 0 {
+            return Ok(());
+        }
+        let (start, end) = self.stored_range(offset as u64, count as u64);
+        self.backend
+            .pre_read(start as usize, (end - start) as usize)
+    }
+
+    /// Whole blocks are decrypted anyway, so gaps within a block never cost an extra request.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        let inner = self.backend.preferred_io_sizes();
+        IoSizes {
+            io_size_max: inner.io_size_max.max(self.block_size),
+            io_size_merge: inner.io_size_merge.max(self.block_size),
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        if offset.checked_add(count).is_none_or(|end| end > self.count) {
+            return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                offset,
+                count,
+                dimension: self.count,
+            });
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let (stored_start, stored_end) = self.stored_range(offset, count);
+        let stored = self
+            .backend
+            .get_bytes_with_fallback(stored_start, stored_end - stored_start)?;
+
+        let first_block = offset / self.block_size;
+        let number_of_blocks = self.number_of_blocks();
+        let mut plaintext = Vec::with_capacity(stored.len());
+        for (i, block) in stored.chunks(self.stored_block_size() as usize).enumerate() {
+            let block_index = first_block + i as u64;
+            let aad = Self::associated_data(block_index, block_index == number_of_blocks - 1);
+            let (nonce, ciphertext) = block.split_at(NONCE_SIZE as usize);
+            let payload = Payload {
+                msg: ciphertext,
+                aad: &aad,
+            };
+            let decrypted = self
+                .cipher
+                .decrypt(Nonce::from_slice(nonce), payload)
+                .map_err(|_| OmFilesRsError::DecryptionFailed)?;
+            plaintext.extend_from_slice(&decrypted);
+        }
+        let from = (offset - first_block * self.block_size) as usize;
+        Ok(plaintext[from..from + count as usize].to_vec())
+    }
+}
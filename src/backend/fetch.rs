@@ -0,0 +1,159 @@
+use crate::backend::backends::{IoSizes, OmFileReaderBackendAsync};
+use crate::errors::OmFilesRsError;
+use js_sys::{Promise, Uint8Array};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response};
+
+#[wasm_bindgen]
+extern "C" {
+    /// Global `fetch`, available in windows and workers
+    #[wasm_bindgen(js_name = fetch)]
+    fn fetch_with_request(input: &Request) -> Promise;
+}
+
+/// Asynchronous backend for browsers that reads remote files with HTTP
+/// Range requests via `fetch`. The server has to support range requests and,
+/// for cross-origin requests, allow the `Range` header with CORS.
+pub struct FetchBackend {
+    url: String,
+    count: usize,
+}
+
+impl FetchBackend {
+    /// Open `url` and determine its length with a `HEAD` request
+    pub async fn new(url: &str) -> Result<Self, OmFilesRsError> {
+        let init = RequestInit::new();
+        init.set_method("HEAD");
+        let response = fetch(url, &init).await?;
+        let count = response
+            .headers()
+            .get("Content-Length")
+            .map_err(map_js_error)?
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or_else(|| OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: format!("Missing Content-Length for {}", url),
+            })?;
+        Ok(Self::with_length(url, count))
+    }
+
+    /// Use a known file length and skip the `HEAD` request
+    pub fn with_length(url: &str, count: usize) -> Self {
+        Self {
+            url: url.to_string(),
+            count,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn fetch_range(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let init = RequestInit::new();
+        init.set_method("GET");
+        let request = Request::new_with_str_and_init(&self.url, &init).map_err(map_js_error)?;
+        request
+            .headers()
+            .set("Range", &format!("bytes={}-{}", offset, offset + count - 1))
+            .map_err(map_js_error)?;
+        let response = send(&request).await?;
+        let buffer = JsFuture::from(response.array_buffer().map_err(map_js_error)?)
+            .await
+            .map_err(map_js_error)?;
+        let data = Uint8Array::new(&buffer).to_vec();
+        // Servers without range support answer with the whole file
+        let data = match response.status() {
+            206 => data,
+            _ => data
+                .get(offset as usize..(offset + count) as usize)
+                .map(|range| range.to_vec())
+                .unwrap_or_default(),
+        };
+        if data.len() as u64 != count {
+            return Err(OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: format!(
+                    "Expected {} bytes at offset {} from {}, got {}",
+                    count,
+                    offset,
+                    self.url,
+                    data.len()
+                ),
+            });
+        }
+        Ok(data)
+    }
+}
+
+impl OmFileReaderBackendAsync for FetchBackend {
+    fn count_async(&self) -> usize {
+        self.count
+    }
+
+    /// Every request is a HTTP round trip, so larger requests are preferred.
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        IoSizes {
+            io_size_max: 1024 * 1024,
+            io_size_merge: 64 * 1024,
+        }
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        AssertSend(self.fetch_range(offset, count))
+    }
+}
+
+async fn fetch(url: &str, init: &RequestInit) -> Result<Response, OmFilesRsError> {
+    let request = Request::new_with_str_and_init(url, init).map_err(map_js_error)?;
+    send(&request).await
+}
+
+async fn send(request: &Request) -> Result<Response, OmFilesRsError> {
+    let response: Response = JsFuture::from(fetch_with_request(request))
+        .await
+        .map_err(map_js_error)?
+        .dyn_into()
+        .map_err(map_js_error)?;
+    if !response.ok() {
+        return Err(OmFilesRsError::FileReaderError {
+            errno: response.status() as i32,
+            error: format!("HTTP {} for {}", response.status(), request.url()),
+        });
+    }
+    Ok(response)
+}
+
+fn map_js_error(error: JsValue) -> OmFilesRsError {
+    OmFilesRsError::FileReaderError {
+        errno: 0,
+        error: format!("{:?}", error),
+    }
+}
+
+/// JavaScript futures are not `Send`. `wasm32-unknown-unknown` runs on a
+/// single thread, so they are never moved to another thread.
+struct AssertSend<F>(F);
+
+unsafe impl<F> Send for AssertSend<F> {}
+
+impl<F: Future> Future for AssertSend<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Structural pinning of the only field
+        unsafe { self.map_unchecked_mut(|s| &mut s.0) }.poll(cx)
+    }
+}
@@ -0,0 +1,699 @@
+use crate::backend::backends::{
+    checked_range, IoSizes, OmFileReaderBackend, OmFileReaderBackendAsync,
+};
+use crate::backend::pread::{map_read_error, PreadFile};
+#[cfg(feature = "tokio")]
+use crate::backend::tokio_file::read_blocking;
+use crate::errors::OmFilesRsError;
+use crate::utils::block_on;
+use futures::future::try_join_all;
+#[cfg(feature = "tokio")]
+use futures::future::Either;
+use io_uring::{opcode, types, IoUring};
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::fs::File;
+use std::future::Future;
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+
+/// Tuning parameters for `IoUringBackend`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IoUringConfig {
+    /// Number of submission queue entries
+    pub queue_depth: u32,
+    /// Number of registered buffers. This is also the maximum number of reads in flight.
+    pub buffer_count: usize,
+    /// Size of each registered buffer in bytes. Larger reads are split into multiple requests.
+    pub buffer_size: usize,
+    /// Alignment of each registered buffer. Must be a power of two. 4096 is required for O_DIRECT.
+    pub alignment: usize,
+}
+
+impl Default for IoUringConfig {
+    fn default() -> Self {
+        Self {
+            queue_depth: 64,
+            buffer_count: 16,
+            buffer_size: 256 * 1024,
+            alignment: 4096,
+        }
+    }
+}
+
+impl IoUringConfig {
+    fn validate(&self) -> Result<(), OmFilesRsError> {
+        let error = if self.buffer_count == 0 {
+            "buffer_count must be larger than 0"
+        } else if self.buffer_count > u16::MAX as usize {
+            "buffer_count must fit into u16"
+        } else if self.buffer_size == 0 || self.buffer_size > u32::MAX as usize {
+            "buffer_size must be larger than 0 and fit into u32"
+        } else if !self.alignment.is_power_of_two() {
+            "alignment must be a power of two"
+        } else if (self.queue_depth as usize) < self.buffer_count {
+            "queue_depth must be at least buffer_count"
+        } else {
+            return Ok(());
+        };
+        Err(OmFilesRsError::InvalidConfiguration(error.to_string()))
+    }
+}
+
+/// Heap buffer with a custom alignment that is registered with the kernel.
+struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    fn new(size: usize, alignment: usize) -> Self {
+        let layout = Layout::from_size_align(size, alignment).expect("Invalid buffer layout");
+        let ptr = NonNull::new(unsafe { alloc_zeroed(layout) }).expect("Out of memory");
+        Self { ptr, layout }
+    }
+
+    fn as_slice(&self, count: usize) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), count) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+// A buffer is only accessed by the owner of its index, see `Buffer`
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+/// User data of `IORING_OP_ASYNC_CANCEL` requests. Reads use the buffer index.
+const CANCEL_USER_DATA: u64 = u64::MAX;
+
+/// State of the read that uses the registered buffer with the same index
+enum Slot {
+    Idle,
+    /// Submitted, the waker of the `FixedRead` is woken on completion
+    InFlight(Option<Waker>),
+    /// Result of the completion entry
+    Completed(i32),
+    /// The `FixedRead` was dropped while the read was in flight. The buffer is
+    /// returned to the pool when the completion arrives.
+    Abandoned,
+}
+
+struct State {
+    ring: IoUring,
+    /// Indices of the buffers that are not owned by any read
+    free: Vec<u16>,
+    slots: Vec<Slot>,
+    /// Tasks waiting for a free buffer
+    buffer_waiters: Vec<Waker>,
+    shutdown: bool,
+}
+
+impl State {
+    /// Drain the completion queue and return the wakers of finished reads
+    fn reap(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        let completed: Vec<(u64, i32)> = self
+            .ring
+            .completion()
+            .map(|entry| (entry.user_data(), entry.result()))
+            .collect();
+        for (user_data, result) in completed {
+            if user_data == CANCEL_USER_DATA {
+                continue;
+            }
+            let index = user_data as usize;
+            match std::mem::replace(&mut self.slots[index], Slot::Completed(result)) {
+                Slot::InFlight(waker) => wakers.extend(waker),
+                Slot::Abandoned => wakers.extend(self.release(index as u16)),
+                Slot::Idle | Slot::Completed(_) => {}
+            }
+        }
+        wakers
+    }
+
+    /// Return a buffer to the pool. The returned wakers must be woken after
+    /// the lock is released.
+    fn release(&mut self, index: u16) -> Vec<Waker> {
+        self.slots[index as usize] = Slot::Idle;
+        self.free.push(index);
+        std::mem::take(&mut self.buffer_waiters)
+    }
+
+    fn in_flight(&self) -> bool {
+        self.slots
+            .iter()
+            .any(|slot| matches!(slot, Slot::InFlight(_) | Slot::Abandoned))
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    buffers: Vec<AlignedBuffer>,
+    /// Registered with the ring and signalled for every completion
+    eventfd: File,
+}
+
+impl Shared {
+    fn release(&self, index: u16) {
+        let wakers = self.state.lock().unwrap().release(index);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Exclusive use of a registered buffer. Dropping it returns the buffer to the pool.
+struct Buffer<'a> {
+    shared: &'a Shared,
+    index: u16,
+}
+
+impl Buffer<'_> {
+    fn as_slice(&self, count: usize) -> &[u8] {
+        self.shared.buffers[self.index as usize].as_slice(count)
+    }
+
+    fn as_mut_ptr(&self) -> *mut u8 {
+        self.shared.buffers[self.index as usize].ptr.as_ptr()
+    }
+}
+
+impl Drop for Buffer<'_> {
+    fn drop(&mut self) {
+        self.shared.release(self.index);
+    }
+}
+
+/// Waits until a registered buffer is free
+struct AcquireBuffer<'a> {
+    shared: &'a Shared,
+}
+
+impl<'a> Future for AcquireBuffer<'a> {
+    type Output = Buffer<'a>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.free.pop() {
+            Some(index) => Poll::Ready(Buffer {
+                shared: self.shared,
+                index,
+            }),
+            None => {
+                state.buffer_waiters.retain(|w| !w.will_wake(cx.waker()));
+                state.buffer_waiters.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A submitted `IORING_OP_READ_FIXED`. Resolves to the buffer and the result
+/// of the completion entry.
+struct FixedRead<'a> {
+    shared: &'a Shared,
+    index: u16,
+    done: bool,
+}
+
+impl<'a> FixedRead<'a> {
+    /// Read `count` bytes at `file_offset` into `buffer` at `buffer_offset`
+    fn submit(
+        buffer: Buffer<'a>,
+        buffer_offset: usize,
+        file_offset: u64,
+        count: usize,
+    ) -> Result<Self, OmFilesRsError> {
+        let shared = buffer.shared;
+        let index = buffer.index;
+        let entry = opcode::ReadFixed::new(
+            types::Fixed(0),
+            unsafe { buffer.as_mut_ptr().add(buffer_offset) },
+            count as u32,
+            index,
+        )
+        .offset(file_offset)
+        .build()
+        .user_data(index as u64);
+
+        let mut state = shared.state.lock().unwrap();
+        // Safety: the buffer is owned by the read until its completion is reaped
+        let pushed = unsafe { state.ring.submission().push(&entry) };
+        if let Err(e) = pushed {
+            drop(state);
+            drop(buffer);
+            return Err(OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: e.to_string(),
+            });
+        }
+        // From here on the kernel may write into the buffer
+        std::mem::forget(buffer);
+        if let Err(e) = state.ring.submit() {
+            // The entry stays queued and is submitted with the next request
+            state.slots[index as usize] = Slot::Abandoned;
+            return Err(map_read_error(e));
+        }
+        state.slots[index as usize] = Slot::InFlight(None);
+        Ok(Self {
+            shared,
+            index,
+            done: false,
+        })
+    }
+}
+
+impl<'a> Future for FixedRead<'a> {
+    type Output = (Buffer<'a>, i32);
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        let slot = &mut state.slots[self.index as usize];
+        match slot {
+            Slot::Completed(result) => {
+                let result = *result;
+                *slot = Slot::Idle;
+                drop(state);
+                self.done = true;
+                Poll::Ready((
+                    Buffer {
+                        shared: self.shared,
+                        index: self.index,
+                    },
+                    result,
+                ))
+            }
+            _ => {
+                *slot = Slot::InFlight(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for FixedRead<'_> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut state = self.shared.state.lock().unwrap();
+        match state.slots[self.index as usize] {
+            Slot::Completed(_) => {
+                let wakers = state.release(self.index);
+                drop(state);
+                wakers.into_iter().for_each(Waker::wake);
+            }
+            // The kernel still owns the buffer. The read is cancelled and the
+            // reaper returns the buffer to the pool once it completes.
+            _ => {
+                state.slots[self.index as usize] = Slot::Abandoned;
+                let cancel = opcode::AsyncCancel::new(self.index as u64)
+                    .build()
+                    .user_data(CANCEL_USER_DATA);
+                // Safety: the cancel request does not reference any memory. If it
+                // cannot be queued, the read still completes on its own.
+                let pushed = unsafe { state.ring.submission().push(&cancel) };
+                if pushed.is_ok() {
+                    let _ = state.ring.submit();
+                }
+            }
+        }
+    }
+}
+
+/// Wait for completions and wake the tasks of finished reads
+fn reap_completions(shared: Arc<Shared>) {
+    let mut counter = [0u8; 8];
+    loop {
+        match (&shared.eventfd).read(&mut counter) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+        let wakers = {
+            let mut state = shared.state.lock().unwrap();
+            if state.shutdown {
+                break;
+            }
+            state.reap()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Reader backend based on Linux io_uring.
+///
+/// A pool of aligned buffers is registered with the kernel (`IORING_REGISTER_BUFFERS`)
+/// and the file descriptor is registered as fixed file. Reads use `IORING_OP_READ_FIXED`,
+/// which avoids mapping user memory and looking up the file descriptor for every request.
+/// Large reads are split and submitted concurrently, one request per registered buffer.
+///
+/// Reads of `OmFileReaderAsync` are submitted when the future is polled and a
+/// background thread reaps the completions and wakes the waiting tasks, so the
+/// executor is never blocked on IO. The synchronous reader waits on the calling
+/// thread.
+///
+/// Dropping a future, e.g. when a `CancellationToken` of `OmFileReaderAsync`
+/// is cancelled, cancels its reads with `IORING_OP_ASYNC_CANCEL`. The
+/// registered buffers are reused once the kernel confirms the cancellation.
+///
+/// `get_bytes_owned`, and therefore `OmFileReader`, copies the data out of the
+/// registered buffers into a new `Vec`. Only `read_with` passes the registered
+/// buffer to the caller without copying.
+pub struct IoUringBackend {
+    file: File,
+    file_size: usize,
+    config: IoUringConfig,
+    shared: Arc<Shared>,
+    reaper: Option<JoinHandle<()>>,
+}
+
+impl IoUringBackend {
+    pub fn new(file: File) -> Result<Self, OmFilesRsError> {
+        Self::with_config(file, IoUringConfig::default())
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, OmFilesRsError> {
+        let file = File::open(path).map_err(|e| OmFilesRsError::CannotOpenFile {
+            filename: path.to_string(),
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+        Self::new(file)
+    }
+
+    pub fn with_config(file: File, config: IoUringConfig) -> Result<Self, OmFilesRsError> {
+        config.validate()?;
+
+        let file_size = file.metadata().map_err(map_read_error)?.len() as usize;
+        let ring = IoUring::new(config.queue_depth).map_err(map_read_error)?;
+        let buffers: Vec<AlignedBuffer> = (0..config.buffer_count)
+            .map(|_| AlignedBuffer::new(config.buffer_size, config.alignment))
+            .collect();
+        let iovecs: Vec<libc::iovec> = buffers
+            .iter()
+            .map(|buffer| libc::iovec {
+                iov_base: buffer.ptr.as_ptr() as *mut libc::c_void,
+                iov_len: config.buffer_size,
+            })
+            .collect();
+        let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if eventfd < 0 {
+            return Err(map_read_error(std::io::Error::last_os_error()));
+        }
+        // Safety: the descriptor was just created and is owned by the `File`
+        let eventfd = unsafe { File::from_raw_fd(eventfd) };
+
+        let submitter = ring.submitter();
+        // Safety: buffers are owned by `Shared` and outlive the registration, which
+        // ends when the ring is dropped.
+        unsafe { submitter.register_buffers(&iovecs) }.map_err(map_read_error)?;
+        submitter
+            .register_files(&[file.as_raw_fd()])
+            .map_err(map_read_error)?;
+        submitter
+            .register_eventfd(eventfd.as_raw_fd())
+            .map_err(map_read_error)?;
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                ring,
+                free: (0..config.buffer_count as u16).rev().collect(),
+                slots: (0..config.buffer_count).map(|_| Slot::Idle).collect(),
+                buffer_waiters: Vec::new(),
+                shutdown: false,
+            }),
+            buffers,
+            eventfd,
+        });
+        let reaper = std::thread::Builder::new()
+            .name("om-io-uring".to_string())
+            .spawn({
+                let shared = shared.clone();
+                move || reap_completions(shared)
+            })
+            .map_err(map_read_error)?;
+
+        Ok(Self {
+            file,
+            file_size,
+            config,
+            shared,
+            reaper: Some(reaper),
+        })
+    }
+
+    pub fn config(&self) -> &IoUringConfig {
+        &self.config
+    }
+
+    /// The underlying file handle
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    fn check_bounds(&self, offset: u64, count: u64) -> Result<(), OmFilesRsError> {
+        checked_range(offset, count, self.file_size).map(|_| ())
+    }
+
+    /// Read `count` bytes at `offset` directly into a registered buffer and pass
+    /// it to `f` without copying. `count` must not exceed `buffer_size`.
+    pub fn read_with<R>(
+        &self,
+        offset: u64,
+        count: u64,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, OmFilesRsError> {
+        self.check_bounds(offset, count)?;
+        if count as usize > self.config.buffer_size {
+            return Err(OmFilesRsError::NotImplementedError(format!(
+                "Zero-copy reads are limited to {} bytes",
+                self.config.buffer_size
+            )));
+        }
+        let buffer = block_on(self.read_fixed(offset, count as usize))?;
+        Ok(f(buffer.as_slice(count as usize)))
+    }
+
+    /// Read `count` bytes at `offset` into a free registered buffer. Short reads
+    /// are continued until the buffer is filled.
+    async fn read_fixed(&self, offset: u64, count: usize) -> Result<Buffer<'_>, OmFilesRsError> {
+        let mut buffer = AcquireBuffer {
+            shared: &self.shared,
+        }
+        .await;
+        let mut read = 0;
+        while read < count {
+            let (returned, result) =
+                FixedRead::submit(buffer, read, offset + read as u64, count - read)?.await;
+            buffer = returned;
+            if result < 0 {
+                return Err(map_read_error(std::io::Error::from_raw_os_error(-result)));
+            }
+            if result == 0 {
+                return Err(OmFilesRsError::FileReaderError {
+                    errno: 0,
+                    error: "Unexpected end of file".to_string(),
+                });
+            }
+            read += result as usize;
+        }
+        Ok(buffer)
+    }
+
+    /// Read `count` bytes at `offset` using all registered buffers concurrently.
+    async fn read_async(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        self.check_bounds(offset, count)?;
+        let buffer_size = self.config.buffer_size;
+        let mut data = vec![0u8; count as usize];
+        try_join_all(
+            data.chunks_mut(buffer_size)
+                .enumerate()
+                .map(|(i, destination)| async move {
+                    let file_offset = offset + (i * buffer_size) as u64;
+                    let buffer = self.read_fixed(file_offset, destination.len()).await?;
+                    destination.copy_from_slice(buffer.as_slice(destination.len()));
+                    Ok::<(), OmFilesRsError>(())
+                }),
+        )
+        .await?;
+        Ok(data)
+    }
+}
+
+impl Drop for IoUringBackend {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        let _ = (&self.shared.eventfd).write(&1u64.to_ne_bytes());
+        if let Some(reaper) = self.reaper.take() {
+            let _ = reaper.join();
+        }
+        // Reads of dropped futures may still be in flight and write into the buffers
+        let mut state = self.shared.state.lock().unwrap();
+        let mut failed = false;
+        while state.in_flight() {
+            match state.ring.submit_and_wait(1) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => {
+                    failed = true;
+                    break;
+                }
+            }
+            state.reap();
+        }
+        drop(state);
+        if failed {
+            // Leak the buffers instead of freeing memory the kernel may still write to
+            if let Some(shared) = Arc::get_mut(&mut self.shared) {
+                std::mem::forget(std::mem::take(&mut shared.buffers));
+            }
+        }
+    }
+}
+
+impl OmFileReaderBackend for IoUringBackend {
+    fn count(&self) -> usize {
+        self.file_size
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        false
+    }
+
+    fn prefetch_data(&self, _offset: usize, _count: usize) {
+        // No-op, reads are explicit
+    }
+
+    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+        // No-op, reads are explicit
+        Ok(())
+    }
+
+    /// A merged request is split over the registered buffers and read concurrently.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        IoSizes {
+            io_size_max: self.config.buffer_size as u64,
+            io_size_merge: 4096,
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        block_on(self.read_async(offset, count))
+    }
+}
+
+impl OmFileReaderBackendAsync for IoUringBackend {
+    fn count_async(&self) -> usize {
+        self.file_size
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        self.preferred_io_sizes()
+    }
+
+    /// Dropping the future before it completes cancels the reads in flight.
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        self.read_async(offset, count)
+    }
+}
+
+/// File backend that uses io_uring if the kernel supports it and falls back to
+/// `pread` otherwise, e.g. on old kernels or if io_uring is disabled by seccomp.
+/// With the `tokio` feature it is also an asynchronous backend, the `pread`
+/// fallback then runs on the blocking thread pool like `TokioFileBackend`.
+pub enum AutoFileBackend {
+    IoUring(Box<IoUringBackend>),
+    Pread(Arc<PreadFile>),
+}
+
+impl AutoFileBackend {
+    pub fn new(file: File) -> Result<Self, OmFilesRsError> {
+        Self::with_config(file, IoUringConfig::default())
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, OmFilesRsError> {
+        Self::new(PreadFile::from_path(path)?.into_file())
+    }
+
+    pub fn with_config(file: File, config: IoUringConfig) -> Result<Self, OmFilesRsError> {
+        let io_uring_file = file.try_clone().map_err(map_read_error)?;
+        match IoUringBackend::with_config(io_uring_file, config) {
+            Ok(backend) => Ok(Self::IoUring(Box::new(backend))),
+            Err(_) => Ok(Self::Pread(Arc::new(PreadFile::new(file)?))),
+        }
+    }
+
+    pub fn is_io_uring(&self) -> bool {
+        matches!(self, Self::IoUring(_))
+    }
+}
+
+impl OmFileReaderBackend for AutoFileBackend {
+    fn count(&self) -> usize {
+        match self {
+            Self::IoUring(backend) => backend.count(),
+            Self::Pread(backend) => backend.count(),
+        }
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        false
+    }
+
+    fn prefetch_data(&self, _offset: usize, _count: usize) {
+        // No-op, reads are explicit
+    }
+
+    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+        // No-op, reads are explicit
+        Ok(())
+    }
+
+    fn preferred_io_sizes(&self) -> IoSizes {
+        match self {
+            Self::IoUring(backend) => backend.preferred_io_sizes(),
+            Self::Pread(backend) => backend.preferred_io_sizes(),
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        match self {
+            Self::IoUring(backend) => backend.get_bytes_owned(offset, count),
+            Self::Pread(backend) => backend.get_bytes_owned(offset, count),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl OmFileReaderBackendAsync for AutoFileBackend {
+    fn count_async(&self) -> usize {
+        self.count()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        self.preferred_io_sizes()
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        match self {
+            Self::IoUring(backend) => Either::Left(backend.read_async(offset, count)),
+            Self::Pread(file) => Either::Right(read_blocking(file.clone(), offset, count)),
+        }
+    }
+}
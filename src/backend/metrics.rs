@@ -0,0 +1,273 @@
+//! Metrics hooks for backend requests and reader caches, e.g. to export IO
+//! behaviour per file from a service that embeds the reader.
+//!
+//! ```ignore
+//! let registry = prometheus::Registry::new();
+//! let metrics = PrometheusMetrics::register(&registry)?;
+//! let backend = MetricsBackend::new(MmapFile::new(file, Mode::ReadOnly)?, metrics.for_file("t2m.om"));
+//! let mut reader = OmFileReader::new(Arc::new(backend))?;
+//! reader.set_metrics(Arc::new(metrics.for_file("t2m.om")));
+//! ```
+
+use crate::backend::backends::{IoSizes, OmFileReaderBackend, OmFileReaderBackendAsync};
+use crate::errors::OmFilesRsError;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Caches of `OmFileReader` that report lookups to `Metrics`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    /// Decoded LUT entries, see `OmFileReader::enable_lut_cache`
+    Index,
+    /// Decoded chunks, see `OmFileReader::enable_chunk_cache`
+    Chunk,
+}
+
+/// Receives IO events. All methods default to doing nothing, so
+/// implementations only override what they collect. Methods are called
+/// from the reading threads and must be cheap.
+pub trait Metrics: Send + Sync {
+    /// A backend request for `bytes` bytes succeeded after `latency`
+    fn record_request(&self, _bytes: u64, _latency: Duration) {}
+
+    /// A backend request failed
+    fn record_error(&self) {}
+
+    /// A reader looked up a block in one of its caches
+    fn record_cache_lookup(&self, _cache: CacheKind, _hit: bool) {}
+}
+
+/// Metrics that are discarded
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Backend that reports every request of another backend to `Metrics`.
+/// Works for synchronous and asynchronous backends.
+pub struct MetricsBackend<Backend, M = NoopMetrics> {
+    backend: Backend,
+    metrics: M,
+}
+
+impl<Backend, M: Metrics> MetricsBackend<Backend, M> {
+    pub fn new(backend: Backend, metrics: M) -> Self {
+        Self { backend, metrics }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+
+    pub fn metrics(&self) -> &M {
+        &self.metrics
+    }
+
+    fn record<T: AsRef<[u8]>>(
+        &self,
+        start: Instant,
+        result: Result<T, OmFilesRsError>,
+    ) -> Result<T, OmFilesRsError> {
+        match &result {
+            Ok(data) => self
+                .metrics
+                .record_request(data.as_ref().len() as u64, start.elapsed()),
+            // Not a request, `get_bytes_with_fallback` tries the other method
+            Err(OmFilesRsError::NotImplementedError(_)) => {}
+            Err(_) => self.metrics.record_error(),
+        }
+        result
+    }
+}
+
+impl<Backend: OmFileReaderBackend, M: Metrics> OmFileReaderBackend for MetricsBackend<Backend, M> {
+    fn count(&self) -> usize {
+        self.backend.count()
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        self.backend.needs_prefetch()
+    }
+
+    fn prefetch_data(&self, offset: usize, count: usize) {
+        self.backend.prefetch_data(offset, count)
+    }
+
+    fn pre_read(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        self.backend.pre_read(offset, count)
+    }
+
+    fn preferred_io_sizes(&self) -> IoSizes {
+        self.backend.preferred_io_sizes()
+    }
+
+    fn get_bytes(&self, offset: u64, count: u64) -> Result<&[u8], OmFilesRsError> {
+        let start = Instant::now();
+        self.record(start, self.backend.get_bytes(offset, count))
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        let start = Instant::now();
+        self.record(start, self.backend.get_bytes_owned(offset, count))
+    }
+}
+
+impl<Backend: OmFileReaderBackendAsync, M: Metrics> MetricsBackend<Backend, M> {
+    async fn get_bytes_recorded(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        let start = Instant::now();
+        let result = self.backend.get_bytes_async(offset, count).await;
+        self.record(start, result)
+    }
+}
+
+impl<Backend, M> OmFileReaderBackendAsync for MetricsBackend<Backend, M>
+where
+    Backend: OmFileReaderBackendAsync + Sync,
+    M: Metrics,
+{
+    fn count_async(&self) -> usize {
+        self.backend.count_async()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        self.backend.preferred_io_sizes_async()
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        self.get_bytes_recorded(offset, count)
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub use prometheus_metrics::{PrometheusFileMetrics, PrometheusMetrics};
+
+#[cfg(feature = "metrics")]
+mod prometheus_metrics {
+    use super::{CacheKind, Metrics};
+    use crate::errors::OmFilesRsError;
+    use prometheus::{
+        Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry,
+    };
+    use std::time::Duration;
+
+    /// Prometheus counters of all files, labelled with the file name:
+    /// `omfiles_bytes_read_total`, `omfiles_requests_total`,
+    /// `omfiles_request_errors_total`, `omfiles_request_duration_seconds` and
+    /// `omfiles_cache_lookups_total` with the labels `cache` and `result`.
+    #[derive(Clone)]
+    pub struct PrometheusMetrics {
+        bytes_read: IntCounterVec,
+        requests: IntCounterVec,
+        errors: IntCounterVec,
+        latency: HistogramVec,
+        cache_lookups: IntCounterVec,
+    }
+
+    impl PrometheusMetrics {
+        /// Create the counters and register them in `registry`. Fails if
+        /// they are registered already.
+        pub fn register(registry: &Registry) -> Result<Self, OmFilesRsError> {
+            Self::register_counters(registry)
+                .map_err(|error| OmFilesRsError::MetricsError(error.to_string()))
+        }
+
+        fn register_counters(registry: &Registry) -> prometheus::Result<Self> {
+            let counter = |name: &str, help: &str, labels: &[&str]| {
+                IntCounterVec::new(Opts::new(name, help), labels)
+            };
+            let metrics = Self {
+                bytes_read: counter(
+                    "omfiles_bytes_read_total",
+                    "Bytes read from the backend",
+                    &["file"],
+                )?,
+                requests: counter(
+                    "omfiles_requests_total",
+                    "Successful backend requests",
+                    &["file"],
+                )?,
+                errors: counter(
+                    "omfiles_request_errors_total",
+                    "Failed backend requests",
+                    &["file"],
+                )?,
+                latency: HistogramVec::new(
+                    HistogramOpts::new(
+                        "omfiles_request_duration_seconds",
+                        "Duration of successful backend requests",
+                    ),
+                    &["file"],
+                )?,
+                cache_lookups: counter(
+                    "omfiles_cache_lookups_total",
+                    "Lookups in the LUT and chunk caches of readers",
+                    &["file", "cache", "result"],
+                )?,
+            };
+            registry.register(Box::new(metrics.bytes_read.clone()))?;
+            registry.register(Box::new(metrics.requests.clone()))?;
+            registry.register(Box::new(metrics.errors.clone()))?;
+            registry.register(Box::new(metrics.latency.clone()))?;
+            registry.register(Box::new(metrics.cache_lookups.clone()))?;
+            Ok(metrics)
+        }
+
+        /// Metrics of one file, to pass to `MetricsBackend` and
+        /// `OmFileReader::set_metrics`
+        pub fn for_file(&self, file: &str) -> PrometheusFileMetrics {
+            let lookups = |cache: &str, result: &str| {
+                self.cache_lookups.with_label_values(&[file, cache, result])
+            };
+            PrometheusFileMetrics {
+                bytes_read: self.bytes_read.with_label_values(&[file]),
+                requests: self.requests.with_label_values(&[file]),
+                errors: self.errors.with_label_values(&[file]),
+                latency: self.latency.with_label_values(&[file]),
+                index_hits: lookups("index", "hit"),
+                index_misses: lookups("index", "miss"),
+                chunk_hits: lookups("chunk", "hit"),
+                chunk_misses: lookups("chunk", "miss"),
+            }
+        }
+    }
+
+    /// Counters of one file of `PrometheusMetrics`
+    #[derive(Clone)]
+    pub struct PrometheusFileMetrics {
+        bytes_read: IntCounter,
+        requests: IntCounter,
+        errors: IntCounter,
+        latency: Histogram,
+        index_hits: IntCounter,
+        index_misses: IntCounter,
+        chunk_hits: IntCounter,
+        chunk_misses: IntCounter,
+    }
+
+    impl Metrics for PrometheusFileMetrics {
+        fn record_request(&self, bytes: u64, latency: Duration) {
+            self.bytes_read.inc_by(bytes);
+            self.requests.inc();
+            self.latency.observe(latency.as_secs_f64());
+        }
+
+        fn record_error(&self) {
+            self.errors.inc();
+        }
+
+        fn record_cache_lookup(&self, cache: CacheKind, hit: bool) {
+            let counter = match (cache, hit) {
+                (CacheKind::Index, true) => &self.index_hits,
+                (CacheKind::Index, false) => &self.index_misses,
+                (CacheKind::Chunk, true) => &self.chunk_hits,
+                (CacheKind::Chunk, false) => &self.chunk_misses,
+            };
+            counter.inc();
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::OmFilesRsError;
 #[cfg(unix)]
 use memmap2::{Advice, UncheckedAdvice};
 use memmap2::{Mmap, MmapMut, MmapOptions};
@@ -7,6 +8,8 @@ use std::fs::File;
 pub struct MmapFile {
     pub data: MmapType,
     pub file: File,
+    /// Advice given for ranges the reader is about to access
+    prefetch_advice: Option<MAdvice>,
 }
 
 /// Specifies how the memory-mapped file should be accessed and whether it is mutable
@@ -59,9 +62,19 @@ impl Mode {
     }
 }
 
+/// Memory advice passed to `madvise`. Ignored on non-Unix systems.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MAdvice {
+    /// Read the pages ahead of access
     WillNeed,
+    /// Release the pages from the mapping, they are read again on the next access
     DontNeed,
+    /// Default read-ahead behaviour
+    Normal,
+    /// Pages are accessed in order, read ahead aggressively and free pages after access
+    Sequential,
+    /// Pages are accessed in random order, disable read-ahead
+    Random,
 }
 
 impl MAdvice {
@@ -72,6 +85,9 @@ impl MAdvice {
             MAdvice::DontNeed => {
                 mmap.unchecked_advise_range(UncheckedAdvice::DontNeed, offset, len)
             }
+            MAdvice::Normal => mmap.advise_range(Advice::Normal, offset, len),
+            MAdvice::Sequential => mmap.advise_range(Advice::Sequential, offset, len),
+            MAdvice::Random => mmap.advise_range(Advice::Random, offset, len),
         }
     }
 
@@ -89,7 +105,50 @@ impl MmapFile {
         } else {
             MmapType::ReadWrite(unsafe { MmapOptions::new().map_mut(&file)? })
         };
-        Ok(MmapFile { data, file })
+        Ok(MmapFile {
+            data,
+            file,
+            prefetch_advice: Some(MAdvice::WillNeed),
+        })
+    }
+
+    /// Advice applied to ranges passed to `prefetch_data`. Defaults to
+    /// `WillNeed`. `None` disables prefetching, e.g. for random point reads
+    /// where read-ahead only pollutes the page cache.
+    pub fn set_prefetch_advice(&mut self, advice: Option<MAdvice>) {
+        self.prefetch_advice = advice;
+    }
+
+    pub fn prefetch_advice(&self) -> Option<MAdvice> {
+        self.prefetch_advice
+    }
+
+    /// Apply an access pattern to the whole mapping, e.g. `Sequential` for
+    /// conversions that scan the file once or `Random` for point reads.
+    pub fn advise(&self, advice: MAdvice) -> Result<(), OmFilesRsError> {
+        advice
+            .advice(&self.data, 0, self.data.len())
+            .map_err(|e| OmFilesRsError::FileReaderError {
+                errno: e.raw_os_error().unwrap_or(0),
+                error: e.to_string(),
+            })
+    }
+
+    /// Release pages of a region that has been processed. During long
+    /// sequential scans this keeps the resident memory of the process flat,
+    /// as released pages can be reclaimed by the kernel first. Data remains
+    /// readable and is loaded again on the next access.
+    pub fn release(&self, offset: usize, count: usize) -> Result<(), OmFilesRsError> {
+        let count = count.min(self.data.len().saturating_sub(offset));
+        if count == 0 {
+            return Ok(());
+        }
+        MAdvice::DontNeed
+            .advice(&self.data, offset, count)
+            .map_err(|e| OmFilesRsError::FileReaderError {
+                errno: e.raw_os_error().unwrap_or(0),
+                error: e.to_string(),
+            })
     }
 
     /// Check if the file was deleted on the file system. Linux keeps the file alive as long as some processes have it open.
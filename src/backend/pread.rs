@@ -0,0 +1,104 @@
+use crate::backend::backends::{checked_range, IoSizes, OmFileReaderBackend};
+use crate::errors::OmFilesRsError;
+use std::fs::File;
+
+/// Reader backend that issues one positional read (`pread`) per request.
+/// Works on every platform and does not depend on mmap or io_uring support.
+pub struct PreadFile {
+    file: File,
+    file_size: usize,
+}
+
+impl PreadFile {
+    pub fn new(file: File) -> Result<Self, OmFilesRsError> {
+        let file_size = file.metadata().map_err(map_read_error)?.len() as usize;
+        Ok(Self { file, file_size })
+    }
+
+    pub fn from_path(path: &str) -> Result<Self, OmFilesRsError> {
+        let file = File::open(path).map_err(|e| OmFilesRsError::CannotOpenFile {
+            filename: path.to_string(),
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+        Self::new(file)
+    }
+
+    /// The underlying file handle
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    pub fn into_file(self) -> File {
+        self.file
+    }
+
+    #[cfg(unix)]
+    fn read_exact_at(&self, destination: &mut [u8], offset: u64) -> std::io::Result<()> {
+        use std::os::unix::fs::FileExt;
+        self.file.read_exact_at(destination, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_exact_at(&self, mut destination: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+        use std::os::windows::fs::FileExt;
+        while !destination.is_empty() {
+            match self.file.seek_read(destination, offset)? {
+                0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                n => {
+                    destination = &mut destination[n..];
+                    offset += n as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_exact_at(&self, _destination: &mut [u8], _offset: u64) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+pub(crate) fn map_read_error(e: std::io::Error) -> OmFilesRsError {
+    OmFilesRsError::FileReaderError {
+        errno: e.raw_os_error().unwrap_or(0),
+        error: e.to_string(),
+    }
+}
+
+impl OmFileReaderBackend for PreadFile {
+    fn count(&self) -> usize {
+        self.file_size
+    }
+
+    fn needs_prefetch(&self) -> bool {
+        false
+    }
+
+    fn prefetch_data(&self, _offset: usize, _count: usize) {
+        // No-op, reads are explicit
+    }
+
+    fn pre_read(&self, _offset: usize, _count: usize) -> Result<(), OmFilesRsError> {
+        // No-op, reads are explicit
+        Ok(())
+    }
+
+    /// Every request is a system call, so small gaps are read instead of
+    /// issuing another request.
+    fn preferred_io_sizes(&self) -> IoSizes {
+        IoSizes {
+            io_size_max: 256 * 1024,
+            io_size_merge: 4096,
+        }
+    }
+
+    fn get_bytes_owned(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        checked_range(offset, count, self.file_size)?;
+        let mut data = vec![0u8; count as usize];
+        self.read_exact_at(&mut data, offset)
+            .map_err(map_read_error)?;
+        Ok(data)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::backend::backends::{IoSizes, OmFileReaderBackendAsync};
+use crate::errors::OmFilesRsError;
+use futures::future::{select, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// When and how often `RetryBackend` repeats a failed request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts per request, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry. It is doubled for every further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts
+    pub max_backoff: Duration,
+    /// Time limit for a single attempt. `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after the failed attempt `attempt`, starting at 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << (attempt.saturating_sub(1)).min(31);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Errors that are worth retrying by default: IO errors reported by the
+/// backend and timeouts. Invalid ranges or decryption failures are permanent.
+pub fn is_transient_error(error: &OmFilesRsError) -> bool {
+    matches!(
+        error.root_cause(),
+        OmFilesRsError::FileReaderError { .. } | OmFilesRsError::RequestTimeout { .. }
+    )
+}
+
+type RetryCondition = Box<dyn Fn(&OmFilesRsError) -> bool + Send + Sync>;
+
+/// Asynchronous backend that retries failed requests of another backend with
+/// exponential backoff and limits the duration of every attempt, so that a
+/// transient network error does not fail a read of many chunks.
+///
+/// Timers depend on the async runtime, so the caller provides `sleep`, e.g.
+/// `tokio::time::sleep`. Nothing is spawned, backoff and timeouts are driven
+/// by the request future.
+pub struct RetryBackend<Backend, Sleep> {
+    backend: Backend,
+    policy: RetryPolicy,
+    sleep: Sleep,
+    retry_condition: RetryCondition,
+    retries: AtomicU64,
+}
+
+impl<Backend, Sleep, SleepFuture> RetryBackend<Backend, Sleep>
+where
+    Backend: OmFileReaderBackendAsync,
+    Sleep: Fn(Duration) -> SleepFuture,
+    SleepFuture: Future<Output = ()>,
+{
+    /// Fails if `policy` allows no attempt at all.
+    pub fn new(
+        backend: Backend,
+        policy: RetryPolicy,
+        sleep: Sleep,
+    ) -> Result<Self, OmFilesRsError> {
+        if policy.max_attempts == 0 {
+            return Err(OmFilesRsError::InvalidConfiguration(
+                "max_attempts must be larger than 0".to_string(),
+            ));
+        }
+        Ok(Self {
+            backend,
+            policy,
+            sleep,
+            retry_condition: Box::new(is_transient_error),
+            retries: AtomicU64::new(0),
+        })
+    }
+
+    /// Decide which errors are retried. Defaults to `is_transient_error`.
+    pub fn with_retry_condition(
+        mut self,
+        retry_condition: impl Fn(&OmFilesRsError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_condition = Box::new(retry_condition);
+        self
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &Backend {
+        &self.backend
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Number of requests that have been repeated since creation
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    async fn attempt(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        let request = self.backend.get_bytes_async(offset, count);
+        let Some(timeout) = self.policy.timeout else {
+            return request.await;
+        };
+        match select(pin!(request), pin!((self.sleep)(timeout))).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(OmFilesRsError::RequestTimeout {
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+        }
+    }
+
+    async fn get_bytes_with_retry(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> Result<Vec<u8>, OmFilesRsError> {
+        let mut attempt = 1;
+        loop {
+            match self.attempt(offset, count).await {
+                Err(error)
+                    if attempt < self.policy.max_attempts && (self.retry_condition)(&error) =>
+                {
+                    (self.sleep)(self.policy.backoff(attempt)).await;
+                    self.retries.fetch_add(1, Ordering::Relaxed);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<Backend, Sleep, SleepFuture> OmFileReaderBackendAsync for RetryBackend<Backend, Sleep>
+where
+    Backend: OmFileReaderBackendAsync + Sync,
+    Sleep: Fn(Duration) -> SleepFuture + Sync,
+    SleepFuture: Future<Output = ()> + Send,
+{
+    fn count_async(&self) -> usize {
+        self.backend.count_async()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        self.backend.preferred_io_sizes_async()
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        self.get_bytes_with_retry(offset, count)
+    }
+}
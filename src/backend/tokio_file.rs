@@ -0,0 +1,65 @@
+use crate::backend::backends::{IoSizes, OmFileReaderBackend, OmFileReaderBackendAsync};
+use crate::backend::pread::PreadFile;
+use crate::errors::OmFilesRsError;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Asynchronous file backend for tokio. Positional reads are executed on the
+/// blocking thread pool of the runtime with `spawn_blocking`, so concurrent
+/// requests of `OmFileReaderAsync` do not block the async worker threads.
+pub struct TokioFileBackend {
+    file: Arc<PreadFile>,
+}
+
+impl TokioFileBackend {
+    pub async fn open(path: &str) -> Result<Self, OmFilesRsError> {
+        let file =
+            tokio::fs::File::open(path)
+                .await
+                .map_err(|e| OmFilesRsError::CannotOpenFile {
+                    filename: path.to_string(),
+                    errno: e.raw_os_error().unwrap_or(0),
+                    error: e.to_string(),
+                })?;
+        Self::from_file(file).await
+    }
+
+    pub async fn from_file(file: tokio::fs::File) -> Result<Self, OmFilesRsError> {
+        let file = PreadFile::new(file.into_std().await)?;
+        Ok(Self {
+            file: Arc::new(file),
+        })
+    }
+}
+
+impl OmFileReaderBackendAsync for TokioFileBackend {
+    fn count_async(&self) -> usize {
+        self.file.count()
+    }
+
+    fn preferred_io_sizes_async(&self) -> IoSizes {
+        self.file.preferred_io_sizes()
+    }
+
+    fn get_bytes_async(
+        &self,
+        offset: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<u8>, OmFilesRsError>> + Send {
+        read_blocking(self.file.clone(), offset, count)
+    }
+}
+
+/// Read with `pread` on the blocking thread pool of the tokio runtime
+pub(crate) async fn read_blocking(
+    file: Arc<PreadFile>,
+    offset: u64,
+    count: u64,
+) -> Result<Vec<u8>, OmFilesRsError> {
+    tokio::task::spawn_blocking(move || file.get_bytes_owned(offset, count))
+        .await
+        .map_err(|e| OmFilesRsError::FileReaderError {
+            errno: 0,
+            error: e.to_string(),
+        })?
+}
@@ -0,0 +1,294 @@
+use ndarray::ArrayD;
+use omfiles_rs::backend::backends::OmFileReaderBackend;
+use omfiles_rs::backend::mmapfile::MmapFile;
+use omfiles_rs::core::data_types::{DataType, OmFileArrayDataType};
+use omfiles_rs::io::describe::VariableDescription;
+use omfiles_rs::io::reader::OmFileReader;
+use omfiles_rs::io::writer::OmFileWriter;
+use std::fmt::Debug;
+use std::fs::File;
+use std::{env, io, ops::Range};
+
+const USAGE: &str = "Usage:
+  omfiles info <file> [--json]
+  omfiles dump <file> [<variable>] [--range <start:end>,<start:end>,...]
+  omfiles rechunk <input> <output> --chunks <c0>,<c1>,...
+  omfiles plot <file> <output.ppm> [--variable <name>] [--axes <y>,<x>] [--index <i0>,<i1>,...]
+               [--range <min>:<max>] [--colormap viridis|grayscale] [--flip-y]";
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("{}", USAGE);
+        return Ok(());
+    }
+
+    match args[1].as_str() {
+        "info" => info(&args[2], args[3..].iter().any(|arg| arg == "--json")),
+        "dump" => {
+            let (positional, range) = split_option(&args[3..], "--range")?;
+            let ranges = range.map(|r| parse_ranges(&r)).transpose()?;
+            dump(&args[2], positional.first().map(|s| s.as_str()), ranges)
+        }
+        "rechunk" => {
+            let (positional, chunks) = split_option(&args[3..], "--chunks")?;
+            let output = positional
+                .first()
+                .ok_or_else(|| invalid_input("Missing output file"))?;
+            let chunks = chunks.ok_or_else(|| invalid_input("Missing --chunks"))?;
+            rechunk(&args[2], output, &parse_list(&chunks)?)
+        }
+        "plot" => plot(&args[2], &args[3..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+fn info(file: &str, json: bool) -> io::Result<()> {
+    let reader = OmFileReader::from_file(file).map_err(other_error)?;
+    let description = reader.describe();
+    if json {
+        return print_json(&description);
+    }
+    print_variable(&description, 0);
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn print_json(description: &VariableDescription) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(description).map_err(other_error)?;
+    println!("{}", json);
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json(_description: &VariableDescription) -> io::Result<()> {
+    Err(invalid_input("--json requires the `serde` feature"))
+}
+
+fn print_variable(variable: &VariableDescription, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let name = variable.name.as_deref().unwrap_or("<unnamed>");
+    match &variable.array {
+        Some(array) => println!(
+            "{}{}: {:?} dimensions={:?} chunks={:?} compression={:?} scale_factor={} add_offset={}",
+            indent,
+            name,
+            variable.data_type,
+            array.dimensions,
+            array.chunks,
+            array.compression,
+            array.scale_factor,
+            array.add_offset
+        ),
+        None => {
+            let value = match (&variable.value, variable.data_type) {
+                (Some(value), DataType::String) => format!(" = {:?}", value),
+                (Some(value), _) => format!(" = {}", value),
+                (None, _) => String::new(),
+            };
+            println!("{}{}: {:?}{}", indent, name, variable.data_type, value)
+        }
+    }
+    for child in &variable.children {
+        print_variable(child, depth + 1);
+    }
+}
+
+fn dump(file: &str, variable: Option<&str>, ranges: Option<Vec<Range<u64>>>) -> io::Result<()> {
+    let reader = open_variable(file, variable)?;
+
+    let dims = reader.get_dimensions();
+    let ranges = ranges.unwrap_or_else(|| dims.iter().map(|&d| 0..d).collect());
+    if ranges.len() != dims.len() {
+        return Err(invalid_input(&format!(
+            "Number of ranges ({}) doesn't match number of dimensions ({})",
+            ranges.len(),
+            dims.len()
+        )));
+    }
+
+    match reader.data_type() {
+        DataType::Int8Array => print_array::<i8, _>(&reader, &ranges),
+        DataType::Uint8Array => print_array::<u8, _>(&reader, &ranges),
+        DataType::Int16Array => print_array::<i16, _>(&reader, &ranges),
+        DataType::Uint16Array => print_array::<u16, _>(&reader, &ranges),
+        DataType::Int32Array => print_array::<i32, _>(&reader, &ranges),
+        DataType::Uint32Array => print_array::<u32, _>(&reader, &ranges),
+        DataType::Int64Array => print_array::<i64, _>(&reader, &ranges),
+        DataType::Uint64Array => print_array::<u64, _>(&reader, &ranges),
+        DataType::FloatArray => print_array::<f32, _>(&reader, &ranges),
+        DataType::DoubleArray => print_array::<f64, _>(&reader, &ranges),
+        data_type => Err(invalid_input(&format!(
+            "Cannot dump variable of type {:?}",
+            data_type
+        ))),
+    }
+}
+
+fn print_array<T, Backend>(reader: &OmFileReader<Backend>, ranges: &[Range<u64>]) -> io::Result<()>
+where
+    T: OmFileArrayDataType + Clone + Default + Debug,
+    Backend: OmFileReaderBackend,
+{
+    let data: ArrayD<T> = reader.read::<T>(ranges, None, None).map_err(other_error)?;
+    println!("{:?}", data);
+    Ok(())
+}
+
+#[cfg(feature = "viz")]
+fn plot(file: &str, args: &[String]) -> io::Result<()> {
+    use omfiles_rs::viz::{Colormap, HeatmapSource};
+
+    let (args, variable) = split_option(args, "--variable")?;
+    let (args, axes) = split_option(&args, "--axes")?;
+    let (args, index) = split_option(&args, "--index")?;
+    let (args, range) = split_option(&args, "--range")?;
+    let (args, colormap) = split_option(&args, "--colormap")?;
+    let flip_y = args.iter().any(|arg| arg == "--flip-y");
+    let output = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| invalid_input("Missing output file"))?;
+
+    let reader = open_variable(file, variable.as_deref())?;
+    let n_dims = reader.get_dimensions().len();
+    let axes = match axes {
+        Some(axes) => parse_list(&axes)?,
+        None if n_dims >= 2 => vec![n_dims as u64 - 2, n_dims as u64 - 1],
+        None => return Err(invalid_input("Variable needs at least 2 dimensions")),
+    };
+    let [y_axis, x_axis] = axes[..] else {
+        return Err(invalid_input("--axes needs exactly 2 dimensions"));
+    };
+    let mut source =
+        HeatmapSource::new(&reader, y_axis as usize, x_axis as usize).map_err(other_error)?;
+    if let Some(index) = index {
+        let index = parse_list(&index)?;
+        if index.len() != n_dims {
+            return Err(invalid_input(
+                "Number of indices doesn't match number of dimensions",
+            ));
+        }
+        for (axis, &i) in index.iter().enumerate() {
+            source = source.with_index(axis, i);
+        }
+    }
+    if let Some(range) = range {
+        let (min, max) = range
+            .split_once(':')
+            .ok_or_else(|| invalid_input(&format!("Invalid range '{}'", range)))?;
+        let min = min.parse::<f64>().map_err(other_error)?;
+        let max = max.parse::<f64>().map_err(other_error)?;
+        source = source.with_value_range(min..max);
+    }
+    let colormap = match colormap.as_deref() {
+        None | Some("viridis") => Colormap::Viridis,
+        Some("grayscale") => Colormap::Grayscale,
+        Some(other) => return Err(invalid_input(&format!("Unknown colormap '{}'", other))),
+    };
+    let heatmap = source
+        .with_colormap(colormap)
+        .with_flip_y(flip_y)
+        .render()
+        .map_err(other_error)?;
+    heatmap
+        .write_ppm(&mut io::BufWriter::new(File::create(output)?))
+        .map_err(other_error)?;
+    println!(
+        "Wrote {}x{} pixels for values {}..{}",
+        heatmap.width, heatmap.height, heatmap.value_range.start, heatmap.value_range.end
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "viz"))]
+fn plot(_file: &str, _args: &[String]) -> io::Result<()> {
+    Err(invalid_input("plot requires the `viz` feature"))
+}
+
+fn rechunk(input: &str, output: &str, chunks: &[u64]) -> io::Result<()> {
+    let reader = OmFileReader::from_file(input).map_err(other_error)?;
+    if chunks.len() != reader.get_dimensions().len() {
+        return Err(invalid_input(
+            "Number of chunk dimensions doesn't match number of dimensions",
+        ));
+    }
+    if !reader.data_type().is_array() || reader.data_type() == DataType::StringArray {
+        return Err(invalid_input(&format!(
+            "Cannot rechunk variable of type {:?}",
+            reader.data_type()
+        )));
+    }
+
+    let file_handle = File::create(output)?;
+    let mut file_writer = OmFileWriter::new(&file_handle, 1024 * 1024);
+    let variable = reader
+        .rechunk_to(&mut file_writer, chunks)
+        .map_err(other_error)?;
+    file_writer.write_trailer(variable).map_err(other_error)?;
+    Ok(())
+}
+
+/// The root variable of `file`, or the variable at path `variable`
+fn open_variable(file: &str, variable: Option<&str>) -> io::Result<OmFileReader<MmapFile>> {
+    let root = OmFileReader::from_file(file).map_err(other_error)?;
+    match variable {
+        None => Ok(root),
+        Some(name) => {
+            let offset_size = root
+                .get_flat_variable_metadata()
+                .remove(name)
+                .ok_or_else(|| invalid_input(&format!("Variable '{}' not found", name)))?;
+            root.open_variable(&offset_size).map_err(other_error)
+        }
+    }
+}
+
+/// Separate positional arguments from a single `--option value` pair
+fn split_option(args: &[String], option: &str) -> io::Result<(Vec<String>, Option<String>)> {
+    let mut positional = Vec::new();
+    let mut value = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == option {
+            let v = iter
+                .next()
+                .ok_or_else(|| invalid_input(&format!("Missing value for {}", option)))?;
+            value = Some(v.clone());
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    Ok((positional, value))
+}
+
+fn parse_ranges(ranges: &str) -> io::Result<Vec<Range<u64>>> {
+    ranges
+        .split(',')
+        .map(|range| {
+            let (start, end) = range
+                .split_once(':')
+                .ok_or_else(|| invalid_input(&format!("Invalid range '{}'", range)))?;
+            let start = start.parse::<u64>().map_err(other_error)?;
+            let end = end.parse::<u64>().map_err(other_error)?;
+            Ok(start..end)
+        })
+        .collect()
+}
+
+fn parse_list(list: &str) -> io::Result<Vec<u64>> {
+    list.split(',')
+        .map(|s| s.parse::<u64>().map_err(other_error))
+        .collect()
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.to_string())
+}
+
+fn other_error<E: std::fmt::Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
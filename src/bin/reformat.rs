@@ -38,15 +38,10 @@ fn main() -> io::Result<()> {
     );
     println!("created writer");
 
-    // let rechunked_dimensions = vec![50, 121];
-    // let rechunked_dimensions = chunks.iter().map(|&x| x).collect::<Vec<_>>();
-    // let rechunked_dimensions = vec![chunks[0], chunks[1]];
-    let rechunked_dimensions = vec![dimensions[0], dimensions[1]];
-
     let mut writer = file_writer
         .prepare_array::<f32>(
             dimensions.to_vec(),
-            rechunked_dimensions.clone(),
+            chunks.to_vec(),
             CompressionType::PforDelta2dInt16,
             reader.scale_factor(),
             reader.add_offset(),
@@ -55,23 +50,12 @@ fn main() -> io::Result<()> {
 
     println!("prepared array");
 
-    // Read and write data in chunks
-    // Iterate over both chunk dimensions at once
-    for chunk_start in (0..dimensions[0]).step_by(rechunked_dimensions[0] as usize) {
-        let chunk_dim_0 = std::cmp::min(rechunked_dimensions[0], dimensions[0] - chunk_start);
-
-        let chunk_data = reader
-            .read::<f32>(
-                &[chunk_start..chunk_start + chunk_dim_0, 0..dimensions[1]],
-                None,
-                None,
-            )
-            .expect("Failed to read chunk data");
-
-        writer
-            .write_data(chunk_data.view(), None, None)
-            .expect("Failed to write chunk data");
-    }
+    // Read and write data in chunk-aligned tiles of at most 256 MB
+    reader
+        .read_tiled::<f32>(256 * 1024 * 1024, |tile, _offset| {
+            writer.write_data(tile.view(), None, None)
+        })
+        .expect("Failed to copy data");
 
     let variable_meta = writer.finalize();
     println!("Finalized Array");
@@ -0,0 +1,268 @@
+//! C API for the Rust reader, enabled with the `capi` feature. The header
+//! `include/omfiles_rs.h` is generated with cbindgen during the build.
+//!
+//! Functions returning `int32_t` return `OM_RS_OK` on success and
+//! `OM_RS_ERROR` otherwise. Functions returning a pointer return NULL on
+//! errors. `om_rs_last_error` describes the last error of the calling thread.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::backend::cached_backend::CachedBackend;
+use crate::backend::mmapfile::{MmapFile, Mode};
+use crate::backend::pread::PreadFile;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fs::File;
+use std::ops::Range;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+pub const OM_RS_OK: i32 = 0;
+pub const OM_RS_ERROR: i32 = -1;
+
+type DynBackend = Box<dyn OmFileReaderBackend + Send + Sync>;
+
+/// Opaque handle of an opened variable
+pub struct OmRsReader {
+    reader: OmFileReader<DynBackend>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Run `f` and store its error or panic message for `om_rs_last_error`
+fn catch<T>(f: impl FnOnce() -> Result<T, OmFilesRsError>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(error)) => {
+            set_last_error(error.to_string());
+            None
+        }
+        Err(_) => {
+            set_last_error("Panic in omfiles-rs".to_string());
+            None
+        }
+    }
+}
+
+fn status(f: impl FnOnce() -> Result<(), OmFilesRsError>) -> i32 {
+    catch(f).map_or(OM_RS_ERROR, |_| OM_RS_OK)
+}
+
+unsafe fn open_with(
+    path: *const c_char,
+    backend: impl FnOnce(File) -> Result<DynBackend, OmFilesRsError>,
+) -> *mut OmRsReader {
+    catch(|| {
+        if path.is_null() {
+            return Err(OmFilesRsError::CannotOpenFile {
+                filename: String::new(),
+                errno: 0,
+                error: "Path is NULL".to_string(),
+            });
+        }
+        let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+        let file = File::open(&path).map_err(|e| OmFilesRsError::CannotOpenFile {
+            filename: path.clone(),
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+        let reader = OmFileReader::new(Arc::new(backend(file)?))?;
+        Ok(OmRsReader { reader })
+    })
+    .map_or(ptr::null_mut(), |reader| Box::into_raw(Box::new(reader)))
+}
+
+/// Open the file at `path` with mmap. Returns NULL on error.
+///
+/// # Safety
+/// `path` must be a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_open(path: *const c_char) -> *mut OmRsReader {
+    open_with(path, |file| {
+        let mmap =
+            MmapFile::new(file, Mode::ReadOnly).map_err(|e| OmFilesRsError::FileReaderError {
+                errno: e.raw_os_error().unwrap_or(0),
+                error: e.to_string(),
+            })?;
+        Ok(Box::new(mmap))
+    })
+}
+
+/// Open the file at `path` with positional reads and keep up to
+/// `max_blocks` blocks of `block_size` bytes in memory. Returns NULL on error.
+///
+/// # Safety
+/// `path` must be a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_open_cached(
+    path: *const c_char,
+    block_size: u64,
+    max_blocks: u64,
+) -> *mut OmRsReader {
+    if block_size == 0 || max_blocks == 0 {
+        set_last_error("block_size and max_blocks must be larger than 0".to_string());
+        return ptr::null_mut();
+    }
+    open_with(path, move |file| {
+        let file = PreadFile::new(file)?;
+        Ok(Box::new(CachedBackend::new(
+            file,
+            block_size,
+            max_blocks as usize,
+        )))
+    })
+}
+
+/// Cache up to `max_bytes` of decoded index (LUT) entries, see `OmFileReader::enable_lut_cache`.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_enable_lut_cache(reader: *mut OmRsReader, max_bytes: u64) -> i32 {
+    let Some(reader) = reader.as_mut() else {
+        set_last_error("Reader is NULL".to_string());
+        return OM_RS_ERROR;
+    };
+    reader.reader.enable_lut_cache(max_bytes);
+    OM_RS_OK
+}
+
+/// Number of dimensions of the variable, 0 if `reader` is NULL.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_dimension_count(reader: *const OmRsReader) -> u64 {
+    reader
+        .as_ref()
+        .map_or(0, |reader| reader.reader.get_dimensions().len() as u64)
+}
+
+/// Copy the dimensions of the variable into `dimensions`, which has room for
+/// `capacity` values.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+/// `dimensions` must point to at least `capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_get_dimensions(
+    reader: *const OmRsReader,
+    dimensions: *mut u64,
+    capacity: u64,
+) -> i32 {
+    copy_out(reader, dimensions, capacity, |r| {
+        r.get_dimensions().to_vec()
+    })
+}
+
+/// Copy the chunk dimensions of the variable into `chunks`, which has room
+/// for `capacity` values.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+/// `chunks` must point to at least `capacity` values.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_get_chunk_dimensions(
+    reader: *const OmRsReader,
+    chunks: *mut u64,
+    capacity: u64,
+) -> i32 {
+    copy_out(reader, chunks, capacity, |r| {
+        r.get_chunk_dimensions().to_vec()
+    })
+}
+
+unsafe fn copy_out(
+    reader: *const OmRsReader,
+    destination: *mut u64,
+    capacity: u64,
+    values: impl FnOnce(&OmFileReader<DynBackend>) -> Vec<u64>,
+) -> i32 {
+    let Some(reader) = reader.as_ref() else {
+        set_last_error("Reader is NULL".to_string());
+        return OM_RS_ERROR;
+    };
+    let values = values(&reader.reader);
+    if destination.is_null() || (values.len() as u64) > capacity {
+        set_last_error(format!("Output needs room for {} values", values.len()));
+        return OM_RS_ERROR;
+    }
+    ptr::copy_nonoverlapping(values.as_ptr(), destination, values.len());
+    OM_RS_OK
+}
+
+/// Read `count[i]` elements starting at `offset[i]` in all `dimension_count`
+/// dimensions of a float variable into `out` in row-major order. `out` must
+/// have room for `out_len` values, which has to be the product of `count`.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+/// `offset` and `count` must point to `dimension_count` values and `out` to
+/// `out_len` values.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_read_f32(
+    reader: *const OmRsReader,
+    offset: *const u64,
+    count: *const u64,
+    dimension_count: u64,
+    out: *mut f32,
+    out_len: u64,
+) -> i32 {
+    let Some(reader) = reader.as_ref() else {
+        set_last_error("Reader is NULL".to_string());
+        return OM_RS_ERROR;
+    };
+    if offset.is_null() || count.is_null() || out.is_null() {
+        set_last_error("offset, count and out must not be NULL".to_string());
+        return OM_RS_ERROR;
+    }
+    let offset = std::slice::from_raw_parts(offset, dimension_count as usize);
+    let count = std::slice::from_raw_parts(count, dimension_count as usize);
+    let out = std::slice::from_raw_parts_mut(out, out_len as usize);
+    let Some(dim_read) = offset
+        .iter()
+        .zip(count.iter())
+        .map(|(&o, &c)| Some(o..o.checked_add(c)?))
+        .collect::<Option<Vec<Range<u64>>>>()
+    else {
+        set_last_error("offset + count overflows".to_string());
+        return OM_RS_ERROR;
+    };
+    status(|| {
+        let elements = count.iter().try_fold(1u64, |n, &c| n.checked_mul(c));
+        if elements != Some(out.len() as u64) {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+        let into_cube_offset = vec![0; dim_read.len()];
+        reader
+            .reader
+            .read_into_flat(out, &dim_read, &into_cube_offset, count, None, None)
+    })
+}
+
+/// Close a reader. NULL is ignored.
+///
+/// # Safety
+/// `reader` must be a handle returned by `om_rs_open` that was not closed.
+#[no_mangle]
+pub unsafe extern "C" fn om_rs_close(reader: *mut OmRsReader) {
+    if !reader.is_null() {
+        drop(Box::from_raw(reader));
+    }
+}
+
+/// Message of the last error on the calling thread. The string is valid
+/// until the next call into this library on the same thread.
+#[no_mangle]
+pub extern "C" fn om_rs_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
@@ -2,6 +2,7 @@ use crate::errors::OmFilesRsError;
 use om_file_format_sys::OmCompression_t;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum CompressionType {
     /// Lossy compression using 2D delta coding and scale-factor.
@@ -14,6 +15,8 @@ pub enum CompressionType {
     PforDelta2d = 2,
     /// Similar to `PforDelta2dInt16` but applies `log10(1+x)` before.
     PforDelta2dInt16Logarithmic = 3,
+    /// No compression, chunks store raw little-endian values.
+    /// Supports all data types. Useful for small arrays like coordinates.
     None = 4,
 }
 
@@ -21,6 +24,19 @@ impl CompressionType {
     pub fn to_c(&self) -> OmCompression_t {
         *self as OmCompression_t
     }
+
+    /// Whether compressed chunks are bit-identical on all platforms. The PFor
+    /// codecs use SIMD implementations that leave different values in unused
+    /// bits on x86 and ARM. The decoder ignores them, so decompressed values
+    /// are identical, but checksums of the files are not.
+    pub fn is_deterministic(&self) -> bool {
+        match self {
+            CompressionType::FpxXor2d | CompressionType::None => true,
+            CompressionType::PforDelta2dInt16
+            | CompressionType::PforDelta2d
+            | CompressionType::PforDelta2dInt16Logarithmic => false,
+        }
+    }
 }
 
 impl TryFrom<u8> for CompressionType {
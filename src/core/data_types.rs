@@ -1,6 +1,8 @@
+use ndarray::ArrayD;
 use om_file_format_sys::OmDataType_t;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum DataType {
     None = 0,
@@ -32,6 +34,36 @@ impl DataType {
     pub fn to_c(&self) -> OmDataType_t {
         *self as OmDataType_t
     }
+
+    /// Whether this is one of the array types
+    pub fn is_array(&self) -> bool {
+        *self as u8 >= DataType::Int8Array as u8
+    }
+
+    /// Size of one element in bytes, `None` for strings and `DataType::None`
+    pub fn element_size(&self) -> Option<usize> {
+        match self {
+            DataType::Int8 | DataType::Uint8 | DataType::Int8Array | DataType::Uint8Array => {
+                Some(1)
+            }
+            DataType::Int16 | DataType::Uint16 | DataType::Int16Array | DataType::Uint16Array => {
+                Some(2)
+            }
+            DataType::Int32
+            | DataType::Uint32
+            | DataType::Float
+            | DataType::Int32Array
+            | DataType::Uint32Array
+            | DataType::FloatArray => Some(4),
+            DataType::Int64
+            | DataType::Uint64
+            | DataType::Double
+            | DataType::Int64Array
+            | DataType::Uint64Array
+            | DataType::DoubleArray => Some(8),
+            DataType::None | DataType::String | DataType::StringArray => None,
+        }
+    }
 }
 
 impl TryFrom<u8> for DataType {
@@ -70,11 +102,31 @@ impl TryFrom<u8> for DataType {
 /// Trait for types that can be stored as arrays in OmFiles
 pub trait OmFileArrayDataType {
     const DATA_TYPE_ARRAY: DataType;
+
+    /// Convert the strings of a string array, which are decoded in Rust
+    /// instead of the C library. Returns `None` for numeric types.
+    fn from_string_array(_strings: ArrayD<String>) -> Option<ArrayD<Self>>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 /// Trait for types that can be stored as scalars in OmFiles
 pub trait OmFileScalarDataType: Default {
     const DATA_TYPE_SCALAR: DataType;
+
+    /// Bytes of variable-length values like strings, which are encoded in Rust
+    /// instead of the C library. Returns `None` for fixed-size numeric types.
+    fn variable_length_bytes(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Decode a variable-length value from its bytes.
+    fn from_variable_length_bytes(_bytes: &[u8]) -> Option<Self> {
+        None
+    }
 }
 
 // Implement both traits for all supported numeric types
@@ -147,3 +199,22 @@ impl OmFileArrayDataType for f64 {
 impl OmFileScalarDataType for f64 {
     const DATA_TYPE_SCALAR: DataType = DataType::Double;
 }
+
+impl OmFileArrayDataType for String {
+    const DATA_TYPE_ARRAY: DataType = DataType::StringArray;
+
+    fn from_string_array(strings: ArrayD<String>) -> Option<ArrayD<Self>> {
+        Some(strings)
+    }
+}
+impl OmFileScalarDataType for String {
+    const DATA_TYPE_SCALAR: DataType = DataType::String;
+
+    fn variable_length_bytes(&self) -> Option<&[u8]> {
+        Some(self.as_bytes())
+    }
+
+    fn from_variable_length_bytes(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
@@ -0,0 +1,320 @@
+use crate::core::compression::CompressionType;
+use crate::core::data_types::DataType;
+use crate::errors::OmFilesRsError;
+
+/// Size of a legacy header, which is also the metadata of legacy files
+const LEGACY_HEADER_SIZE: usize = 40;
+
+/// Size of the fixed part of a version 3 array variable
+const ARRAY_HEADER_SIZE: usize = 40;
+
+/// Size of the fixed part of a version 3 scalar variable
+const SCALAR_HEADER_SIZE: usize = 8;
+
+/// Read-only view on the metadata of a version 3 array variable.
+///
+/// The layout is
+/// `data_type: u8, compression: u8, name_length: u16, number_of_children: u32,
+/// lut_size: u64, lut_offset: u64, dimension_count: u64, scale_factor: f32,
+/// add_offset: f32`, followed by the children sizes, the children offsets,
+/// the dimensions, the chunk dimensions and finally the name.
+///
+/// This is used for data types the C library has no knowledge about,
+/// like string arrays.
+pub(crate) struct ArrayVariableView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ArrayVariableView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, OmFilesRsError> {
+        let view = Self { data };
+        if data.len() < ARRAY_HEADER_SIZE {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        let required = (view.number_of_children() as usize)
+            .saturating_mul(16)
+            .saturating_add(view.dimension_count().saturating_mul(16))
+            .saturating_add(ARRAY_HEADER_SIZE + view.name_length());
+        if data.len() < required {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        Ok(view)
+    }
+
+    fn u64_at(&self, position: usize) -> u64 {
+        u64::from_le_bytes(self.data[position..position + 8].try_into().unwrap())
+    }
+
+    fn name_length(&self) -> usize {
+        u16::from_le_bytes([self.data[2], self.data[3]]) as usize
+    }
+
+    pub fn number_of_children(&self) -> u32 {
+        u32::from_le_bytes(self.data[4..8].try_into().unwrap())
+    }
+
+    pub fn lut_size(&self) -> u64 {
+        self.u64_at(8)
+    }
+
+    pub fn lut_offset(&self) -> u64 {
+        self.u64_at(16)
+    }
+
+    pub fn dimension_count(&self) -> usize {
+        self.u64_at(24) as usize
+    }
+
+    fn dimensions_position(&self) -> usize {
+        ARRAY_HEADER_SIZE + 16 * self.number_of_children() as usize
+    }
+
+    fn name_position(&self) -> usize {
+        self.dimensions_position() + 16 * self.dimension_count()
+    }
+
+    /// Returns `(offset, size)` of a child variable
+    pub fn child(&self, index: u32) -> Option<(u64, u64)> {
+        let count = self.number_of_children() as usize;
+        let index = index as usize;
+        if index >= count {
+            return None;
+        }
+        let size = self.u64_at(ARRAY_HEADER_SIZE + 8 * index);
+        let offset = self.u64_at(ARRAY_HEADER_SIZE + 8 * (count + index));
+        Some((offset, size))
+    }
+
+    /// Interpret `count` values at `position` as a slice of u64. Like the
+    /// C library this relies on the metadata buffer being 8 byte aligned.
+    fn u64_slice(&self, position: usize, count: usize) -> &'a [u64] {
+        let ptr = self.data[position..position + 8 * count].as_ptr();
+        debug_assert_eq!(ptr as usize % std::mem::align_of::<u64>(), 0);
+        unsafe { std::slice::from_raw_parts(ptr as *const u64, count) }
+    }
+
+    pub fn dimensions(&self) -> &'a [u64] {
+        self.u64_slice(self.dimensions_position(), self.dimension_count())
+    }
+
+    /// Whether any chunk dimension is 0. Reads the values without requiring
+    /// an aligned buffer.
+    fn has_empty_chunk(&self) -> bool {
+        let start = self.dimensions_position() + 8 * self.dimension_count();
+        (0..self.dimension_count()).any(|i| self.u64_at(start + 8 * i) == 0)
+    }
+
+    pub fn chunks(&self) -> &'a [u64] {
+        let start = self.dimensions_position() + 8 * self.dimension_count();
+        self.u64_slice(start, self.dimension_count())
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        let start = self.name_position();
+        &self.data[start..start + self.name_length()]
+    }
+}
+
+/// Read-only view on the metadata of a version 3 string scalar.
+///
+/// The layout is
+/// `data_type: u8, compression: u8, name_length: u16, number_of_children: u32`,
+/// followed by the children sizes, the children offsets, the string length
+/// as u64, the UTF-8 bytes of the string and finally the name.
+/// Strings are not null terminated.
+pub(crate) struct StringScalarView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StringScalarView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, OmFilesRsError> {
+        let view = Self { data };
+        if data.len() < SCALAR_HEADER_SIZE {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        let value_position = view.value_length_position();
+        if value_position.saturating_add(8) > data.len() {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        let required =
+            (view.value_length() as usize).saturating_add(value_position + 8 + view.name_length());
+        if data.len() < required {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        Ok(view)
+    }
+
+    fn u64_at(&self, position: usize) -> u64 {
+        u64::from_le_bytes(self.data[position..position + 8].try_into().unwrap())
+    }
+
+    fn name_length(&self) -> usize {
+        u16::from_le_bytes([self.data[2], self.data[3]]) as usize
+    }
+
+    pub fn number_of_children(&self) -> u32 {
+        u32::from_le_bytes(self.data[4..8].try_into().unwrap())
+    }
+
+    fn value_length_position(&self) -> usize {
+        (self.number_of_children() as usize)
+            .saturating_mul(16)
+            .saturating_add(SCALAR_HEADER_SIZE)
+    }
+
+    fn value_length(&self) -> u64 {
+        self.u64_at(self.value_length_position())
+    }
+
+    /// Returns `(offset, size)` of a child variable
+    pub fn child(&self, index: u32) -> Option<(u64, u64)> {
+        let count = self.number_of_children() as usize;
+        let index = index as usize;
+        if index >= count {
+            return None;
+        }
+        let size = self.u64_at(SCALAR_HEADER_SIZE + 8 * index);
+        let offset = self.u64_at(SCALAR_HEADER_SIZE + 8 * (count + index));
+        Some((offset, size))
+    }
+
+    /// UTF-8 bytes of the string value
+    pub fn value(&self) -> &'a [u8] {
+        let start = self.value_length_position() + 8;
+        &self.data[start..start + self.value_length() as usize]
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        let start = self.value_length_position() + 8 + self.value_length() as usize;
+        &self.data[start..start + self.name_length()]
+    }
+}
+
+/// Metadata of data types that are not supported by the C library and are
+/// therefore parsed in Rust.
+pub(crate) enum RustVariableView<'a> {
+    StringArray(ArrayVariableView<'a>),
+    String(StringScalarView<'a>),
+}
+
+impl<'a> RustVariableView<'a> {
+    pub fn new(data_type: DataType, data: &'a [u8]) -> Option<Self> {
+        match data_type {
+            DataType::StringArray => ArrayVariableView::new(data).ok().map(Self::StringArray),
+            DataType::String => StringScalarView::new(data).ok().map(Self::String),
+            _ => None,
+        }
+    }
+
+    pub fn number_of_children(&self) -> u32 {
+        match self {
+            Self::StringArray(view) => view.number_of_children(),
+            Self::String(view) => view.number_of_children(),
+        }
+    }
+
+    pub fn child(&self, index: u32) -> Option<(u64, u64)> {
+        match self {
+            Self::StringArray(view) => view.child(index),
+            Self::String(view) => view.child(index),
+        }
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        match self {
+            Self::StringArray(view) => view.name(),
+            Self::String(view) => view.name(),
+        }
+    }
+}
+
+/// Check that variable metadata contains all fields that are accessed by the
+/// C library and the views above. The C library trusts the sizes stored in
+/// the metadata, so this must pass before `om_variable_init` is called.
+/// Returns the number of bytes used by the metadata, which may be less than
+/// the length of `data`.
+pub(crate) fn validate_variable_metadata(data: &[u8]) -> Result<usize, OmFilesRsError> {
+    if data.len() >= 3 && data[0] == b'O' && data[1] == b'M' && matches!(data[2], 1 | 2) {
+        // Legacy header: magic, version, compression, scale factor, 2 dimensions, 2 chunks
+        if data.len() < LEGACY_HEADER_SIZE {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        CompressionType::try_from(data[3])?;
+        let u64_at =
+            |position: usize| u64::from_le_bytes(data[position..position + 8].try_into().unwrap());
+        if u64_at(24) == 0 || u64_at(32) == 0 {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        return Ok(LEGACY_HEADER_SIZE);
+    }
+    if data.len() < SCALAR_HEADER_SIZE {
+        return Err(OmFilesRsError::NotAnOmFile);
+    }
+    let data_type = DataType::try_from(data[0]).map_err(|_| OmFilesRsError::NotAnOmFile)?;
+    if data_type == DataType::String {
+        let view = StringScalarView::new(data)?;
+        return Ok(string_scalar_size(
+            view.name_length(),
+            view.number_of_children() as usize,
+            view.value_length() as usize,
+        ));
+    }
+    if data_type.is_array() {
+        CompressionType::try_from(data[1])?;
+        let view = ArrayVariableView::new(data)?;
+        if view.has_empty_chunk() {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        // String arrays are decoded in Rust, which requires at least one dimension
+        if data_type == DataType::StringArray && view.dimensions().is_empty() {
+            return Err(OmFilesRsError::NotAnOmFile);
+        }
+        return Ok(view.name_position() + view.name_length());
+    }
+    // Numeric scalars and groups: children, value and name follow the header
+    let name_length = u16::from_le_bytes([data[2], data[3]]) as usize;
+    let number_of_children = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+    let required = number_of_children
+        .saturating_mul(16)
+        .saturating_add(data_type.element_size().unwrap_or(0))
+        .saturating_add(SCALAR_HEADER_SIZE + name_length);
+    if data.len() < required {
+        return Err(OmFilesRsError::NotAnOmFile);
+    }
+    Ok(required)
+}
+
+/// Size in bytes of a string scalar variable
+pub(crate) fn string_scalar_size(
+    name_length: usize,
+    number_of_children: usize,
+    value_length: usize,
+) -> usize {
+    SCALAR_HEADER_SIZE + 16 * number_of_children + 8 + value_length + name_length
+}
+
+/// Serialize a string scalar variable into `destination` using the layout
+/// described in `StringScalarView`.
+pub(crate) fn write_string_scalar(
+    destination: &mut [u8],
+    name: &str,
+    children_offsets: &[u64],
+    children_sizes: &[u64],
+    value: &[u8],
+) {
+    let count = children_offsets.len();
+    destination[0] = DataType::String as u8;
+    destination[1] = 0;
+    destination[2..4].copy_from_slice(&(name.len() as u16).to_le_bytes());
+    destination[4..8].copy_from_slice(&(count as u32).to_le_bytes());
+    let mut position = SCALAR_HEADER_SIZE;
+    for value in children_sizes.iter().chain(children_offsets.iter()) {
+        destination[position..position + 8].copy_from_slice(&value.to_le_bytes());
+        position += 8;
+    }
+    destination[position..position + 8].copy_from_slice(&(value.len() as u64).to_le_bytes());
+    position += 8;
+    destination[position..position + value.len()].copy_from_slice(value);
+    position += value.len();
+    destination[position..position + name.len()].copy_from_slice(name.as_bytes());
+}
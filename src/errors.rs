@@ -1,3 +1,27 @@
+use crate::core::data_types::DataType;
+
+/// Broad category of an error, e.g. to decide whether a request can be
+/// retried or to map errors to the error types of bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Reading from or writing to a backend failed
+    Io,
+    /// The file or a part of it is malformed or was written by another version
+    Format,
+    /// Decompressing chunks failed
+    Decoder,
+    /// Compressing chunks failed
+    Encoder,
+    /// Arguments are inconsistent with each other or with the file
+    InvalidArgument,
+    /// A variable or archive entry does not exist
+    NotFound,
+    /// The operation is not supported for this file or backend
+    Unsupported,
+    /// The operation was cancelled by the caller
+    Cancelled,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum OmFilesRsError {
     CannotOpenFile {
@@ -9,6 +33,10 @@ pub enum OmFilesRsError {
         errno: i32,
         error: String,
     },
+    FileReaderError {
+        errno: i32,
+        error: String,
+    },
     ChunkHasWrongNumberOfElements,
     OffsetAndCountExceedDimension {
         offset: u64,
@@ -31,6 +59,181 @@ pub enum OmFilesRsError {
     NotAnOmFile,
     NotImplementedError(String),
     ArrayNotContiguous,
+    VariableNotFound(String),
+    InvalidAxesPermutation,
+    IncompatibleFiles(String),
+    DecryptionFailed,
+    InvalidHistogramBins,
+    InvalidCheckpoint(String),
+    UnknownQuantizationFilter(String),
+    ChunkExceedsDimension {
+        index: usize,
+        chunk: u64,
+        dimension: u64,
+    },
+    TooManyDimensions {
+        count: usize,
+        max: usize,
+    },
+    ZeroDimension {
+        index: usize,
+    },
+    ArchiveEntryNotFound(String),
+    CompressedArchiveEntry(String),
+    Cancelled,
+    RequestTimeout {
+        timeout_ms: u64,
+    },
+    TooManySaturatedValues {
+        count: u64,
+        max: u64,
+    },
+    /// `count` values of a chunk decoded right after writing differ from the
+    /// written values by more than the quantization tolerance
+    ChunkVerificationFailed {
+        count: u64,
+        max_difference: f64,
+    },
+    EncoderError(String),
+    /// An offset and size read from the file point outside of the file
+    InvalidOffsetSize {
+        offset: u64,
+        size: u64,
+        file_size: u64,
+    },
+    /// Variable metadata in the file is larger than `max` bytes
+    MetadataTooLarge {
+        size: u64,
+        max: u64,
+    },
+    /// Watching files for changes failed
+    WatchError(String),
+    /// Metrics could not be registered, e.g. because they exist already
+    MetricsError(String),
+    /// A chunk of a parallel array was written more than once
+    ChunkWrittenTwice {
+        chunk: u64,
+    },
+    /// Chunks of a parallel array were not written before it was finalized
+    MissingChunks {
+        missing: u64,
+        total: u64,
+    },
+    /// A value read as `from` cannot be represented as `to`
+    ConversionOutOfRange {
+        from: DataType,
+        to: DataType,
+    },
+    /// A GRIB message is valid at a time that is not a step of the time axis
+    GribTimeNotOnAxis {
+        time: i64,
+    },
+    /// A GRIB message has a different grid than the first message
+    GribGridMismatch {
+        time: i64,
+    },
+    /// Several GRIB messages are valid at the same time
+    DuplicateGribMessage {
+        time: i64,
+    },
+    /// No GRIB messages were passed, so the grid is unknown
+    GribNoMessages,
+    /// Overviews must reduce the resolution by a factor of at least 2
+    InvalidOverviewFactor {
+        factor: u64,
+    },
+    /// The array has no regular latitude/longitude grid definition
+    NoLatLonGrid,
+    /// A service request could not be parsed
+    InvalidRequest(String),
+    /// A service request selects more elements than allowed
+    SelectionTooLarge {
+        elements: u64,
+        max: u64,
+    },
+    /// Options of a backend, reader or writer are out of range
+    InvalidConfiguration(String),
+    /// Adds context like the variable name or the chunk index to an error
+    Context {
+        context: String,
+        source: Box<OmFilesRsError>,
+    },
+}
+
+impl OmFilesRsError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            OmFilesRsError::CannotOpenFile { .. }
+            | OmFilesRsError::FileWriterError { .. }
+            | OmFilesRsError::FileReaderError { .. }
+            | OmFilesRsError::FileExistsAlready { .. }
+            | OmFilesRsError::RequestTimeout { .. }
+            | OmFilesRsError::WatchError(_) => ErrorKind::Io,
+            OmFilesRsError::NotAnOmFile
+            | OmFilesRsError::InvalidOffsetSize { .. }
+            | OmFilesRsError::MetadataTooLarge { .. }
+            | OmFilesRsError::DecryptionFailed
+            | OmFilesRsError::InvalidCheckpoint(_)
+            | OmFilesRsError::UnknownQuantizationFilter(_) => ErrorKind::Format,
+            OmFilesRsError::DecoderError(_) => ErrorKind::Decoder,
+            OmFilesRsError::EncoderError(_)
+            | OmFilesRsError::TooManySaturatedValues { .. }
+            | OmFilesRsError::ChunkVerificationFailed { .. } => ErrorKind::Encoder,
+            OmFilesRsError::ChunkHasWrongNumberOfElements
+            | OmFilesRsError::OffsetAndCountExceedDimension { .. }
+            | OmFilesRsError::DimensionOutOfBounds { .. }
+            | OmFilesRsError::ChunkDimensionIsSmallerThanOverallDim
+            | OmFilesRsError::DimensionMustBeLargerThan0
+            | OmFilesRsError::MismatchingCubeDimensionLength
+            | OmFilesRsError::InvalidCompressionType
+            | OmFilesRsError::InvalidDataType
+            | OmFilesRsError::ArrayNotContiguous
+            | OmFilesRsError::InvalidAxesPermutation
+            | OmFilesRsError::IncompatibleFiles(_)
+            | OmFilesRsError::InvalidHistogramBins
+            | OmFilesRsError::ChunkExceedsDimension { .. }
+            | OmFilesRsError::TooManyDimensions { .. }
+            | OmFilesRsError::ZeroDimension { .. }
+            | OmFilesRsError::ChunkWrittenTwice { .. }
+            | OmFilesRsError::MissingChunks { .. }
+            | OmFilesRsError::ConversionOutOfRange { .. }
+            | OmFilesRsError::GribTimeNotOnAxis { .. }
+            | OmFilesRsError::GribGridMismatch { .. }
+            | OmFilesRsError::DuplicateGribMessage { .. }
+            | OmFilesRsError::GribNoMessages
+            | OmFilesRsError::InvalidOverviewFactor { .. }
+            | OmFilesRsError::NoLatLonGrid
+            | OmFilesRsError::InvalidRequest(_)
+            | OmFilesRsError::SelectionTooLarge { .. }
+            | OmFilesRsError::InvalidConfiguration(_)
+            | OmFilesRsError::MetricsError(_) => ErrorKind::InvalidArgument,
+            OmFilesRsError::VariableNotFound(_) | OmFilesRsError::ArchiveEntryNotFound(_) => {
+                ErrorKind::NotFound
+            }
+            OmFilesRsError::NotImplementedError(_) | OmFilesRsError::CompressedArchiveEntry(_) => {
+                ErrorKind::Unsupported
+            }
+            OmFilesRsError::Cancelled => ErrorKind::Cancelled,
+            OmFilesRsError::Context { source, .. } => source.kind(),
+        }
+    }
+
+    /// Wrap the error with a description of what was being done, e.g.
+    /// `"variable 'temperature'"`. The original error is kept as `source`.
+    pub fn context(self, context: impl Into<String>) -> Self {
+        OmFilesRsError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost error without any context
+    pub fn root_cause(&self) -> &OmFilesRsError {
+        match self {
+            OmFilesRsError::Context { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
 }
 
 impl std::fmt::Display for OmFilesRsError {
@@ -50,6 +253,9 @@ impl std::fmt::Display for OmFilesRsError {
             OmFilesRsError::FileWriterError { errno, error } => {
                 write!(f, "File writer error: errno {}, error: {}", errno, error)
             }
+            OmFilesRsError::FileReaderError { errno, error } => {
+                write!(f, "File reader error: errno {}, error: {}", errno, error)
+            }
             OmFilesRsError::ChunkHasWrongNumberOfElements => {
                 write!(f, "Chunk has wrong number of elements")
             }
@@ -101,8 +307,170 @@ impl std::fmt::Display for OmFilesRsError {
             OmFilesRsError::ArrayNotContiguous => {
                 write!(f, "Array not contiguous")
             }
+            OmFilesRsError::VariableNotFound(name) => {
+                write!(f, "Variable '{}' not found", name)
+            }
+            OmFilesRsError::InvalidAxesPermutation => {
+                write!(f, "Invalid axes permutation")
+            }
+            OmFilesRsError::IncompatibleFiles(e) => {
+                write!(f, "Incompatible files: {}", e)
+            }
+            OmFilesRsError::DecryptionFailed => {
+                write!(f, "Decryption failed")
+            }
+            OmFilesRsError::InvalidHistogramBins => {
+                write!(f, "Histogram bins must be at least two increasing edges")
+            }
+            OmFilesRsError::InvalidCheckpoint(e) => {
+                write!(f, "Invalid writer checkpoint: {}", e)
+            }
+            OmFilesRsError::UnknownQuantizationFilter(id) => {
+                write!(f, "Unknown quantization filter '{}'", id)
+            }
+            OmFilesRsError::ChunkExceedsDimension {
+                index,
+                chunk,
+                dimension,
+            } => {
+                write!(
+                    f,
+                    "Chunk dimension {} exceeds dimension {} at index {}",
+                    chunk, dimension, index
+                )
+            }
+            OmFilesRsError::TooManyDimensions { count, max } => {
+                write!(
+                    f,
+                    "Too many dimensions: {}, at most {} are supported",
+                    count, max
+                )
+            }
+            OmFilesRsError::ZeroDimension { index } => {
+                write!(
+                    f,
+                    "Dimension and chunk dimension at index {} must be larger than 0",
+                    index
+                )
+            }
+            OmFilesRsError::ArchiveEntryNotFound(entry) => {
+                write!(f, "Archive entry '{}' not found", entry)
+            }
+            OmFilesRsError::CompressedArchiveEntry(entry) => {
+                write!(
+                    f,
+                    "Archive entry '{}' is compressed or not a regular file, only uncompressed entries can be read",
+                    entry
+                )
+            }
+            OmFilesRsError::Cancelled => {
+                write!(f, "Read was cancelled")
+            }
+            OmFilesRsError::RequestTimeout { timeout_ms } => {
+                write!(f, "Request timed out after {} ms", timeout_ms)
+            }
+            OmFilesRsError::EncoderError(e) => {
+                write!(f, "Encoder error {}", e)
+            }
+            OmFilesRsError::InvalidOffsetSize {
+                offset,
+                size,
+                file_size,
+            } => {
+                write!(
+                    f,
+                    "Invalid offset {} and size {} in a file of {} bytes",
+                    offset, size, file_size
+                )
+            }
+            OmFilesRsError::MetadataTooLarge { size, max } => {
+                write!(
+                    f,
+                    "Variable metadata of {} bytes exceeds the limit of {} bytes",
+                    size, max
+                )
+            }
+            OmFilesRsError::WatchError(e) => {
+                write!(f, "Cannot watch files: {}", e)
+            }
+            OmFilesRsError::MetricsError(e) => {
+                write!(f, "Cannot register metrics: {}", e)
+            }
+            OmFilesRsError::ChunkWrittenTwice { chunk } => {
+                write!(f, "Chunk {} was written more than once", chunk)
+            }
+            OmFilesRsError::MissingChunks { missing, total } => {
+                write!(f, "{} of {} chunks were not written", missing, total)
+            }
+            OmFilesRsError::ConversionOutOfRange { from, to } => {
+                write!(
+                    f,
+                    "Values of type {:?} cannot be converted to {:?}",
+                    from, to
+                )
+            }
+            OmFilesRsError::GribTimeNotOnAxis { time } => {
+                write!(f, "GRIB message at {} is not on the time axis", time)
+            }
+            OmFilesRsError::GribGridMismatch { time } => {
+                write!(
+                    f,
+                    "GRIB message at {} does not match the grid of the first message",
+                    time
+                )
+            }
+            OmFilesRsError::DuplicateGribMessage { time } => {
+                write!(f, "Several GRIB messages are valid at {}", time)
+            }
+            OmFilesRsError::GribNoMessages => write!(f, "No GRIB messages to write"),
+            OmFilesRsError::InvalidOverviewFactor { factor } => {
+                write!(f, "Overview factor {} must be at least 2", factor)
+            }
+            OmFilesRsError::NoLatLonGrid => {
+                write!(f, "Array has no regular latitude/longitude grid")
+            }
+            OmFilesRsError::InvalidRequest(e) => {
+                write!(f, "Invalid request: {}", e)
+            }
+            OmFilesRsError::SelectionTooLarge { elements, max } => {
+                write!(
+                    f,
+                    "Selection of {} elements exceeds the limit of {} elements",
+                    elements, max
+                )
+            }
+            OmFilesRsError::InvalidConfiguration(e) => {
+                write!(f, "Invalid configuration: {}", e)
+            }
+            OmFilesRsError::Context { context, source } => {
+                write!(f, "{}: {}", context, source)
+            }
+            OmFilesRsError::TooManySaturatedValues { count, max } => {
+                write!(
+                    f,
+                    "{} values exceed the int16 range after scaling, at most {} are allowed",
+                    count, max
+                )
+            }
+            OmFilesRsError::ChunkVerificationFailed {
+                count,
+                max_difference,
+            } => {
+                write!(
+                    f,
+                    "{} values differ from the written values after decoding, by up to {}",
+                    count, max_difference
+                )
+            }
         }
     }
 }
 
-impl std::error::Error for OmFilesRsError {}
+impl std::error::Error for OmFilesRsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OmFilesRsError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
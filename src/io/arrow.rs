@@ -0,0 +1,98 @@
+//! Reads into Arrow arrays for DataFusion, Polars and other Arrow based
+//! tools. Numeric values are decoded straight into the buffer of the Arrow
+//! array without an intermediate `ndarray`.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{ArrayRef, ArrowPrimitiveType, PrimitiveArray, RecordBatch, StringArray};
+use arrow_buffer::ScalarBuffer;
+use arrow_schema::{Field, Schema};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Field metadata with the shape of the selection, e.g. `2,3`
+pub const ARROW_SHAPE_KEY: &str = "om:shape";
+/// Field metadata with the index of the first element of the selection in
+/// every dimension, e.g. `10,0`
+pub const ARROW_OFFSET_KEY: &str = "om:offset";
+/// Field metadata with the dimension names, if the variable has any, e.g.
+/// `lat,lon`
+pub const ARROW_DIMENSION_NAMES_KEY: &str = "om:dimension_names";
+
+impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
+    /// Read a selection as record batch with a single column named after the
+    /// variable. Values are flattened in row-major order, the shape of the
+    /// selection is stored in the field metadata. The column has the Arrow
+    /// type of the element type, e.g. `Float32` for float arrays.
+    pub fn read_arrow(&self, ranges: &[Range<u64>]) -> Result<RecordBatch, OmFilesRsError> {
+        let shape: Vec<u64> = ranges
+            .iter()
+            .map(|range| range.end.saturating_sub(range.start))
+            .collect();
+        let values: ArrayRef = match self.data_type() {
+            DataType::Int8Array => self.read_arrow_primitive::<Int8Type>(ranges, &shape)?,
+            DataType::Uint8Array => self.read_arrow_primitive::<UInt8Type>(ranges, &shape)?,
+            DataType::Int16Array => self.read_arrow_primitive::<Int16Type>(ranges, &shape)?,
+            DataType::Uint16Array => self.read_arrow_primitive::<UInt16Type>(ranges, &shape)?,
+            DataType::Int32Array => self.read_arrow_primitive::<Int32Type>(ranges, &shape)?,
+            DataType::Uint32Array => self.read_arrow_primitive::<UInt32Type>(ranges, &shape)?,
+            DataType::Int64Array => self.read_arrow_primitive::<Int64Type>(ranges, &shape)?,
+            DataType::Uint64Array => self.read_arrow_primitive::<UInt64Type>(ranges, &shape)?,
+            DataType::FloatArray => self.read_arrow_primitive::<Float32Type>(ranges, &shape)?,
+            DataType::DoubleArray => self.read_arrow_primitive::<Float64Type>(ranges, &shape)?,
+            DataType::StringArray => {
+                let strings = self.read_string_array(ranges)?;
+                Arc::new(StringArray::from_iter_values(strings))
+            }
+            _ => return Err(OmFilesRsError::InvalidDataType),
+        };
+
+        let join = |values: &[u64]| {
+            let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+            values.join(",")
+        };
+        let offset: Vec<u64> = ranges.iter().map(|range| range.start).collect();
+        let mut metadata = HashMap::from([
+            (ARROW_SHAPE_KEY.to_string(), join(&shape)),
+            (ARROW_OFFSET_KEY.to_string(), join(&offset)),
+        ]);
+        if let Some(names) = self.get_dimension_names() {
+            metadata.insert(ARROW_DIMENSION_NAMES_KEY.to_string(), names.join(","));
+        }
+        let name = self.get_name().unwrap_or_else(|| "value".to_string());
+        let field = Field::new(name, values.data_type().clone(), false).with_metadata(metadata);
+        RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![values])
+            .map_err(|e| OmFilesRsError::DecoderError(e.to_string()))
+    }
+
+    fn read_arrow_primitive<A: ArrowPrimitiveType>(
+        &self,
+        ranges: &[Range<u64>],
+        shape: &[u64],
+    ) -> Result<ArrayRef, OmFilesRsError>
+    where
+        A::Native: OmFileArrayDataType,
+    {
+        let mut values = vec![A::Native::default(); shape.iter().product::<u64>() as usize];
+        self.read_into_flat(
+            &mut values,
+            ranges,
+            &vec![0; ranges.len()],
+            shape,
+            None,
+            None,
+        )?;
+        // Takes ownership of the allocation without copying
+        Ok(Arc::new(PrimitiveArray::<A>::new(
+            ScalarBuffer::from(values),
+            None,
+        )))
+    }
+}
@@ -0,0 +1,236 @@
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::c_defaults::{c_error_string, new_data_read, new_index_read};
+use crate::errors::OmFilesRsError;
+use om_file_format_sys::{
+    om_decoder_decode_chunks, om_decoder_next_data_read, om_decoder_next_index_read,
+    OmDecoder_dataRead_t, OmDecoder_indexRead_t, OmDecoder_t, OmError_t, OmError_t_ERROR_OK,
+};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+
+/// An initialized decoder together with the buffers it points to.
+/// The decoder keeps raw pointers into the read/cube vectors and into the
+/// variable metadata of the reader, so this struct must not outlive the reader.
+pub(crate) struct PreparedRead<'a> {
+    pub decoder: OmDecoder_t,
+    pub chunk_buffer: Vec<u8>,
+    _read_offset: Vec<u64>,
+    _read_count: Vec<u64>,
+    _cube_offset: Vec<u64>,
+    _cube_dimension: Vec<u64>,
+    _variable: PhantomData<&'a [u8]>,
+}
+
+impl<'a> PreparedRead<'a> {
+    /// Takes ownership of the buffers that were passed to `om_decoder_init`.
+    /// Moving the vectors keeps their heap allocations in place, therefore
+    /// pointers held by the decoder stay valid.
+    pub fn new(
+        decoder: OmDecoder_t,
+        chunk_buffer: Vec<u8>,
+        read_offset: Vec<u64>,
+        read_count: Vec<u64>,
+        cube_offset: Vec<u64>,
+        cube_dimension: Vec<u64>,
+    ) -> Self {
+        Self {
+            decoder,
+            chunk_buffer,
+            _read_offset: read_offset,
+            _read_count: read_count,
+            _cube_offset: cube_offset,
+            _cube_dimension: cube_dimension,
+            _variable: PhantomData,
+        }
+    }
+}
+
+// The decoder only points to the buffers owned by `PreparedRead` and to the
+// immutable variable metadata borrowed for `'a`, so it can be moved to another thread.
+unsafe impl Send for PreparedRead<'_> {}
+
+/// Byte blocks fetched from a backend after merging nearby ranges.
+pub(crate) struct CoalescedBytes<'b> {
+    /// Sorted by offset, blocks never overlap
+    blocks: Vec<(u64, Cow<'b, [u8]>)>,
+}
+
+impl<'b> CoalescedBytes<'b> {
+    /// Merge all `(offset, count)` ranges whose gap is at most `io_size_merge`
+    /// as long as the merged block does not grow beyond `io_size_max`, then
+    /// fetch each merged block with a single backend request.
+    pub fn fetch<Backend: OmFileReaderBackend>(
+        backend: &'b Backend,
+        ranges: &[(u64, u64)],
+        io_size_max: u64,
+        io_size_merge: u64,
+    ) -> Result<Self, OmFilesRsError> {
+        let merged = merge_ranges(ranges, io_size_max, io_size_merge);
+        let mut blocks = Vec::with_capacity(merged.len());
+        for (offset, count) in merged {
+            blocks.push((offset, backend.get_bytes_with_fallback(offset, count)?));
+        }
+        Ok(Self { blocks })
+    }
+
+    /// Use blocks that have already been fetched, e.g. by an async backend.
+    pub fn from_blocks(blocks: Vec<(u64, Vec<u8>)>) -> Self {
+        let mut blocks: Vec<(u64, Cow<'b, [u8]>)> = blocks
+            .into_iter()
+            .map(|(offset, data)| (offset, Cow::Owned(data)))
+            .collect();
+        blocks.sort_unstable_by_key(|(offset, _)| *offset);
+        Self { blocks }
+    }
+
+    /// Returns the bytes for a range that was part of the fetched ranges.
+    pub fn get(&self, offset: u64, count: u64) -> &[u8] {
+        let block_index = self.blocks.partition_point(|(start, _)| *start <= offset) - 1;
+        let (start, data) = &self.blocks[block_index];
+        let begin = (offset - start) as usize;
+        &data[begin..begin + count as usize]
+    }
+}
+
+/// Sort ranges by offset and combine neighbours into larger reads.
+pub(crate) fn merge_ranges(
+    ranges: &[(u64, u64)],
+    io_size_max: u64,
+    io_size_merge: u64,
+) -> Vec<(u64, u64)> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (offset, count) in sorted {
+        if let Some((start, size)) = merged.last_mut() {
+            let end = *start + *size;
+            let new_end = end.max(offset + count);
+            if offset <= end + io_size_merge && (offset < end || new_end - *start <= io_size_max) {
+                *size = new_end - *start;
+                continue;
+            }
+        }
+        merged.push((offset, count));
+    }
+    merged
+}
+
+/// Decode several prepared reads at once. Index and data reads of all decoders
+/// are collected first, so that backend requests can be merged across
+/// variables. This reduces the number of round trips for remote backends at the
+/// cost of keeping all compressed data of the batch in memory.
+pub(crate) fn decode_batch<T, Backend: OmFileReaderBackend>(
+    backend: &Backend,
+    reads: &mut [(PreparedRead<'_>, &mut [T])],
+    io_size_max: u64,
+    io_size_merge: u64,
+) -> Result<(), OmFilesRsError> {
+    // Collect index reads of all decoders
+    let mut index_reads: Vec<(usize, OmDecoder_indexRead_t)> = Vec::new();
+    for (i, (read, _)) in reads.iter().enumerate() {
+        let mut index_read = new_index_read(&read.decoder);
+        while unsafe { om_decoder_next_index_read(&read.decoder, &mut index_read) } {
+            index_reads.push((i, index_read));
+        }
+    }
+    let index_ranges: Vec<(u64, u64)> = index_reads
+        .iter()
+        .map(|(_, r)| (r.offset, r.count))
+        .collect();
+    let index_data = CoalescedBytes::fetch(backend, &index_ranges, io_size_max, io_size_merge)?;
+
+    // Resolve data reads using the fetched index data
+    let mut data_reads: Vec<(usize, OmDecoder_dataRead_t)> = Vec::new();
+    for (i, index_read) in index_reads.iter() {
+        let decoder = &reads[*i].0.decoder;
+        let index_bytes = index_data.get(index_read.offset, index_read.count);
+        for data_read in collect_data_reads(decoder, index_read, index_bytes)? {
+            data_reads.push((*i, data_read));
+        }
+    }
+    let data_ranges: Vec<(u64, u64)> = data_reads
+        .iter()
+        .map(|(_, r)| (r.offset, r.count))
+        .collect();
+    let data = CoalescedBytes::fetch(backend, &data_ranges, io_size_max, io_size_merge)?;
+
+    // Decode all chunks into their target arrays
+    for (i, data_read) in data_reads.iter() {
+        let (read, into) = &mut reads[*i];
+        let data_bytes = data.get(data_read.offset, data_read.count);
+        decode_chunks(
+            &read.decoder,
+            &mut read.chunk_buffer,
+            into,
+            data_read,
+            data_bytes,
+        )?;
+    }
+    Ok(())
+}
+
+/// All data reads of one index block.
+pub(crate) fn collect_data_reads(
+    decoder: &OmDecoder_t,
+    index_read: &OmDecoder_indexRead_t,
+    index_bytes: &[u8],
+) -> Result<Vec<OmDecoder_dataRead_t>, OmFilesRsError> {
+    let mut data_reads = Vec::new();
+    let mut data_read = new_data_read(index_read);
+    let mut error = OmError_t_ERROR_OK;
+    while unsafe {
+        om_decoder_next_data_read(
+            decoder,
+            &mut data_read,
+            index_bytes.as_ptr() as *const c_void,
+            index_read.count,
+            &mut error,
+        )
+    } {
+        data_reads.push(data_read);
+    }
+    if error != OmError_t_ERROR_OK {
+        return Err(OmFilesRsError::DecoderError(c_error_string(error))
+            .context(format!("index block at offset {}", index_read.offset)));
+    }
+    Ok(data_reads)
+}
+
+/// Decode the compressed chunks of one data read into `into`.
+pub(crate) fn decode_chunks<T>(
+    decoder: &OmDecoder_t,
+    chunk_buffer: &mut [u8],
+    into: &mut [T],
+    data_read: &OmDecoder_dataRead_t,
+    data_bytes: &[u8],
+) -> Result<(), OmFilesRsError> {
+    let mut error = OmError_t_ERROR_OK;
+    if !unsafe {
+        om_decoder_decode_chunks(
+            decoder,
+            data_read.chunkIndex,
+            data_bytes.as_ptr() as *const c_void,
+            data_read.count,
+            into.as_mut_ptr() as *mut c_void,
+            chunk_buffer.as_mut_ptr() as *mut c_void,
+            &mut error,
+        )
+    } {
+        return Err(chunk_decode_error(error, data_read));
+    }
+    Ok(())
+}
+
+/// Decoder error with the chunk indices of the failed data read
+pub(crate) fn chunk_decode_error(
+    error: OmError_t,
+    data_read: &OmDecoder_dataRead_t,
+) -> OmFilesRsError {
+    let chunks = data_read.chunkIndex;
+    OmFilesRsError::DecoderError(c_error_string(error)).context(format!(
+        "chunks {}..{}",
+        chunks.lowerBound, chunks.upperBound
+    ))
+}
@@ -0,0 +1,152 @@
+//! Evaluate compression settings on real data. Every combination of codec
+//! and chunk dimensions is written to memory and read back, so settings can
+//! be chosen empirically for a dataset.
+
+use crate::backend::backends::InMemoryBackend;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::OmFileArrayDataType;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::io::writer::OmFileWriter;
+use ndarray::ArrayViewD;
+use num_traits::ToPrimitive;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of reads used to measure `random_read_time`
+pub const RANDOM_READS: u32 = 100;
+
+/// Compression type with the scale factor and offset it is used with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodecCandidate {
+    pub compression: CompressionType,
+    pub scale_factor: f32,
+    pub add_offset: f32,
+}
+
+impl CodecCandidate {
+    pub fn new(compression: CompressionType, scale_factor: f32) -> Self {
+        Self {
+            compression,
+            scale_factor,
+            add_offset: 0.0,
+        }
+    }
+}
+
+impl From<CompressionType> for CodecCandidate {
+    fn from(compression: CompressionType) -> Self {
+        Self::new(compression, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionEvaluation {
+    pub codec: CodecCandidate,
+    pub chunks: Vec<u64>,
+    /// Size of the whole file including metadata
+    pub compressed_bytes: u64,
+    /// Size of the uncompressed values divided by `compressed_bytes`
+    pub compression_ratio: f64,
+    pub write_time: Duration,
+    /// Average time to read a region of one chunk size at a random position
+    pub random_read_time: Duration,
+    /// Largest absolute difference between written and read values. NaN in
+    /// only one of both counts as infinite difference.
+    pub max_error: f64,
+}
+
+/// Write `data` with every combination of `candidate_codecs` and
+/// `candidate_chunks` to memory and measure size, write time, random read
+/// time and error. Results are ordered by codec, then by chunks. Random
+/// positions are the same for every run, so results are comparable.
+pub fn evaluate_compression<T>(
+    data: ArrayViewD<T>,
+    candidate_codecs: &[CodecCandidate],
+    candidate_chunks: &[Vec<u64>],
+) -> Result<Vec<CompressionEvaluation>, OmFilesRsError>
+where
+    T: OmFileArrayDataType + Clone + Default + ToPrimitive,
+{
+    let dimensions: Vec<u64> = data.shape().iter().map(|&x| x as u64).collect();
+    let all: Vec<Range<u64>> = dimensions.iter().map(|&dimension| 0..dimension).collect();
+    let uncompressed_bytes = (data.len() * std::mem::size_of::<T>()) as f64;
+    let mut evaluations = Vec::new();
+
+    for codec in candidate_codecs {
+        for chunks in candidate_chunks {
+            let mut backend = InMemoryBackend::new(vec![]);
+            let start = Instant::now();
+            {
+                let mut file_writer = OmFileWriter::new(&mut backend, 8);
+                let mut writer = file_writer.prepare_array::<T>(
+                    dimensions.clone(),
+                    chunks.clone(),
+                    codec.compression,
+                    codec.scale_factor,
+                    codec.add_offset,
+                )?;
+                writer.write_data(data.view(), None, None)?;
+                let variable_meta = writer.finalize();
+                let variable = file_writer.write_array(variable_meta, "data", &[])?;
+                file_writer.write_trailer(variable)?;
+            }
+            let write_time = start.elapsed();
+            let compressed_bytes = backend.len() as u64;
+            let reader = OmFileReader::new(Arc::new(backend))?;
+
+            let read = reader.read::<T>(&all, None, None)?;
+            let max_error = read
+                .iter()
+                .zip(data.iter())
+                .map(|(a, b)| difference(a.to_f64(), b.to_f64()))
+                .fold(0.0, f64::max);
+
+            let mut random = 0x9E37_79B9_7F4A_7C15u64;
+            let start = Instant::now();
+            for _ in 0..RANDOM_READS {
+                let region: Vec<Range<u64>> = dimensions
+                    .iter()
+                    .zip(chunks.iter())
+                    .map(|(&dimension, &chunk)| {
+                        random = xorshift(random);
+                        let offset = random % (dimension - chunk + 1);
+                        offset..offset + chunk
+                    })
+                    .collect();
+                reader.read::<T>(&region, None, None)?;
+            }
+            let random_read_time = start.elapsed() / RANDOM_READS;
+
+            evaluations.push(CompressionEvaluation {
+                codec: *codec,
+                chunks: chunks.clone(),
+                compressed_bytes,
+                compression_ratio: uncompressed_bytes / compressed_bytes as f64,
+                write_time,
+                random_read_time,
+                max_error,
+            });
+        }
+    }
+    Ok(evaluations)
+}
+
+fn difference(a: Option<f64>, b: Option<f64>) -> f64 {
+    let (Some(a), Some(b)) = (a, b) else {
+        return f64::INFINITY;
+    };
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => 0.0,
+        (false, false) => (a - b).abs(),
+        _ => f64::INFINITY,
+    }
+}
+
+fn xorshift(mut x: u64) -> u64 {
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
@@ -1,6 +1,21 @@
 use crate::backend::backends::OmFileWriterBackend;
 use crate::errors::OmFilesRsError;
 use crate::utils::divide_rounded_up;
+use std::time::{Duration, Instant};
+
+/// When the writer asks the backend to synchronize data to disk while a
+/// file is written. Syncing regularly avoids a large build up of dirty pages
+/// when very large files are written.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SyncPolicy {
+    /// Never sync while writing. Atomic writers still sync before the rename.
+    #[default]
+    Off,
+    /// Sync whenever at least this many bytes were flushed since the last sync
+    Bytes(u64),
+    /// Sync on the first flush after this much time has passed since the last sync
+    Interval(Duration),
+}
 
 /// All data is written to a buffer before flushed to a backend
 pub struct OmBufferedWriter<Backend: OmFileWriterBackend> {
@@ -14,6 +29,11 @@ pub struct OmBufferedWriter<Backend: OmFileWriterBackend> {
     pub total_bytes_written: usize,
     /// Initial capacity for reallocation sizing
     pub initial_capacity: usize,
+    sync_policy: SyncPolicy,
+    /// Bytes flushed to the backend since the last sync
+    bytes_since_sync: u64,
+    /// Time of the last sync, or of the first flush if there was no sync yet
+    last_sync: Option<Instant>,
 }
 
 impl<Backend: OmFileWriterBackend> OmBufferedWriter<Backend> {
@@ -24,9 +44,44 @@ impl<Backend: OmFileWriterBackend> OmBufferedWriter<Backend> {
             write_position: 0,
             total_bytes_written: 0,
             initial_capacity,
+            sync_policy: SyncPolicy::Off,
+            bytes_since_sync: 0,
+            last_sync: None,
         }
     }
 
+    pub fn set_sync_policy(&mut self, sync_policy: SyncPolicy) {
+        self.sync_policy = sync_policy;
+        self.bytes_since_sync = 0;
+        self.last_sync = None;
+    }
+
+    pub fn sync_policy(&self) -> SyncPolicy {
+        self.sync_policy
+    }
+
+    /// Sync the backend if the policy requires it after `bytes` were flushed
+    fn sync_if_required(&mut self, bytes: usize) -> Result<(), OmFilesRsError> {
+        self.bytes_since_sync += bytes as u64;
+        let sync = match self.sync_policy {
+            SyncPolicy::Off => false,
+            SyncPolicy::Bytes(threshold) => self.bytes_since_sync >= threshold,
+            // Instant is only queried if required, it is not available on all targets
+            SyncPolicy::Interval(interval) => {
+                let last_sync = *self.last_sync.get_or_insert_with(Instant::now);
+                last_sync.elapsed() >= interval
+            }
+        };
+        if sync {
+            self.backend.synchronize()?;
+            self.bytes_since_sync = 0;
+            if matches!(self.sync_policy, SyncPolicy::Interval(_)) {
+                self.last_sync = Some(Instant::now());
+            }
+        }
+        Ok(())
+    }
+
     pub fn increment_write_position(&mut self, bytes: usize) {
         self.write_position += bytes;
         self.total_bytes_written += bytes;
@@ -94,6 +149,7 @@ impl<Backend: OmFileWriterBackend> OmBufferedWriter<Backend> {
         }
 
         self.backend.write(&self.buffer[..self.write_position])?;
+        self.sync_if_required(self.write_position)?;
 
         // Clear buffer contents
         self.buffer[..self.write_position].fill(0);
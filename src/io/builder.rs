@@ -0,0 +1,206 @@
+use crate::backend::backends::{
+    IoSizes, OmFileReaderBackend, OmFileReaderBackendAsync, OmFileWriterBackend,
+};
+use crate::errors::OmFilesRsError;
+use crate::io::buffered_writer::SyncPolicy;
+use crate::io::cancellation::CancellationToken;
+use crate::io::reader::OmFileReader;
+use crate::io::reader_async::OmFileReaderAsync;
+use crate::io::writer::OmFileWriter;
+use std::fs::File;
+use std::sync::Arc;
+
+/// Default size of the write buffer in bytes. The buffer grows if a single
+/// chunk or the metadata of a variable does not fit.
+const DEFAULT_INITIAL_CAPACITY: u64 = 1024 * 1024;
+
+/// Options for `OmFileWriter`. New options are added here instead of to the
+/// constructor, so existing code keeps compiling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmFileWriterBuilder {
+    initial_capacity: u64,
+    sync_policy: SyncPolicy,
+    deterministic: bool,
+    string_arrays: bool,
+    backup_trailer: bool,
+    verify_fraction: f64,
+}
+
+impl Default for OmFileWriterBuilder {
+    fn default() -> Self {
+        Self {
+            initial_capacity: DEFAULT_INITIAL_CAPACITY,
+            sync_policy: SyncPolicy::Off,
+            deterministic: false,
+            string_arrays: false,
+            backup_trailer: false,
+            verify_fraction: 0.0,
+        }
+    }
+}
+
+impl OmFileWriterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initial size of the write buffer in bytes
+    pub fn initial_capacity(mut self, initial_capacity: u64) -> Self {
+        self.initial_capacity = initial_capacity;
+        self
+    }
+
+    /// When data is synced to disk while writing
+    pub fn sync_policy(mut self, sync_policy: SyncPolicy) -> Self {
+        self.sync_policy = sync_policy;
+        self
+    }
+
+    /// Reject codecs whose output differs between platforms, see
+    /// `OmFileWriter::set_deterministic`
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Allow writing string arrays, see `OmFileWriter::set_string_arrays`
+    pub fn string_arrays(mut self, string_arrays: bool) -> Self {
+        self.string_arrays = string_arrays;
+        self
+    }
+
+    /// Write a copy of the trailer for recovery, see
+    /// `OmFileWriter::set_backup_trailer`
+    pub fn backup_trailer(mut self, backup_trailer: bool) -> Self {
+        self.backup_trailer = backup_trailer;
+        self
+    }
+
+    /// Fraction of chunks that are decoded and compared after writing, see
+    /// `OmFileWriter::set_verification`
+    pub fn verification(mut self, fraction: f64) -> Self {
+        self.verify_fraction = fraction;
+        self
+    }
+
+    pub fn build<Backend: OmFileWriterBackend>(self, backend: Backend) -> OmFileWriter<Backend> {
+        let mut writer = OmFileWriter::new(backend, self.initial_capacity);
+        writer.set_sync_policy(self.sync_policy);
+        writer.set_deterministic(self.deterministic);
+        writer.set_string_arrays(self.string_arrays);
+        writer.set_backup_trailer(self.backup_trailer);
+        writer.set_verification(self.verify_fraction);
+        writer
+    }
+
+    /// Like `OmFileWriter::create_atomic` with the options of this builder
+    pub fn create_atomic(
+        self,
+        path: &str,
+        overwrite: bool,
+    ) -> Result<OmFileWriter<File>, OmFilesRsError> {
+        let mut writer = OmFileWriter::create_atomic(path, overwrite, self.initial_capacity)?;
+        writer.set_sync_policy(self.sync_policy);
+        writer.set_deterministic(self.deterministic);
+        writer.set_string_arrays(self.string_arrays);
+        writer.set_backup_trailer(self.backup_trailer);
+        writer.set_verification(self.verify_fraction);
+        Ok(writer)
+    }
+}
+
+/// Options for `OmFileReader` and `OmFileReaderAsync`. Options that only
+/// apply to one of the readers are ignored by the other.
+#[derive(Debug, Clone, Default)]
+pub struct OmFileReaderBuilder {
+    io_sizes: Option<IoSizes>,
+    lut_cache: Option<u64>,
+    chunk_cache: Option<u64>,
+    max_concurrency: Option<usize>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl OmFileReaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// IO sizes used if a read does not specify them. Defaults to the
+    /// preferred sizes of the backend.
+    pub fn io_sizes(mut self, io_sizes: IoSizes) -> Self {
+        self.io_sizes = Some(io_sizes);
+        self
+    }
+
+    /// Cache up to `max_bytes` of decoded LUT entries, see `OmFileReader::enable_lut_cache`.
+    /// Only used by `OmFileReader`.
+    pub fn lut_cache(mut self, max_bytes: u64) -> Self {
+        self.lut_cache = Some(max_bytes);
+        self
+    }
+
+    /// Cache up to `max_bytes` of decoded chunks, see
+    /// `OmFileReader::enable_chunk_cache`. Only used by `OmFileReader`.
+    pub fn chunk_cache(mut self, max_bytes: u64) -> Self {
+        self.chunk_cache = Some(max_bytes);
+        self
+    }
+
+    /// Concurrent backend requests per read. Only used by `OmFileReaderAsync`.
+    /// Must be larger than 0.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
+    /// Abort reads once `token` is cancelled. Only used by `OmFileReaderAsync`.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn build<Backend: OmFileReaderBackend>(
+        self,
+        backend: Arc<Backend>,
+    ) -> Result<OmFileReader<Backend>, OmFilesRsError> {
+        self.validate()?;
+        let mut reader = OmFileReader::new(backend)?;
+        if let Some(io_sizes) = self.io_sizes {
+            reader.set_io_sizes(io_sizes);
+        }
+        if let Some(max_bytes) = self.lut_cache {
+            reader.enable_lut_cache(max_bytes);
+        }
+        if let Some(max_bytes) = self.chunk_cache {
+            reader.enable_chunk_cache(max_bytes);
+        }
+        Ok(reader)
+    }
+
+    pub async fn build_async<Backend: OmFileReaderBackendAsync>(
+        self,
+        backend: Arc<Backend>,
+    ) -> Result<OmFileReaderAsync<Backend>, OmFilesRsError> {
+        self.validate()?;
+        let mut reader = OmFileReaderAsync::new(backend).await?;
+        if let Some(io_sizes) = self.io_sizes {
+            reader.set_io_sizes(io_sizes);
+        }
+        if let Some(max_concurrency) = self.max_concurrency {
+            reader.set_max_concurrency(max_concurrency);
+        }
+        if let Some(token) = self.cancellation {
+            reader.set_cancellation_token(token);
+        }
+        Ok(reader)
+    }
+
+    fn validate(&self) -> Result<(), OmFilesRsError> {
+        if self.max_concurrency == Some(0) {
+            return Err(OmFilesRsError::InvalidConfiguration(
+                "max_concurrency must be larger than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
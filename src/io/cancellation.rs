@@ -0,0 +1,106 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: bool,
+    /// Id of the next `Cancelled` future that registers a waker
+    next_id: u64,
+    /// Wakers of pending `Cancelled` futures keyed by their id. Several
+    /// futures of the same task have the same waker.
+    wakers: Vec<(u64, Waker)>,
+}
+
+/// Cooperative cancellation for async reads. Clones share the same state, so a
+/// token can be handed to a reader and cancelled from another task or thread.
+/// Once cancelled, a token stays cancelled.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<Mutex<TokenState>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all reads using this token and wake up tasks waiting on it.
+    pub fn cancel(&self) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            std::mem::take(&mut state.wakers)
+        };
+        for (_, waker) in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Future that completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled {
+            token: self,
+            id: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled`. Its waker is
+/// unregistered when the future is dropped.
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    /// Set once a waker is registered
+    id: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.token.state.lock().unwrap();
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+        let registered = self
+            .id
+            .and_then(|id| state.wakers.iter_mut().find(|(other, _)| *other == id));
+        match registered {
+            Some((_, waker)) => {
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+            }
+            None => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.wakers.push((id, cx.waker().clone()));
+                drop(state);
+                self.id = Some(id);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.token.state.lock().unwrap();
+            state.wakers.retain(|(other, _)| *other != id);
+        }
+    }
+}
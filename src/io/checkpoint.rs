@@ -0,0 +1,248 @@
+use crate::core::compression::CompressionType;
+use crate::core::data_types::DataType;
+use crate::errors::OmFilesRsError;
+use crate::io::geo::GridDefinition;
+use crate::io::time::TimeAxis;
+use crate::io::writer::FillValue;
+use std::fs::File;
+use std::io::Write;
+
+const MAGIC: &[u8; 4] = b"OMCP";
+const VERSION: u8 = 2;
+
+/// State of a partially written array, created by
+/// `OmFileWriterArray::checkpoint`. It can be stored in a sidecar file next to
+/// the output file and passed to `OmFileWriter::resume` and
+/// `OmFileWriter::resume_array` to continue writing after a crash. Dimension
+/// names, fill value, time axis and grid are restored with the array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmFileWriterCheckpoint {
+    pub data_type: DataType,
+    pub compression: CompressionType,
+    pub scale_factor: f32,
+    pub add_offset: f32,
+    pub dimensions: Vec<u64>,
+    pub chunks: Vec<u64>,
+    /// Number of chunks written before the checkpoint
+    pub chunk_index: u64,
+    /// Look up table of all written chunks, `chunk_index + 1` entries
+    pub look_up_table: Vec<u64>,
+    /// Length of the output file at the checkpoint. Data after it is discarded.
+    pub bytes_written: u64,
+    pub dimension_names: Option<Vec<String>>,
+    pub fill_value: Option<FillValue>,
+    pub time_axis: Option<TimeAxis>,
+    pub grid: Option<GridDefinition>,
+}
+
+impl OmFileWriterCheckpoint {
+    /// Write the checkpoint to `path`. The file is replaced atomically, so
+    /// a crash while saving keeps the previous checkpoint.
+    pub fn save(&self, path: &str) -> Result<(), OmFilesRsError> {
+        let temporary = format!("{}~", path);
+        let mut file = File::create(&temporary).map_err(|e| map_io_error(&temporary, e))?;
+        file.write_all(&self.to_bytes())
+            .map_err(|e| map_io_error(&temporary, e))?;
+        file.sync_all().map_err(|e| map_io_error(&temporary, e))?;
+        std::fs::rename(&temporary, path).map_err(|e| map_io_error(path, e))
+    }
+
+    /// Read a checkpoint written by `save`
+    pub fn load(path: &str) -> Result<Self, OmFilesRsError> {
+        let bytes = std::fs::read(path).map_err(|e| OmFilesRsError::CannotOpenFile {
+            filename: path.to_string(),
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.data_type as u8);
+        bytes.push(self.compression as u8);
+        bytes.extend_from_slice(&self.scale_factor.to_le_bytes());
+        bytes.extend_from_slice(&self.add_offset.to_le_bytes());
+        for values in [&self.dimensions, &self.chunks, &self.look_up_table] {
+            bytes.extend_from_slice(&(values.len() as u64).to_le_bytes());
+            values
+                .iter()
+                .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+        }
+        bytes.extend_from_slice(&self.chunk_index.to_le_bytes());
+        bytes.extend_from_slice(&self.bytes_written.to_le_bytes());
+
+        bytes.push(self.dimension_names.is_some() as u8);
+        if let Some(names) = &self.dimension_names {
+            bytes.extend_from_slice(&(names.len() as u64).to_le_bytes());
+            names
+                .iter()
+                .for_each(|name| push_bytes(&mut bytes, name.as_bytes()));
+        }
+        bytes.push(self.fill_value.is_some() as u8);
+        if let Some(fill_value) = &self.fill_value {
+            bytes.push(fill_value.data_type as u8);
+            push_bytes(&mut bytes, &fill_value.bytes);
+        }
+        bytes.push(self.time_axis.is_some() as u8);
+        if let Some(time_axis) = &self.time_axis {
+            bytes.extend_from_slice(&time_axis.start.to_le_bytes());
+            bytes.extend_from_slice(&time_axis.step.to_le_bytes());
+            bytes.extend_from_slice(&time_axis.length.to_le_bytes());
+            push_bytes(&mut bytes, time_axis.calendar.as_bytes());
+        }
+        bytes.push(self.grid.is_some() as u8);
+        if let Some(grid) = &self.grid {
+            for value in [
+                grid.lat_min,
+                grid.lat_max,
+                grid.lon_min,
+                grid.lon_max,
+                grid.dx,
+                grid.dy,
+            ] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            push_bytes(&mut bytes, grid.projection.as_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, OmFilesRsError> {
+        let mut reader = ByteReader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC || reader.take(1)?[0] != VERSION {
+            return Err(OmFilesRsError::InvalidCheckpoint(
+                "Unknown file format".to_string(),
+            ));
+        }
+        let data_type =
+            DataType::try_from(reader.take(1)?[0]).map_err(|_| OmFilesRsError::InvalidDataType)?;
+        let compression = CompressionType::try_from(reader.take(1)?[0])?;
+        let scale_factor = f32::from_le_bytes(reader.array()?);
+        let add_offset = f32::from_le_bytes(reader.array()?);
+        let dimensions = reader.u64_vec()?;
+        let chunks = reader.u64_vec()?;
+        let look_up_table = reader.u64_vec()?;
+        let chunk_index = reader.u64()?;
+        let bytes_written = reader.u64()?;
+        let dimension_names = match reader.flag()? {
+            true => {
+                let count = reader.u64()?;
+                Some(
+                    (0..count)
+                        .map(|_| reader.string())
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            false => None,
+        };
+        let fill_value = match reader.flag()? {
+            true => Some(FillValue {
+                data_type: DataType::try_from(reader.take(1)?[0])
+                    .map_err(|_| OmFilesRsError::InvalidDataType)?,
+                bytes: reader.bytes()?.to_vec(),
+            }),
+            false => None,
+        };
+        let time_axis = match reader.flag()? {
+            true => Some(TimeAxis {
+                start: i64::from_le_bytes(reader.array()?),
+                step: i64::from_le_bytes(reader.array()?),
+                length: reader.u64()?,
+                calendar: reader.string()?,
+            }),
+            false => None,
+        };
+        let grid = match reader.flag()? {
+            true => Some(GridDefinition {
+                lat_min: f64::from_le_bytes(reader.array()?),
+                lat_max: f64::from_le_bytes(reader.array()?),
+                lon_min: f64::from_le_bytes(reader.array()?),
+                lon_max: f64::from_le_bytes(reader.array()?),
+                dx: f64::from_le_bytes(reader.array()?),
+                dy: f64::from_le_bytes(reader.array()?),
+                projection: reader.string()?,
+            }),
+            false => None,
+        };
+        Ok(Self {
+            data_type,
+            compression,
+            scale_factor,
+            add_offset,
+            dimensions,
+            chunks,
+            chunk_index,
+            look_up_table,
+            bytes_written,
+            dimension_names,
+            fill_value,
+            time_axis,
+            grid,
+        })
+    }
+}
+
+/// Append `value` with its length
+fn push_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(value);
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], OmFilesRsError> {
+        let end = self.position.saturating_add(count);
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| OmFilesRsError::InvalidCheckpoint("File is truncated".to_string()))?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], OmFilesRsError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u64(&mut self) -> Result<u64, OmFilesRsError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn flag(&mut self) -> Result<bool, OmFilesRsError> {
+        Ok(self.take(1)?[0] != 0)
+    }
+
+    /// Bytes written by `push_bytes`
+    fn bytes(&mut self) -> Result<&'a [u8], OmFilesRsError> {
+        let count = self.u64()? as usize;
+        self.take(count)
+    }
+
+    fn string(&mut self) -> Result<String, OmFilesRsError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| OmFilesRsError::InvalidCheckpoint("Invalid string".to_string()))
+    }
+
+    fn u64_vec(&mut self) -> Result<Vec<u64>, OmFilesRsError> {
+        let count = self.u64()? as usize;
+        let bytes = self.take(count.saturating_mul(8))?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|value| u64::from_le_bytes(value.try_into().unwrap()))
+            .collect())
+    }
+}
+
+fn map_io_error(path: &str, e: std::io::Error) -> OmFilesRsError {
+    OmFilesRsError::FileWriterError {
+        errno: e.raw_os_error().unwrap_or(0),
+        error: format!("{}: {}", path, e),
+    }
+}
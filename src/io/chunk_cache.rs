@@ -0,0 +1,73 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Offset of the variable metadata and chunk index
+type ChunkKey = (u64, u64);
+
+struct ChunkCacheState {
+    /// Chunk -> (decoded values as bytes, last access tick)
+    chunks: HashMap<ChunkKey, (Arc<[u8]>, u64)>,
+    /// Last access tick -> key, the first entry is the least recently used chunk
+    lru: BTreeMap<u64, ChunkKey>,
+    tick: u64,
+    bytes: u64,
+}
+
+/// Least recently used cache for decoded chunks. Shared between a reader and
+/// its children, variables are distinguished by the offset of their metadata.
+pub(crate) struct ChunkCache {
+    max_bytes: u64,
+    state: Mutex<ChunkCacheState>,
+}
+
+impl ChunkCache {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            state: Mutex::new(ChunkCacheState {
+                chunks: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                bytes: 0,
+            }),
+        }
+    }
+
+    pub fn get(&self, variable: u64, chunk: u64) -> Option<Arc<[u8]>> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let (data, last_used) = state.chunks.get_mut(&(variable, chunk))?;
+        let data = data.clone();
+        let previous = std::mem::replace(last_used, tick);
+        state.lru.remove(&previous);
+        state.lru.insert(tick, (variable, chunk));
+        Some(data)
+    }
+
+    /// Insert a decoded chunk and evict least recently used chunks to stay
+    /// within `max_bytes`. Chunks larger than the cache are not stored.
+    pub fn insert(&self, variable: u64, chunk: u64, data: Arc<[u8]>) {
+        let size = data.len() as u64;
+        if size > self.max_bytes {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.chunks.contains_key(&(variable, chunk)) {
+            return;
+        }
+        while state.bytes + size > self.max_bytes {
+            let Some((_, evicted)) = state.lru.pop_first() else {
+                break;
+            };
+            if let Some((data, _)) = state.chunks.remove(&evicted) {
+                state.bytes -= data.len() as u64;
+            }
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.chunks.insert((variable, chunk), (data, tick));
+        state.lru.insert(tick, (variable, chunk));
+        state.bytes += size;
+    }
+}
@@ -0,0 +1,124 @@
+//! Suggestions for chunk dimensions. Arrays are expected to have time as
+//! last dimension, e.g. `[lat, lon, time]`, like all Open-Meteo files.
+
+/// Uncompressed chunk size used by `ChunkSpec::auto`
+pub const DEFAULT_TARGET_CHUNK_BYTES: u64 = 64 * 1024;
+
+/// How an array is mostly read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessPattern {
+    /// Long time series of few locations. Chunks span the time dimension.
+    TimeSeries,
+    /// Whole fields of single time steps. Chunks contain one time step.
+    Spatial,
+    /// No preference, chunks are about equally long in all dimensions.
+    Balanced,
+}
+
+/// Chunk dimensions for `OmFileWriter::prepare_array`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkSpec {
+    /// Explicit chunk dimensions
+    Fixed(Vec<u64>),
+    /// Chunk dimensions from `suggest_chunks` for the element size of the array
+    Auto {
+        target_chunk_bytes: u64,
+        access_pattern: AccessPattern,
+    },
+}
+
+impl ChunkSpec {
+    /// Automatic chunks of about `DEFAULT_TARGET_CHUNK_BYTES`
+    pub fn auto(access_pattern: AccessPattern) -> Self {
+        Self::Auto {
+            target_chunk_bytes: DEFAULT_TARGET_CHUNK_BYTES,
+            access_pattern,
+        }
+    }
+
+    /// Chunk dimensions for an array of `dimensions` with elements of `element_size` bytes
+    pub fn resolve(self, dimensions: &[u64], element_size: usize) -> Vec<u64> {
+        match self {
+            ChunkSpec::Fixed(chunks) => chunks,
+            ChunkSpec::Auto {
+                target_chunk_bytes,
+                access_pattern,
+            } => suggest_chunks(dimensions, element_size, target_chunk_bytes, access_pattern),
+        }
+    }
+}
+
+impl From<Vec<u64>> for ChunkSpec {
+    fn from(chunks: Vec<u64>) -> Self {
+        ChunkSpec::Fixed(chunks)
+    }
+}
+
+/// Recommend chunk dimensions so that one uncompressed chunk has at most
+/// about `target_chunk_bytes`. Every chunk dimension is at least 1 and at
+/// most the array dimension.
+pub fn suggest_chunks(
+    dimensions: &[u64],
+    element_size: usize,
+    target_chunk_bytes: u64,
+    access_pattern: AccessPattern,
+) -> Vec<u64> {
+    let budget = (target_chunk_bytes / element_size.max(1) as u64).max(1);
+    let Some((&time, spatial)) = dimensions.split_last() else {
+        return vec![];
+    };
+    match access_pattern {
+        AccessPattern::Balanced => balanced(dimensions, budget),
+        AccessPattern::TimeSeries => {
+            let time_chunk = time.clamp(1, budget);
+            let mut chunks = balanced(spatial, budget / time_chunk);
+            chunks.push(time_chunk);
+            chunks
+        }
+        AccessPattern::Spatial => {
+            let mut chunks = balanced(spatial, budget);
+            chunks.push(1);
+            chunks
+        }
+    }
+}
+
+/// Spread `budget` elements evenly over all dimensions. Dimensions shorter
+/// than their share are taken completely and leave more for the others.
+fn balanced(dimensions: &[u64], mut budget: u64) -> Vec<u64> {
+    let mut chunks = vec![0u64; dimensions.len()];
+    loop {
+        let open: Vec<usize> = (0..dimensions.len()).filter(|&i| chunks[i] == 0).collect();
+        if open.is_empty() {
+            return chunks;
+        }
+        let side = integer_root(budget, open.len() as u32);
+        let mut saturated = false;
+        for &i in &open {
+            let dimension = dimensions[i].max(1);
+            if dimension <= side {
+                chunks[i] = dimension;
+                budget = (budget / dimension).max(1);
+                saturated = true;
+            }
+        }
+        if !saturated {
+            for &i in &open {
+                chunks[i] = side;
+            }
+            return chunks;
+        }
+    }
+}
+
+/// Largest `x >= 1` with `x^n <= value`
+fn integer_root(value: u64, n: u32) -> u64 {
+    let mut x = (value as f64).powf(1.0 / n as f64).round().max(1.0) as u64;
+    while x > 1 && x.checked_pow(n).is_none_or(|p| p > value) {
+        x -= 1;
+    }
+    while (x + 1).checked_pow(n).is_some_and(|p| p <= value) {
+        x += 1;
+    }
+    x
+}
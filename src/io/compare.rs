@@ -0,0 +1,226 @@
+//! Compare two files, e.g. to validate a conversion or new compression
+//! parameters. Variables are matched by name, unnamed children by position.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::utils::divide_rounded_up;
+use ndarray::ArrayD;
+use std::ops::Range;
+
+/// One difference between two files. `path` names the variable with the
+/// names of all parents separated by `/`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// The variable only exists in the first or only in the second file
+    MissingVariable { path: String, in_first: bool },
+    /// Data type, layout, compression or scalar value differ
+    Metadata {
+        path: String,
+        field: String,
+        first: String,
+        second: String,
+    },
+    /// `count` values in `region` differ by more than the tolerance. NaN in
+    /// only one of the files counts as infinite difference.
+    Data {
+        path: String,
+        region: Vec<Range<u64>>,
+        count: u64,
+        max_difference: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    pub differences: Vec<Difference>,
+}
+
+impl DiffReport {
+    /// Whether no differences were found
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// Compare the variable trees of `first` and `second`. Numeric arrays with
+/// equal dimensions are compared chunk by chunk as `f64`, values are equal if
+/// they differ by at most `tolerance`. Each chunk of `first` with differences
+/// is reported as one region. String arrays are only compared by metadata.
+pub fn diff<A: OmFileReaderBackend, B: OmFileReaderBackend>(
+    first: &OmFileReader<A>,
+    second: &OmFileReader<B>,
+    tolerance: f64,
+) -> Result<DiffReport, OmFilesRsError> {
+    let mut report = DiffReport::default();
+    let path = first.get_name().unwrap_or_default();
+    compare_variable(first, second, &path, tolerance, &mut report)?;
+    Ok(report)
+}
+
+fn compare_variable<A: OmFileReaderBackend, B: OmFileReaderBackend>(
+    first: &OmFileReader<A>,
+    second: &OmFileReader<B>,
+    path: &str,
+    tolerance: f64,
+    report: &mut DiffReport,
+) -> Result<(), OmFilesRsError> {
+    let mut metadata = |field: &str, a: String, b: String| {
+        if a != b {
+            report.differences.push(Difference::Metadata {
+                path: path.to_string(),
+                field: field.to_string(),
+                first: a,
+                second: b,
+            });
+        }
+    };
+    let data_type = first.data_type();
+    metadata(
+        "data_type",
+        format!("{:?}", data_type),
+        format!("{:?}", second.data_type()),
+    );
+    if data_type.is_array() && second.data_type().is_array() {
+        metadata(
+            "dimensions",
+            format!("{:?}", first.get_dimensions()),
+            format!("{:?}", second.get_dimensions()),
+        );
+        metadata(
+            "chunks",
+            format!("{:?}", first.get_chunk_dimensions()),
+            format!("{:?}", second.get_chunk_dimensions()),
+        );
+        metadata(
+            "compression",
+            format!("{:?}", first.compression()),
+            format!("{:?}", second.compression()),
+        );
+        metadata(
+            "scale_factor",
+            first.scale_factor().to_string(),
+            second.scale_factor().to_string(),
+        );
+        metadata(
+            "add_offset",
+            first.add_offset().to_string(),
+            second.add_offset().to_string(),
+        );
+        let numeric =
+            data_type.element_size().is_some() && second.data_type().element_size().is_some();
+        if numeric && first.get_dimensions() == second.get_dimensions() {
+            compare_data(first, second, path, tolerance, report)?;
+        }
+    } else if !data_type.is_array() && data_type == second.data_type() {
+        let first_value = first.scalar_string();
+        let second_value = second.scalar_string();
+        if first_value != second_value {
+            report.differences.push(Difference::Metadata {
+                path: path.to_string(),
+                field: "value".to_string(),
+                first: first_value.unwrap_or_default(),
+                second: second_value.unwrap_or_default(),
+            });
+        }
+    }
+
+    let first_children = children_by_key(first);
+    let second_children = children_by_key(second);
+    for (key, child) in &first_children {
+        let child_path = format!("{}/{}", path, key);
+        match second_children.iter().find(|(k, _)| k == key) {
+            Some((_, other)) => compare_variable(child, other, &child_path, tolerance, report)?,
+            None => report.differences.push(Difference::MissingVariable {
+                path: child_path,
+                in_first: true,
+            }),
+        }
+    }
+    for (key, _) in &second_children {
+        if !first_children.iter().any(|(k, _)| k == key) {
+            report.differences.push(Difference::MissingVariable {
+                path: format!("{}/{}", path, key),
+                in_first: false,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Children with their name, or `#<index>` if they have no name
+fn children_by_key<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+) -> Vec<(String, OmFileReader<Backend>)> {
+    (0..reader.number_of_children())
+        .filter_map(|i| {
+            let child = reader.get_child(i)?;
+            let key = child.get_name().unwrap_or_else(|| format!("#{}", i));
+            Some((key, child))
+        })
+        .collect()
+}
+
+fn compare_data<A: OmFileReaderBackend, B: OmFileReaderBackend>(
+    first: &OmFileReader<A>,
+    second: &OmFileReader<B>,
+    path: &str,
+    tolerance: f64,
+    report: &mut DiffReport,
+) -> Result<(), OmFilesRsError> {
+    let dimensions = first.get_dimensions();
+    let chunks = first.get_chunk_dimensions();
+    if dimensions.contains(&0) {
+        return Ok(());
+    }
+    let grid: Vec<usize> = dimensions
+        .iter()
+        .zip(chunks.iter())
+        .map(|(&dimension, &chunk)| divide_rounded_up(dimension as usize, chunk as usize))
+        .collect();
+    for index in ndarray::indices(grid) {
+        let region: Vec<Range<u64>> = (0..dimensions.len())
+            .map(|i| {
+                let start = index[i] as u64 * chunks[i];
+                start..(start + chunks[i]).min(dimensions[i])
+            })
+            .collect();
+        let a = read_as_f64(first, &region)?;
+        let b = read_as_f64(second, &region)?;
+        let mut count = 0;
+        let mut max_difference: f64 = 0.0;
+        for (&a, &b) in a.iter().zip(b.iter()) {
+            if a.is_nan() && b.is_nan() {
+                continue;
+            }
+            let difference = if a.is_nan() || b.is_nan() {
+                f64::INFINITY
+            } else {
+                (a - b).abs()
+            };
+            if difference > tolerance {
+                count += 1;
+                max_difference = max_difference.max(difference);
+            }
+        }
+        if count > 0 {
+            report.differences.push(Difference::Data {
+                path: path.to_string(),
+                region,
+                count,
+                max_difference,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn read_as_f64<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+    region: &[Range<u64>],
+) -> Result<ArrayD<f64>, OmFilesRsError> {
+    reader
+        .read_dynamic(region)?
+        .to_f64()
+        .ok_or(OmFilesRsError::InvalidDataType)
+}
@@ -0,0 +1,382 @@
+use crate::backend::backends::{OmFileReaderBackend, OmFileWriterBackend};
+use crate::core::data_types::{DataType, OmFileArrayDataType, OmFileScalarDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::io::writer::{
+    OmFileWriter, OmOffsetSize, CHUNK_MAX_VARIABLE, CHUNK_MIN_VARIABLE, DIMENSION_NAMES_VARIABLE,
+};
+use crate::utils::divide_rounded_up;
+use std::ops::Range;
+
+/// Default upper bound for the number of elements read at once while copying
+pub(crate) const DEFAULT_MAX_READ_ELEMENTS: u64 = 16 * 1024 * 1024;
+
+/// Copy a variable and all of its children into `writer`. Arrays are copied
+/// block by block in their native data type, keeping chunk dimensions,
+/// compression, scale factor and offset. Deterministic writers replace codecs
+/// that are not deterministic, see `OmFileWriter::set_deterministic`.
+/// Returns the offset and size of the copied variable. The caller still has to
+/// write the trailer.
+pub fn copy_variable<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let mut children = Vec::with_capacity(reader.number_of_children() as usize);
+    for i in 0..reader.number_of_children() {
+        if let Some(child) = reader.get_child(i) {
+            children.push(copy_variable(&child, writer)?);
+        }
+    }
+
+    let name = reader.get_name().unwrap_or_default();
+    match reader.data_type() {
+        DataType::None => Err(OmFilesRsError::InvalidDataType),
+        DataType::Int8 => copy_scalar::<i8, _, _>(reader, writer, &name, &children),
+        DataType::Uint8 => copy_scalar::<u8, _, _>(reader, writer, &name, &children),
+        DataType::Int16 => copy_scalar::<i16, _, _>(reader, writer, &name, &children),
+        DataType::Uint16 => copy_scalar::<u16, _, _>(reader, writer, &name, &children),
+        DataType::Int32 => copy_scalar::<i32, _, _>(reader, writer, &name, &children),
+        DataType::Uint32 => copy_scalar::<u32, _, _>(reader, writer, &name, &children),
+        DataType::Int64 => copy_scalar::<i64, _, _>(reader, writer, &name, &children),
+        DataType::Uint64 => copy_scalar::<u64, _, _>(reader, writer, &name, &children),
+        DataType::Float => copy_scalar::<f32, _, _>(reader, writer, &name, &children),
+        DataType::Double => copy_scalar::<f64, _, _>(reader, writer, &name, &children),
+        DataType::String => copy_scalar::<String, _, _>(reader, writer, &name, &children),
+        DataType::Int8Array => copy_array::<i8, _, _>(reader, writer, &name, &children),
+        DataType::Uint8Array => copy_array::<u8, _, _>(reader, writer, &name, &children),
+        DataType::Int16Array => copy_array::<i16, _, _>(reader, writer, &name, &children),
+        DataType::Uint16Array => copy_array::<u16, _, _>(reader, writer, &name, &children),
+        DataType::Int32Array => copy_array::<i32, _, _>(reader, writer, &name, &children),
+        DataType::Uint32Array => copy_array::<u32, _, _>(reader, writer, &name, &children),
+        DataType::Int64Array => copy_array::<i64, _, _>(reader, writer, &name, &children),
+        DataType::Uint64Array => copy_array::<u64, _, _>(reader, writer, &name, &children),
+        DataType::FloatArray => copy_array::<f32, _, _>(reader, writer, &name, &children),
+        DataType::DoubleArray => copy_array::<f64, _, _>(reader, writer, &name, &children),
+        DataType::StringArray => copy_string_array(reader, writer, &name, &children),
+    }
+}
+
+fn copy_scalar<T, Backend, WriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    name: &str,
+    children: &[OmOffsetSize],
+) -> Result<OmOffsetSize, OmFilesRsError>
+where
+    T: OmFileScalarDataType,
+    Backend: OmFileReaderBackend,
+    WriterBackend: OmFileWriterBackend,
+{
+    let value = reader
+        .read_scalar::<T>()
+        .ok_or(OmFilesRsError::InvalidDataType)?;
+    writer.write_scalar(value, name, children)
+}
+
+fn copy_array<T, Backend, WriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    name: &str,
+    children: &[OmOffsetSize],
+) -> Result<OmOffsetSize, OmFilesRsError>
+where
+    T: OmFileArrayDataType + Clone + Default,
+    Backend: OmFileReaderBackend,
+    WriterBackend: OmFileWriterBackend,
+{
+    let axes: Vec<usize> = (0..reader.get_dimensions().len()).collect();
+    let chunks = reader.get_chunk_dimensions().to_vec();
+    let max = DEFAULT_MAX_READ_ELEMENTS;
+    copy_blocks::<T, _, _>(reader, writer, name, children, &axes, &chunks, max)
+}
+
+/// Copy an array variable into `writer` using new chunk dimensions. Children
+/// are copied unchanged, except chunk statistics that no longer match the
+/// chunks. Data is streamed in blocks of at most
+/// `max_read_elements` elements (default 16 Mi), with a minimum of one chunk.
+/// Returns the offset and size of the new variable. The caller still has to
+/// write the trailer.
+pub fn rechunk<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    chunks: &[u64],
+    max_read_elements: Option<u64>,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    if chunks.len() != reader.get_dimensions().len() {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    let max_read_elements = max_read_elements.unwrap_or(DEFAULT_MAX_READ_ELEMENTS);
+
+    let mut children = Vec::with_capacity(reader.number_of_children() as usize);
+    for i in 0..reader.number_of_children() {
+        let Some(child) = reader.get_child(i) else {
+            continue;
+        };
+        if !is_chunk_statistics(&child) {
+            children.push(copy_variable(&child, writer)?);
+        }
+    }
+
+    let axes: Vec<usize> = (0..chunks.len()).collect();
+    copy_array_with_layout(reader, writer, &children, &axes, chunks, max_read_elements)
+}
+
+/// Copy an array variable into `writer` with its dimensions reordered.
+/// Dimension `i` of the new variable is dimension `axes[i]` of the source,
+/// like [`ndarray::ArrayBase::permuted_axes`]. Chunk dimensions and dimension
+/// names are permuted accordingly, chunk statistics are dropped and other
+/// children are copied unchanged.
+/// Blocks of at most `max_read_elements` elements (default 16 Mi) are read,
+/// permuted in memory and written.
+/// Returns the offset and size of the new variable. The caller still has to
+/// write the trailer.
+pub fn copy_transposed<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    axes: &[usize],
+    max_read_elements: Option<u64>,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let n_dims = reader.get_dimensions().len();
+    let mut seen = vec![false; n_dims];
+    for &axis in axes {
+        if axis >= n_dims || seen[axis] {
+            return Err(OmFilesRsError::InvalidAxesPermutation);
+        }
+        seen[axis] = true;
+    }
+    if axes.len() != n_dims {
+        return Err(OmFilesRsError::InvalidAxesPermutation);
+    }
+    let max_read_elements = max_read_elements.unwrap_or(DEFAULT_MAX_READ_ELEMENTS);
+
+    let mut children = Vec::with_capacity(reader.number_of_children() as usize);
+    for i in 0..reader.number_of_children() {
+        let Some(child) = reader.get_child(i) else {
+            continue;
+        };
+        if child.get_name().as_deref() == Some(DIMENSION_NAMES_VARIABLE) {
+            let names = reader
+                .get_dimension_names()
+                .filter(|names| names.len() == n_dims)
+                .ok_or(OmFilesRsError::MismatchingCubeDimensionLength)?;
+            let permuted: Vec<&str> = axes.iter().map(|&a| names[a].as_str()).collect();
+            children.push(writer.write_dimension_names(&permuted)?);
+        } else if !is_chunk_statistics(&child) {
+            children.push(copy_variable(&child, writer)?);
+        }
+    }
+
+    let source_chunks = reader.get_chunk_dimensions();
+    let chunks: Vec<u64> = axes.iter().map(|&a| source_chunks[a]).collect();
+    copy_array_with_layout(reader, writer, &children, axes, &chunks, max_read_elements)
+}
+
+/// Chunk statistics describe the chunk grid of the source and are dropped if
+/// the layout changes.
+fn is_chunk_statistics<Backend: OmFileReaderBackend>(child: &OmFileReader<Backend>) -> bool {
+    matches!(
+        child.get_name().as_deref(),
+        Some(CHUNK_MIN_VARIABLE) | Some(CHUNK_MAX_VARIABLE)
+    )
+}
+
+pub(crate) fn copy_array_with_layout<
+    Backend: OmFileReaderBackend,
+    WriterBackend: OmFileWriterBackend,
+>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    children: &[OmOffsetSize],
+    axes: &[usize],
+    chunks: &[u64],
+    max_read_elements: u64,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let name = reader.get_name().unwrap_or_default();
+    let n = &name;
+    let c = children;
+    let m = max_read_elements;
+    match reader.data_type() {
+        DataType::Int8Array => copy_blocks::<i8, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Uint8Array => copy_blocks::<u8, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Int16Array => copy_blocks::<i16, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Uint16Array => copy_blocks::<u16, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Int32Array => copy_blocks::<i32, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Uint32Array => copy_blocks::<u32, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Int64Array => copy_blocks::<i64, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::Uint64Array => copy_blocks::<u64, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::FloatArray => copy_blocks::<f32, _, _>(reader, writer, n, c, axes, chunks, m),
+        DataType::DoubleArray => copy_blocks::<f64, _, _>(reader, writer, n, c, axes, chunks, m),
+        _ => Err(OmFilesRsError::InvalidDataType),
+    }
+}
+
+/// Write an array block by block. Dimension `i` of the new array is dimension
+/// `axes[i]` of the source.
+fn copy_blocks<T, Backend, WriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    name: &str,
+    children: &[OmOffsetSize],
+    axes: &[usize],
+    chunks: &[u64],
+    max_read_elements: u64,
+) -> Result<OmOffsetSize, OmFilesRsError>
+where
+    T: OmFileArrayDataType + Clone + Default,
+    Backend: OmFileReaderBackend,
+    WriterBackend: OmFileWriterBackend,
+{
+    let source_dimensions = reader.get_dimensions();
+    let source_chunks = reader.get_chunk_dimensions();
+    let dimensions: Vec<u64> = axes.iter().map(|&a| source_dimensions[a]).collect();
+    let source_chunks: Vec<u64> = axes.iter().map(|&a| source_chunks[a]).collect();
+
+    let compression = writer.deterministic_compression(reader.compression(), T::DATA_TYPE_ARRAY);
+    let mut array_writer = writer.prepare_array::<T>(
+        dimensions.clone(),
+        chunks.to_vec(),
+        compression,
+        reader.scale_factor(),
+        reader.add_offset(),
+    )?;
+
+    for block in read_blocks(&dimensions, chunks, &source_chunks, max_read_elements) {
+        let mut source_block = vec![0..0; block.len()];
+        for (range, &axis) in block.into_iter().zip(axes) {
+            source_block[axis] = range;
+        }
+        let data = reader.read::<T>(&source_block, None, None)?;
+        let data = data.permuted_axes(axes);
+        array_writer.write_data(data.as_standard_layout().view(), None, None)?;
+    }
+
+    let variable_meta = array_writer.finalize();
+    writer.write_array(variable_meta, name, children)
+}
+
+/// Split an array into blocks that can be passed to the writer one after
+/// another. The writer expects chunks in row-major order, therefore a block
+/// spans a single chunk in all dimensions before some dimension `k`, several
+/// chunks in dimension `k` and the full extent of all dimensions after `k`.
+/// `k` is the outermost dimension for which a block fits into
+/// `max_read_elements`. If possible, the extent in dimension `k` is a multiple
+/// of the source chunk dimension, so that source chunks are decoded only once.
+pub(crate) fn read_blocks(
+    dimensions: &[u64],
+    chunks: &[u64],
+    source_chunks: &[u64],
+    max_read_elements: u64,
+) -> Vec<Vec<Range<u64>>> {
+    let n = dimensions.len();
+    if n == 0 || dimensions.contains(&0) {
+        return vec![];
+    }
+    let block_size = |k: usize| -> u64 {
+        chunks[..=k].iter().product::<u64>() * dimensions[k + 1..].iter().product::<u64>()
+    };
+    let k = (0..n)
+        .find(|&k| block_size(k) <= max_read_elements)
+        .unwrap_or(n - 1);
+
+    let multiplier = (max_read_elements / block_size(k)).max(1);
+    let mut extent = chunks[k] * multiplier;
+    let aligned = lcm(chunks[k], source_chunks[k]);
+    if aligned <= extent {
+        extent = extent / aligned * aligned;
+    }
+    let extent = extent.min(dimensions[k]);
+
+    let steps: Vec<u64> = (0..=k)
+        .map(|i| if i == k { extent } else { chunks[i] })
+        .collect();
+    let grid: Vec<usize> = (0..=k)
+        .map(|i| divide_rounded_up(dimensions[i] as usize, steps[i] as usize))
+        .collect();
+
+    ndarray::indices(grid)
+        .into_iter()
+        .map(|index| {
+            (0..n)
+                .map(|i| {
+                    if i > k {
+                        return 0..dimensions[i];
+                    }
+                    let start = index[i] as u64 * steps[i];
+                    start..(start + steps[i]).min(dimensions[i])
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Split `dim_read` into blocks of at most `max_read_elements` that are
+/// aligned to the chunks of the file, with a minimum of one chunk.
+pub(crate) fn aligned_tiles(
+    dimensions: &[u64],
+    chunks: &[u64],
+    dim_read: &[Range<u64>],
+    max_read_elements: u64,
+) -> Result<Vec<Vec<Range<u64>>>, OmFilesRsError> {
+    if dimensions.len() != dim_read.len() {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+        if range.start > range.end || range.end > dimension {
+            return Err(OmFilesRsError::DimensionOutOfBounds {
+                range: range.start as usize..range.end as usize,
+                allowed: dimension as usize,
+            });
+        }
+    }
+
+    let aligned_start: Vec<u64> = dim_read
+        .iter()
+        .zip(chunks.iter())
+        .map(|(range, &chunk)| range.start / chunk * chunk)
+        .collect();
+    let aligned_dimensions: Vec<u64> = dim_read
+        .iter()
+        .zip(aligned_start.iter())
+        .map(|(range, &start)| range.end - start)
+        .collect();
+    let blocks = read_blocks(&aligned_dimensions, chunks, chunks, max_read_elements);
+    Ok(blocks
+        .into_iter()
+        .map(|block| {
+            block
+                .iter()
+                .zip(aligned_start.iter().zip(dim_read.iter()))
+                .map(|(range, (&start, read))| {
+                    (range.start + start).max(read.start)..(range.end + start).min(read.end)
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|block_read| block_read.iter().all(|r| !r.is_empty()))
+        .collect())
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+fn copy_string_array<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    name: &str,
+    children: &[OmOffsetSize],
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let dimensions = reader.get_dimensions().to_vec();
+    let ranges: Vec<Range<u64>> = dimensions.iter().map(|&d| 0..d).collect();
+    let strings = reader.read_string_array(&ranges)?;
+
+    let mut string_writer = writer.prepare_string_array(dimensions)?;
+    string_writer.write_data(strings.view())?;
+    let variable_meta = string_writer.finalize()?;
+    writer.write_array(variable_meta, name, children)
+}
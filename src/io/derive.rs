@@ -0,0 +1,120 @@
+//! Variables computed element by element from other variables, e.g. wind
+//! speed from its u and v components.
+
+use crate::backend::backends::{OmFileReaderBackend, OmFileWriterBackend};
+use crate::core::compression::CompressionType;
+use crate::errors::OmFilesRsError;
+use crate::io::copy::{read_blocks, DEFAULT_MAX_READ_ELEMENTS};
+use crate::io::reader::OmFileReader;
+use crate::io::writer::{OmFileWriter, OmOffsetSize};
+use ndarray::ArrayD;
+
+/// Layout and encoding of the array written by `derive_variable`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedArraySpec {
+    /// Chunk dimensions, by default those of the first input
+    pub chunks: Option<Vec<u64>>,
+    pub compression: CompressionType,
+    /// Scale factor, by default the largest scale factor of all inputs, so
+    /// the result is at least as precise as the most precise input
+    pub scale_factor: Option<f32>,
+    pub add_offset: f32,
+}
+
+/// Write a float array `name` with `f` applied to the values of all `inputs`
+/// at every position. `f` receives one value per input in the order of
+/// `inputs`. It is not called if any input is NaN, the result is NaN then.
+///
+/// Inputs must have the same dimensions, but may have any numeric data type,
+/// scale factor and chunk dimensions. They are read as `f32` in blocks aligned
+/// to the output chunks, so memory does not depend on the size of the
+/// variables. Dimension names, time axis and grid of the first input are
+/// written with the result.
+///
+/// Returns the offset and size of the array. The caller still has to write
+/// the trailer.
+pub fn derive_variable<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    inputs: &[OmFileReader<Backend>],
+    f: impl Fn(&[f32]) -> f32,
+    file_writer: &mut OmFileWriter<WriterBackend>,
+    name: &str,
+    spec: &DerivedArraySpec,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let first = inputs.first().ok_or_else(|| {
+        OmFilesRsError::IncompatibleFiles("At least one input is required".to_string())
+    })?;
+    let dimensions = first.get_dimensions().to_vec();
+    for (i, input) in inputs.iter().enumerate() {
+        if input.get_dimensions() != dimensions {
+            return Err(OmFilesRsError::IncompatibleFiles(format!(
+                "Input {} has dimensions {:?} instead of {:?}",
+                i,
+                input.get_dimensions(),
+                dimensions
+            )));
+        }
+    }
+    let chunks = match &spec.chunks {
+        Some(chunks) if chunks.len() != dimensions.len() => {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        Some(chunks) => chunks.clone(),
+        None => first.get_chunk_dimensions().to_vec(),
+    };
+    let scale_factor = spec.scale_factor.unwrap_or_else(|| {
+        inputs
+            .iter()
+            .map(|input| input.scale_factor())
+            .fold(f32::MIN, f32::max)
+    });
+
+    let mut writer = file_writer.prepare_array::<f32>(
+        dimensions.clone(),
+        chunks.clone(),
+        spec.compression,
+        scale_factor,
+        spec.add_offset,
+    )?;
+    if let Some(names) = first.get_dimension_names() {
+        writer = writer.with_dimension_names(&names)?;
+    }
+    if let Some(time_axis) = first.get_time_axis() {
+        writer = writer.with_time_axis(time_axis);
+    }
+    if let Some(grid) = first.get_grid() {
+        writer = writer.with_grid(grid);
+    }
+
+    let max_read_elements = DEFAULT_MAX_READ_ELEMENTS / inputs.len() as u64;
+    let source_chunks = first.get_chunk_dimensions();
+    for block in read_blocks(&dimensions, &chunks, source_chunks, max_read_elements) {
+        let values = inputs
+            .iter()
+            .map(|input| input.read_converted::<f32>(&block))
+            .collect::<Result<Vec<ArrayD<f32>>, _>>()?;
+        let shape = values[0].raw_dim();
+        let values: Vec<&[f32]> = values
+            .iter()
+            .map(|array| array.as_slice().ok_or(OmFilesRsError::ArrayNotContiguous))
+            .collect::<Result<_, _>>()?;
+
+        let mut arguments = vec![0.0; inputs.len()];
+        let result: Vec<f32> = (0..values[0].len())
+            .map(|i| {
+                for (argument, input) in arguments.iter_mut().zip(&values) {
+                    *argument = input[i];
+                }
+                if arguments.iter().any(|x| x.is_nan()) {
+                    f32::NAN
+                } else {
+                    f(&arguments)
+                }
+            })
+            .collect();
+        let result = ArrayD::from_shape_vec(shape, result).expect("shape of the inputs");
+        writer.write_data(result.view(), None, None)?;
+    }
+
+    let variable_meta = writer.finalize();
+    file_writer.write_array(variable_meta, name, &[])
+}
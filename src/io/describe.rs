@@ -0,0 +1,47 @@
+//! Summary of the variable tree of a file returned by
+//! `OmFileReader::describe`. With the `serde` feature it can be serialized,
+//! e.g. to print JSON or YAML summaries of files.
+
+use crate::core::compression::CompressionType;
+use crate::core::data_types::DataType;
+
+/// One variable of a file together with all of its children
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableDescription {
+    pub name: Option<String>,
+    pub data_type: DataType,
+    /// Offset of the variable metadata in the file, `None` for legacy files
+    pub offset: Option<u64>,
+    /// Size of the variable metadata in bytes
+    pub metadata_bytes: u64,
+    /// Layout of arrays, `None` for scalars
+    pub array: Option<ArrayDescription>,
+    /// Value of numeric and string scalars formatted as text
+    pub value: Option<String>,
+    pub children: Vec<VariableDescription>,
+}
+
+/// Layout and compression of an array variable
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArrayDescription {
+    pub dimensions: Vec<u64>,
+    pub chunks: Vec<u64>,
+    pub compression: CompressionType,
+    pub scale_factor: f32,
+    pub add_offset: f32,
+    /// Size of the decompressed array in bytes, `None` for string arrays
+    pub uncompressed_bytes: Option<u64>,
+}
+
+impl VariableDescription {
+    /// Number of variables in this tree including this variable
+    pub fn count_variables(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(|child| child.count_variables())
+            .sum::<usize>()
+    }
+}
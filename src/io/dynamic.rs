@@ -0,0 +1,173 @@
+//! Reads without knowing the element type at compile time, e.g. for tools
+//! that dump or convert arbitrary variables.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use ndarray::ArrayD;
+use num_traits::{NumCast, ToPrimitive};
+use std::ops::Range;
+
+/// Array of any element type that can be stored in a file
+#[derive(Debug, Clone, PartialEq)]
+pub enum OmArray {
+    I8(ArrayD<i8>),
+    U8(ArrayD<u8>),
+    I16(ArrayD<i16>),
+    U16(ArrayD<u16>),
+    I32(ArrayD<i32>),
+    U32(ArrayD<u32>),
+    I64(ArrayD<i64>),
+    U64(ArrayD<u64>),
+    F32(ArrayD<f32>),
+    F64(ArrayD<f64>),
+    String(ArrayD<String>),
+}
+
+impl OmArray {
+    /// Data type of the variable the array was read from
+    pub fn data_type(&self) -> DataType {
+        match self {
+            OmArray::I8(_) => DataType::Int8Array,
+            OmArray::U8(_) => DataType::Uint8Array,
+            OmArray::I16(_) => DataType::Int16Array,
+            OmArray::U16(_) => DataType::Uint16Array,
+            OmArray::I32(_) => DataType::Int32Array,
+            OmArray::U32(_) => DataType::Uint32Array,
+            OmArray::I64(_) => DataType::Int64Array,
+            OmArray::U64(_) => DataType::Uint64Array,
+            OmArray::F32(_) => DataType::FloatArray,
+            OmArray::F64(_) => DataType::DoubleArray,
+            OmArray::String(_) => DataType::StringArray,
+        }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            OmArray::I8(array) => array.shape(),
+            OmArray::U8(array) => array.shape(),
+            OmArray::I16(array) => array.shape(),
+            OmArray::U16(array) => array.shape(),
+            OmArray::I32(array) => array.shape(),
+            OmArray::U32(array) => array.shape(),
+            OmArray::I64(array) => array.shape(),
+            OmArray::U64(array) => array.shape(),
+            OmArray::F32(array) => array.shape(),
+            OmArray::F64(array) => array.shape(),
+            OmArray::String(array) => array.shape(),
+        }
+    }
+
+    /// Numeric values converted to `f64`. 64 bit integers beyond 2^53 lose
+    /// precision. Returns `None` for strings.
+    pub fn to_f64(&self) -> Option<ArrayD<f64>> {
+        fn convert<T: ToPrimitive>(array: &ArrayD<T>) -> ArrayD<f64> {
+            array.map(|v| v.to_f64().unwrap_or(f64::NAN))
+        }
+        match self {
+            OmArray::I8(array) => Some(convert(array)),
+            OmArray::U8(array) => Some(convert(array)),
+            OmArray::I16(array) => Some(convert(array)),
+            OmArray::U16(array) => Some(convert(array)),
+            OmArray::I32(array) => Some(convert(array)),
+            OmArray::U32(array) => Some(convert(array)),
+            OmArray::I64(array) => Some(convert(array)),
+            OmArray::U64(array) => Some(convert(array)),
+            OmArray::F32(array) => Some(convert(array)),
+            OmArray::F64(array) => Some(array.clone()),
+            OmArray::String(_) => None,
+        }
+    }
+
+    /// Numeric values in row-major order as little-endian bytes. Returns
+    /// `None` for strings.
+    pub fn to_le_bytes(&self) -> Option<Vec<u8>> {
+        fn bytes<T: Copy, const N: usize>(array: &ArrayD<T>, f: fn(T) -> [u8; N]) -> Vec<u8> {
+            array.iter().flat_map(|&v| f(v)).collect()
+        }
+        match self {
+            OmArray::I8(array) => Some(bytes(array, i8::to_le_bytes)),
+            OmArray::U8(array) => Some(bytes(array, u8::to_le_bytes)),
+            OmArray::I16(array) => Some(bytes(array, i16::to_le_bytes)),
+            OmArray::U16(array) => Some(bytes(array, u16::to_le_bytes)),
+            OmArray::I32(array) => Some(bytes(array, i32::to_le_bytes)),
+            OmArray::U32(array) => Some(bytes(array, u32::to_le_bytes)),
+            OmArray::I64(array) => Some(bytes(array, i64::to_le_bytes)),
+            OmArray::U64(array) => Some(bytes(array, u64::to_le_bytes)),
+            OmArray::F32(array) => Some(bytes(array, f32::to_le_bytes)),
+            OmArray::F64(array) => Some(bytes(array, f64::to_le_bytes)),
+            OmArray::String(_) => None,
+        }
+    }
+
+    /// Numeric values converted to `T`. Fractional parts are truncated when
+    /// converting to integers. Fails if any value, including NaN for integer
+    /// targets, cannot be represented by `T`.
+    pub fn convert<T: OmFileArrayDataType + NumCast>(&self) -> Result<ArrayD<T>, OmFilesRsError> {
+        fn convert<S: ToPrimitive + Copy, T: OmFileArrayDataType + NumCast>(
+            array: &ArrayD<S>,
+            from: DataType,
+        ) -> Result<ArrayD<T>, OmFilesRsError> {
+            let values = array
+                .iter()
+                .map(|&v| T::from(v))
+                .collect::<Option<Vec<T>>>()
+                .ok_or(OmFilesRsError::ConversionOutOfRange {
+                    from,
+                    to: T::DATA_TYPE_ARRAY,
+                })?;
+            Ok(ArrayD::from_shape_vec(array.raw_dim(), values)
+                .expect("shape matches the number of values"))
+        }
+        let from = self.data_type();
+        match self {
+            OmArray::I8(array) => convert(array, from),
+            OmArray::U8(array) => convert(array, from),
+            OmArray::I16(array) => convert(array, from),
+            OmArray::U16(array) => convert(array, from),
+            OmArray::I32(array) => convert(array, from),
+            OmArray::U32(array) => convert(array, from),
+            OmArray::I64(array) => convert(array, from),
+            OmArray::U64(array) => convert(array, from),
+            OmArray::F32(array) => convert(array, from),
+            OmArray::F64(array) => convert(array, from),
+            OmArray::String(_) => Err(OmFilesRsError::InvalidDataType),
+        }
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
+    /// Read a selection with the element type of the variable. Scalars and
+    /// variables without data return `InvalidDataType`.
+    pub fn read_dynamic(&self, dim_read: &[Range<u64>]) -> Result<OmArray, OmFilesRsError> {
+        Ok(match self.data_type() {
+            DataType::Int8Array => OmArray::I8(self.read(dim_read, None, None)?),
+            DataType::Uint8Array => OmArray::U8(self.read(dim_read, None, None)?),
+            DataType::Int16Array => OmArray::I16(self.read(dim_read, None, None)?),
+            DataType::Uint16Array => OmArray::U16(self.read(dim_read, None, None)?),
+            DataType::Int32Array => OmArray::I32(self.read(dim_read, None, None)?),
+            DataType::Uint32Array => OmArray::U32(self.read(dim_read, None, None)?),
+            DataType::Int64Array => OmArray::I64(self.read(dim_read, None, None)?),
+            DataType::Uint64Array => OmArray::U64(self.read(dim_read, None, None)?),
+            DataType::FloatArray => OmArray::F32(self.read(dim_read, None, None)?),
+            DataType::DoubleArray => OmArray::F64(self.read(dim_read, None, None)?),
+            DataType::StringArray => OmArray::String(self.read_string_array(dim_read)?),
+            _ => return Err(OmFilesRsError::InvalidDataType),
+        })
+    }
+
+    /// Read a selection as `T` regardless of the stored element type, e.g. an
+    /// `i16` variable as `f32`. Scale factor and offset of quantizing codecs
+    /// are applied by the decoder before values are converted with
+    /// `OmArray::convert`.
+    pub fn read_converted<T: OmFileArrayDataType + NumCast + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        if self.data_type() == T::DATA_TYPE_ARRAY {
+            return self.read(dim_read, None, None);
+        }
+        self.read_dynamic(dim_read)?.convert()
+    }
+}
@@ -0,0 +1,179 @@
+//! Export of small selections to tabular formats for analytics tools. Every
+//! element becomes one row with its index in each dimension followed by its
+//! value.
+//!
+//! Dimension columns are named after the dimension names of the variable, or
+//! `dim0`, `dim1`, ... if none are stored. The value column is named after
+//! the variable. The selection is read into memory at once.
+//!
+//! NumPy files keep the shape and element type of the selection instead, so
+//! they can be loaded with `numpy.load` without any om bindings.
+
+use crate::backend::backends::{map_io_error, OmFileReaderBackend};
+use crate::errors::OmFilesRsError;
+use crate::io::dynamic::OmArray;
+use crate::io::npy::write_npy;
+use crate::io::reader::OmFileReader;
+use ndarray::{ArrayD, Dimension};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Write `ranges` of the variable as CSV with a header row to `path`
+pub fn to_csv<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+    ranges: &[Range<u64>],
+    path: impl AsRef<Path>,
+) -> Result<(), OmFilesRsError> {
+    let file = File::create(path).map_err(map_io_error)?;
+    let mut out = BufWriter::new(file);
+    write_csv(reader, ranges, &mut out)?;
+    out.flush().map_err(map_io_error)
+}
+
+/// Like `to_csv`, but writes to `out`, e.g. stdout or a buffer
+pub fn write_csv<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+    ranges: &[Range<u64>],
+    out: &mut impl Write,
+) -> Result<(), OmFilesRsError> {
+    let columns = column_names(reader);
+    let header: Vec<String> = columns.iter().map(|name| csv_field(name)).collect();
+    writeln!(out, "{}", header.join(",")).map_err(map_io_error)?;
+
+    let offsets: Vec<u64> = ranges.iter().map(|range| range.start).collect();
+    match reader.read_dynamic(ranges)? {
+        OmArray::I8(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::U8(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::I16(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::U16(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::I32(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::U32(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::I64(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::U64(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::F32(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::F64(array) => write_csv_rows(out, &array, &offsets),
+        OmArray::String(array) => write_csv_rows(out, &array.map(|s| csv_field(s)), &offsets),
+    }
+}
+
+/// Write `ranges` of the variable as Parquet file to `path`. Dimension
+/// columns are `UInt64`, the value column has the Arrow type of
+/// `OmFileReader::read_arrow`.
+#[cfg(feature = "parquet")]
+pub fn to_parquet<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+    ranges: &[Range<u64>],
+    path: impl AsRef<Path>,
+) -> Result<(), OmFilesRsError> {
+    use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let columns = column_names(reader);
+    let values = reader.read_arrow(ranges)?.column(0).clone();
+    let shape: Vec<usize> = ranges
+        .iter()
+        .map(|range| range.end.saturating_sub(range.start) as usize)
+        .collect();
+
+    let mut indices: Vec<Vec<u64>> = vec![Vec::with_capacity(values.len()); shape.len()];
+    for index in ndarray::indices(shape) {
+        for (dimension, &i) in index.slice().iter().enumerate() {
+            indices[dimension].push(ranges[dimension].start + i as u64);
+        }
+    }
+    let arrays = indices
+        .into_iter()
+        .map(|column| Arc::new(UInt64Array::from(column)) as ArrayRef)
+        .chain(std::iter::once(values));
+    let batch = RecordBatch::try_from_iter(columns.into_iter().zip(arrays))
+        .map_err(|e| OmFilesRsError::EncoderError(e.to_string()))?;
+
+    let parquet_error = |e: parquet::errors::ParquetError| OmFilesRsError::FileWriterError {
+        errno: 0,
+        error: e.to_string(),
+    };
+    let file = File::create(path).map_err(map_io_error)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(parquet_error)?;
+    writer.write(&batch).map_err(parquet_error)?;
+    writer.close().map_err(parquet_error)?;
+    Ok(())
+}
+
+/// Write `ranges` of the variable as NumPy `.npy` file to `out`, with the
+/// element type of the variable and the shape of the selection. String
+/// arrays become fixed-width unicode arrays.
+pub fn to_npy<Backend: OmFileReaderBackend>(
+    reader: &OmFileReader<Backend>,
+    ranges: &[Range<u64>],
+    out: &mut impl Write,
+) -> Result<(), OmFilesRsError> {
+    write_npy(&reader.read_dynamic(ranges)?, out)
+}
+
+/// Write selections of several variables as NumPy `.npz` archive to `out`.
+/// Each `(name, reader, ranges)` becomes the array `name` of the archive.
+/// Entries are stored without compression, like `numpy.savez` does.
+#[cfg(feature = "archive")]
+pub fn to_npz<'a, Backend: OmFileReaderBackend + 'a>(
+    variables: impl IntoIterator<Item = (&'a str, &'a OmFileReader<Backend>, &'a [Range<u64>])>,
+    out: &mut (impl Write + std::io::Seek),
+) -> Result<(), OmFilesRsError> {
+    use crate::io::npy::to_npy_bytes;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    let zip_error = |e: zip::result::ZipError| OmFilesRsError::FileWriterError {
+        errno: 0,
+        error: e.to_string(),
+    };
+    let mut zip = ZipWriter::new(out);
+    for (name, reader, ranges) in variables {
+        let data = to_npy_bytes(&reader.read_dynamic(ranges)?)?;
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(data.len() as u64 >= u32::MAX as u64);
+        zip.start_file(format!("{}.npy", name), options)
+            .map_err(zip_error)?;
+        zip.write_all(&data).map_err(map_io_error)?;
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+/// Dimension columns followed by the value column
+fn column_names<Backend: OmFileReaderBackend>(reader: &OmFileReader<Backend>) -> Vec<String> {
+    let n_dims = reader.get_dimensions().len();
+    let mut columns = reader
+        .get_dimension_names()
+        .filter(|names| names.len() == n_dims)
+        .unwrap_or_else(|| (0..n_dims).map(|i| format!("dim{}", i)).collect());
+    columns.push(reader.get_name().unwrap_or_else(|| "value".to_string()));
+    columns
+}
+
+fn write_csv_rows<T: Display>(
+    out: &mut impl Write,
+    array: &ArrayD<T>,
+    offsets: &[u64],
+) -> Result<(), OmFilesRsError> {
+    for (index, value) in array.indexed_iter() {
+        for (&i, offset) in index.slice().iter().zip(offsets) {
+            write!(out, "{},", offset + i as u64).map_err(map_io_error)?;
+        }
+        writeln!(out, "{}", value).map_err(map_io_error)?;
+    }
+    Ok(())
+}
+
+/// Quote fields that contain separators, quotes or line breaks
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,215 @@
+use crate::backend::mmapfile::{MmapFile, Mode};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+#[cfg(feature = "notify")]
+use crate::io::watcher::PathWatcher;
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Identifies the file behind a path. Replacing a file with a rename creates
+/// a new inode, while the reader still maps the old one.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileIdentity {
+    #[cfg(unix)]
+    device: u64,
+    #[cfg(unix)]
+    inode: u64,
+    length: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileIdentity {
+    pub(crate) fn new(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+        Self {
+            #[cfg(unix)]
+            device: metadata.dev(),
+            #[cfg(unix)]
+            inode: metadata.ino(),
+            length: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Identity of the file that `path` currently refers to
+    pub(crate) fn of_path(path: &Path) -> Option<Self> {
+        std::fs::metadata(path)
+            .ok()
+            .map(|metadata| Self::new(&metadata))
+    }
+}
+
+struct ManagedFile {
+    reader: OmFileReader<MmapFile>,
+    identity: FileIdentity,
+    last_access: Instant,
+    /// Changes are reported by the watcher, `get` does not check the path
+    watched: bool,
+}
+
+/// Cache of open readers by path for services that read from many files.
+///
+/// At most `max_open` files are kept open, the least recently used file is
+/// closed first. Files that were not used for `ttl` are closed as well.
+/// Every `get` checks that the path still refers to the opened file, files
+/// that were replaced, e.g. by `OmFileWriter::create_atomic`, are reopened
+/// transparently. Readers returned earlier keep their mapping alive, so reads
+/// in flight are never affected by closing or reopening a file.
+pub struct OmFileManager {
+    max_open: usize,
+    ttl: Duration,
+    files: Arc<Mutex<HashMap<PathBuf, ManagedFile>>>,
+    /// Incremented for every change reported by the watcher
+    changes: Arc<AtomicU64>,
+    #[cfg(feature = "notify")]
+    watcher: Option<Mutex<PathWatcher>>,
+}
+
+impl OmFileManager {
+    pub fn new(max_open: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            max_open: max_open.get(),
+            ttl,
+            files: Arc::default(),
+            changes: Arc::default(),
+            #[cfg(feature = "notify")]
+            watcher: None,
+        }
+    }
+
+    /// Like `new`, but replaced and deleted files are detected by file system
+    /// notifications. Their old mapping is released right away and `get`
+    /// does not need to check the path on every call. Files in directories
+    /// that cannot be watched are checked on every `get` as usual.
+    #[cfg(feature = "notify")]
+    pub fn with_watcher(max_open: NonZeroUsize, ttl: Duration) -> Result<Self, OmFilesRsError> {
+        let mut manager = Self::new(max_open, ttl);
+        let files = manager.files.clone();
+        let changes = manager.changes.clone();
+        let watcher = PathWatcher::new(move |path| {
+            let mut files = files.lock().unwrap();
+            files.remove(path);
+            changes.fetch_add(1, Ordering::SeqCst);
+        })?;
+        manager.watcher = Some(Mutex::new(watcher));
+        Ok(manager)
+    }
+
+    /// Reader for the root variable of the file at `path`. The file is opened
+    /// if it is not cached yet or if it was replaced or deleted since.
+    pub fn get(&self, path: impl AsRef<Path>) -> Result<OmFileReader<MmapFile>, OmFilesRsError> {
+        let path = path.as_ref();
+        let cached = {
+            let mut files = self.files.lock().unwrap();
+            self.remove_expired(&mut files);
+            files.get_mut(path).map(|file| {
+                file.last_access = Instant::now();
+                (file.reader.clone(), file.identity.clone(), file.watched)
+            })
+        };
+        if let Some((reader, identity, watched)) = cached {
+            if watched
+                || (FileIdentity::of_path(path).as_ref() == Some(&identity)
+                    && !reader.was_deleted())
+            {
+                return Ok(reader);
+            }
+        }
+
+        // Changes while the file is opened are only reliably seen if the
+        // watch is set up before
+        let watched = self.watch(path);
+        let changes = self.changes.load(Ordering::SeqCst);
+
+        // Open without holding the lock, other files stay available meanwhile
+        let (reader, identity) = open_file(path)?;
+
+        let mut files = self.files.lock().unwrap();
+        let watched = watched && self.changes.load(Ordering::SeqCst) == changes;
+        if !files.contains_key(path) && files.len() >= self.max_open {
+            let least_recently_used = files
+                .iter()
+                .min_by_key(|(_, file)| file.last_access)
+                .map(|(path, _)| path.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                files.remove(&least_recently_used);
+            }
+        }
+        files.insert(
+            path.to_path_buf(),
+            ManagedFile {
+                reader: reader.clone(),
+                identity,
+                last_access: Instant::now(),
+                watched,
+            },
+        );
+        Ok(reader)
+    }
+
+    /// Close the file at `path`. Returns whether it was open.
+    pub fn remove(&self, path: impl AsRef<Path>) -> bool {
+        self.files.lock().unwrap().remove(path.as_ref()).is_some()
+    }
+
+    /// Close all files
+    pub fn clear(&self) {
+        self.files.lock().unwrap().clear();
+    }
+
+    /// Close files that were not used for `ttl`. This also happens on every
+    /// `get`, call it periodically to release idle files without new requests.
+    pub fn close_expired(&self) {
+        let mut files = self.files.lock().unwrap();
+        self.remove_expired(&mut files);
+    }
+
+    /// Number of open files
+    pub fn len(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn remove_expired(&self, files: &mut HashMap<PathBuf, ManagedFile>) {
+        files.retain(|_, file| file.last_access.elapsed() < self.ttl);
+    }
+
+    /// Whether changes of `path` are reported by the watcher
+    #[cfg(feature = "notify")]
+    fn watch(&self, path: &Path) -> bool {
+        match &self.watcher {
+            Some(watcher) => watcher.lock().unwrap().watch(path).is_ok(),
+            None => false,
+        }
+    }
+
+    #[cfg(not(feature = "notify"))]
+    fn watch(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+/// Map the file at `path` and read its root variable
+pub(crate) fn open_file(
+    path: &Path,
+) -> Result<(OmFileReader<MmapFile>, FileIdentity), OmFilesRsError> {
+    let cannot_open = |e: std::io::Error| OmFilesRsError::CannotOpenFile {
+        filename: path.display().to_string(),
+        errno: e.raw_os_error().unwrap_or(0),
+        error: e.to_string(),
+    };
+    let file = File::open(path).map_err(cannot_open)?;
+    let identity = FileIdentity::new(&file.metadata().map_err(cannot_open)?);
+    let mmap = MmapFile::new(file, Mode::ReadOnly).map_err(cannot_open)?;
+    let reader = OmFileReader::new(Arc::new(mmap))?;
+    Ok((reader, identity))
+}
@@ -0,0 +1,70 @@
+//! Grid definition of a variable, stored as child variables. Coordinates are
+//! cell centers in degrees.
+
+/// Projection string of regular latitude/longitude grids
+pub const LATLON_PROJECTION: &str = "EPSG:4326";
+
+/// Regular grid covering `lat_min..=lat_max` and `lon_min..=lon_max` with a
+/// resolution of `dy` and `dx`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridDefinition {
+    pub lat_min: f64,
+    pub lat_max: f64,
+    pub lon_min: f64,
+    pub lon_max: f64,
+    pub dx: f64,
+    pub dy: f64,
+    /// Projection, e.g. `EPSG:4326` or a PROJ string
+    pub projection: String,
+}
+
+impl GridDefinition {
+    /// Regular latitude/longitude grid
+    pub fn latlon(
+        lat_min: f64,
+        lat_max: f64,
+        lon_min: f64,
+        lon_max: f64,
+        dx: f64,
+        dy: f64,
+    ) -> Self {
+        Self {
+            lat_min,
+            lat_max,
+            lon_min,
+            lon_max,
+            dx,
+            dy,
+            projection: LATLON_PROJECTION.to_string(),
+        }
+    }
+
+    /// Number of grid points along latitude
+    pub fn ny(&self) -> u64 {
+        ((self.lat_max - self.lat_min) / self.dy).round() as u64 + 1
+    }
+
+    /// Number of grid points along longitude
+    pub fn nx(&self) -> u64 {
+        ((self.lon_max - self.lon_min) / self.dx).round() as u64 + 1
+    }
+
+    /// Index `(y, x)` of the grid point nearest to `lat` and `lon`. Longitudes
+    /// are wrapped by 360 degrees. Returns `None` outside of the grid and for
+    /// projections other than `EPSG:4326`.
+    pub fn latlon_to_index(&self, lat: f64, lon: f64) -> Option<(u64, u64)> {
+        if self.projection != LATLON_PROJECTION {
+            return None;
+        }
+        let y = ((lat - self.lat_min) / self.dy).round();
+        let mut x = ((lon - self.lon_min).rem_euclid(360.0) / self.dx).round() as u64;
+        // Global grids wrap around at the antimeridian
+        if (self.nx() as f64 * self.dx - 360.0).abs() < self.dx / 2.0 {
+            x %= self.nx();
+        }
+        if y < 0.0 || y >= self.ny() as f64 || x >= self.nx() {
+            return None;
+        }
+        Some((y as u64, x))
+    }
+}
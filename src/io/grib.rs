@@ -0,0 +1,141 @@
+//! Ingestion of decoded GRIB2 messages into a `time × lat × lon` array.
+//!
+//! The module is independent of any GRIB decoder and needs no additional
+//! dependencies. Implement `GribMessage` for the message type of the decoder
+//! in use, e.g. eccodes or gribberish, and pass the messages of one parameter
+//! and level to `write_grib_messages`.
+
+use crate::backend::backends::OmFileWriterBackend;
+use crate::core::compression::CompressionType;
+use crate::errors::OmFilesRsError;
+use crate::io::geo::GridDefinition;
+use crate::io::time::TimeAxis;
+use crate::io::writer::{OmFileWriter, OmOffsetSize};
+use ndarray::ArrayViewD;
+
+/// Field of a decoded GRIB2 message
+pub trait GribMessage {
+    /// Valid time as unix timestamp in seconds, i.e. reference time plus
+    /// forecast time
+    fn valid_time(&self) -> i64;
+
+    /// Grid of the field. All messages written into one array must have the
+    /// same grid.
+    fn grid(&self) -> GridDefinition;
+
+    /// Values in row-major order with latitude as outer dimension, starting
+    /// at `lat_min` and `lon_min`. Missing values are NaN. Decoding errors
+    /// should be returned as `OmFilesRsError::DecoderError`.
+    fn values(&self) -> Result<Vec<f32>, OmFilesRsError>;
+}
+
+/// Layout and encoding of the array written by `write_grib_messages`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GribIngestOptions {
+    /// Time steps of the array. Messages must be valid at one of the steps.
+    pub time_axis: TimeAxis,
+    /// Chunk dimensions in `time × lat × lon` order
+    pub chunks: [u64; 3],
+    pub compression: CompressionType,
+    pub scale_factor: f32,
+    pub add_offset: f32,
+}
+
+/// Write the fields of `messages` into an array `name` with dimensions
+/// `time × lat × lon`, together with its time axis, grid and dimension names.
+///
+/// Messages can be passed in any order. Every time step may only occur once
+/// and all messages must have the grid of the first message. Time steps
+/// without a message are written as NaN. Only the messages of one row of time
+/// chunks are decoded at once, so memory is bounded by
+/// `chunks[0] × lat × lon` values.
+///
+/// Returns the offset and size of the array. The caller still has to write
+/// the trailer.
+pub fn write_grib_messages<M: GribMessage, Backend: OmFileWriterBackend>(
+    file_writer: &mut OmFileWriter<Backend>,
+    name: &str,
+    messages: impl IntoIterator<Item = M>,
+    options: &GribIngestOptions,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let time_axis = &options.time_axis;
+    let mut steps: Vec<Option<M>> = (0..time_axis.length).map(|_| None).collect();
+    let mut grid: Option<GridDefinition> = None;
+    for message in messages {
+        let time = message.valid_time();
+        let index = time_axis
+            .index_of(time)
+            .ok_or(OmFilesRsError::GribTimeNotOnAxis { time })?;
+        match &grid {
+            Some(grid) if !same_grid(grid, &message.grid()) => {
+                return Err(OmFilesRsError::GribGridMismatch { time });
+            }
+            Some(_) => {}
+            None => grid = Some(message.grid()),
+        }
+        let step = &mut steps[index as usize];
+        if step.is_some() {
+            return Err(OmFilesRsError::DuplicateGribMessage { time });
+        }
+        *step = Some(message);
+    }
+    let grid = grid.ok_or(OmFilesRsError::GribNoMessages)?;
+
+    let (ny, nx) = (grid.ny(), grid.nx());
+    let dimensions = vec![time_axis.length, ny, nx];
+    let chunks: Vec<u64> = options
+        .chunks
+        .iter()
+        .zip(&dimensions)
+        .map(|(&chunk, &dimension)| chunk.min(dimension).max(1))
+        .collect();
+    let mut writer = file_writer
+        .prepare_array::<f32>(
+            dimensions,
+            chunks.clone(),
+            options.compression,
+            options.scale_factor,
+            options.add_offset,
+        )?
+        .with_dimension_names(&["time", "lat", "lon"])?
+        .with_time_axis(time_axis.clone())
+        .with_grid(grid);
+
+    let field_size = (ny * nx) as usize;
+    for start in (0..time_axis.length).step_by(chunks[0] as usize) {
+        let end = (start + chunks[0]).min(time_axis.length);
+        let mut block = vec![f32::NAN; (end - start) as usize * field_size];
+        for (index, field) in (start..end).zip(block.chunks_exact_mut(field_size)) {
+            let Some(message) = &steps[index as usize] else {
+                continue;
+            };
+            let values = message.values()?;
+            if values.len() != field_size {
+                return Err(OmFilesRsError::GribGridMismatch {
+                    time: message.valid_time(),
+                });
+            }
+            field.copy_from_slice(&values);
+        }
+        let shape = [(end - start) as usize, ny as usize, nx as usize];
+        let block =
+            ArrayViewD::from_shape(&shape[..], &block).expect("block has the size of its shape");
+        writer.write_data(block, None, None)?;
+    }
+
+    let variable_meta = writer.finalize();
+    file_writer.write_array(variable_meta, name, &[])
+}
+
+/// Grids are compared with a tolerance, because decoders compute coordinates
+/// from scaled integers.
+fn same_grid(a: &GridDefinition, b: &GridDefinition) -> bool {
+    let close = |x: f64, y: f64| (x - y).abs() <= 1e-6 * x.abs().max(y.abs()).max(1.0);
+    a.projection == b.projection
+        && close(a.lat_min, b.lat_min)
+        && close(a.lat_max, b.lat_max)
+        && close(a.lon_min, b.lon_min)
+        && close(a.lon_max, b.lon_max)
+        && close(a.dx, b.dx)
+        && close(a.dy, b.dy)
+}
@@ -0,0 +1,94 @@
+use crate::errors::OmFilesRsError;
+use std::cmp::Ordering;
+
+/// Counts of values in bins given by increasing edges. Bin `i` covers
+/// `edges[i]..edges[i + 1]`, the last bin also includes its upper edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<u64>,
+    /// Values below the first edge
+    pub below: u64,
+    /// Values above the last edge
+    pub above: u64,
+    pub nan_count: u64,
+}
+
+impl Histogram {
+    /// Empty histogram. Requires at least two strictly increasing edges.
+    pub fn new(edges: &[f64]) -> Result<Self, OmFilesRsError> {
+        if edges.len() < 2
+            || edges
+                .windows(2)
+                .any(|w| w[0].partial_cmp(&w[1]) != Some(Ordering::Less))
+        {
+            return Err(OmFilesRsError::InvalidHistogramBins);
+        }
+        Ok(Self {
+            edges: edges.to_vec(),
+            counts: vec![0; edges.len() - 1],
+            below: 0,
+            above: 0,
+            nan_count: 0,
+        })
+    }
+
+    /// `bins` equally sized bins from `min` to `max`
+    pub fn uniform(min: f64, max: f64, bins: usize) -> Result<Self, OmFilesRsError> {
+        let width = (max - min) / bins as f64;
+        let edges: Vec<f64> = (0..=bins)
+            .map(|i| {
+                if i == bins {
+                    max
+                } else {
+                    min + i as f64 * width
+                }
+            })
+            .collect();
+        Self::new(&edges)
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            self.nan_count += 1;
+        } else if value < self.edges[0] {
+            self.below += 1;
+        } else if value > self.edges[self.edges.len() - 1] {
+            self.above += 1;
+        } else {
+            // The last edge belongs to the last bin
+            let bin = self.edges.partition_point(|edge| *edge <= value);
+            let last = self.counts.len();
+            self.counts[bin.min(last) - 1] += 1;
+        }
+    }
+
+    /// Number of values that are not NaN
+    pub fn count(&self) -> u64 {
+        self.below + self.above + self.counts.iter().sum::<u64>()
+    }
+
+    /// Approximate quantile `q` in `0..=1`, interpolated linearly within a
+    /// bin. Values outside of the edges are clamped to the first and last
+    /// edge. Returns `None` if there are no values.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total = self.count();
+        if total == 0 || !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        let target = q * total as f64;
+        let mut cumulative = self.below as f64;
+        if self.below > 0 && target <= cumulative {
+            return Some(self.edges[0]);
+        }
+        for (i, &count) in self.counts.iter().enumerate() {
+            let next = cumulative + count as f64;
+            if target <= next && count > 0 {
+                let fraction = (target - cumulative) / count as f64;
+                return Some(self.edges[i] + fraction * (self.edges[i + 1] - self.edges[i]));
+            }
+            cumulative = next;
+        }
+        Some(self.edges[self.edges.len() - 1])
+    }
+}
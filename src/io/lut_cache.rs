@@ -0,0 +1,90 @@
+use om_file_format_sys::OmDecoder_dataRead_t;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Offset and count of an index block
+type IndexKey = (u64, u64);
+
+/// Decoded LUT entries of one index block. Each entry is the data read of
+/// a single chunk, keyed by its chunk index. Only chunks that have been
+/// read so far are present.
+pub(crate) type IndexEntries = BTreeMap<u64, OmDecoder_dataRead_t>;
+
+struct LutCacheState {
+    /// Index block -> (decoded entries, last access tick)
+    blocks: HashMap<IndexKey, (Arc<IndexEntries>, u64)>,
+    /// Last access tick -> key, the first entry is the least recently used block
+    lru: BTreeMap<u64, IndexKey>,
+    tick: u64,
+    bytes: u64,
+}
+
+impl LutCacheState {
+    fn remove(&mut self, key: IndexKey) {
+        if let Some((entries, last_used)) = self.blocks.remove(&key) {
+            self.lru.remove(&last_used);
+            self.bytes -= size_of_entries(&entries);
+        }
+    }
+}
+
+/// Memory used by `entries`
+fn size_of_entries(entries: &IndexEntries) -> u64 {
+    (entries.len() * std::mem::size_of::<(u64, OmDecoder_dataRead_t)>()) as u64
+}
+
+/// Least recently used cache for decoded LUT entries per index block.
+/// Shared between a reader and its children, because keys are file offsets.
+pub(crate) struct LutCache {
+    max_bytes: u64,
+    state: Mutex<LutCacheState>,
+}
+
+impl LutCache {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            state: Mutex::new(LutCacheState {
+                blocks: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                bytes: 0,
+            }),
+        }
+    }
+
+    pub fn get(&self, offset: u64, count: u64) -> Option<Arc<IndexEntries>> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let (entries, last_used) = state.blocks.get_mut(&(offset, count))?;
+        let entries = entries.clone();
+        let previous = std::mem::replace(last_used, tick);
+        state.lru.remove(&previous);
+        state.lru.insert(tick, (offset, count));
+        Some(entries)
+    }
+
+    /// Insert or replace the entries of an index block and evict least
+    /// recently used blocks to stay within `max_bytes`. Blocks larger than
+    /// the cache are not stored.
+    pub fn insert(&self, offset: u64, count: u64, entries: Arc<IndexEntries>) {
+        let size = size_of_entries(&entries);
+        let mut state = self.state.lock().unwrap();
+        state.remove((offset, count));
+        if size > self.max_bytes {
+            return;
+        }
+        while state.bytes + size > self.max_bytes {
+            let Some((_, evicted)) = state.lru.pop_first() else {
+                break;
+            };
+            state.remove(evicted);
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.blocks.insert((offset, count), (entries, tick));
+        state.lru.insert(tick, (offset, count));
+        state.bytes += size;
+    }
+}
@@ -0,0 +1,145 @@
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use ndarray::ArrayD;
+use std::ops::Range;
+
+/// Reads an ordered list of files as one array that is concatenated along
+/// `axis`, e.g. an archive where every file holds one month of time steps.
+/// All files must have the same data type, and the same dimensions and chunk
+/// dimensions apart from `axis`.
+pub struct MultiFileReader<Backend: OmFileReaderBackend> {
+    readers: Vec<OmFileReader<Backend>>,
+    axis: usize,
+    /// Start of each file along `axis`, followed by the total length
+    offsets: Vec<u64>,
+    dimensions: Vec<u64>,
+}
+
+impl<Backend: OmFileReaderBackend> MultiFileReader<Backend> {
+    pub fn new(readers: Vec<OmFileReader<Backend>>, axis: usize) -> Result<Self, OmFilesRsError> {
+        let first = readers.first().ok_or_else(|| {
+            OmFilesRsError::IncompatibleFiles("At least one file is required".to_string())
+        })?;
+        let data_type = first.data_type();
+        if (data_type as u8) < DataType::Int8Array as u8 || data_type == DataType::StringArray {
+            return Err(OmFilesRsError::InvalidDataType);
+        }
+        let n_dims = first.get_dimensions().len();
+        if axis >= n_dims {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+
+        let mut offsets = vec![0];
+        for (i, reader) in readers.iter().enumerate() {
+            if reader.data_type() != data_type {
+                return Err(OmFilesRsError::IncompatibleFiles(format!(
+                    "File {} has data type {:?} instead of {:?}",
+                    i,
+                    reader.data_type(),
+                    data_type
+                )));
+            }
+            let dimensions = reader.get_dimensions();
+            let chunks = reader.get_chunk_dimensions();
+            if dimensions.len() != n_dims {
+                return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+            }
+            for d in (0..n_dims).filter(|&d| d != axis) {
+                if dimensions[d] != first.get_dimensions()[d] {
+                    return Err(OmFilesRsError::IncompatibleFiles(format!(
+                        "File {} has dimensions {:?} instead of {:?}",
+                        i,
+                        dimensions,
+                        first.get_dimensions()
+                    )));
+                }
+                if chunks[d] != first.get_chunk_dimensions()[d] {
+                    return Err(OmFilesRsError::IncompatibleFiles(format!(
+                        "File {} has chunk dimensions {:?} instead of {:?}",
+                        i,
+                        chunks,
+                        first.get_chunk_dimensions()
+                    )));
+                }
+            }
+            offsets.push(offsets[i] + dimensions[axis]);
+        }
+
+        let mut dimensions = first.get_dimensions().to_vec();
+        dimensions[axis] = *offsets.last().unwrap();
+        Ok(Self {
+            readers,
+            axis,
+            offsets,
+            dimensions,
+        })
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.readers[0].data_type()
+    }
+
+    /// Dimensions of the concatenated array
+    pub fn get_dimensions(&self) -> &[u64] {
+        &self.dimensions
+    }
+
+    pub fn axis(&self) -> usize {
+        self.axis
+    }
+
+    /// The underlying readers in order
+    pub fn readers(&self) -> &[OmFileReader<Backend>] {
+        &self.readers
+    }
+
+    /// Read a selection of the concatenated array. Only files that overlap
+    /// the selection along `axis` are read, directly into the output array.
+    pub fn read<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        if dim_read.len() != self.dimensions.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        for (range, &dimension) in dim_read.iter().zip(self.dimensions.iter()) {
+            if range.start > range.end || range.end > dimension {
+                return Err(OmFilesRsError::DimensionOutOfBounds {
+                    range: range.start as usize..range.end as usize,
+                    allowed: dimension as usize,
+                });
+            }
+        }
+        let selection = &dim_read[self.axis];
+
+        let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let out_dims_usize: Vec<usize> = out_dims.iter().map(|&x| x as usize).collect();
+        let mut out = ArrayD::<T>::default(out_dims_usize);
+
+        for (reader, start) in self.readers.iter().zip(self.offsets.windows(2)) {
+            let (file_start, file_end) = (start[0], start[1]);
+            let from = selection.start.max(file_start);
+            let to = selection.end.min(file_end);
+            if from >= to {
+                continue;
+            }
+            let mut file_read = dim_read.to_vec();
+            file_read[self.axis] = from - file_start..to - file_start;
+            let mut into_cube_offset = vec![0; dim_read.len()];
+            into_cube_offset[self.axis] = from - selection.start;
+            reader.read_into(
+                &mut out,
+                &file_read,
+                &into_cube_offset,
+                &out_dims,
+                io_size_max,
+                io_size_merge,
+            )?;
+        }
+        Ok(out)
+    }
+}
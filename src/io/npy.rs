@@ -0,0 +1,94 @@
+//! Encoding of arrays in the NumPy `.npy` format, version 1.0, which
+//! `numpy.load` reads without further information about shape or type.
+//! Values are stored in C order with little-endian byte order. Strings are
+//! stored as fixed-width UTF-32 with the length of the longest string.
+
+use crate::backend::backends::map_io_error;
+use crate::errors::OmFilesRsError;
+use crate::io::dynamic::OmArray;
+use ndarray::ArrayD;
+use std::io::Write;
+
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+/// Header and data are aligned to this many bytes, as written by NumPy
+const HEADER_ALIGNMENT: usize = 64;
+
+/// Write `array` as `.npy` file to `out`
+pub fn write_npy(array: &OmArray, out: &mut impl Write) -> Result<(), OmFilesRsError> {
+    let descr = match array {
+        OmArray::I8(_) => "|i1",
+        OmArray::U8(_) => "|u1",
+        OmArray::I16(_) => "<i2",
+        OmArray::U16(_) => "<u2",
+        OmArray::I32(_) => "<i4",
+        OmArray::U32(_) => "<u4",
+        OmArray::I64(_) => "<i8",
+        OmArray::U64(_) => "<u8",
+        OmArray::F32(_) => "<f4",
+        OmArray::F64(_) => "<f8",
+        OmArray::String(strings) => return write_strings(strings, out),
+    };
+    let data = array
+        .to_le_bytes()
+        .expect("numeric arrays have a byte representation");
+    out.write_all(&header(descr, array.shape()))
+        .map_err(map_io_error)?;
+    out.write_all(&data).map_err(map_io_error)
+}
+
+/// Like `write_npy`, but returns the encoded file
+pub fn to_npy_bytes(array: &OmArray) -> Result<Vec<u8>, OmFilesRsError> {
+    let mut out = Vec::new();
+    write_npy(array, &mut out)?;
+    Ok(out)
+}
+
+/// Strings padded with zeros to the length of the longest string
+fn write_strings(strings: &ArrayD<String>, out: &mut impl Write) -> Result<(), OmFilesRsError> {
+    let width = strings
+        .iter()
+        .map(|s| s.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let mut data = Vec::with_capacity(strings.len() * width * 4);
+    for s in strings.iter() {
+        let padding = width - s.chars().count();
+        data.extend(s.chars().flat_map(|c| (c as u32).to_le_bytes()));
+        data.extend(std::iter::repeat_n(0, padding * 4));
+    }
+    let descr = format!("<U{}", width);
+    out.write_all(&header(&descr, strings.shape()))
+        .map_err(map_io_error)?;
+    out.write_all(&data).map_err(map_io_error)
+}
+
+/// Magic string, version, header length and the header dictionary, padded
+/// with spaces and terminated by a newline
+fn header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        shape => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    let unpadded = MAGIC.len() + 2 + dict.len() + 1;
+    let padding = unpadded.next_multiple_of(HEADER_ALIGNMENT) - unpadded;
+    dict.extend(std::iter::repeat_n(' ', padding));
+    dict.push('\n');
+
+    let mut header = MAGIC.to_vec();
+    header.extend((dict.len() as u16).to_le_bytes());
+    header.extend(dict.as_bytes());
+    header
+}
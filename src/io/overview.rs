@@ -0,0 +1,219 @@
+//! Overviews of an array at reduced resolution for visualization, similar to
+//! GeoTIFF overviews. Every overview is a float child variable of the array
+//! in which the last two dimensions are reduced by an integer factor. All
+//! other dimensions, e.g. time, are kept.
+
+use crate::backend::backends::{OmFileReaderBackend, OmFileWriterBackend};
+use crate::core::data_types::DataType;
+use crate::errors::OmFilesRsError;
+use crate::io::copy::{
+    copy_array_with_layout, copy_variable, read_blocks, DEFAULT_MAX_READ_ELEMENTS,
+};
+use crate::io::reader::OmFileReader;
+use crate::io::writer::{OmFileWriter, OmOffsetSize};
+use ndarray::ArrayD;
+use std::ops::Range;
+
+/// Prefix of the names of overview children, followed by the factor
+pub const OVERVIEW_VARIABLE_PREFIX: &str = "_overview_";
+
+/// How the values of a block of `factor × factor` elements are combined.
+/// NaN values are ignored, blocks without other values are NaN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Mean,
+    Min,
+    Max,
+}
+
+impl Aggregation {
+    fn aggregate(self, values: impl Iterator<Item = f32>) -> f32 {
+        let mut values = values.filter(|x| !x.is_nan());
+        let Some(first) = values.next() else {
+            return f32::NAN;
+        };
+        match self {
+            Aggregation::Mean => {
+                let (sum, count) = values.fold((first as f64, 1u32), |(sum, count), x| {
+                    (sum + x as f64, count + 1)
+                });
+                (sum / count as f64) as f32
+            }
+            Aggregation::Min => values.fold(first, f32::min),
+            Aggregation::Max => values.fold(first, f32::max),
+        }
+    }
+}
+
+/// Write one overview of the array of `reader` for every factor in
+/// `factors`, e.g. `[2, 4, 8]`. Overviews use the chunk dimensions,
+/// compression, scale factor and offset of the source array and are read in
+/// blocks, so memory does not depend on the size of the array.
+///
+/// Returns the overview variables, which are passed as children to
+/// `OmFileWriter::write_array` of the full resolution array. Use
+/// `copy_with_overviews` to add overviews to an existing file.
+pub fn write_overviews<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    file_writer: &mut OmFileWriter<WriterBackend>,
+    factors: &[u64],
+    aggregation: Aggregation,
+) -> Result<Vec<OmOffsetSize>, OmFilesRsError> {
+    let data_type = reader.data_type();
+    if (data_type as u8) < DataType::Int8Array as u8 || data_type == DataType::StringArray {
+        return Err(OmFilesRsError::InvalidDataType);
+    }
+    if reader.get_dimensions().len() < 2 {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    factors
+        .iter()
+        .map(|&factor| write_overview(reader, file_writer, factor, aggregation))
+        .collect()
+}
+
+/// Copy the array of `reader` into `writer` with overviews for `factors`.
+/// Existing children, including previous overviews, are copied unchanged.
+/// Returns the offset and size of the copied variable. The caller still has
+/// to write the trailer.
+pub fn copy_with_overviews<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    writer: &mut OmFileWriter<WriterBackend>,
+    factors: &[u64],
+    aggregation: Aggregation,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    let mut children = write_overviews(reader, writer, factors, aggregation)?;
+    for i in 0..reader.number_of_children() {
+        if let Some(child) = reader.get_child(i) {
+            children.push(copy_variable(&child, writer)?);
+        }
+    }
+    let axes: Vec<usize> = (0..reader.get_dimensions().len()).collect();
+    let chunks = reader.get_chunk_dimensions().to_vec();
+    copy_array_with_layout(
+        reader,
+        writer,
+        &children,
+        &axes,
+        &chunks,
+        DEFAULT_MAX_READ_ELEMENTS,
+    )
+}
+
+fn write_overview<Backend: OmFileReaderBackend, WriterBackend: OmFileWriterBackend>(
+    reader: &OmFileReader<Backend>,
+    file_writer: &mut OmFileWriter<WriterBackend>,
+    factor: u64,
+    aggregation: Aggregation,
+) -> Result<OmOffsetSize, OmFilesRsError> {
+    if factor < 2 {
+        return Err(OmFilesRsError::InvalidOverviewFactor { factor });
+    }
+    let source_dimensions = reader.get_dimensions();
+    let n = source_dimensions.len();
+    let spatial = n - 2..n;
+    let dimensions: Vec<u64> = (0..n)
+        .map(|i| {
+            if spatial.contains(&i) {
+                source_dimensions[i].div_ceil(factor)
+            } else {
+                source_dimensions[i]
+            }
+        })
+        .collect();
+    let chunks: Vec<u64> = reader
+        .get_chunk_dimensions()
+        .iter()
+        .zip(&dimensions)
+        .map(|(&chunk, &dimension)| chunk.min(dimension).max(1))
+        .collect();
+
+    let compression =
+        file_writer.deterministic_compression(reader.compression(), DataType::FloatArray);
+    let mut writer = file_writer.prepare_array::<f32>(
+        dimensions.clone(),
+        chunks.clone(),
+        compression,
+        reader.scale_factor(),
+        reader.add_offset(),
+    )?;
+    let max_read_elements = DEFAULT_MAX_READ_ELEMENTS / (factor * factor);
+    for block in read_blocks(&dimensions, &chunks, &chunks, max_read_elements) {
+        let source_block: Vec<Range<u64>> = (0..n)
+            .map(|i| {
+                if spatial.contains(&i) {
+                    block[i].start * factor..(block[i].end * factor).min(source_dimensions[i])
+                } else {
+                    block[i].clone()
+                }
+            })
+            .collect();
+        let source = reader.read_converted::<f32>(&source_block)?;
+        let source = source
+            .as_slice()
+            .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+
+        let shape: Vec<usize> = block.iter().map(|r| (r.end - r.start) as usize).collect();
+        let (sy, sx) = (
+            (source_block[n - 2].end - source_block[n - 2].start) as usize,
+            (source_block[n - 1].end - source_block[n - 1].start) as usize,
+        );
+        let (by, bx) = (shape[n - 2], shape[n - 1]);
+        let leading: usize = shape[..n - 2].iter().product();
+        let f = factor as usize;
+        let mut out = Vec::with_capacity(leading * by * bx);
+        for l in 0..leading {
+            let field = &source[l * sy * sx..(l + 1) * sy * sx];
+            for y in 0..by {
+                for x in 0..bx {
+                    let rows = y * f..((y + 1) * f).min(sy);
+                    let values = rows.flat_map(|row| {
+                        field[row * sx + x * f..row * sx + ((x + 1) * f).min(sx)]
+                            .iter()
+                            .copied()
+                    });
+                    out.push(aggregation.aggregate(values));
+                }
+            }
+        }
+        let out = ArrayD::from_shape_vec(shape, out).expect("shape of the block");
+        writer.write_data(out.view(), None, None)?;
+    }
+
+    let variable_meta = writer.finalize();
+    let name = format!("{}{}", OVERVIEW_VARIABLE_PREFIX, factor);
+    file_writer.write_array(variable_meta, &name, &[])
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
+    /// Overviews written with `write_overviews` by factor, sorted from the
+    /// finest to the coarsest
+    pub fn get_overviews(&self) -> Vec<(u64, Self)> {
+        let mut overviews: Vec<(u64, Self)> = (0..self.number_of_children())
+            .filter_map(|i| self.get_child(i))
+            .filter_map(|child| {
+                let name = child.get_name()?;
+                let factor = name.strip_prefix(OVERVIEW_VARIABLE_PREFIX)?.parse().ok()?;
+                Some((factor, child))
+            })
+            .collect();
+        overviews.sort_by_key(|(factor, _)| *factor);
+        overviews
+    }
+
+    /// The coarsest level that still has at least `min_y × min_x` elements
+    /// in the last two dimensions, e.g. the pixel size of a map tile. Returns
+    /// the factor and the reader, which is this array with factor 1 if no
+    /// overview is coarse enough.
+    pub fn overview_for_resolution(&self, min_y: u64, min_x: u64) -> (u64, Self) {
+        self.get_overviews()
+            .into_iter()
+            .rev()
+            .find(|(_, overview)| {
+                let dimensions = overview.get_dimensions();
+                let n = dimensions.len();
+                n >= 2 && dimensions[n - 2] >= min_y && dimensions[n - 1] >= min_x
+            })
+            .unwrap_or_else(|| (1, self.clone()))
+    }
+}
@@ -0,0 +1,259 @@
+use crate::backend::backends::OmFileWriterBackend;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::OmFileArrayDataType;
+use crate::errors::OmFilesRsError;
+use crate::io::verify::{is_selected, verify_chunk};
+use crate::io::writer::{
+    chunk_region, init_encoder, write_lut, OmFileWriter, OmFileWriterArrayFinalized,
+};
+use ndarray::ArrayViewD;
+use om_file_format_sys::{
+    om_encoder_chunk_buffer_size, om_encoder_compress_chunk,
+    om_encoder_compressed_chunk_buffer_size, om_encoder_count_chunks, OmEncoder_t,
+};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+/// Array writer for producers that run in parallel, e.g. one thread per time
+/// slice. Blocks of whole chunks can be written from several threads in any
+/// order. Chunks are compressed right away and kept in memory until
+/// `finalize` writes them to the file in the order of the lookup table.
+///
+/// Memory grows with the compressed size of the array, so very large arrays
+/// are better split into several variables or written with
+/// `OmFileWriterArray`.
+pub struct ParallelArrayWriter<OmType: OmFileArrayDataType> {
+    encoder: OmEncoder_t,
+    scale_factor: f32,
+    add_offset: f32,
+    compression: CompressionType,
+    dimensions: Vec<u64>,
+    chunks: Vec<u64>,
+    compressed_chunk_buffer_size: usize,
+    chunk_buffer_size: usize,
+    /// Compressed bytes by chunk index
+    compressed: Mutex<Vec<Option<Vec<u8>>>>,
+    /// Fraction of chunks that are verified, see `with_verification`
+    verify_fraction: f64,
+    data_type: PhantomData<OmType>,
+}
+
+// The encoder only points to `dimensions` and `chunks`, which are never
+// modified, and compressing a chunk does not modify the encoder.
+unsafe impl<OmType: OmFileArrayDataType> Send for ParallelArrayWriter<OmType> {}
+unsafe impl<OmType: OmFileArrayDataType> Sync for ParallelArrayWriter<OmType> {}
+
+impl<OmType: OmFileArrayDataType> ParallelArrayWriter<OmType> {
+    pub fn new(
+        dimensions: Vec<u64>,
+        chunks: Vec<u64>,
+        compression: CompressionType,
+        scale_factor: f32,
+        add_offset: f32,
+    ) -> Result<Self, OmFilesRsError> {
+        let encoder = init_encoder(
+            &dimensions,
+            &chunks,
+            compression,
+            OmType::DATA_TYPE_ARRAY,
+            scale_factor,
+            add_offset,
+        )?;
+        let number_of_chunks = unsafe { om_encoder_count_chunks(&encoder) } as usize;
+        let compressed_chunk_buffer_size =
+            unsafe { om_encoder_compressed_chunk_buffer_size(&encoder) } as usize;
+        let chunk_buffer_size = unsafe { om_encoder_chunk_buffer_size(&encoder) } as usize;
+        Ok(Self {
+            encoder,
+            scale_factor,
+            add_offset,
+            compression,
+            dimensions,
+            chunks,
+            compressed_chunk_buffer_size,
+            chunk_buffer_size,
+            compressed: Mutex::new(vec![None; number_of_chunks]),
+            verify_fraction: 0.0,
+            data_type: PhantomData,
+        })
+    }
+
+    /// Decode a `fraction` of all chunks right after compressing them and
+    /// compare them with the block passed to `write_block`, like
+    /// `OmFileWriterArray::with_verification`. A mismatch fails `write_block`
+    /// with `ChunkVerificationFailed`. `OmFileWriter::set_verification` does
+    /// not apply to this writer.
+    pub fn with_verification(mut self, fraction: f64) -> Self {
+        self.verify_fraction = fraction;
+        self
+    }
+
+    /// Compress `block`, which starts at element `offset` of the array. The
+    /// offset must be a multiple of the chunk dimensions and the block must
+    /// cover whole chunks, only blocks at the end of a dimension may be
+    /// shorter. Every chunk can only be written once.
+    pub fn write_block(
+        &self,
+        offset: &[u64],
+        block: ArrayViewD<OmType>,
+    ) -> Result<(), OmFilesRsError> {
+        let shape: Vec<u64> = block.shape().iter().map(|&x| x as u64).collect();
+        self.check_block(offset, &shape)
+            .map_err(|error| error.context(format!("block at offset {:?}", offset)))?;
+        let block = block.as_slice().ok_or(OmFilesRsError::ArrayNotContiguous)?;
+
+        let chunks_in_block: Vec<u64> = shape
+            .iter()
+            .zip(&self.chunks)
+            .map(|(count, chunk)| count.div_ceil(*chunk))
+            .collect();
+        let block_offset = vec![0u64; shape.len()];
+        let mut chunk_buffer = vec![0u8; self.chunk_buffer_size];
+        let mut compressed = Vec::new();
+        for chunk_offset in 0..chunks_in_block.iter().product::<u64>() {
+            let chunk_index = self.chunk_index(offset, &chunks_in_block, chunk_offset);
+            let mut out = vec![0u8; self.compressed_chunk_buffer_size];
+            let bytes_written = unsafe {
+                om_encoder_compress_chunk(
+                    &self.encoder,
+                    block.as_ptr() as *const c_void,
+                    shape.as_ptr(),
+                    block_offset.as_ptr(),
+                    shape.as_ptr(),
+                    chunk_index,
+                    chunk_offset,
+                    out.as_mut_ptr(),
+                    chunk_buffer.as_mut_ptr(),
+                )
+            };
+            out.truncate(bytes_written as usize);
+            if self.verify_fraction > 0.0 && is_selected(chunk_index, self.verify_fraction) {
+                let region = chunk_region(
+                    &self.dimensions,
+                    &self.chunks,
+                    &block_offset,
+                    &shape,
+                    chunk_index,
+                    chunk_offset,
+                );
+                verify_chunk(
+                    &out,
+                    block,
+                    &shape,
+                    &region,
+                    self.compression,
+                    self.scale_factor,
+                    self.add_offset,
+                )
+                .map_err(|error| error.context(format!("verifying chunk {}", chunk_index)))?;
+            }
+            compressed.push((chunk_index, out));
+        }
+
+        let mut chunks = self.compressed.lock().unwrap();
+        if let Some((chunk, _)) = compressed
+            .iter()
+            .find(|(chunk, _)| chunks[*chunk as usize].is_some())
+        {
+            return Err(OmFilesRsError::ChunkWrittenTwice { chunk: *chunk });
+        }
+        for (chunk, bytes) in compressed {
+            chunks[chunk as usize] = Some(bytes);
+        }
+        Ok(())
+    }
+
+    /// Number of chunks that have been written
+    pub fn chunks_written(&self) -> u64 {
+        let chunks = self.compressed.lock().unwrap();
+        chunks.iter().filter(|chunk| chunk.is_some()).count() as u64
+    }
+
+    /// Write all chunks and the lookup table to `file_writer`. Fails if any
+    /// chunk has not been written. The result is passed to
+    /// `OmFileWriter::write_array` like for `OmFileWriterArray`.
+    pub fn finalize<Backend: OmFileWriterBackend>(
+        self,
+        file_writer: &mut OmFileWriter<Backend>,
+    ) -> Result<OmFileWriterArrayFinalized, OmFilesRsError> {
+        let compressed = self.compressed.into_inner().unwrap();
+        let missing = compressed.iter().filter(|chunk| chunk.is_none()).count() as u64;
+        if missing > 0 {
+            return Err(OmFilesRsError::MissingChunks {
+                missing,
+                total: compressed.len() as u64,
+            });
+        }
+
+        file_writer.write_header_if_required()?;
+        let buffer = file_writer.buffer_mut();
+        let mut look_up_table = Vec::with_capacity(compressed.len() + 1);
+        look_up_table.push(buffer.total_bytes_written as u64);
+        for bytes in compressed.into_iter().flatten() {
+            buffer.reallocate(bytes.len())?;
+            buffer.buffer_at_write_position()[..bytes.len()].copy_from_slice(&bytes);
+            buffer.increment_write_position(bytes.len());
+            look_up_table.push(buffer.total_bytes_written as u64);
+        }
+        let lut_offset = buffer.total_bytes_written as u64;
+        let lut_size = write_lut(buffer, &look_up_table)?;
+
+        Ok(OmFileWriterArrayFinalized {
+            scale_factor: self.scale_factor,
+            add_offset: self.add_offset,
+            compression: self.compression,
+            data_type: OmType::DATA_TYPE_ARRAY,
+            dimensions: self.dimensions,
+            chunks: self.chunks,
+            lut_size,
+            lut_offset,
+            dimension_names: None,
+            fill_value: None,
+            statistics: None,
+            chunk_statistics: None,
+            saturation: None,
+            quantization_filter: None,
+            time_axis: None,
+            grid: None,
+        })
+    }
+
+    fn check_block(&self, offset: &[u64], shape: &[u64]) -> Result<(), OmFilesRsError> {
+        if offset.len() != self.dimensions.len() || shape.len() != self.dimensions.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        for i in 0..self.dimensions.len() {
+            let (dimension, chunk) = (self.dimensions[i], self.chunks[i]);
+            if offset[i] + shape[i] > dimension {
+                return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                    offset: offset[i],
+                    count: shape[i],
+                    dimension,
+                });
+            }
+            let aligned = offset[i] % chunk == 0
+                && shape[i] > 0
+                && (shape[i] % chunk == 0 || offset[i] + shape[i] == dimension);
+            if !aligned {
+                return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+            }
+        }
+        Ok(())
+    }
+
+    /// Index in the lookup table of chunk number `chunk_offset` of a block
+    /// at `offset` that spans `chunks_in_block` chunks.
+    fn chunk_index(&self, offset: &[u64], chunks_in_block: &[u64], chunk_offset: u64) -> u64 {
+        let mut local = chunk_offset;
+        let mut chunk_index = 0;
+        let mut stride = 1;
+        for i in (0..self.dimensions.len()).rev() {
+            let position = offset[i] / self.chunks[i] + local % chunks_in_block[i];
+            local /= chunks_in_block[i];
+            chunk_index += position * stride;
+            stride *= self.dimensions[i].div_ceil(self.chunks[i]);
+        }
+        chunk_index
+    }
+}
@@ -0,0 +1,135 @@
+use crate::core::data_types::DataType;
+use crate::core::variable_metadata::ArrayVariableView;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::io::writer::OmOffsetSize;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Deepest nesting of variables that is accepted. Real files use a handful
+/// of levels, the limit protects against stack exhaustion.
+const MAX_DEPTH: u32 = 64;
+
+/// Overview of a file that passed `validate_bytes`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileSummary {
+    /// The file uses the legacy format without trailer
+    pub legacy: bool,
+    pub file_size: u64,
+    /// Number of distinct variables including the root variable
+    pub variables: u64,
+    /// Number of array variables
+    pub arrays: u64,
+    /// Deepest nesting level, the root variable has depth 0
+    pub max_depth: u32,
+    /// Compressed chunk data referenced by all lookup tables in bytes
+    pub data_bytes: u64,
+}
+
+/// Parse an entire file from memory without decompressing any chunk:
+/// header, trailer, the metadata of all variables and the lookup tables of
+/// all arrays. Every offset and size is checked against the file before it
+/// is passed to the C library, so arbitrary input returns an error instead of
+/// panicking or reading out of bounds. This is the entry point for fuzzing.
+pub fn validate_bytes(data: &[u8]) -> Result<FileSummary, OmFilesRsError> {
+    let backend: Arc<[u8]> = Arc::from(data);
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    let mut summary = FileSummary {
+        legacy: reader.offset_size().is_none(),
+        file_size: data.len() as u64,
+        ..Default::default()
+    };
+    let mut visited = HashSet::new();
+    let mut path = Vec::new();
+    validate_variable(&reader, 0, &mut path, &mut visited, &mut summary)?;
+    Ok(summary)
+}
+
+fn validate_variable(
+    reader: &OmFileReader<Arc<[u8]>>,
+    depth: u32,
+    path: &mut Vec<u64>,
+    visited: &mut HashSet<u64>,
+    summary: &mut FileSummary,
+) -> Result<(), OmFilesRsError> {
+    if depth > MAX_DEPTH {
+        return Err(OmFilesRsError::NotAnOmFile.context("variables are nested too deeply"));
+    }
+    summary.variables += 1;
+    summary.max_depth = summary.max_depth.max(depth);
+
+    // Names are only parsed on access
+    reader.get_name();
+    if reader.data_type().is_array() {
+        summary.arrays += 1;
+        summary.data_bytes += validate_lut(reader, summary.file_size)?;
+    }
+
+    for index in 0..reader.number_of_children() {
+        let Some(offset_size) = reader.variable_ref().child_offset_size(index) else {
+            return Err(OmFilesRsError::NotAnOmFile);
+        };
+        if path.contains(&offset_size.offset) {
+            return Err(OmFilesRsError::NotAnOmFile.context(format!(
+                "variable at offset {} is its own ancestor",
+                offset_size.offset
+            )));
+        }
+        if !visited.insert(offset_size.offset) {
+            continue;
+        }
+        let child = reader
+            .init_child_from_offset_size(offset_size.clone())
+            .map_err(|error| variable_context(error, &offset_size))?;
+        path.push(offset_size.offset);
+        validate_variable(&child, depth + 1, path, visited, summary)
+            .map_err(|error| variable_context(error, &offset_size))?;
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Check the lookup table of an array and return the size of the data it
+/// references.
+fn validate_lut(reader: &OmFileReader<Arc<[u8]>>, file_size: u64) -> Result<u64, OmFilesRsError> {
+    let dimensions = reader.get_dimensions();
+    let chunks = reader.get_chunk_dimensions();
+
+    // Every chunk occupies at least one byte. This rejects huge dimensions
+    // before the decoder iterates over all of their chunks.
+    let mut number_of_chunks = 1u64;
+    for (dimension, chunk) in dimensions.iter().zip(chunks) {
+        number_of_chunks = number_of_chunks
+            .checked_mul(dimension.div_ceil(*chunk))
+            .filter(|count| *count <= file_size)
+            .ok_or(OmFilesRsError::NotAnOmFile)?;
+    }
+
+    if number_of_chunks == 0 {
+        return Ok(0);
+    }
+
+    // Legacy files store an uncompressed lookup table right after the header
+    if reader.offset_size().is_some() {
+        let view = ArrayVariableView::new(reader.variable_data())?;
+        let lut = OmOffsetSize::new(view.lut_offset(), view.lut_size());
+        lut.check_within(file_size)
+            .map_err(|error| error.context("lookup table"))?;
+    }
+    if reader.data_type() == DataType::StringArray {
+        return Ok(0);
+    }
+
+    let ranges: Vec<_> = dimensions.iter().map(|dimension| 0..*dimension).collect();
+    let plan = reader.prefetch(&ranges, None, None)?;
+    let mut data_bytes = 0;
+    for range in plan.data_ranges {
+        OmOffsetSize::new(range.start, range.end - range.start).check_within(file_size)?;
+        data_bytes += range.end - range.start;
+    }
+    Ok(data_bytes)
+}
+
+fn variable_context(error: OmFilesRsError, offset_size: &OmOffsetSize) -> OmFilesRsError {
+    error.context(format!("variable at offset {}", offset_size.offset))
+}
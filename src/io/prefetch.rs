@@ -0,0 +1,22 @@
+use std::ops::Range;
+
+/// Byte ranges of the file that a read touches, in the order the reader
+/// requests them. Returned by `OmFileReader::prefetch`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrefetchPlan {
+    /// Reads of the look up table (LUT)
+    pub index_ranges: Vec<Range<u64>>,
+    /// Reads of compressed chunk data
+    pub data_ranges: Vec<Range<u64>>,
+}
+
+impl PrefetchPlan {
+    /// Total number of bytes of all ranges
+    pub fn total_bytes(&self) -> u64 {
+        self.index_ranges
+            .iter()
+            .chain(self.data_ranges.iter())
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+}
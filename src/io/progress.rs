@@ -0,0 +1,33 @@
+/// Progress of writing a single array
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WriteProgress {
+    /// Number of chunks that have been compressed so far
+    pub chunks_written: u64,
+    /// Total number of chunks of the array
+    pub total_chunks: u64,
+    /// Compressed bytes written for this array so far
+    pub bytes_written: u64,
+}
+
+impl WriteProgress {
+    /// Fraction of chunks written between 0 and 1
+    pub fn fraction(&self) -> f64 {
+        if self.total_chunks == 0 {
+            return 1.0;
+        }
+        self.chunks_written as f64 / self.total_chunks as f64
+    }
+}
+
+/// Receives progress updates while array data is compressed, e.g. to render
+/// progress bars or to emit metrics.
+pub trait ProgressSink {
+    /// Called after each compressed chunk
+    fn on_progress(&mut self, progress: WriteProgress);
+}
+
+impl<F: FnMut(WriteProgress)> ProgressSink for F {
+    fn on_progress(&mut self, progress: WriteProgress) {
+        self(progress)
+    }
+}
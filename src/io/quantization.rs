@@ -0,0 +1,206 @@
+//! Filters that transform values before they are quantized by the int16
+//! codecs, e.g. to spend more precision on small values. The identifier of
+//! the filter is stored with the array, so `OmFileReader::read_dequantized`
+//! can invert it.
+
+use crate::errors::OmFilesRsError;
+use ndarray::ArrayViewD;
+
+/// Invertible transformation applied to every value before compression.
+pub trait QuantizationFilter {
+    /// Identifier stored in the file. Built-in filters are recognized by
+    /// `filter_from_id`, custom filters have to be passed to the reader.
+    fn id(&self) -> String;
+    fn forward(&self, value: f32) -> f32;
+    fn inverse(&self, value: f32) -> f32;
+}
+
+impl<F: QuantizationFilter + ?Sized> QuantizationFilter for Box<F> {
+    fn id(&self) -> String {
+        (**self).id()
+    }
+
+    fn forward(&self, value: f32) -> f32 {
+        (**self).forward(value)
+    }
+
+    fn inverse(&self, value: f32) -> f32 {
+        (**self).inverse(value)
+    }
+}
+
+/// `value * scale + offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFilter {
+    pub scale: f32,
+    pub offset: f32,
+}
+
+impl QuantizationFilter for LinearFilter {
+    fn id(&self) -> String {
+        format!("linear:{}:{}", self.scale, self.offset)
+    }
+
+    fn forward(&self, value: f32) -> f32 {
+        value * self.scale + self.offset
+    }
+
+    fn inverse(&self, value: f32) -> f32 {
+        (value - self.offset) / self.scale
+    }
+}
+
+/// `value * scale + offset` computed in double precision for `f64` arrays,
+/// see `OmFileWriterArray::with_double_scale`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoubleLinearFilter {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl DoubleLinearFilter {
+    /// Identifier stored in the file. `f64` formatting round trips exactly.
+    pub fn id(&self) -> String {
+        format!("linear64:{}:{}", self.scale, self.offset)
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        let mut parts = id.strip_prefix("linear64:")?.split(':');
+        let scale = parts.next()?.parse().ok()?;
+        let offset = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { scale, offset })
+    }
+
+    pub fn forward(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+
+    pub fn inverse(&self, value: f64) -> f64 {
+        (value - self.offset) / self.scale
+    }
+}
+
+/// `log10(1 + value)`, suited for values like precipitation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogFilter;
+
+impl QuantizationFilter for LogFilter {
+    fn id(&self) -> String {
+        "log10p1".to_string()
+    }
+
+    fn forward(&self, value: f32) -> f32 {
+        value.ln_1p() / std::f32::consts::LN_10
+    }
+
+    fn inverse(&self, value: f32) -> f32 {
+        (value * std::f32::consts::LN_10).exp_m1()
+    }
+}
+
+/// Custom filter given as a pair of closures. It is stored as `custom:<name>`.
+pub struct ClosureFilter<F, I> {
+    name: String,
+    forward: F,
+    inverse: I,
+}
+
+impl<F: Fn(f32) -> f32, I: Fn(f32) -> f32> ClosureFilter<F, I> {
+    pub fn new(name: &str, forward: F, inverse: I) -> Self {
+        Self {
+            name: name.to_string(),
+            forward,
+            inverse,
+        }
+    }
+}
+
+impl<F: Fn(f32) -> f32, I: Fn(f32) -> f32> QuantizationFilter for ClosureFilter<F, I> {
+    fn id(&self) -> String {
+        format!("custom:{}", self.name)
+    }
+
+    fn forward(&self, value: f32) -> f32 {
+        (self.forward)(value)
+    }
+
+    fn inverse(&self, value: f32) -> f32 {
+        (self.inverse)(value)
+    }
+}
+
+/// Built-in filter for an identifier stored in a file
+pub fn filter_from_id(id: &str) -> Option<Box<dyn QuantizationFilter>> {
+    if id == LogFilter.id() {
+        return Some(Box::new(LogFilter));
+    }
+    let mut parts = id.strip_prefix("linear:")?.split(':');
+    let scale = parts.next()?.parse().ok()?;
+    let offset = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Box::new(LinearFilter { scale, offset }))
+}
+
+/// Largest stored int16 value, `i16::MAX` marks NaN
+const INT16_LIMIT: f64 = (i16::MAX - 1) as f64;
+
+/// Scale factor and offset chosen by `estimate_scale_factor`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleEstimate {
+    pub scale_factor: f32,
+    pub add_offset: f32,
+    /// Largest absolute quantization error, `0.5 / scale_factor`
+    pub precision: f32,
+}
+
+/// Choose `scale_factor` and `add_offset` for `PforDelta2dInt16`, so values
+/// are stored with an absolute error of at most `required_precision`. Values
+/// are stored as `round((value - add_offset) * scale_factor)`. The offset is
+/// only used if the range of `data` does not fit otherwise. If the range is
+/// too large for the precision, the scale factor is reduced and `precision`
+/// reports the achievable error. NaN and infinite values are ignored.
+/// `required_precision` has to be positive and finite.
+pub fn estimate_scale_factor(
+    data: ArrayViewD<f32>,
+    required_precision: f32,
+) -> Result<ScaleEstimate, OmFilesRsError> {
+    if !(required_precision > 0.0 && required_precision.is_finite()) {
+        return Err(OmFilesRsError::InvalidConfiguration(format!(
+            "required_precision must be positive and finite, got {}",
+            required_precision
+        )));
+    }
+    let (min, max) = data
+        .iter()
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value as f64), max.max(value as f64))
+        });
+    let mut scale_factor = 0.5 / required_precision as f64;
+    if min > max {
+        return Ok(ScaleEstimate {
+            scale_factor: scale_factor as f32,
+            add_offset: 0.0,
+            precision: required_precision,
+        });
+    }
+    let add_offset = if min.abs().max(max.abs()) * scale_factor <= INT16_LIMIT {
+        0.0
+    } else {
+        (min + max) / 2.0
+    };
+    let half_range = (max - add_offset).max(add_offset - min);
+    if half_range * scale_factor > INT16_LIMIT {
+        scale_factor = INT16_LIMIT / half_range;
+    }
+    Ok(ScaleEstimate {
+        scale_factor: scale_factor as f32,
+        add_offset: add_offset as f32,
+        precision: (0.5 / scale_factor) as f32,
+    })
+}
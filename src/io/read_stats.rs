@@ -0,0 +1,64 @@
+use om_file_format_sys::OmDecoder_dataRead_t;
+use std::time::{Duration, Instant};
+
+/// Counters collected while reading an array. Useful to tune `io_size_max`
+/// and `io_size_merge` for a backend. Counters are accumulated, so the same
+/// instance can be passed to several reads.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReadStats {
+    /// Number of requests for index (LUT) data
+    pub index_reads: u64,
+    /// Index blocks served from the LUT cache of the reader
+    pub index_cache_hits: u64,
+    /// Chunks served from the decoded chunk cache of the reader
+    pub chunk_cache_hits: u64,
+    /// Number of requests for compressed chunk data
+    pub data_reads: u64,
+    /// Data requests that cover more than one chunk
+    pub merged_data_reads: u64,
+    /// Total bytes requested from the backend
+    pub bytes_fetched: u64,
+    /// Number of chunks that have been decompressed
+    pub chunks_decoded: u64,
+    /// Time spent decompressing chunks
+    pub decode_time: Duration,
+}
+
+impl ReadStats {
+    /// Total number of backend requests
+    pub fn requests(&self) -> u64 {
+        self.index_reads + self.data_reads
+    }
+
+    pub(crate) fn record_index_read(&mut self, count: u64) {
+        self.index_reads += 1;
+        self.bytes_fetched += count;
+    }
+
+    pub(crate) fn record_data_read(&mut self, data_read: &OmDecoder_dataRead_t) {
+        let chunks = data_read.chunkIndex.upperBound - data_read.chunkIndex.lowerBound;
+        self.record_data_request(data_read.count, chunks);
+    }
+
+    /// Account a backend request of `count` bytes that covers `chunks` chunks
+    pub(crate) fn record_data_request(&mut self, count: u64, chunks: u64) {
+        self.data_reads += 1;
+        self.bytes_fetched += count;
+        if chunks > 1 {
+            self.merged_data_reads += 1;
+        }
+    }
+
+    /// Run `decode` and account its duration and the chunks of `data_read`.
+    pub(crate) fn time_decode<R>(
+        &mut self,
+        data_read: &OmDecoder_dataRead_t,
+        decode: impl FnOnce() -> R,
+    ) -> R {
+        let start = Instant::now();
+        let result = decode();
+        self.decode_time += start.elapsed();
+        self.chunks_decoded += data_read.chunkIndex.upperBound - data_read.chunkIndex.lowerBound;
+        result
+    }
+}
@@ -1,20 +1,37 @@
 #![allow(non_snake_case)]
-use crate::backend::backends::OmFileReaderBackend;
+use crate::backend::backends::{IoSizes, OmFileReaderBackend, OmFileWriterBackend};
+use crate::backend::metrics::{CacheKind, Metrics};
 use crate::backend::mmapfile::{MmapFile, Mode};
-use crate::core::c_defaults::{c_error_string, create_uninit_decoder};
+use crate::core::c_defaults::new_index_read;
 use crate::core::compression::CompressionType;
 use crate::core::data_types::{DataType, OmFileArrayDataType, OmFileScalarDataType};
+use crate::core::variable_metadata::{validate_variable_metadata, ArrayVariableView};
 use crate::errors::OmFilesRsError;
-use ndarray::ArrayD;
-use num_traits::Zero;
+use crate::io::batch_reader::{
+    collect_data_reads, decode_batch, decode_chunks, merge_ranges, CoalescedBytes,
+};
+use crate::io::chunk_cache::ChunkCache;
+use crate::io::copy::{aligned_tiles, rechunk, DEFAULT_MAX_READ_ELEMENTS};
+use crate::io::describe::{ArrayDescription, VariableDescription};
+use crate::io::geo::GridDefinition;
+use crate::io::histogram::Histogram;
+use crate::io::lut_cache::{IndexEntries, LutCache};
+use crate::io::prefetch::PrefetchPlan;
+use crate::io::quantization::{filter_from_id, DoubleLinearFilter, QuantizationFilter};
+use crate::io::read_stats::ReadStats;
+use crate::io::statistics::{ArrayStatistics, ChunkStatistics};
+use crate::io::time::TimeAxis;
+use crate::io::variable::{
+    check_metadata_location, OmVariableContainer, VariableRef, MAX_METADATA_SIZE,
+};
+use crate::io::variable_slice::VariableSlice;
+use crate::utils::divide_rounded_up;
+use ndarray::{Array2, ArrayD, Slice};
+use num_traits::ToPrimitive;
 use om_file_format_sys::{
-    om_decoder_init, om_decoder_read_buffer_size, om_header_size, om_header_type, om_trailer_read,
-    om_trailer_size, om_variable_get_add_offset, om_variable_get_children,
-    om_variable_get_children_count, om_variable_get_chunks, om_variable_get_compression,
-    om_variable_get_dimensions, om_variable_get_name, om_variable_get_scalar,
-    om_variable_get_scale_factor, om_variable_get_type, om_variable_init, OmError_t_ERROR_OK,
-    OmHeaderType_t_OM_HEADER_INVALID, OmHeaderType_t_OM_HEADER_LEGACY,
-    OmHeaderType_t_OM_HEADER_READ_TRAILER, OmVariable_t,
+    om_decoder_next_index_read, om_header_size, om_header_type, om_trailer_read, om_trailer_size,
+    OmDecoder_t, OmHeaderType_t_OM_HEADER_INVALID, OmHeaderType_t_OM_HEADER_LEGACY,
+    OmHeaderType_t_OM_HEADER_READ_TRAILER,
 };
 use std::collections::HashMap;
 use std::fs::File;
@@ -22,45 +39,77 @@ use std::ops::Range;
 use std::os::raw::c_void;
 use std::sync::Arc;
 
-use super::writer::OmOffsetSize;
+use super::writer::{
+    OmFileWriter, OmOffsetSize, CHUNK_MAX_VARIABLE, CHUNK_MIN_VARIABLE, DIMENSION_NAMES_VARIABLE,
+    FILL_VALUE_VARIABLE, GRID_VARIABLE, QUANTIZATION_FILTER_VARIABLE, STATISTICS_VARIABLE,
+    TIME_AXIS_VARIABLE,
+};
 
+/// Reader for one variable of a file.
+///
+/// Clones are cheap, they share the backend, the variable metadata and the
+/// caches. The reader is `Send + Sync` if the backend is, and all methods take
+/// `&self`, so one reader or its clones can read concurrently from many
+/// threads. Every read uses its own decoder and buffers.
 pub struct OmFileReader<Backend: OmFileReaderBackend> {
     offset_size: Option<OmOffsetSize>,
     /// The backend that provides data via the get_bytes method
     pub backend: Arc<Backend>,
-    /// Holds the data where the meta information of the variable is stored, is not supposed to go out of scope
-    /// Here the LUT and additional attributes of the variable need to be stored.
-    pub variable_data: Vec<u8>,
-    /// Opaque pointer to the variable defined by header/trailer
-    pub variable: *const OmVariable_t,
+    /// Metadata of the variable defined by header/trailer
+    variable: Arc<OmVariableContainer>,
+    lut_cache: Option<Arc<LutCache>>,
+    chunk_cache: Option<Arc<ChunkCache>>,
+    io_sizes: Option<IoSizes>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl<Backend: OmFileReaderBackend> Clone for OmFileReader<Backend> {
+    fn clone(&self) -> Self {
+        Self {
+            offset_size: self.offset_size.clone(),
+            backend: self.backend.clone(),
+            variable: self.variable.clone(),
+            lut_cache: self.lut_cache.clone(),
+            chunk_cache: self.chunk_cache.clone(),
+            io_sizes: self.io_sizes,
+            metrics: self.metrics.clone(),
+        }
+    }
 }
 
 impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
     #[allow(non_upper_case_globals)]
     pub fn new(backend: Arc<Backend>) -> Result<Self, OmFilesRsError> {
+        let file_size = backend.count() as u64;
         let header_size = unsafe { om_header_size() } as u64;
-        let owned_data: Result<Vec<u8>, OmFilesRsError> = backend.get_bytes_owned(0, header_size);
-        let header_data = match owned_data {
-            Ok(data) => data,
-            Err(error) => backend
-                .forward_unimplemented_error(error, || backend.get_bytes(0, header_size))?
-                .to_vec(),
-        };
+        // Version 3 files may be smaller than a legacy header. Pad the header,
+        // so that the header type can be checked without reading beyond it.
+        let mut header_data = backend
+            .get_bytes_with_fallback(0, header_size.min(file_size))?
+            .into_owned();
+        header_data.resize(header_size as usize, 0);
 
         let header_type = unsafe { om_header_type(header_data.as_ptr() as *const c_void) };
 
         let variable_and_offset = {
             match header_type {
-                OmHeaderType_t_OM_HEADER_LEGACY => Ok((header_data, None)),
+                OmHeaderType_t_OM_HEADER_LEGACY => {
+                    if file_size < header_size {
+                        return Err(OmFilesRsError::NotAnOmFile);
+                    }
+                    Ok((OmVariableContainer::new(header_data)?, None))
+                }
                 OmHeaderType_t_OM_HEADER_READ_TRAILER => unsafe {
-                    let file_size = backend.count();
-                    let trailer_size = om_trailer_size();
-                    let trailer_offset = (file_size - trailer_size) as u64;
-                    let owned_data = backend.get_bytes_owned(trailer_offset, trailer_size as u64);
+                    let trailer_size = om_trailer_size() as u64;
+                    if file_size < trailer_size {
+                        return Err(OmFilesRsError::NotAnOmFile);
+                    }
+                    let trailer_offset = file_size - trailer_size;
+                    let owned_data = backend.get_bytes_owned(trailer_offset, trailer_size);
                     let this_trailer = match owned_data {
                         Ok(ref data) => data.as_slice(),
                         Err(error) => backend.forward_unimplemented_error(error, || {
-                            backend.get_bytes(trailer_offset, trailer_size as u64)
+                            backend.get_bytes(trailer_offset, trailer_size)
                         })?,
                     };
                     let mut offset = 0u64;
@@ -74,15 +123,9 @@ impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
                     }
 
                     let offset_size = OmOffsetSize::new(offset, size);
-
-                    let owned_data = backend.get_bytes_owned(offset, size);
-                    let variable_data = match owned_data {
-                        Ok(data) => data,
-                        Err(error) => backend
-                            .forward_unimplemented_error(error, || backend.get_bytes(offset, size))?
-                            .to_vec(),
-                    };
-                    Ok((variable_data, Some(offset_size)))
+                    check_metadata_location(&offset_size, trailer_offset)?;
+                    let variable = OmVariableContainer::from_backend(&backend, &offset_size)?;
+                    Ok((variable, Some(offset_size)))
                 },
                 OmHeaderType_t_OM_HEADER_INVALID => {
                     return Err(OmFilesRsError::NotAnOmFile);
@@ -91,160 +134,328 @@ impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
             }
         };
 
-        let (variable_data, offset_size) = variable_and_offset?;
+        let (variable, offset_size) = variable_and_offset?;
 
-        let variable_ptr = unsafe { om_variable_init(variable_data.as_ptr() as *const c_void) };
         Ok(Self {
             offset_size,
             backend,
-            variable_data,
-            variable: variable_ptr,
+            variable: Arc::new(variable),
+            lut_cache: None,
+            chunk_cache: None,
+            io_sizes: None,
+            metrics: None,
         })
     }
 
-    pub fn data_type(&self) -> DataType {
-        unsafe {
-            DataType::try_from(om_variable_get_type(self.variable) as u8)
-                .expect("Invalid data type")
+    /// Open a file whose trailer is missing or damaged, e.g. because the end
+    /// of the file was truncated. Files that `new` can open are opened as
+    /// usual. Otherwise the file is scanned backwards for a copy of the
+    /// trailer written with `OmFileWriter::set_backup_trailer`, or else for
+    /// the last named variable whose children are all valid, which is the
+    /// root variable unless it was lost as well.
+    ///
+    /// The scan reads the file from the end and is meant for memory mapped
+    /// or in-memory backends. Returns the error of `new` if nothing is found.
+    pub fn recover(backend: Arc<Backend>) -> Result<Self, OmFilesRsError> {
+        let error = match Self::new(backend.clone()) {
+            Ok(reader) => return Ok(reader),
+            Err(error) => error,
+        };
+        let file_size = backend.count() as u64;
+        let header_size = unsafe { om_header_size() } as u64;
+        let mut header_data = backend
+            .get_bytes_with_fallback(0, header_size.min(file_size))?
+            .into_owned();
+        header_data.resize(header_size as usize, 0);
+        let header_type = unsafe { om_header_type(header_data.as_ptr() as *const c_void) };
+        if header_type != OmHeaderType_t_OM_HEADER_READ_TRAILER {
+            return Err(error);
         }
+
+        // Variables and trailers are 8 byte aligned and follow the header
+        let trailer_size = unsafe { om_trailer_size() } as u64;
+        for offset in (1..file_size.div_ceil(8)).rev().map(|i| i * 8) {
+            let offset_size = recover_trailer_at(&backend, offset, trailer_size)
+                .or_else(|| recover_variable_at(&backend, offset, file_size));
+            let Some(offset_size) = offset_size else {
+                continue;
+            };
+            let Ok(variable) = OmVariableContainer::from_backend(&backend, &offset_size) else {
+                continue;
+            };
+            return Ok(Self {
+                offset_size: Some(offset_size),
+                backend,
+                variable: Arc::new(variable),
+                lut_cache: None,
+                chunk_cache: None,
+                io_sizes: None,
+                metrics: None,
+            });
+        }
+        Err(error)
+    }
+
+    /// Offset and size of this variable in the file. `None` for legacy files.
+    pub fn offset_size(&self) -> Option<&OmOffsetSize> {
+        self.offset_size.as_ref()
+    }
+
+    /// Metadata accessors that do not need the backend
+    pub(crate) fn variable_ref(&self) -> VariableRef<'_> {
+        self.variable.variable_ref()
+    }
+
+    /// Raw metadata bytes of the variable
+    pub fn variable_data(&self) -> &[u8] {
+        self.variable.data()
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.variable_ref().data_type()
     }
 
     pub fn compression(&self) -> CompressionType {
-        unsafe {
-            CompressionType::try_from(om_variable_get_compression(self.variable) as u8)
-                .expect("Invalid compression type")
-        }
+        self.variable_ref().compression()
     }
 
     pub fn scale_factor(&self) -> f32 {
-        unsafe { om_variable_get_scale_factor(self.variable) }
+        self.variable_ref().scale_factor()
     }
 
     pub fn add_offset(&self) -> f32 {
-        unsafe { om_variable_get_add_offset(self.variable) }
+        self.variable_ref().add_offset()
     }
 
     pub fn get_dimensions(&self) -> &[u64] {
-        unsafe {
-            let dims = om_variable_get_dimensions(self.variable);
-            std::slice::from_raw_parts(dims.values, dims.count as usize)
-        }
+        self.variable_ref().get_dimensions()
     }
 
     pub fn get_chunk_dimensions(&self) -> &[u64] {
-        unsafe {
-            let chunks = om_variable_get_chunks(self.variable);
-            std::slice::from_raw_parts(chunks.values, chunks.count as usize)
-        }
+        self.variable_ref().get_chunk_dimensions()
+    }
+
+    /// Names of all dimensions, if they were stored with `with_dimension_names`
+    pub fn get_dimension_names(&self) -> Option<Vec<String>> {
+        let names = self.get_child_by_name(DIMENSION_NAMES_VARIABLE)?;
+        let count: u64 = names.read_scalar()?;
+        (0..count)
+            .map(|i| names.get_child_by_name(&i.to_string())?.read_scalar())
+            .collect()
+    }
+
+    /// Fill value of the array, if it was stored with `with_fill_value`
+    pub fn get_fill_value<T: OmFileScalarDataType>(&self) -> Option<T> {
+        self.get_child_by_name(FILL_VALUE_VARIABLE)?.read_scalar()
+    }
+
+    /// Statistics of the array, if they were stored with `with_statistics`
+    pub fn get_statistics(&self) -> Option<ArrayStatistics> {
+        let statistics = self.get_child_by_name(STATISTICS_VARIABLE)?;
+        Some(ArrayStatistics {
+            min: statistics.get_child_by_name("min")?.read_scalar()?,
+            max: statistics.get_child_by_name("max")?.read_scalar()?,
+            mean: statistics.get_child_by_name("mean")?.read_scalar()?,
+            count: statistics.read_scalar()?,
+            nan_count: statistics.get_child_by_name("nan_count")?.read_scalar()?,
+        })
+    }
+
+    /// Time axis of the array, if it was stored with `with_time_axis`
+    pub fn get_time_axis(&self) -> Option<TimeAxis> {
+        let time_axis = self.get_child_by_name(TIME_AXIS_VARIABLE)?;
+        Some(TimeAxis {
+            start: time_axis.read_scalar()?,
+            step: time_axis.get_child_by_name("step")?.read_scalar()?,
+            length: time_axis.get_child_by_name("length")?.read_scalar()?,
+            calendar: time_axis.get_child_by_name("calendar")?.read_scalar()?,
+        })
+    }
+
+    /// Grid definition of the array, if it was stored with `with_grid`
+    pub fn get_grid(&self) -> Option<GridDefinition> {
+        let grid = self.get_child_by_name(GRID_VARIABLE)?;
+        let value = |name: &str| grid.get_child_by_name(name)?.read_scalar::<f64>();
+        Some(GridDefinition {
+            lat_min: value("lat_min")?,
+            lat_max: value("lat_max")?,
+            lon_min: value("lon_min")?,
+            lon_max: value("lon_max")?,
+            dx: value("dx")?,
+            dy: value("dy")?,
+            projection: grid.read_scalar()?,
+        })
+    }
+
+    /// Index `(y, x)` of the grid point nearest to `lat` and `lon`, see
+    /// `GridDefinition::latlon_to_index`. `None` without grid definition.
+    pub fn latlon_to_index(&self, lat: f64, lon: f64) -> Option<(u64, u64)> {
+        self.get_grid()?.latlon_to_index(lat, lon)
+    }
+
+    /// Identifier of the quantization filter, if the array was written with
+    /// `with_quantization_filter`
+    pub fn get_quantization_filter(&self) -> Option<String> {
+        self.get_child_by_name(QUANTIZATION_FILTER_VARIABLE)?
+            .read_scalar()
+    }
+
+    /// Read a float array and invert its quantization filter. Built-in
+    /// filters are detected from the file, custom filters have to be passed
+    /// as `filter` with the same identifier. Arrays without filter are
+    /// returned as stored.
+    pub fn read_dequantized(
+        &self,
+        dim_read: &[Range<u64>],
+        filter: Option<&dyn QuantizationFilter>,
+    ) -> Result<ArrayD<f32>, OmFilesRsError> {
+        let mut data = self.read::<f32>(dim_read, None, None)?;
+        let Some(id) = self.get_quantization_filter() else {
+            return Ok(data);
+        };
+        let built_in = filter_from_id(&id);
+        let filter = match (filter, built_in.as_deref()) {
+            (Some(filter), _) if filter.id() == id => filter,
+            (_, Some(built_in)) => built_in,
+            _ => return Err(OmFilesRsError::UnknownQuantizationFilter(id)),
+        };
+        data.mapv_inplace(|value| filter.inverse(value));
+        Ok(data)
+    }
+
+    /// Read a double array and invert the scale and offset of
+    /// `OmFileWriterArray::with_double_scale`. Arrays without filter are
+    /// returned as stored.
+    pub fn read_dequantized_f64(
+        &self,
+        dim_read: &[Range<u64>],
+    ) -> Result<ArrayD<f64>, OmFilesRsError> {
+        let mut data = self.read::<f64>(dim_read, None, None)?;
+        let Some(id) = self.get_quantization_filter() else {
+            return Ok(data);
+        };
+        let filter = DoubleLinearFilter::from_id(&id)
+            .ok_or(OmFilesRsError::UnknownQuantizationFilter(id))?;
+        data.mapv_inplace(|value| filter.inverse(value));
+        Ok(data)
+    }
+
+    /// First direct child with the given name
+    fn get_child_by_name(&self, name: &str) -> Option<Self> {
+        (0..self.number_of_children())
+            .filter_map(|i| self.get_child(i))
+            .find(|child| child.get_name().as_deref() == Some(name))
     }
 
     pub fn get_name(&self) -> Option<String> {
-        unsafe {
-            let name = om_variable_get_name(self.variable);
-            if name.size == 0 {
-                return None;
-            }
-            let bytes = std::slice::from_raw_parts(name.value as *const u8, name.size as usize);
-            String::from_utf8(bytes.to_vec()).ok()
-        }
+        self.variable_ref().get_name()
     }
 
     /// Returns a HashMap mapping variable names to their offset and size
     /// This function needs to traverse the entire variable tree, therefore
     /// it is best to make sure that variable metadata is close to each other
     /// at the end of the file (before the trailer). The caller could then
-    /// make sure that this part of the file is loaded/cached in memory
+    /// make sure that this part of the file is loaded/cached in memory.
+    /// The map can also be cached outside of the file and variables reopened
+    /// with `open_variable` without traversing the tree again.
     pub fn get_flat_variable_metadata(&self) -> HashMap<String, OmOffsetSize> {
-        let mut result = HashMap::new();
-        self.collect_variable_metadata(Vec::new(), &mut result);
-        result
+        // TODO: This requires names to not repeat in this flattened hashmap
+        self.iter_variables()
+            .filter_map(|(_, variable)| Some((variable.get_name()?, variable.offset_size?)))
+            .collect()
     }
 
-    /// Helper function that recursively collects variable metadata
-    fn collect_variable_metadata(
-        &self,
-        current_path: Vec<u32>,
-        result: &mut HashMap<String, OmOffsetSize>,
-    ) {
-        // Add current variable's metadata if it has a name and offset_size
-        // TODO: This requires to not repeat in this flattened hashmap
-        if let Some(name) = self.get_name() {
-            if let Some(offset_size) = &self.offset_size {
-                result.insert(name.to_string(), offset_size.clone());
+    /// Describe this variable and all of its children, e.g. to print a
+    /// summary of the file. Reads the metadata of the whole variable tree.
+    pub fn describe(&self) -> VariableDescription {
+        let data_type = self.data_type();
+        let array = data_type.is_array().then(|| {
+            let dimensions = self.get_dimensions().to_vec();
+            let uncompressed_bytes = data_type
+                .element_size()
+                .map(|size| dimensions.iter().product::<u64>() * size as u64);
+            ArrayDescription {
+                chunks: self.get_chunk_dimensions().to_vec(),
+                dimensions,
+                compression: self.compression(),
+                scale_factor: self.scale_factor(),
+                add_offset: self.add_offset(),
+                uncompressed_bytes,
             }
+        });
+        VariableDescription {
+            name: self.get_name(),
+            data_type,
+            offset: self.offset_size.as_ref().map(|o| o.offset),
+            metadata_bytes: self.variable_data().len() as u64,
+            array,
+            value: self.scalar_string(),
+            children: (0..self.number_of_children())
+                .filter_map(|i| self.get_child(i))
+                .map(|child| child.describe())
+                .collect(),
         }
+    }
 
-        // Process children
-        let num_children = self.number_of_children();
-        for i in 0..num_children {
-            let mut path = current_path.clone();
-            path.push(i);
-
-            if let Some(child) = self.get_child(i) {
-                child.collect_variable_metadata(path, result);
-            }
+    /// Value of numeric and string scalars formatted as text
+    pub(crate) fn scalar_string(&self) -> Option<String> {
+        match self.data_type() {
+            DataType::Int8 => self.read_scalar::<i8>().map(|v| v.to_string()),
+            DataType::Uint8 => self.read_scalar::<u8>().map(|v| v.to_string()),
+            DataType::Int16 => self.read_scalar::<i16>().map(|v| v.to_string()),
+            DataType::Uint16 => self.read_scalar::<u16>().map(|v| v.to_string()),
+            DataType::Int32 => self.read_scalar::<i32>().map(|v| v.to_string()),
+            DataType::Uint32 => self.read_scalar::<u32>().map(|v| v.to_string()),
+            DataType::Int64 => self.read_scalar::<i64>().map(|v| v.to_string()),
+            DataType::Uint64 => self.read_scalar::<u64>().map(|v| v.to_string()),
+            DataType::Float => self.read_scalar::<f32>().map(|v| v.to_string()),
+            DataType::Double => self.read_scalar::<f64>().map(|v| v.to_string()),
+            DataType::String => self.read_scalar::<String>(),
+            _ => None,
         }
     }
 
     pub fn number_of_children(&self) -> u32 {
-        unsafe { om_variable_get_children_count(self.variable) }
+        self.variable_ref().number_of_children()
     }
 
+    /// Returns `None` if `index` is out of range or the metadata of the child
+    /// is invalid. `init_child_from_offset_size` returns the error instead.
     pub fn get_child(&self, index: u32) -> Option<Self> {
-        let mut offset = 0u64;
-        let mut size = 0u64;
-        if !unsafe { om_variable_get_children(self.variable, index, 1, &mut offset, &mut size) } {
-            return None;
-        }
-
-        let offset_size = OmOffsetSize::new(offset, size);
-        let child = self
-            .init_child_from_offset_size(offset_size)
-            .expect("Failed to init child");
-        Some(child)
+        let offset_size = self.variable_ref().child_offset_size(index)?;
+        self.init_child_from_offset_size(offset_size).ok()
     }
 
+    /// Open the variable whose metadata is at `offset_size` in the same file,
+    /// e.g. a value of `get_flat_variable_metadata`. The variable does not
+    /// have to be a direct child. Fails if the location is outside of the
+    /// file or does not contain valid variable metadata.
     pub fn init_child_from_offset_size(
         &self,
         offset_size: OmOffsetSize,
     ) -> Result<Self, OmFilesRsError> {
-        let owned_data: Result<Vec<u8>, OmFilesRsError> = self
-            .backend
-            .get_bytes_owned(offset_size.offset, offset_size.size);
-        let child_variable = match owned_data {
-            Ok(data) => data,
-            Err(error) => {
-                let fallback_result = self.backend.forward_unimplemented_error(error, || {
-                    self.backend.get_bytes(offset_size.offset, offset_size.size)
-                })?;
-                fallback_result.to_vec()
-            }
-        };
-
-        let child_variable_ptr =
-            unsafe { om_variable_init(child_variable.as_ptr() as *const c_void) };
+        check_metadata_location(&offset_size, self.backend.count() as u64)?;
+        let variable = OmVariableContainer::from_backend(&self.backend, &offset_size)?;
 
         Ok(Self {
             offset_size: Some(offset_size),
             backend: self.backend.clone(),
-            variable_data: child_variable,
-            variable: child_variable_ptr,
+            variable: Arc::new(variable),
+            lut_cache: self.lut_cache.clone(),
+            chunk_cache: self.chunk_cache.clone(),
+            io_sizes: self.io_sizes,
+            metrics: self.metrics.clone(),
         })
     }
 
-    pub fn read_scalar<T: OmFileScalarDataType>(&self) -> Option<T> {
-        if T::DATA_TYPE_SCALAR != self.data_type() {
-            return None;
-        }
-        let mut value = T::default();
-
-        let error =
-            unsafe { om_variable_get_scalar(self.variable, &mut value as *mut T as *mut c_void) };
+    /// Open the variable at `offset_size`, e.g. from a cached copy of
+    /// `get_flat_variable_metadata`. See `init_child_from_offset_size`.
+    pub fn open_variable(&self, offset_size: &OmOffsetSize) -> Result<Self, OmFilesRsError> {
+        self.init_child_from_offset_size(offset_size.clone())
+    }
 
-        if error != OmError_t_ERROR_OK {
-            return None;
-        }
-        Some(value)
+    pub fn read_scalar<T: OmFileScalarDataType>(&self) -> Option<T> {
+        self.variable_ref().read_scalar()
     }
 
     /// Read a variable as an array of a dynamic data type.
@@ -257,72 +468,552 @@ impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
         io_size_max: Option<u64>,
         io_size_merge: Option<u64>,
     ) -> Result<(), OmFilesRsError> {
-        let io_size_max = io_size_max.unwrap_or(65536);
-        let io_size_merge = io_size_merge.unwrap_or(512);
+        self.read_into_with_stats(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+            &mut ReadStats::default(),
+        )
+    }
 
-        // Verify data type
-        if T::DATA_TYPE_ARRAY != self.data_type() {
-            return Err(OmFilesRsError::InvalidDataType);
+    /// Like `read_into`, but accumulates the number of requests, fetched bytes,
+    /// decoded chunks and decode time in `stats`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn read_into_with_stats<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let into = into
+            .as_slice_mut()
+            .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+        self.read_into_flat_with_stats(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+            stats,
+        )
+    }
+
+    /// Like `read_into`, but decodes into a flat row-major buffer with the
+    /// shape `into_cube_dimension` instead of an `ArrayD`. The selection is
+    /// written at `into_cube_offset`.
+    pub fn read_into_flat<T: OmFileArrayDataType>(
+        &self,
+        into: &mut [T],
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<(), OmFilesRsError> {
+        if into_cube_dimension.iter().product::<u64>() != into.len() as u64 {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+        if dim_read.len() != into_cube_offset.len() || dim_read.len() != into_cube_dimension.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        for ((range, &offset), &dimension) in dim_read
+            .iter()
+            .zip(into_cube_offset.iter())
+            .zip(into_cube_dimension.iter())
+        {
+            let count = range.end.saturating_sub(range.start);
+            if offset + count > dimension {
+                return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                    offset,
+                    count,
+                    dimension,
+                });
+            }
+        }
+        self.read_into_flat_with_stats(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+            &mut ReadStats::default(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read_into_flat_with_stats<T: OmFileArrayDataType>(
+        &self,
+        into: &mut [T],
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let result = match &self.chunk_cache {
+            Some(chunk_cache) => self.read_with_chunk_cache(
+                chunk_cache,
+                into,
+                dim_read,
+                into_cube_offset,
+                into_cube_dimension,
+                io_size_max,
+                io_size_merge,
+                stats,
+            ),
+            None => self.decode_region(
+                into,
+                dim_read,
+                into_cube_offset,
+                into_cube_dimension,
+                io_size_max,
+                io_size_merge,
+                stats,
+            ),
+        };
+        result.map_err(|error| self.variable_ref().decode_error_context(error))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn decode_region<T: OmFileArrayDataType>(
+        &self,
+        into: &mut [T],
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let preferred = self.io_sizes();
+        let io_size_max = io_size_max.unwrap_or(preferred.io_size_max);
+        let io_size_merge = io_size_merge.unwrap_or(preferred.io_size_merge);
+
+        let mut prepared = self.variable_ref().prepare_read::<T>(
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+        )?;
+
+        // Perform decoding
+        match &self.lut_cache {
+            Some(lut_cache) => self.decode_with_lut_cache(
+                lut_cache,
+                &prepared.decoder,
+                dim_read,
+                into,
+                prepared.chunk_buffer.as_mut_slice(),
+                io_size_max,
+                io_size_merge,
+                stats,
+            )?,
+            None => self.backend.decode_flat_with_stats(
+                &prepared.decoder,
+                into,
+                prepared.chunk_buffer.as_mut_slice(),
+                stats,
+            )?,
+        }
+
+        Ok(())
+    }
+
+    /// Keep up to `max_bytes` of decoded LUT entries in memory, so repeated
+    /// reads of the same region do not fetch the LUT again, whatever IO sizes
+    /// they use. The cache is shared with child readers created afterwards.
+    /// Useful for backends with a high latency per request; mmap backends do
+    /// not benefit.
+    pub fn enable_lut_cache(&mut self, max_bytes: u64) {
+        self.lut_cache = Some(Arc::new(LutCache::new(max_bytes)));
+    }
+
+    /// Keep up to `max_bytes` of decoded chunks in memory, so reads of
+    /// overlapping regions, e.g. while scrolling through time steps in a
+    /// viewer, only decode chunks that were not read before. Reads are
+    /// decoded in whole chunks, so small reads that never repeat become more
+    /// expensive. The cache is shared with child readers created afterwards.
+    pub fn enable_chunk_cache(&mut self, max_bytes: u64) {
+        self.chunk_cache = Some(Arc::new(ChunkCache::new(max_bytes)));
+    }
+
+    /// IO sizes used if a read does not specify them, instead of the preferred
+    /// sizes of the backend. Child readers created afterwards inherit them.
+    pub fn set_io_sizes(&mut self, io_sizes: IoSizes) {
+        self.io_sizes = Some(io_sizes);
+    }
+
+    /// Report lookups in the LUT and chunk caches to `metrics`. Child readers
+    /// created afterwards inherit it. Backend requests are reported by
+    /// wrapping the backend in a `MetricsBackend`.
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    fn record_cache_lookup(&self, cache: CacheKind, hit: bool) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_lookup(cache, hit);
         }
+    }
 
-        let n_dimensions_read = dim_read.len();
-        // TODO: Maybe cache this in the reader struct
-        let n_dims = self.get_dimensions().len();
+    /// IO sizes used if a read does not specify them
+    pub fn io_sizes(&self) -> IoSizes {
+        self.io_sizes
+            .unwrap_or_else(|| self.backend.preferred_io_sizes())
+    }
 
-        // Validate dimension counts
-        if n_dims != n_dimensions_read
-            || n_dimensions_read != into_cube_offset.len()
-            || n_dimensions_read != into_cube_dimension.len()
+    /// Read every chunk that intersects `dim_read` from the chunk cache or
+    /// decode it completely, then copy the intersection into `into`.
+    #[allow(clippy::too_many_arguments)]
+    fn read_with_chunk_cache<T: OmFileArrayDataType>(
+        &self,
+        chunk_cache: &ChunkCache,
+        into: &mut [T],
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        // Strings are not decoded by the C library
+        if T::DATA_TYPE_ARRAY != self.data_type() || T::DATA_TYPE_ARRAY == DataType::StringArray {
+            return Err(OmFilesRsError::InvalidDataType);
+        }
+        let dimensions = self.get_dimensions();
+        let chunks = self.get_chunk_dimensions();
+        let n_dims = dimensions.len();
+        if dim_read.len() != n_dims
+            || into_cube_offset.len() != n_dims
+            || into_cube_dimension.len() != n_dims
         {
             return Err(OmFilesRsError::MismatchingCubeDimensionLength);
         }
+        for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+            if range.start > range.end || range.end > dimension {
+                return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                    offset: range.start,
+                    count: range.end.saturating_sub(range.start),
+                    dimension,
+                });
+            }
+        }
+        if n_dims == 0 || dim_read.iter().any(|range| range.is_empty()) {
+            return Ok(());
+        }
 
-        // Prepare read parameters
-        let read_offset: Vec<u64> = dim_read.iter().map(|r| r.start).collect();
-        let read_count: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
-
-        // Initialize decoder
-        let mut decoder = unsafe { create_uninit_decoder() };
-        let error = unsafe {
-            om_decoder_init(
-                &mut decoder,
-                self.variable,
-                n_dimensions_read as u64,
-                read_offset.as_ptr(),
-                read_count.as_ptr(),
-                into_cube_offset.as_ptr(),
-                into_cube_dimension.as_ptr(),
-                io_size_merge,
-                io_size_max,
+        let variable = self.offset_size.as_ref().map_or(0, |o| o.offset);
+        let element_size = std::mem::size_of::<T>();
+        // Chunks are cached as bytes, because `T` is only known per read
+        let into = unsafe {
+            std::slice::from_raw_parts_mut(
+                into.as_mut_ptr() as *mut u8,
+                std::mem::size_of_val(into),
             )
         };
+        let grid: Vec<u64> = dimensions
+            .iter()
+            .zip(chunks.iter())
+            .map(|(&dimension, &chunk)| {
+                divide_rounded_up(dimension as usize, chunk as usize) as u64
+            })
+            .collect();
+        let first_chunk: Vec<u64> = dim_read
+            .iter()
+            .zip(chunks.iter())
+            .map(|(range, &chunk)| range.start / chunk)
+            .collect();
+        let chunk_counts: Vec<usize> = dim_read
+            .iter()
+            .zip(chunks.iter())
+            .zip(first_chunk.iter())
+            .map(|((range, &chunk), &first)| {
+                divide_rounded_up(range.end as usize, chunk as usize) - first as usize
+            })
+            .collect();
 
-        if error != OmError_t_ERROR_OK {
-            let error_string = c_error_string(error);
-            return Err(OmFilesRsError::DecoderError(error_string));
+        for position in ndarray::indices(chunk_counts) {
+            let chunk: Vec<u64> = (0..n_dims)
+                .map(|i| first_chunk[i] + position[i] as u64)
+                .collect();
+            let chunk_index = chunk
+                .iter()
+                .zip(grid.iter())
+                .fold(0, |index, (&c, &g)| index * g + c);
+            let region: Vec<Range<u64>> = (0..n_dims)
+                .map(|i| {
+                    let start = chunk[i] * chunks[i];
+                    start..(start + chunks[i]).min(dimensions[i])
+                })
+                .collect();
+            let region_count: Vec<u64> = region.iter().map(|r| r.end - r.start).collect();
+
+            let cached = chunk_cache.get(variable, chunk_index);
+            self.record_cache_lookup(CacheKind::Chunk, cached.is_some());
+            let data = match cached {
+                Some(data) => {
+                    stats.chunk_cache_hits += 1;
+                    data
+                }
+                None => {
+                    let length = region_count.iter().product::<u64>() as usize;
+                    // `u64` words are aligned for every data type
+                    let mut words = vec![0u64; divide_rounded_up(length * element_size, 8)];
+                    let values = unsafe {
+                        std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut T, length)
+                    };
+                    self.decode_region(
+                        values,
+                        &region,
+                        &vec![0; n_dims],
+                        &region_count,
+                        io_size_max,
+                        io_size_merge,
+                        stats,
+                    )?;
+                    let bytes = unsafe {
+                        std::slice::from_raw_parts(
+                            words.as_ptr() as *const u8,
+                            length * element_size,
+                        )
+                    };
+                    let data: Arc<[u8]> = bytes.into();
+                    chunk_cache.insert(variable, chunk_index, data.clone());
+                    data
+                }
+            };
+
+            // Copy the intersection row by row along the last dimension
+            let start: Vec<u64> = (0..n_dims)
+                .map(|i| region[i].start.max(dim_read[i].start))
+                .collect();
+            let end: Vec<u64> = (0..n_dims)
+                .map(|i| region[i].end.min(dim_read[i].end))
+                .collect();
+            let row_bytes = (end[n_dims - 1] - start[n_dims - 1]) as usize * element_size;
+            let rows: Vec<usize> = (0..n_dims - 1)
+                .map(|i| (end[i] - start[i]) as usize)
+                .collect();
+            for row in ndarray::indices(rows) {
+                let mut source = 0;
+                let mut destination = 0;
+                for i in 0..n_dims {
+                    let index = start[i] + if i < n_dims - 1 { row[i] as u64 } else { 0 };
+                    source = source * region_count[i] + index - region[i].start;
+                    destination =
+                        destination * into_cube_dimension[i] + into_cube_offset[i] + index
+                            - dim_read[i].start;
+                }
+                let source = source as usize * element_size;
+                let destination = destination as usize * element_size;
+                into[destination..destination + row_bytes]
+                    .copy_from_slice(&data[source..source + row_bytes]);
+            }
         }
+        Ok(())
+    }
 
-        // Allocate chunk buffer
-        let chunk_buffer_size = unsafe { om_decoder_read_buffer_size(&decoder) };
-        let mut chunk_buffer = Vec::<u8>::with_capacity(chunk_buffer_size as usize);
+    /// Like `OmFileReaderBackend::decode_with_stats`, but decoded LUT entries
+    /// are taken from the LUT cache if possible. Index blocks that are not
+    /// cached and compressed data are fetched with requests merged according
+    /// to `io_size_max` and `io_size_merge`.
+    #[allow(clippy::too_many_arguments)]
+    fn decode_with_lut_cache<T: OmFileArrayDataType>(
+        &self,
+        lut_cache: &LutCache,
+        decoder: &OmDecoder_t,
+        dim_read: &[Range<u64>],
+        into: &mut [T],
+        chunk_buffer: &mut [u8],
+        io_size_max: u64,
+        io_size_merge: u64,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        // Without merging, every index read covers a single index block and
+        // every data read a single chunk, so cached entries do not depend on
+        // the IO sizes of a read
+        let mut lut_decoder = *decoder;
+        lut_decoder.io_size_max = 0;
+        lut_decoder.io_size_merge = 0;
 
-        // Perform decoding
-        self.backend
-            .decode(&mut decoder, into, chunk_buffer.as_mut_slice())?;
+        // Chunks of an index block that intersect `dim_read`
+        let dimensions = self.get_dimensions();
+        let chunks = self.get_chunk_dimensions();
+        let intersects = |mut chunk_index: u64| {
+            for i in (0..dimensions.len()).rev() {
+                let grid = divide_rounded_up(dimensions[i] as usize, chunks[i] as usize) as u64;
+                let start = (chunk_index % grid) * chunks[i];
+                chunk_index /= grid;
+                if start >= dim_read[i].end || start + chunks[i] <= dim_read[i].start {
+                    return false;
+                }
+            }
+            true
+        };
+        let covers = |entries: &IndexEntries, chunk_index: u64| {
+            entries
+                .range(..=chunk_index)
+                .next_back()
+                .is_some_and(|(_, data_read)| data_read.chunkIndex.upperBound > chunk_index)
+        };
+
+        let mut index_blocks = Vec::new();
+        let mut index_read = new_index_read(&lut_decoder);
+        while unsafe { om_decoder_next_index_read(&lut_decoder, &mut index_read) } {
+            let needed: Vec<u64> = (index_read.chunkIndex.lowerBound
+                ..index_read.chunkIndex.upperBound)
+                .filter(|&chunk_index| intersects(chunk_index))
+                .collect();
+            let cached = lut_cache
+                .get(index_read.offset, index_read.count)
+                .filter(|entries| needed.iter().all(|&c| covers(entries, c)));
+            self.record_cache_lookup(CacheKind::Index, cached.is_some());
+            if cached.is_some() {
+                stats.index_cache_hits += 1;
+            }
+            index_blocks.push((index_read, needed, cached));
+        }
 
+        // Fetch and decode index blocks that are missing or incomplete
+        let missing: Vec<(u64, u64)> = index_blocks
+            .iter()
+            .filter(|(_, _, cached)| cached.is_none())
+            .map(|(index_read, _, _)| (index_read.offset, index_read.count))
+            .collect();
+        let mut fetched = Vec::new();
+        for (offset, count) in merge_ranges(&missing, io_size_max, io_size_merge) {
+            let data = self.backend.get_bytes_with_fallback(offset, count)?;
+            stats.record_index_read(count);
+            fetched.push((offset, data.into_owned()));
+        }
+        let index_data = CoalescedBytes::from_blocks(fetched);
+        for (index_read, _, cached) in index_blocks.iter_mut().filter(|(_, _, c)| c.is_none()) {
+            let mut entries = lut_cache
+                .get(index_read.offset, index_read.count)
+                .map(|entries| (*entries).clone())
+                .unwrap_or_default();
+            let index_bytes = index_data.get(index_read.offset, index_read.count);
+            for data_read in collect_data_reads(&lut_decoder, index_read, index_bytes)? {
+                entries.insert(data_read.chunkIndex.lowerBound, data_read);
+            }
+            let entries = Arc::new(entries);
+            lut_cache.insert(index_read.offset, index_read.count, entries.clone());
+            *cached = Some(entries);
+        }
+
+        // Data reads of all chunks, merged into as few requests as the IO
+        // sizes allow
+        let mut data_reads = Vec::new();
+        for (_, needed, entries) in index_blocks.iter() {
+            let Some(entries) = entries else { continue };
+            for &chunk_index in needed {
+                if let Some((_, data_read)) = entries.range(..=chunk_index).next_back() {
+                    data_reads.push(*data_read);
+                }
+            }
+        }
+        data_reads.sort_unstable_by_key(|r| (r.offset, r.chunkIndex.lowerBound));
+        data_reads.dedup_by_key(|r| r.chunkIndex.lowerBound);
+        let data_ranges: Vec<(u64, u64)> = data_reads.iter().map(|r| (r.offset, r.count)).collect();
+        let mut first = 0;
+        for (offset, count) in merge_ranges(&data_ranges, io_size_max, io_size_merge) {
+            let last = first + data_reads[first..].partition_point(|r| r.offset < offset + count);
+            let group = &data_reads[first..last];
+            first = last;
+            let data = self.backend.get_bytes_with_fallback(offset, count)?;
+            let chunks = group
+                .iter()
+                .map(|r| r.chunkIndex.upperBound - r.chunkIndex.lowerBound)
+                .sum();
+            stats.record_data_request(count, chunks);
+            for data_read in group {
+                let start = (data_read.offset - offset) as usize;
+                let data_bytes = &data[start..start + data_read.count as usize];
+                stats.time_decode(data_read, || {
+                    decode_chunks(decoder, chunk_buffer, into, data_read, data_bytes)
+                })?;
+            }
+        }
         Ok(())
     }
 
-    pub fn read<T: OmFileArrayDataType + Clone + Zero>(
+    /// Compute the byte ranges of LUT and compressed data that `read` of
+    /// `dim_read` would fetch and advise the backend to load them with
+    /// `prefetch_data`, e.g. `madvise(WILLNEED)` for mmap. Only the LUT is
+    /// read to locate the data. Async backends can use the returned plan to
+    /// warm their caches.
+    pub fn prefetch(
+        &self,
+        dim_read: &[Range<u64>],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<PrefetchPlan, OmFilesRsError> {
+        let preferred = self.io_sizes();
+        let count: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let prepared = self.variable_ref().prepare_decoder(
+            dim_read,
+            &vec![0; dim_read.len()],
+            &count,
+            io_size_max.unwrap_or(preferred.io_size_max),
+            io_size_merge.unwrap_or(preferred.io_size_merge),
+        )?;
+        let decoder = &prepared.decoder;
+
+        let mut plan = PrefetchPlan::default();
+        let mut index_read = new_index_read(decoder);
+        while unsafe { om_decoder_next_index_read(decoder, &mut index_read) } {
+            self.backend
+                .prefetch_data(index_read.offset as usize, index_read.count as usize);
+            plan.index_ranges
+                .push(index_read.offset..index_read.offset + index_read.count);
+            let index_data = self
+                .backend
+                .get_bytes_with_fallback(index_read.offset, index_read.count)?;
+            for data_read in collect_data_reads(decoder, &index_read, &index_data)? {
+                self.backend
+                    .prefetch_data(data_read.offset as usize, data_read.count as usize);
+                plan.data_ranges
+                    .push(data_read.offset..data_read.offset + data_read.count);
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Select `ranges` without reading any data. The returned slice can be
+    /// sliced further and is read with `to_array` or `read_into`.
+    pub fn slice(
+        &self,
+        ranges: &[Range<u64>],
+    ) -> Result<VariableSlice<'_, Backend>, OmFilesRsError> {
+        VariableSlice::new(self, ranges)
+    }
+
+    /// Read a selection of an array. String arrays are read with `T = String`.
+    pub fn read<T: OmFileArrayDataType + Clone + Default>(
         &self,
         dim_read: &[Range<u64>],
         io_size_max: Option<u64>,
         io_size_merge: Option<u64>,
     ) -> Result<ArrayD<T>, OmFilesRsError> {
+        if T::DATA_TYPE_ARRAY == DataType::StringArray {
+            return T::from_string_array(self.read_string_array(dim_read)?)
+                .ok_or(OmFilesRsError::InvalidDataType);
+        }
         let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
         let out_dims_usize = out_dims.iter().map(|&x| x as usize).collect::<Vec<_>>();
 
-        let mut out = ArrayD::<T>::zeros(out_dims_usize);
+        let mut out = ArrayD::<T>::default(out_dims_usize);
 
         self.read_into::<T>(
             &mut out,
@@ -335,6 +1026,744 @@ impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
 
         Ok(out)
     }
+
+    /// Read like `read`, but decode with up to `n_threads` threads. The selection
+    /// is split into slabs along the first dimension that are aligned to chunk
+    /// boundaries. Each thread decodes its own chunks into a distinct part of
+    /// the output array, so the achievable parallelism is limited by the number
+    /// of chunks along the first dimension.
+    pub fn read_parallel<T: OmFileArrayDataType + Clone + Default + Send>(
+        &self,
+        dim_read: &[Range<u64>],
+        n_threads: usize,
+    ) -> Result<ArrayD<T>, OmFilesRsError>
+    where
+        Backend: Sync,
+    {
+        let IoSizes {
+            io_size_max,
+            io_size_merge,
+        } = self.io_sizes();
+
+        let dimensions = self.get_dimensions();
+        if dim_read.len() != dimensions.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        if n_threads <= 1 || dim_read.is_empty() || dim_read[0].is_empty() {
+            return self.read(dim_read, Some(io_size_max), Some(io_size_merge));
+        }
+
+        let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let mut out =
+            ArrayD::<T>::default(out_dims.iter().map(|&x| x as usize).collect::<Vec<_>>());
+
+        // Chunk boundaries along the first dimension
+        let chunk = self.get_chunk_dimensions()[0];
+        let range = &dim_read[0];
+        let mut boundaries = vec![range.start];
+        let mut boundary = (range.start / chunk + 1) * chunk;
+        while boundary < range.end {
+            boundaries.push(boundary);
+            boundary += chunk;
+        }
+        boundaries.push(range.end);
+
+        // Combine chunk rows into one slab per thread
+        let rows = boundaries.len() - 1;
+        let slabs = n_threads.min(rows);
+        let row_length: u64 = out_dims[1..].iter().product();
+
+        let mut remaining = out
+            .as_slice_mut()
+            .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+        let mut jobs = Vec::with_capacity(slabs);
+        for slab in 0..slabs {
+            let start = boundaries[slab * rows / slabs];
+            let end = boundaries[(slab + 1) * rows / slabs];
+            let mut slab_read = dim_read.to_vec();
+            slab_read[0] = start..end;
+            let mut slab_dims = out_dims.clone();
+            slab_dims[0] = end - start;
+            let prepared = self.variable_ref().prepare_read::<T>(
+                &slab_read,
+                &vec![0; dim_read.len()],
+                &slab_dims,
+                io_size_max,
+                io_size_merge,
+            )?;
+            let (into, tail) = remaining.split_at_mut(((end - start) * row_length) as usize);
+            remaining = tail;
+            jobs.push((prepared, into));
+        }
+
+        let backend = self.backend.as_ref();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = jobs
+                .into_iter()
+                .map(|job| {
+                    scope.spawn(move || {
+                        decode_batch(backend, &mut [job], io_size_max, io_size_merge)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("Decoder thread panicked"))
+        })?;
+
+        Ok(out)
+    }
+
+    /// Read data together with a validity mask that is `false` where the data
+    /// equals the fill value. All elements are valid if no fill value is stored.
+    pub fn read_with_validity<T>(
+        &self,
+        dim_read: &[Range<u64>],
+    ) -> Result<(ArrayD<T>, ArrayD<bool>), OmFilesRsError>
+    where
+        T: OmFileArrayDataType + OmFileScalarDataType + Clone + Default + PartialEq,
+    {
+        let data = self.read::<T>(dim_read, None, None)?;
+        let validity = match self.get_fill_value::<T>() {
+            Some(fill_value) => data.map(|v| !is_fill_value(v, &fill_value)),
+            None => ArrayD::from_elem(data.raw_dim(), true),
+        };
+        Ok((data, validity))
+    }
+
+    /// Read data and replace the fill value with `sentinel`, e.g. a fill value
+    /// of `i16::MIN` with 0.
+    pub fn read_replacing_fill_value<T>(
+        &self,
+        dim_read: &[Range<u64>],
+        sentinel: T,
+    ) -> Result<ArrayD<T>, OmFilesRsError>
+    where
+        T: OmFileArrayDataType + OmFileScalarDataType + Clone + Default + PartialEq,
+    {
+        let mut data = self.read::<T>(dim_read, None, None)?;
+        if let Some(fill_value) = self.get_fill_value::<T>() {
+            data.mapv_inplace(|v| {
+                if is_fill_value(&v, &fill_value) {
+                    sentinel.clone()
+                } else {
+                    v
+                }
+            });
+        }
+        Ok(data)
+    }
+
+    /// Histogram of all values in `dim_read` with bins given by `edges`. The
+    /// selection is read in chunk-aligned blocks, so memory usage does not
+    /// depend on the size of the selection. Use `Histogram::quantile` for
+    /// approximate quantiles.
+    pub fn compute_histogram<T>(
+        &self,
+        dim_read: &[Range<u64>],
+        edges: &[f64],
+    ) -> Result<Histogram, OmFilesRsError>
+    where
+        T: OmFileArrayDataType + Clone + Default + ToPrimitive,
+    {
+        let mut histogram = Histogram::new(edges)?;
+        for block_read in aligned_tiles(
+            self.get_dimensions(),
+            self.get_chunk_dimensions(),
+            dim_read,
+            DEFAULT_MAX_READ_ELEMENTS,
+        )? {
+            let data = self.read::<T>(&block_read, None, None)?;
+            data.iter()
+                .for_each(|v| histogram.add(v.to_f64().unwrap_or(f64::NAN)));
+        }
+        Ok(histogram)
+    }
+
+    /// Read the whole variable in tiles of at most `max_bytes` and call `tile`
+    /// with each tile and the offset of its first element. Tiles are aligned
+    /// to chunks and contain at least one chunk, so every chunk is decoded
+    /// once. They are passed in the order `OmFileWriterArray::write_data`
+    /// expects for an array with the same chunk dimensions.
+    pub fn read_tiled<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        max_bytes: u64,
+        mut tile: impl FnMut(ArrayD<T>, &[u64]) -> Result<(), OmFilesRsError>,
+    ) -> Result<(), OmFilesRsError> {
+        let dim_read: Vec<Range<u64>> = self.get_dimensions().iter().map(|&x| 0..x).collect();
+        let max_read_elements = max_bytes / std::mem::size_of::<T>() as u64;
+        for block_read in aligned_tiles(
+            self.get_dimensions(),
+            self.get_chunk_dimensions(),
+            &dim_read,
+            max_read_elements,
+        )? {
+            let offset: Vec<u64> = block_read.iter().map(|range| range.start).collect();
+            tile(self.read::<T>(&block_read, None, None)?, &offset)?;
+        }
+        Ok(())
+    }
+
+    /// Read only the chunks that may contain values in `values`, based on the
+    /// chunk statistics stored with `with_chunk_statistics`. Returns the data
+    /// and a mask of all elements within `values`. Elements of skipped chunks
+    /// are zero. Without chunk statistics, all chunks are read.
+    pub fn read_where<T>(
+        &self,
+        dim_read: &[Range<u64>],
+        values: Range<f64>,
+    ) -> Result<(ArrayD<T>, ArrayD<bool>), OmFilesRsError>
+    where
+        T: OmFileArrayDataType + Clone + Default + ToPrimitive,
+    {
+        let dimensions = self.get_dimensions();
+        let chunks = self.get_chunk_dimensions();
+        if dimensions.len() != dim_read.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+            if range.start > range.end || range.end > dimension {
+                return Err(OmFilesRsError::DimensionOutOfBounds {
+                    range: range.start as usize..range.end as usize,
+                    allowed: dimension as usize,
+                });
+            }
+        }
+        let in_range = |v: &T| {
+            let v = v.to_f64().unwrap_or(f64::NAN);
+            v >= values.start && v < values.end
+        };
+
+        let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let out_dims_usize = out_dims.iter().map(|&x| x as usize).collect::<Vec<_>>();
+        let mut out = ArrayD::<T>::default(out_dims_usize.clone());
+        let mut mask = ArrayD::from_elem(out_dims_usize, false);
+        if out.is_empty() {
+            return Ok((out, mask));
+        }
+
+        let (Some(min), Some(max)) = (
+            self.get_child_by_name(CHUNK_MIN_VARIABLE),
+            self.get_child_by_name(CHUNK_MAX_VARIABLE),
+        ) else {
+            let data = self.read::<T>(dim_read, None, None)?;
+            let mask = data.map(in_range);
+            return Ok((data, mask));
+        };
+
+        // Statistics of all chunks that overlap the selection
+        let grid_read: Vec<Range<u64>> = dim_read
+            .iter()
+            .zip(chunks.iter())
+            .map(|(range, &chunk)| {
+                range.start / chunk..divide_rounded_up(range.end as usize, chunk as usize) as u64
+            })
+            .collect();
+        let grid_min = min.read::<f64>(&grid_read, None, None)?;
+        let statistics = ChunkStatistics {
+            max: max
+                .read::<f64>(&grid_read, None, None)?
+                .into_iter()
+                .collect(),
+            min: grid_min.iter().copied().collect(),
+        };
+
+        for (i, (grid_position, _)) in grid_min.indexed_iter().enumerate() {
+            if !statistics.intersects(i, &values) {
+                continue;
+            }
+            // Part of the chunk within the selection
+            let chunk_read: Vec<Range<u64>> = (0..dim_read.len())
+                .map(|d| {
+                    let chunk_start = (grid_read[d].start + grid_position[d] as u64) * chunks[d];
+                    let start = chunk_start.max(dim_read[d].start);
+                    let end = (chunk_start + chunks[d]).min(dim_read[d].end);
+                    start..end.max(start)
+                })
+                .collect();
+            if chunk_read.iter().any(|r| r.is_empty()) {
+                continue;
+            }
+            let into_offset: Vec<u64> = chunk_read
+                .iter()
+                .zip(dim_read.iter())
+                .map(|(r, read)| r.start - read.start)
+                .collect();
+            self.read_into::<T>(&mut out, &chunk_read, &into_offset, &out_dims, None, None)?;
+
+            let region = |axis: ndarray::AxisDescription| {
+                let r = &chunk_read[axis.axis.index()];
+                let offset = into_offset[axis.axis.index()] as usize;
+                Slice::from(offset..offset + (r.end - r.start) as usize)
+            };
+            mask.slice_each_axis_mut(region)
+                .zip_mut_with(&out.slice_each_axis(region), |m, v| *m = in_range(v));
+        }
+        Ok((out, mask))
+    }
+
+    /// Read all values of a vector written with `OmFileWriter::write_small_array`.
+    /// Arrays of more dimensions are returned flattened in row-major order.
+    pub fn read_small_array<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+    ) -> Result<Vec<T>, OmFilesRsError> {
+        let dim_read: Vec<Range<u64>> = self.get_dimensions().iter().map(|&x| 0..x).collect();
+        let values = self.read::<T>(&dim_read, None, None)?;
+        Ok(values.into_iter().collect())
+    }
+
+    /// Read a mask written with `OmFileWriter::write_mask`. Any non-zero value
+    /// of an `u8` array is `true`.
+    pub fn read_mask(&self, dim_read: &[Range<u64>]) -> Result<ArrayD<bool>, OmFilesRsError> {
+        let values = self.read::<u8>(dim_read, None, None)?;
+        Ok(values.mapv(|v| v != 0))
+    }
+
+    /// Read every `step[i]`-th element of `dim_read[i]`, e.g. every 24th time
+    /// step. Only chunks that contain selected elements are read and decoded,
+    /// so steps larger than the chunk dimension skip whole chunks.
+    pub fn read_strided<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+        step: &[u64],
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        let chunks = self.get_chunk_dimensions();
+        if dim_read.len() != chunks.len() || step.len() != chunks.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        if step.contains(&0) {
+            return Err(OmFilesRsError::DimensionMustBeLargerThan0);
+        }
+
+        let out_dims: Vec<usize> = dim_read
+            .iter()
+            .zip(step)
+            .map(|(r, &s)| divide_rounded_up(r.end.saturating_sub(r.start) as usize, s as usize))
+            .collect();
+        let mut out = ArrayD::<T>::default(out_dims);
+        if out.is_empty() {
+            return Ok(out);
+        }
+
+        // Per dimension, contiguous ranges that cover the selected elements of
+        // neighbouring chunks. Chunks without selected elements are skipped.
+        let segments: Vec<Vec<Range<u64>>> = (0..chunks.len())
+            .map(|i| strided_segments(&dim_read[i], step[i], chunks[i]))
+            .collect();
+        let counts: Vec<usize> = segments.iter().map(|s| s.len()).collect();
+
+        for index in ndarray::indices(counts) {
+            let ranges: Vec<Range<u64>> = (0..chunks.len())
+                .map(|i| segments[i][index[i]].clone())
+                .collect();
+            let block = self.read::<T>(&ranges, None, None)?;
+            let block =
+                block.slice_each_axis(|axis| Slice::new(0, None, step[axis.axis.index()] as isize));
+            out.slice_each_axis_mut(|axis| {
+                let i = axis.axis.index();
+                let start = ((ranges[i].start - dim_read[i].start) / step[i]) as usize;
+                Slice::from(start..start + block.len_of(axis.axis))
+            })
+            .assign(&block);
+        }
+        Ok(out)
+    }
+
+    /// Copy this variable with new chunk dimensions into `writer`. See
+    /// [`rechunk`] for details. The caller still has to write the trailer.
+    pub fn rechunk_to<WriterBackend: OmFileWriterBackend>(
+        &self,
+        writer: &mut OmFileWriter<WriterBackend>,
+        chunks: &[u64],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        rechunk(self, writer, chunks, None)
+    }
+
+    /// Read a selection of a string array, like `read::<String>`.
+    pub fn read_string_array(
+        &self,
+        dim_read: &[Range<u64>],
+    ) -> Result<ArrayD<String>, OmFilesRsError> {
+        if self.data_type() != DataType::StringArray {
+            return Err(OmFilesRsError::InvalidDataType);
+        }
+        let view = ArrayVariableView::new(self.variable.data())?;
+        let dimensions = view.dimensions();
+        if dimensions.is_empty() {
+            return Err(OmFilesRsError::DimensionMustBeLargerThan0);
+        }
+        if dimensions.len() != dim_read.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+            if range.start > range.end || range.end > dimension {
+                return Err(OmFilesRsError::DimensionOutOfBounds {
+                    range: range.start as usize..range.end as usize,
+                    allowed: dimension as usize,
+                });
+            }
+        }
+
+        let out_dims: Vec<usize> = dim_read
+            .iter()
+            .map(|r| (r.end - r.start) as usize)
+            .collect();
+        if out_dims.contains(&0) {
+            return Ok(ArrayD::from_shape_vec(out_dims, vec![]).unwrap());
+        }
+
+        // Flat indices of all selected elements in row-major order
+        let mut strides = vec![1u64; dimensions.len()];
+        for i in (0..dimensions.len() - 1).rev() {
+            strides[i] = strides[i + 1] * dimensions[i + 1];
+        }
+        let indices: Vec<u64> = ndarray::indices(out_dims.clone())
+            .into_iter()
+            .map(|index| {
+                (0..dimensions.len())
+                    .map(|k| (dim_read[k].start + index[k] as u64) * strides[k])
+                    .sum()
+            })
+            .collect();
+        let first = indices[0];
+        let last = indices[indices.len() - 1];
+
+        // Only fetch the part of the offset table and of the string data that is needed
+        let table = self
+            .backend
+            .get_bytes_with_fallback(view.lut_offset() + first * 8, (last - first + 2) * 8)?;
+        let offset_at = |index: u64| {
+            let position = ((index - first) * 8) as usize;
+            u64::from_le_bytes(table[position..position + 8].try_into().unwrap())
+        };
+        let data_start = offset_at(first);
+        let data_end = offset_at(last + 1);
+        if data_end < data_start {
+            return Err(OmFilesRsError::DecoderError(
+                "Invalid string array offsets".to_string(),
+            ));
+        }
+        let data = self
+            .backend
+            .get_bytes_with_fallback(data_start, data_end - data_start)?;
+
+        let strings = indices
+            .iter()
+            .map(|&index| {
+                let start = offset_at(index);
+                let end = offset_at(index + 1);
+                if start < data_start || end < start || end > data_end {
+                    return Err(OmFilesRsError::DecoderError(
+                        "Invalid string array offsets".to_string(),
+                    ));
+                }
+                let bytes = &data[(start - data_start) as usize..(end - data_start) as usize];
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|e| OmFilesRsError::DecoderError(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ArrayD::from_shape_vec(out_dims, strings).unwrap())
+    }
+
+    /// Read several variables of this file in one call. Variables are looked up
+    /// by name in the whole variable tree. Backend requests of all variables are
+    /// merged if they are close to each other, which reduces the number of round
+    /// trips for remote backends. All compressed data of the requested ranges is
+    /// kept in memory until decoding is done.
+    pub fn read_vars<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        variables: &[(&str, &[Range<u64>])],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<Vec<ArrayD<T>>, OmFilesRsError> {
+        let preferred = self.io_sizes();
+        let io_size_max = io_size_max.unwrap_or(preferred.io_size_max);
+        let io_size_merge = io_size_merge.unwrap_or(preferred.io_size_merge);
+
+        let metadata = self.get_flat_variable_metadata();
+        let readers = variables
+            .iter()
+            .map(|(name, _)| {
+                let offset_size = metadata
+                    .get(*name)
+                    .ok_or_else(|| OmFilesRsError::VariableNotFound(name.to_string()))?;
+                self.init_child_from_offset_size(offset_size.clone())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = variables
+            .iter()
+            .map(|(_, dim_read)| {
+                let out_dims: Vec<usize> = dim_read
+                    .iter()
+                    .map(|r| (r.end - r.start) as usize)
+                    .collect();
+                ArrayD::<T>::default(out_dims)
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut reads = Vec::with_capacity(readers.len());
+            for ((reader, (_, dim_read)), out) in
+                readers.iter().zip(variables.iter()).zip(outputs.iter_mut())
+            {
+                let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+                let prepared = reader.variable_ref().prepare_read::<T>(
+                    dim_read,
+                    &vec![0; dim_read.len()],
+                    &out_dims,
+                    io_size_max,
+                    io_size_merge,
+                )?;
+                let into = out
+                    .as_slice_mut()
+                    .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+                reads.push((prepared, into));
+            }
+            decode_batch(
+                self.backend.as_ref(),
+                &mut reads,
+                io_size_max,
+                io_size_merge,
+            )?;
+        }
+
+        Ok(outputs)
+    }
+
+    /// Read many small selections, e.g. time series at thousands of points.
+    /// All chunks touched by any selection are collected first, backend
+    /// requests are merged and every chunk is decoded only once, even if
+    /// several selections share it. Decoded chunks are kept in memory until
+    /// all selections are copied out.
+    pub fn read_batch<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        selections: &[Vec<Range<u64>>],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<Vec<ArrayD<T>>, OmFilesRsError> {
+        let preferred = self.io_sizes();
+        let io_size_max = io_size_max.unwrap_or(preferred.io_size_max);
+        let io_size_merge = io_size_merge.unwrap_or(preferred.io_size_merge);
+        let dimensions = self.get_dimensions();
+        let chunks = self.get_chunk_dimensions();
+        for dim_read in selections {
+            if dim_read.len() != dimensions.len() {
+                return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+            }
+            for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+                if range.start > range.end || range.end > dimension {
+                    return Err(OmFilesRsError::DimensionOutOfBounds {
+                        range: range.start as usize..range.end as usize,
+                        allowed: dimension as usize,
+                    });
+                }
+            }
+        }
+
+        let touched: Vec<Vec<Vec<u64>>> = selections
+            .iter()
+            .map(|dim_read| chunks_in_selection(dim_read, chunks))
+            .collect();
+        let mut unique: Vec<Vec<u64>> = touched.iter().flatten().cloned().collect();
+        unique.sort_unstable();
+        unique.dedup();
+
+        let chunk_ranges = |coordinate: &[u64]| -> Vec<Range<u64>> {
+            coordinate
+                .iter()
+                .zip(chunks.iter().zip(dimensions.iter()))
+                .map(|(&c, (&chunk, &dimension))| c * chunk..((c + 1) * chunk).min(dimension))
+                .collect()
+        };
+        let mut decoded: Vec<ArrayD<T>> = unique
+            .iter()
+            .map(|coordinate| {
+                let shape: Vec<usize> = chunk_ranges(coordinate)
+                    .iter()
+                    .map(|r| (r.end - r.start) as usize)
+                    .collect();
+                ArrayD::<T>::default(shape)
+            })
+            .collect();
+        {
+            let mut reads = Vec::with_capacity(unique.len());
+            for (coordinate, out) in unique.iter().zip(decoded.iter_mut()) {
+                let dim_read = chunk_ranges(coordinate);
+                let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+                let prepared = self.variable_ref().prepare_read::<T>(
+                    &dim_read,
+                    &vec![0; dim_read.len()],
+                    &out_dims,
+                    io_size_max,
+                    io_size_merge,
+                )?;
+                let into = out
+                    .as_slice_mut()
+                    .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+                reads.push((prepared, into));
+            }
+            decode_batch(
+                self.backend.as_ref(),
+                &mut reads,
+                io_size_max,
+                io_size_merge,
+            )?;
+        }
+
+        // Copy the intersection of every selection with its chunks
+        let outputs = selections
+            .iter()
+            .zip(touched.iter())
+            .map(|(dim_read, coordinates)| {
+                let shape: Vec<usize> = dim_read
+                    .iter()
+                    .map(|r| (r.end - r.start) as usize)
+                    .collect();
+                let mut out = ArrayD::<T>::default(shape);
+                for coordinate in coordinates {
+                    let index = unique.binary_search(coordinate).unwrap();
+                    let chunk = chunk_ranges(coordinate);
+                    let intersection: Vec<Range<u64>> = dim_read
+                        .iter()
+                        .zip(chunk.iter())
+                        .map(|(r, c)| r.start.max(c.start)..r.end.min(c.end))
+                        .collect();
+                    let source = decoded[index].slice_each_axis(|axis| {
+                        let i = axis.axis.index();
+                        let start = (intersection[i].start - chunk[i].start) as usize;
+                        let end = (intersection[i].end - chunk[i].start) as usize;
+                        Slice::from(start..end)
+                    });
+                    out.slice_each_axis_mut(|axis| {
+                        let i = axis.axis.index();
+                        let start = (intersection[i].start - dim_read[i].start) as usize;
+                        let end = (intersection[i].end - dim_read[i].start) as usize;
+                        Slice::from(start..end)
+                    })
+                    .assign(&source);
+                }
+                out
+            })
+            .collect();
+        Ok(outputs)
+    }
+
+    /// Interpolate bilinearly at fractional grid coordinates `(y, x)` for
+    /// all time steps in `time_range`. The array must have the dimensions
+    /// `[y, x, time]`. Only the four neighbouring cells of every point are
+    /// read with `read_batch`. Returns an array of shape `[points, time]`.
+    pub fn sample_bilinear(
+        &self,
+        points: &[(f64, f64)],
+        time_range: Range<u64>,
+    ) -> Result<Array2<f32>, OmFilesRsError> {
+        let dimensions = self.get_dimensions();
+        if dimensions.len() != 3 {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        let (ny, nx) = (dimensions[0], dimensions[1]);
+        // Index of the lower neighbour and fraction towards the upper one
+        let neighbour = |value: f64, n: u64| -> Result<(u64, f64), OmFilesRsError> {
+            if n == 0 || !(0.0..=(n - 1) as f64).contains(&value) {
+                return Err(OmFilesRsError::DimensionOutOfBounds {
+                    range: value as usize..value as usize + 1,
+                    allowed: n as usize,
+                });
+            }
+            let lower = (value.floor() as u64).min(n.saturating_sub(2));
+            Ok((lower, value - lower as f64))
+        };
+        let neighbours = points
+            .iter()
+            .map(|&(y, x)| Ok((neighbour(y, ny)?, neighbour(x, nx)?)))
+            .collect::<Result<Vec<_>, OmFilesRsError>>()?;
+        let selections: Vec<Vec<Range<u64>>> = neighbours
+            .iter()
+            .map(|&((y, _), (x, _))| {
+                vec![y..(y + 2).min(ny), x..(x + 2).min(nx), time_range.clone()]
+            })
+            .collect();
+        let blocks = self.read_batch::<f32>(&selections, None, None)?;
+
+        let time_steps = (time_range.end - time_range.start) as usize;
+        let mut out = Array2::<f32>::zeros((points.len(), time_steps));
+        for (i, (block, &((_, fy), (_, fx)))) in blocks.iter().zip(neighbours.iter()).enumerate() {
+            let (h, w) = (block.shape()[0], block.shape()[1]);
+            let value = |y: usize, x: usize, t: usize| block[[y.min(h - 1), x.min(w - 1), t]];
+            let (fy, fx) = (fy as f32, fx as f32);
+            let weights = [
+                (0, 0, (1.0 - fy) * (1.0 - fx)),
+                (0, 1, (1.0 - fy) * fx),
+                (1, 0, fy * (1.0 - fx)),
+                (1, 1, fy * fx),
+            ];
+            for t in 0..time_steps {
+                // Cells without weight are skipped, so their NaNs do not propagate
+                out[[i, t]] = weights
+                    .iter()
+                    .filter(|(_, _, weight)| *weight > 0.0)
+                    .map(|&(y, x, weight)| value(y, x, t) * weight)
+                    .sum();
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Root variable of a trailer at `offset`, if there is one that points to
+/// valid metadata before it
+fn recover_trailer_at<Backend: OmFileReaderBackend>(
+    backend: &Arc<Backend>,
+    offset: u64,
+    trailer_size: u64,
+) -> Option<OmOffsetSize> {
+    if offset + trailer_size > backend.count() as u64 {
+        return None;
+    }
+    let trailer = backend.get_bytes_with_fallback(offset, trailer_size).ok()?;
+    let (mut root_offset, mut root_size) = (0u64, 0u64);
+    if !unsafe {
+        om_trailer_read(
+            trailer.as_ptr() as *const c_void,
+            &mut root_offset,
+            &mut root_size,
+        )
+    } {
+        return None;
+    }
+    let offset_size = OmOffsetSize::new(root_offset, root_size);
+    check_metadata_location(&offset_size, offset).ok()?;
+    Some(offset_size)
+}
+
+/// Location of a named variable at `offset` whose children are all valid
+/// variables before it. The bytes of compressed data rarely pass all checks.
+fn recover_variable_at<Backend: OmFileReaderBackend>(
+    backend: &Arc<Backend>,
+    offset: u64,
+    file_size: u64,
+) -> Option<OmOffsetSize> {
+    let data = backend
+        .get_bytes_with_fallback(offset, (file_size - offset).min(MAX_METADATA_SIZE))
+        .ok()?;
+    if data.starts_with(b"OM") {
+        return None;
+    }
+    let size = validate_variable_metadata(&data).ok()?;
+    let variable = OmVariableContainer::new(data[..size].to_vec()).ok()?;
+    let variable = variable.variable_ref();
+    variable.get_name()?;
+    for i in 0..variable.number_of_children() {
+        let child = variable.child_offset_size(i)?;
+        check_metadata_location(&child, offset).ok()?;
+        let child_data = backend
+            .get_bytes_with_fallback(child.offset, child.size)
+            .ok()?;
+        validate_variable_metadata(&child_data).ok()?;
+    }
+    Some(OmOffsetSize::new(offset, size as u64))
 }
 
 impl OmFileReader<MmapFile> {
@@ -361,3 +1790,57 @@ impl OmFileReader<MmapFile> {
         self.backend.was_deleted()
     }
 }
+
+/// Whether `value` equals `fill_value`. A NaN fill value matches all NaNs,
+/// which are the only values that are not equal to themselves.
+#[allow(clippy::eq_op)]
+fn is_fill_value<T: PartialEq>(value: &T, fill_value: &T) -> bool {
+    value == fill_value || (value != value && fill_value != fill_value)
+}
+
+/// Coordinates in the chunk grid of all chunks that intersect `dim_read`
+fn chunks_in_selection(dim_read: &[Range<u64>], chunks: &[u64]) -> Vec<Vec<u64>> {
+    if dim_read.iter().any(|r| r.start == r.end) {
+        return vec![];
+    }
+    let first: Vec<u64> = dim_read
+        .iter()
+        .zip(chunks.iter())
+        .map(|(r, &chunk)| r.start / chunk)
+        .collect();
+    let shape: Vec<usize> = dim_read
+        .iter()
+        .zip(chunks.iter().zip(first.iter()))
+        .map(|(r, (&chunk, &first))| {
+            divide_rounded_up(r.end as usize, chunk as usize) - first as usize
+        })
+        .collect();
+    ndarray::indices(shape)
+        .into_iter()
+        .map(|index| {
+            first
+                .iter()
+                .enumerate()
+                .map(|(i, &first)| first + index[i] as u64)
+                .collect()
+        })
+        .collect()
+}
+
+/// Split the selected elements `range.start, range.start + step, ...` into
+/// ranges that span consecutive chunks and start and end at selected elements.
+fn strided_segments(range: &Range<u64>, step: u64, chunk: u64) -> Vec<Range<u64>> {
+    let mut segments: Vec<Range<u64>> = Vec::new();
+    let mut i = range.start;
+    while i < range.end {
+        let chunk_index = i / chunk;
+        let end = ((chunk_index + 1) * chunk).min(range.end);
+        let last = i + (end - 1 - i) / step * step;
+        match segments.last_mut() {
+            Some(segment) if (segment.end - 1) / chunk + 1 == chunk_index => segment.end = last + 1,
+            _ => segments.push(i..last + 1),
+        }
+        i = last + step;
+    }
+    segments
+}
@@ -0,0 +1,550 @@
+use crate::backend::backends::{IoSizes, OmFileReaderBackendAsync};
+use crate::core::c_defaults::new_index_read;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::{DataType, OmFileArrayDataType, OmFileScalarDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::batch_reader::{collect_data_reads, decode_chunks, merge_ranges, CoalescedBytes};
+use crate::io::cancellation::CancellationToken;
+use crate::io::copy::aligned_tiles;
+use crate::io::read_stats::ReadStats;
+use crate::io::variable::{check_metadata_location, OmVariableContainer, VariableRef};
+use crate::io::writer::OmOffsetSize;
+use futures::future::{select, Either};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use ndarray::ArrayD;
+use om_file_format_sys::{
+    om_decoder_next_index_read, om_header_size, om_header_type, om_trailer_read, om_trailer_size,
+    OmHeaderType_t_OM_HEADER_LEGACY, OmHeaderType_t_OM_HEADER_READ_TRAILER,
+};
+use std::ops::Range;
+use std::os::raw::c_void;
+use std::pin::pin;
+use std::sync::Arc;
+
+/// Default number of backend requests in flight per read
+const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
+/// Settings of a single read of `OmFileReaderAsync`. Unset values fall back
+/// to the settings of the reader, so one shared reader can serve small
+/// latency-sensitive reads and large scans with different settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReadOptions {
+    /// Overrides `OmFileReaderAsync::max_concurrency` for this read
+    pub max_concurrency: Option<usize>,
+    /// Overrides `OmFileReaderAsync::io_sizes` for this read
+    pub io_sizes: Option<IoSizes>,
+}
+
+impl ReadOptions {
+    /// Limit the number of concurrent backend requests of the read. 0 is
+    /// raised to 1.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+
+    pub fn with_io_sizes(mut self, io_sizes: IoSizes) -> Self {
+        self.io_sizes = Some(io_sizes);
+        self
+    }
+}
+
+/// How `OmFileReaderAsync::stream_tiles` splits a selection into tiles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileSpec {
+    /// Tiles of at most this many bytes that are aligned to chunks and
+    /// contain at least one chunk, as in `OmFileReader::read_tiled`
+    MaxBytes(u64),
+    /// Tiles of this shape, starting at the start of the selection. Tiles at
+    /// the end of the selection may be smaller.
+    Shape(Vec<u64>),
+}
+
+impl TileSpec {
+    /// Ranges of all tiles of `dim_read` in row-major order
+    fn tiles<T>(
+        &self,
+        dimensions: &[u64],
+        chunks: &[u64],
+        dim_read: &[Range<u64>],
+    ) -> Result<Vec<Vec<Range<u64>>>, OmFilesRsError> {
+        let shape = match self {
+            TileSpec::MaxBytes(max_bytes) => {
+                let max_read_elements = max_bytes / std::mem::size_of::<T>() as u64;
+                return aligned_tiles(dimensions, chunks, dim_read, max_read_elements);
+            }
+            TileSpec::Shape(shape) => shape,
+        };
+        if shape.len() != dim_read.len() || dimensions.len() != dim_read.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        if shape.contains(&0) {
+            return Err(OmFilesRsError::DimensionMustBeLargerThan0);
+        }
+        for (range, &dimension) in dim_read.iter().zip(dimensions.iter()) {
+            if range.start > range.end || range.end > dimension {
+                return Err(OmFilesRsError::DimensionOutOfBounds {
+                    range: range.start as usize..range.end as usize,
+                    allowed: dimension as usize,
+                });
+            }
+        }
+        if dim_read.iter().any(|range| range.is_empty()) {
+            return Ok(vec![]);
+        }
+        let grid: Vec<usize> = dim_read
+            .iter()
+            .zip(shape.iter())
+            .map(|(range, &step)| (range.end - range.start).div_ceil(step) as usize)
+            .collect();
+        Ok(ndarray::indices(grid)
+            .into_iter()
+            .map(|index| {
+                dim_read
+                    .iter()
+                    .zip(shape.iter())
+                    .enumerate()
+                    .map(|(i, (range, &step))| {
+                        let start = range.start + index[i] as u64 * step;
+                        start..(start + step).min(range.end)
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+/// Reader for backends with high latency. Index and chunk reads of all index
+/// blocks are merged into larger requests where `io_size_merge` allows, which
+/// keeps the number of round trips low for point reads over many chunks.
+/// Requests are sent concurrently, up to `max_concurrency` at a time, and
+/// chunks are decoded as soon as they arrive while later requests are still in
+/// flight. Memory use is bounded by the index data of the read plus
+/// `max_concurrency` requests of at most `io_size_max` bytes.
+/// Requests are driven as futures inside the read call and no task is ever
+/// spawned, so the reader works with any async runtime and takes no spawner.
+/// Decoding runs on the task that awaits the read. To decode several reads in
+/// parallel, share the reader through an `Arc` and spawn the reads on the
+/// runtime of the application, the reader is `Send + Sync` if the backend is.
+pub struct OmFileReaderAsync<Backend: OmFileReaderBackendAsync> {
+    offset_size: Option<OmOffsetSize>,
+    max_concurrency: usize,
+    cancellation: Option<CancellationToken>,
+    io_sizes: Option<IoSizes>,
+    /// The backend that provides data via the get_bytes_async method
+    pub backend: Arc<Backend>,
+    /// Metadata of the variable defined by header/trailer
+    variable: OmVariableContainer,
+}
+
+impl<Backend: OmFileReaderBackendAsync> OmFileReaderAsync<Backend> {
+    #[allow(non_upper_case_globals)]
+    pub async fn new(backend: Arc<Backend>) -> Result<Self, OmFilesRsError> {
+        let file_size = backend.count_async() as u64;
+        let header_size = unsafe { om_header_size() } as u64;
+        // Version 3 files may be smaller than a legacy header
+        let mut header_data = backend
+            .get_bytes_async(0, header_size.min(file_size))
+            .await?;
+        header_data.resize(header_size as usize, 0);
+        let header_type = unsafe { om_header_type(header_data.as_ptr() as *const c_void) };
+
+        let (variable_data, offset_size) = match header_type {
+            OmHeaderType_t_OM_HEADER_LEGACY => {
+                if file_size < header_size {
+                    return Err(OmFilesRsError::NotAnOmFile);
+                }
+                (header_data, None)
+            }
+            OmHeaderType_t_OM_HEADER_READ_TRAILER => {
+                let trailer_size = unsafe { om_trailer_size() } as u64;
+                if file_size < trailer_size {
+                    return Err(OmFilesRsError::NotAnOmFile);
+                }
+                let trailer_offset = file_size - trailer_size;
+                let trailer = backend
+                    .get_bytes_async(trailer_offset, trailer_size)
+                    .await?;
+                let mut offset = 0u64;
+                let mut size = 0u64;
+                if !unsafe {
+                    om_trailer_read(trailer.as_ptr() as *const c_void, &mut offset, &mut size)
+                } {
+                    return Err(OmFilesRsError::NotAnOmFile);
+                }
+                let offset_size = OmOffsetSize::new(offset, size);
+                check_metadata_location(&offset_size, trailer_offset)?;
+                let variable_data = backend.get_bytes_async(offset, size).await?;
+                (variable_data, Some(offset_size))
+            }
+            _ => return Err(OmFilesRsError::NotAnOmFile),
+        };
+
+        Ok(Self {
+            offset_size,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            cancellation: None,
+            io_sizes: None,
+            backend,
+            variable: OmVariableContainer::new(variable_data)?,
+        })
+    }
+
+    /// Limit the number of concurrent backend requests per read. Child
+    /// readers created afterwards inherit this setting. 0 is raised to 1.
+    pub fn set_max_concurrency(&mut self, max_concurrency: usize) {
+        self.max_concurrency = max_concurrency.max(1);
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// Abort reads once `token` is cancelled. Pending backend requests are
+    /// dropped and the read returns `OmFilesRsError::Cancelled`. Child readers
+    /// created afterwards inherit the token.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    /// IO sizes used if a read does not specify them, instead of the preferred
+    /// sizes of the backend. Child readers created afterwards inherit them.
+    pub fn set_io_sizes(&mut self, io_sizes: IoSizes) {
+        self.io_sizes = Some(io_sizes);
+    }
+
+    /// IO sizes used if a read does not specify them
+    pub fn io_sizes(&self) -> IoSizes {
+        self.io_sizes
+            .unwrap_or_else(|| self.backend.preferred_io_sizes_async())
+    }
+
+    /// IO sizes of the reader with single values overridden
+    fn io_sizes_with(&self, io_size_max: Option<u64>, io_size_merge: Option<u64>) -> IoSizes {
+        let preferred = self.io_sizes();
+        IoSizes {
+            io_size_max: io_size_max.unwrap_or(preferred.io_size_max),
+            io_size_merge: io_size_merge.unwrap_or(preferred.io_size_merge),
+        }
+    }
+
+    fn variable_ref(&self) -> VariableRef<'_> {
+        self.variable.variable_ref()
+    }
+
+    /// Raw metadata bytes of the variable
+    pub fn variable_data(&self) -> &[u8] {
+        self.variable.data()
+    }
+
+    pub fn data_type(&self) -> DataType {
+        self.variable_ref().data_type()
+    }
+
+    pub fn compression(&self) -> CompressionType {
+        self.variable_ref().compression()
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.variable_ref().scale_factor()
+    }
+
+    pub fn add_offset(&self) -> f32 {
+        self.variable_ref().add_offset()
+    }
+
+    pub fn get_dimensions(&self) -> &[u64] {
+        self.variable_ref().get_dimensions()
+    }
+
+    pub fn get_chunk_dimensions(&self) -> &[u64] {
+        self.variable_ref().get_chunk_dimensions()
+    }
+
+    pub fn get_name(&self) -> Option<String> {
+        self.variable_ref().get_name()
+    }
+
+    /// Offset and size of this variable in the file. `None` for legacy files.
+    pub fn offset_size(&self) -> Option<&OmOffsetSize> {
+        self.offset_size.as_ref()
+    }
+
+    pub fn number_of_children(&self) -> u32 {
+        self.variable_ref().number_of_children()
+    }
+
+    /// Returns `None` if `index` is out of range or the metadata of the child
+    /// is invalid. `init_child_from_offset_size` returns the error instead.
+    pub async fn get_child(&self, index: u32) -> Option<Self> {
+        let offset_size = self.variable_ref().child_offset_size(index)?;
+        self.init_child_from_offset_size(offset_size).await.ok()
+    }
+
+    pub async fn init_child_from_offset_size(
+        &self,
+        offset_size: OmOffsetSize,
+    ) -> Result<Self, OmFilesRsError> {
+        check_metadata_location(&offset_size, self.backend.count_async() as u64)?;
+        let variable_data = self
+            .backend
+            .get_bytes_async(offset_size.offset, offset_size.size)
+            .await?;
+        Ok(Self {
+            offset_size: Some(offset_size),
+            max_concurrency: self.max_concurrency,
+            cancellation: self.cancellation.clone(),
+            io_sizes: self.io_sizes,
+            backend: self.backend.clone(),
+            variable: OmVariableContainer::new(variable_data)?,
+        })
+    }
+
+    pub fn read_scalar<T: OmFileScalarDataType>(&self) -> Option<T> {
+        self.variable_ref().read_scalar()
+    }
+
+    /// Read a variable as an array of a dynamic data type.
+    pub async fn read_into<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<(), OmFilesRsError> {
+        self.read_into_with_stats(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+            &mut ReadStats::default(),
+        )
+        .await
+    }
+
+    /// Like `read_into`, but accumulates the number of requests, fetched bytes,
+    /// decoded chunks and decode time in `stats`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn read_into_with_stats<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let io_sizes = self.io_sizes_with(io_size_max, io_size_merge);
+        let options = ReadOptions::default().with_io_sizes(io_sizes);
+        self.read_into_with_options(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            &options,
+            stats,
+        )
+        .await
+    }
+
+    /// Like `read_into_with_stats`, with concurrency and IO sizes taken from
+    /// `options` instead of the reader.
+    pub async fn read_into_with_options<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        options: &ReadOptions,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let io_sizes = options.io_sizes.unwrap_or_else(|| self.io_sizes());
+        // The field of `ReadOptions` is public and may be 0
+        let max_concurrency = options
+            .max_concurrency
+            .unwrap_or(self.max_concurrency)
+            .max(1);
+        let read = self.read_pipelined(
+            into,
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_sizes,
+            max_concurrency,
+            stats,
+        );
+        let result = match &self.cancellation {
+            None => read.await,
+            Some(token) if token.is_cancelled() => Err(OmFilesRsError::Cancelled),
+            Some(token) => match select(pin!(read), token.cancelled()).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Err(OmFilesRsError::Cancelled),
+            },
+        };
+        result.map_err(|error| self.variable_ref().decode_error_context(error))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn read_pipelined<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_sizes: IoSizes,
+        max_concurrency: usize,
+        stats: &mut ReadStats,
+    ) -> Result<(), OmFilesRsError> {
+        let IoSizes {
+            io_size_max,
+            io_size_merge,
+        } = io_sizes;
+
+        let mut prepared = self.variable_ref().prepare_read::<T>(
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+        )?;
+        let into = into
+            .as_slice_mut()
+            .ok_or(OmFilesRsError::ArrayNotContiguous)?;
+        let decoder = &prepared.decoder;
+        let chunk_buffer = prepared.chunk_buffer.as_mut_slice();
+
+        // Index reads of all index blocks are merged into as few backend
+        // requests as `io_size_merge` and `io_size_max` allow
+        let mut index_reads = Vec::new();
+        let mut index_read = new_index_read(decoder);
+        while unsafe { om_decoder_next_index_read(decoder, &mut index_read) } {
+            index_reads.push(index_read);
+        }
+        let index_ranges: Vec<(u64, u64)> =
+            index_reads.iter().map(|r| (r.offset, r.count)).collect();
+        let index_blocks: Vec<(u64, Vec<u8>)> =
+            stream::iter(merge_ranges(&index_ranges, io_size_max, io_size_merge))
+                .map(|(offset, count)| async move {
+                    let data = self.backend.get_bytes_async(offset, count).await?;
+                    Ok::<_, OmFilesRsError>((offset, data))
+                })
+                .buffered(max_concurrency)
+                .try_collect()
+                .await?;
+        for (_, data) in index_blocks.iter() {
+            stats.record_index_read(data.len() as u64);
+        }
+        let index_data = CoalescedBytes::from_blocks(index_blocks);
+
+        // Data reads of neighbouring index blocks are merged as well
+        let mut data_reads = Vec::new();
+        for index_read in index_reads.iter() {
+            let index_bytes = index_data.get(index_read.offset, index_read.count);
+            data_reads.extend(collect_data_reads(decoder, index_read, index_bytes)?);
+        }
+        data_reads.sort_unstable_by_key(|r| r.offset);
+        let data_ranges: Vec<(u64, u64)> = data_reads.iter().map(|r| (r.offset, r.count)).collect();
+        let mut groups = Vec::new();
+        let mut first = 0;
+        for (offset, count) in merge_ranges(&data_ranges, io_size_max, io_size_merge) {
+            let last = first + data_reads[first..].partition_point(|r| r.offset < offset + count);
+            groups.push((offset, count, &data_reads[first..last]));
+            first = last;
+        }
+
+        // Merged requests are fetched concurrently and decoded in order as
+        // soon as they arrive. At most `max_concurrency` requests are held in
+        // memory at any time.
+        let mut blocks = stream::iter(groups)
+            .map(|(offset, count, group)| async move {
+                let data = self.backend.get_bytes_async(offset, count).await?;
+                Ok::<_, OmFilesRsError>((offset, group, data))
+            })
+            .buffered(max_concurrency);
+        while let Some((offset, group, data)) = blocks.try_next().await? {
+            let chunks = group
+                .iter()
+                .map(|r| r.chunkIndex.upperBound - r.chunkIndex.lowerBound)
+                .sum();
+            stats.record_data_request(data.len() as u64, chunks);
+            for data_read in group {
+                let start = (data_read.offset - offset) as usize;
+                let data_bytes = &data[start..start + data_read.count as usize];
+                stats.time_decode(data_read, || {
+                    decode_chunks(decoder, chunk_buffer, into, data_read, data_bytes)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read `dim_read` tile by tile. Each item holds the offset of the first
+    /// element of the tile in the variable and the data of the tile. A tile
+    /// is only fetched and decoded when the stream is polled, so memory use
+    /// is bounded by one tile, however large the selection is. Invalid
+    /// selections yield a single error.
+    pub fn stream_tiles<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+        tile_spec: &TileSpec,
+    ) -> impl Stream<Item = Result<(Vec<u64>, ArrayD<T>), OmFilesRsError>> + '_ {
+        let tiles = match tile_spec.tiles::<T>(
+            self.get_dimensions(),
+            self.get_chunk_dimensions(),
+            dim_read,
+        ) {
+            Ok(tiles) => tiles.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        };
+        stream::iter(tiles).and_then(move |tile: Vec<Range<u64>>| async move {
+            let offset = tile.iter().map(|range| range.start).collect();
+            let data = self.read::<T>(&tile, None, None).await?;
+            Ok((offset, data))
+        })
+    }
+
+    pub async fn read<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+        io_size_max: Option<u64>,
+        io_size_merge: Option<u64>,
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        let io_sizes = self.io_sizes_with(io_size_max, io_size_merge);
+        self.read_with_options(dim_read, &ReadOptions::default().with_io_sizes(io_sizes))
+            .await
+    }
+
+    /// Like `read`, with concurrency and IO sizes taken from `options`
+    /// instead of the reader.
+    pub async fn read_with_options<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+        dim_read: &[Range<u64>],
+        options: &ReadOptions,
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        let out_dims: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let out_dims_usize = out_dims.iter().map(|&x| x as usize).collect::<Vec<_>>();
+
+        let mut out = ArrayD::<T>::default(out_dims_usize);
+
+        self.read_into_with_options::<T>(
+            &mut out,
+            dim_read,
+            &vec![0; dim_read.len()],
+            &out_dims,
+            options,
+            &mut ReadStats::default(),
+        )
+        .await?;
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,222 @@
+use crate::errors::OmFilesRsError;
+use num_traits::ToPrimitive;
+use std::ops::Range;
+
+/// Summary statistics of all values written to an array. NaN values are only
+/// counted, all other statistics ignore them. Values are collected before
+/// compression, so they do not include the quantization of `scale_factor`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArrayStatistics {
+    /// Smallest value, NaN if there are no valid values
+    pub min: f64,
+    /// Largest value, NaN if there are no valid values
+    pub max: f64,
+    /// Arithmetic mean of all valid values, NaN if there are none
+    pub mean: f64,
+    /// Number of valid (not NaN) values
+    pub count: u64,
+    /// Number of NaN values
+    pub nan_count: u64,
+}
+
+/// Accumulates `ArrayStatistics` while the writer compresses chunks.
+pub(crate) struct StatisticsAccumulator<T> {
+    to_f64: fn(&T) -> f64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+    nan_count: u64,
+}
+
+impl<T> StatisticsAccumulator<T> {
+    pub(crate) fn new() -> Self
+    where
+        T: ToPrimitive,
+    {
+        Self {
+            to_f64: |value| value.to_f64().unwrap_or(f64::NAN),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            sum: 0.0,
+            count: 0,
+            nan_count: 0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, value: &T) {
+        let value = (self.to_f64)(value);
+        if value.is_nan() {
+            self.nan_count += 1;
+            return;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub(crate) fn finish(&self) -> ArrayStatistics {
+        if self.count == 0 {
+            return ArrayStatistics {
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                count: 0,
+                nan_count: self.nan_count,
+            };
+        }
+        ArrayStatistics {
+            min: self.min,
+            max: self.max,
+            mean: self.sum / self.count as f64,
+            count: self.count,
+            nan_count: self.nan_count,
+        }
+    }
+}
+
+/// Minimum and maximum of every chunk in row-major chunk order. Chunks
+/// without valid values have NaN bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkStatistics {
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+}
+
+impl ChunkStatistics {
+    /// Whether chunk `index` may contain values in `values`
+    pub fn intersects(&self, index: usize, values: &Range<f64>) -> bool {
+        // NaN bounds never intersect
+        self.max[index] >= values.start && self.min[index] < values.end
+    }
+}
+
+/// Accumulates `ChunkStatistics` while the writer compresses chunks.
+pub(crate) struct ChunkStatisticsAccumulator<T> {
+    to_f64: fn(&T) -> f64,
+    statistics: ChunkStatistics,
+}
+
+impl<T> ChunkStatisticsAccumulator<T> {
+    pub(crate) fn new(number_of_chunks: usize) -> Self
+    where
+        T: ToPrimitive,
+    {
+        Self {
+            to_f64: |value| value.to_f64().unwrap_or(f64::NAN),
+            statistics: ChunkStatistics {
+                min: vec![f64::NAN; number_of_chunks],
+                max: vec![f64::NAN; number_of_chunks],
+            },
+        }
+    }
+
+    pub(crate) fn add_chunk<'a>(&mut self, chunk_index: usize, values: impl Iterator<Item = &'a T>)
+    where
+        T: 'a,
+    {
+        let (min, max) = values
+            .map(|value| (self.to_f64)(value))
+            .filter(|value| !value.is_nan())
+            .fold((f64::NAN, f64::NAN), |(min, max), value| {
+                (min.min(value), max.max(value))
+            });
+        self.statistics.min[chunk_index] = min;
+        self.statistics.max[chunk_index] = max;
+    }
+
+    pub(crate) fn finish(self) -> ChunkStatistics {
+        self.statistics
+    }
+}
+
+/// Number of values per chunk in row-major chunk order that exceed the int16
+/// range after scaling. The int16 codecs clamp these values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaturationStatistics {
+    pub saturated: Vec<u64>,
+}
+
+impl SaturationStatistics {
+    /// Number of saturated values in all chunks
+    pub fn total(&self) -> u64 {
+        self.saturated.iter().sum()
+    }
+}
+
+/// Counts saturated values while the writer compresses chunks.
+pub(crate) struct SaturationAccumulator<T> {
+    to_f64: fn(&T) -> f64,
+    scale_factor: f64,
+    add_offset: f64,
+    /// `PforDelta2dInt16Logarithmic` scales `log10(1 + value)`
+    logarithmic: bool,
+    max_saturated: Option<u64>,
+    statistics: SaturationStatistics,
+}
+
+impl<T> SaturationAccumulator<T> {
+    pub(crate) fn new(
+        number_of_chunks: usize,
+        scale_factor: f32,
+        add_offset: f32,
+        logarithmic: bool,
+        max_saturated: Option<u64>,
+    ) -> Self
+    where
+        T: ToPrimitive,
+    {
+        Self {
+            to_f64: |value| value.to_f64().unwrap_or(f64::NAN),
+            scale_factor: scale_factor as f64,
+            add_offset: add_offset as f64,
+            logarithmic,
+            max_saturated,
+            statistics: SaturationStatistics {
+                saturated: vec![0; number_of_chunks],
+            },
+        }
+    }
+
+    /// Count the saturated values of a chunk. Fails if the total exceeds
+    /// `max_saturated`.
+    pub(crate) fn add_chunk<'a>(
+        &mut self,
+        chunk_index: usize,
+        values: impl Iterator<Item = &'a T>,
+    ) -> Result<(), OmFilesRsError>
+    where
+        T: 'a,
+    {
+        let saturated = values
+            .filter(|value| self.is_saturated((self.to_f64)(value)))
+            .count() as u64;
+        self.statistics.saturated[chunk_index] = saturated;
+        if let Some(max) = self.max_saturated {
+            let count = self.statistics.total();
+            if count > max {
+                return Err(OmFilesRsError::TooManySaturatedValues { count, max });
+            }
+        }
+        Ok(())
+    }
+
+    fn is_saturated(&self, value: f64) -> bool {
+        if value.is_nan() {
+            return false;
+        }
+        let value = if self.logarithmic {
+            value.ln_1p() / std::f64::consts::LN_10
+        } else {
+            value
+        };
+        let scaled = ((value - self.add_offset) * self.scale_factor).round();
+        // `i16::MAX` marks NaN, invalid logarithms are saturated as well
+        !(scaled >= i16::MIN as f64 && scaled < i16::MAX as f64)
+    }
+
+    pub(crate) fn finish(self) -> SaturationStatistics {
+        self.statistics
+    }
+}
@@ -0,0 +1,180 @@
+//! Reads of web mercator map tiles in XYZ addressing, as used by slippy maps
+//! like OpenStreetMap or Leaflet, from arrays with a regular
+//! latitude/longitude grid.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::errors::OmFilesRsError;
+use crate::io::geo::{GridDefinition, LATLON_PROJECTION};
+use crate::io::reader::OmFileReader;
+use ndarray::Array2;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+/// Map tile `x`, `y` at zoom level `z`. Tile `0/0/0` covers the whole web
+/// mercator world, `y` grows to the south.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XyzTile {
+    z: u32,
+    x: u32,
+    y: u32,
+}
+
+impl XyzTile {
+    /// Tile `x`, `y` at zoom level `z`. Both `x` and `y` have to be smaller
+    /// than `2^z`, and `z` smaller than 64.
+    pub fn new(z: u32, x: u32, y: u32) -> Result<Self, OmFilesRsError> {
+        let tiles = 1u64.checked_shl(z).ok_or_else(|| {
+            OmFilesRsError::InvalidConfiguration(format!("zoom level {} is too large", z))
+        })?;
+        if x as u64 >= tiles || y as u64 >= tiles {
+            return Err(OmFilesRsError::InvalidConfiguration(format!(
+                "tile {}/{} does not exist at zoom level {}",
+                x, y, z
+            )));
+        }
+        Ok(Self { z, x, y })
+    }
+
+    pub fn z(&self) -> u32 {
+        self.z
+    }
+
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    /// Longitude of the position `u` from the left edge, in tiles
+    fn lon(&self, u: f64) -> f64 {
+        (self.x as f64 + u) / (1u64 << self.z) as f64 * 360.0 - 180.0
+    }
+
+    /// Latitude of the position `v` from the upper edge, in tiles
+    fn lat(&self, v: f64) -> f64 {
+        let y = (self.y as f64 + v) / (1u64 << self.z) as f64;
+        (PI * (1.0 - 2.0 * y)).sinh().atan().to_degrees()
+    }
+
+    /// Latitudes and longitudes covered by the tile
+    pub fn bounds(&self) -> (Range<f64>, Range<f64>) {
+        (self.lat(1.0)..self.lat(0.0), self.lon(0.0)..self.lon(1.0))
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
+    /// Read the part of map tile `tile` that is covered by the grid of the
+    /// array. The last two dimensions are latitude and longitude, `leading`
+    /// selects the index in all other dimensions, e.g. the time step.
+    ///
+    /// Without `size`, the grid points within the tile are returned as they
+    /// are stored, with latitudes increasing along the first axis. The array
+    /// is empty if the tile does not overlap the grid.
+    ///
+    /// With `size`, the tile is resampled to `size × size` pixels in web
+    /// mercator projection with nearest neighbour interpolation, with the
+    /// northern edge in the first row like an image. Pixels outside of the
+    /// grid are NaN. For low zoom levels, read from an overview picked with
+    /// `overview_for_resolution` instead of the full resolution array.
+    pub fn read_xyz_tile(
+        &self,
+        tile: XyzTile,
+        leading: &[u64],
+        size: Option<usize>,
+    ) -> Result<Array2<f32>, OmFilesRsError> {
+        let grid = self
+            .get_grid()
+            .filter(|grid| grid.projection == LATLON_PROJECTION)
+            .ok_or(OmFilesRsError::NoLatLonGrid)?;
+        let n = self.get_dimensions().len();
+        if n < 2 || leading.len() != n - 2 {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        match size {
+            Some(size) => self.read_tile_resampled(&grid, tile, leading, size),
+            None => {
+                let (lat, lon) = tile.bounds();
+                let rows = index_range(lat, grid.lat_min, grid.dy, grid.ny());
+                let columns = index_range(lon, grid.lon_min, grid.dx, grid.nx());
+                if rows.is_empty() || columns.is_empty() {
+                    return Ok(Array2::zeros((0, 0)));
+                }
+                self.read_block_2d(leading, rows, columns)
+            }
+        }
+    }
+
+    fn read_tile_resampled(
+        &self,
+        grid: &GridDefinition,
+        tile: XyzTile,
+        leading: &[u64],
+        size: usize,
+    ) -> Result<Array2<f32>, OmFilesRsError> {
+        // Rows only depend on the latitude and columns on the longitude
+        let rows: Vec<Option<u64>> = (0..size)
+            .map(|i| tile.lat((i as f64 + 0.5) / size as f64))
+            .map(|lat| Some(grid.latlon_to_index(lat, grid.lon_min)?.0))
+            .collect();
+        let columns: Vec<Option<u64>> = (0..size)
+            .map(|j| tile.lon((j as f64 + 0.5) / size as f64))
+            .map(|lon| Some(grid.latlon_to_index(grid.lat_min, lon)?.1))
+            .collect();
+
+        let mut out = Array2::from_elem((size, size), f32::NAN);
+        let span = |indices: &[Option<u64>]| {
+            let min = indices.iter().flatten().min()?;
+            let max = indices.iter().flatten().max()?;
+            Some(*min..*max + 1)
+        };
+        let (Some(row_span), Some(column_span)) = (span(&rows), span(&columns)) else {
+            return Ok(out);
+        };
+        let block = self.read_block_2d(leading, row_span.clone(), column_span.clone())?;
+        for (i, row) in rows.iter().enumerate() {
+            let Some(row) = row else {
+                continue;
+            };
+            for (j, column) in columns.iter().enumerate() {
+                let Some(column) = column else {
+                    continue;
+                };
+                out[[i, j]] = block[[
+                    (row - row_span.start) as usize,
+                    (column - column_span.start) as usize,
+                ]];
+            }
+        }
+        Ok(out)
+    }
+
+    fn read_block_2d(
+        &self,
+        leading: &[u64],
+        rows: Range<u64>,
+        columns: Range<u64>,
+    ) -> Result<Array2<f32>, OmFilesRsError> {
+        let ranges: Vec<Range<u64>> = leading
+            .iter()
+            .map(|&i| i..i + 1)
+            .chain([rows.clone(), columns.clone()])
+            .collect();
+        let shape = (
+            (rows.end - rows.start) as usize,
+            (columns.end - columns.start) as usize,
+        );
+        self.read_converted::<f32>(&ranges)?
+            .into_shape_with_order(shape)
+            .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)
+    }
+}
+
+/// Indices of all grid points of an axis starting at `min` with `step`
+/// between them that are within `values`, clamped to `count` points
+fn index_range(values: Range<f64>, min: f64, step: f64, count: u64) -> Range<u64> {
+    let start = (((values.start - min) / step).ceil().max(0.0) as u64).min(count);
+    let end = ((((values.end - min) / step).floor() + 1.0).max(0.0) as u64).min(count);
+    start..end.max(start)
+}
@@ -0,0 +1,69 @@
+//! Time axis of a variable, stored as child variables so that all tools agree
+//! on its semantics. Times are unix timestamps in seconds (UTC) on the
+//! proleptic Gregorian calendar.
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use std::ops::Range;
+
+/// Calendar of all time axes written by this library
+pub const GREGORIAN_CALENDAR: &str = "proleptic_gregorian";
+
+/// Regular time axis with `length` steps of `step` seconds starting at `start`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeAxis {
+    /// Unix timestamp of the first step in seconds
+    pub start: i64,
+    /// Seconds between two steps
+    pub step: i64,
+    /// Number of steps
+    pub length: u64,
+    pub calendar: String,
+}
+
+impl TimeAxis {
+    pub fn new(start: i64, step: i64, length: u64) -> Self {
+        Self {
+            start,
+            step,
+            length,
+            calendar: GREGORIAN_CALENDAR.to_string(),
+        }
+    }
+
+    /// Unix timestamp of step `index`
+    pub fn time_at(&self, index: u64) -> i64 {
+        self.start + index as i64 * self.step
+    }
+
+    /// Index of the step at `time`, `None` if `time` is not on the axis
+    pub fn index_of(&self, time: i64) -> Option<u64> {
+        let offset = time - self.start;
+        if self.step <= 0 || offset < 0 || offset % self.step != 0 {
+            return None;
+        }
+        let index = (offset / self.step) as u64;
+        (index < self.length).then_some(index)
+    }
+
+    /// Indices of all steps in `start..end`, clamped to the axis
+    pub fn index_range(&self, times: Range<i64>) -> Range<u64> {
+        if self.step <= 0 {
+            return 0..0;
+        }
+        let clamp = |time: i64| {
+            let offset = (time - self.start).max(0);
+            ((offset + self.step - 1) / self.step).min(self.length as i64) as u64
+        };
+        let start = clamp(times.start);
+        start..clamp(times.end).max(start)
+    }
+
+    /// Timestamps of all steps
+    #[cfg(feature = "chrono")]
+    pub fn timestamps(&self) -> Vec<DateTime<Utc>> {
+        (0..self.length)
+            .filter_map(|index| DateTime::from_timestamp(self.time_at(index), 0))
+            .collect()
+    }
+}
@@ -0,0 +1,346 @@
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::c_defaults::{c_error_string, create_uninit_decoder};
+use crate::core::compression::CompressionType;
+use crate::core::data_types::{DataType, OmFileArrayDataType, OmFileScalarDataType};
+use crate::core::variable_metadata::{
+    validate_variable_metadata, ArrayVariableView, RustVariableView,
+};
+use crate::errors::{ErrorKind, OmFilesRsError};
+use crate::io::batch_reader::PreparedRead;
+use crate::io::writer::OmOffsetSize;
+use om_file_format_sys::{
+    om_decoder_init, om_decoder_read_buffer_size, om_variable_get_add_offset,
+    om_variable_get_children, om_variable_get_children_count, om_variable_get_chunks,
+    om_variable_get_compression, om_variable_get_dimensions, om_variable_get_name,
+    om_variable_get_scalar, om_variable_get_scale_factor, om_variable_get_type, om_variable_init,
+    OmError_t_ERROR_OK, OmVariable_t,
+};
+use std::any::Any;
+use std::ops::Range;
+use std::os::raw::c_void;
+use std::sync::Arc;
+
+/// Largest variable metadata that is read from a file. Metadata grows with
+/// the number of children and dimensions, real files stay far below this.
+/// Corrupted sizes would otherwise allocate up to the size of the file.
+pub(crate) const MAX_METADATA_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Check the location of variable metadata read from a trailer or a parent
+/// variable before it is fetched. It must end at or before `end`.
+pub(crate) fn check_metadata_location(
+    offset_size: &OmOffsetSize,
+    end: u64,
+) -> Result<(), OmFilesRsError> {
+    offset_size.check_within(end)?;
+    if offset_size.size > MAX_METADATA_SIZE {
+        return Err(OmFilesRsError::MetadataTooLarge {
+            size: offset_size.size,
+            max: MAX_METADATA_SIZE,
+        });
+    }
+    Ok(())
+}
+
+enum MetadataBytes {
+    Owned(Vec<u8>),
+    /// Points into the memory of a backend with stable bytes. The owner is
+    /// type erased, so that the container does not need a type parameter.
+    Borrowed {
+        data: *const u8,
+        len: usize,
+        _owner: Arc<dyn Any + Send + Sync>,
+    },
+}
+
+/// Metadata of a variable together with the C handle that points into it.
+/// The bytes are never modified, and moving the container moves neither the
+/// heap allocation nor the memory of the backend, so the handle stays valid
+/// for the lifetime of the container. Accessors only hand out borrowed
+/// `VariableRef`s.
+pub(crate) struct OmVariableContainer {
+    data: MetadataBytes,
+    variable: *const OmVariable_t,
+}
+
+// The handle only reads from the immutable bytes. The owner of borrowed bytes
+// is `Send + Sync`.
+unsafe impl Send for OmVariableContainer {}
+unsafe impl Sync for OmVariableContainer {}
+
+impl OmVariableContainer {
+    /// Fails if the metadata is truncated or inconsistent, so that the C
+    /// library never reads beyond `data`.
+    pub fn new(data: Vec<u8>) -> Result<Self, OmFilesRsError> {
+        validate_variable_metadata(&data)?;
+        let variable = unsafe { om_variable_init(data.as_ptr() as *const c_void) };
+        Ok(Self {
+            data: MetadataBytes::Owned(data),
+            variable,
+        })
+    }
+
+    /// Read the metadata at `offset_size`. Metadata of backends with a stable
+    /// bytes owner is not copied, the container keeps a view into the backend
+    /// and a reference to the owner instead. Metadata that is not 8 byte
+    /// aligned is always copied.
+    pub fn from_backend<Backend: OmFileReaderBackend>(
+        backend: &Arc<Backend>,
+        offset_size: &OmOffsetSize,
+    ) -> Result<Self, OmFilesRsError> {
+        let (offset, size) = (offset_size.offset, offset_size.size);
+        if let Some(owner) = backend.clone().stable_bytes_owner() {
+            let data = backend.get_bytes(offset, size)?;
+            if data.as_ptr().align_offset(std::mem::align_of::<u64>()) == 0 {
+                validate_variable_metadata(data)?;
+                let variable = unsafe { om_variable_init(data.as_ptr() as *const c_void) };
+                return Ok(Self {
+                    data: MetadataBytes::Borrowed {
+                        data: data.as_ptr(),
+                        len: data.len(),
+                        _owner: owner,
+                    },
+                    variable,
+                });
+            }
+        }
+        Self::new(backend.get_bytes_with_fallback(offset, size)?.into_owned())
+    }
+
+    pub fn variable_ref(&self) -> VariableRef<'_> {
+        VariableRef::new(self.data(), self.variable)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        match &self.data {
+            MetadataBytes::Owned(data) => data,
+            MetadataBytes::Borrowed { data, len, .. } => unsafe {
+                std::slice::from_raw_parts(*data, *len)
+            },
+        }
+    }
+}
+
+/// Borrowed view on the metadata of a variable. None of these accessors need
+/// the backend, so they are shared by the synchronous and asynchronous readers.
+#[derive(Clone, Copy)]
+pub(crate) struct VariableRef<'a> {
+    data: &'a [u8],
+    variable: *const OmVariable_t,
+}
+
+impl<'a> VariableRef<'a> {
+    /// `variable` must have been initialized from `data` with `om_variable_init`.
+    pub fn new(data: &'a [u8], variable: *const OmVariable_t) -> Self {
+        Self { data, variable }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        unsafe {
+            DataType::try_from(om_variable_get_type(self.variable) as u8)
+                .expect("Invalid data type")
+        }
+    }
+
+    pub fn compression(&self) -> CompressionType {
+        unsafe {
+            CompressionType::try_from(om_variable_get_compression(self.variable) as u8)
+                .expect("Invalid compression type")
+        }
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        unsafe { om_variable_get_scale_factor(self.variable) }
+    }
+
+    pub fn add_offset(&self) -> f32 {
+        unsafe { om_variable_get_add_offset(self.variable) }
+    }
+
+    /// String arrays are not known to the C library. Their metadata is parsed
+    /// in Rust instead.
+    pub fn string_array_view(&self) -> Option<ArrayVariableView<'a>> {
+        if self.data_type() != DataType::StringArray {
+            return None;
+        }
+        ArrayVariableView::new(self.data).ok()
+    }
+
+    /// Metadata of data types that are unknown to the C library.
+    pub fn rust_variable_view(&self) -> Option<RustVariableView<'a>> {
+        RustVariableView::new(self.data_type(), self.data)
+    }
+
+    pub fn get_dimensions(&self) -> &'a [u64] {
+        if let Some(view) = self.string_array_view() {
+            return view.dimensions();
+        }
+        unsafe {
+            let dims = om_variable_get_dimensions(self.variable);
+            std::slice::from_raw_parts(dims.values, dims.count as usize)
+        }
+    }
+
+    pub fn get_chunk_dimensions(&self) -> &'a [u64] {
+        if let Some(view) = self.string_array_view() {
+            return view.chunks();
+        }
+        unsafe {
+            let chunks = om_variable_get_chunks(self.variable);
+            std::slice::from_raw_parts(chunks.values, chunks.count as usize)
+        }
+    }
+
+    pub fn get_name(&self) -> Option<String> {
+        if let Some(view) = self.rust_variable_view() {
+            let name = view.name();
+            if name.is_empty() {
+                return None;
+            }
+            return String::from_utf8(name.to_vec()).ok();
+        }
+        unsafe {
+            let name = om_variable_get_name(self.variable);
+            if name.size == 0 {
+                return None;
+            }
+            let bytes = std::slice::from_raw_parts(name.value as *const u8, name.size as usize);
+            String::from_utf8(bytes.to_vec()).ok()
+        }
+    }
+
+    /// Add the name of the variable to decoder errors
+    pub fn decode_error_context(&self, error: OmFilesRsError) -> OmFilesRsError {
+        match (error.kind(), self.get_name()) {
+            (ErrorKind::Decoder, Some(name)) => error.context(format!("variable '{}'", name)),
+            _ => error,
+        }
+    }
+
+    pub fn number_of_children(&self) -> u32 {
+        if let Some(view) = self.rust_variable_view() {
+            return view.number_of_children();
+        }
+        unsafe { om_variable_get_children_count(self.variable) }
+    }
+
+    /// Offset and size of a child variable
+    pub fn child_offset_size(&self, index: u32) -> Option<OmOffsetSize> {
+        let mut offset = 0u64;
+        let mut size = 0u64;
+        if let Some(view) = self.rust_variable_view() {
+            (offset, size) = view.child(index)?;
+        } else if !unsafe {
+            om_variable_get_children(self.variable, index, 1, &mut offset, &mut size)
+        } {
+            return None;
+        }
+        Some(OmOffsetSize::new(offset, size))
+    }
+
+    pub fn read_scalar<T: OmFileScalarDataType>(&self) -> Option<T> {
+        if T::DATA_TYPE_SCALAR != self.data_type() {
+            return None;
+        }
+        if let Some(RustVariableView::String(view)) = self.rust_variable_view() {
+            return T::from_variable_length_bytes(view.value());
+        }
+        let mut value = T::default();
+
+        let error =
+            unsafe { om_variable_get_scalar(self.variable, &mut value as *mut T as *mut c_void) };
+
+        if error != OmError_t_ERROR_OK {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Validate the read request and initialize a decoder for it.
+    pub fn prepare_read<T: OmFileArrayDataType>(
+        &self,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: u64,
+        io_size_merge: u64,
+    ) -> Result<PreparedRead<'a>, OmFilesRsError> {
+        // Verify data type
+        if T::DATA_TYPE_ARRAY != self.data_type() {
+            return Err(OmFilesRsError::InvalidDataType);
+        }
+        // Strings are not decoded by the C library
+        if T::DATA_TYPE_ARRAY == DataType::StringArray {
+            return Err(OmFilesRsError::InvalidDataType
+                .context("string arrays can only be read with read::<String>"));
+        }
+        self.prepare_decoder(
+            dim_read,
+            into_cube_offset,
+            into_cube_dimension,
+            io_size_max,
+            io_size_merge,
+        )
+    }
+
+    /// Like `prepare_read`, but for any data type, e.g. to plan reads.
+    pub fn prepare_decoder(
+        &self,
+        dim_read: &[Range<u64>],
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+        io_size_max: u64,
+        io_size_merge: u64,
+    ) -> Result<PreparedRead<'a>, OmFilesRsError> {
+        let n_dimensions_read = dim_read.len();
+        // TODO: Maybe cache this in the reader struct
+        let n_dims = self.get_dimensions().len();
+
+        // Validate dimension counts
+        if n_dims != n_dimensions_read
+            || n_dimensions_read != into_cube_offset.len()
+            || n_dimensions_read != into_cube_dimension.len()
+        {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+
+        // Prepare read parameters
+        let read_offset: Vec<u64> = dim_read.iter().map(|r| r.start).collect();
+        let read_count: Vec<u64> = dim_read.iter().map(|r| r.end - r.start).collect();
+        let into_cube_offset = into_cube_offset.to_vec();
+        let into_cube_dimension = into_cube_dimension.to_vec();
+
+        // Initialize decoder
+        let mut decoder = unsafe { create_uninit_decoder() };
+        let error = unsafe {
+            om_decoder_init(
+                &mut decoder,
+                self.variable,
+                n_dimensions_read as u64,
+                read_offset.as_ptr(),
+                read_count.as_ptr(),
+                into_cube_offset.as_ptr(),
+                into_cube_dimension.as_ptr(),
+                io_size_merge,
+                io_size_max,
+            )
+        };
+
+        if error != OmError_t_ERROR_OK {
+            let error_string = c_error_string(error);
+            return Err(OmFilesRsError::DecoderError(error_string));
+        }
+
+        // Allocate chunk buffer
+        let chunk_buffer_size = unsafe { om_decoder_read_buffer_size(&decoder) };
+        let chunk_buffer = vec![0u8; chunk_buffer_size as usize];
+
+        Ok(PreparedRead::new(
+            decoder,
+            chunk_buffer,
+            read_offset,
+            read_count,
+            into_cube_offset,
+            into_cube_dimension,
+        ))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::backend::backends::OmFileReaderBackend;
+use crate::core::data_types::OmFileArrayDataType;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use ndarray::ArrayD;
+use std::ops::Range;
+
+/// Selection of a variable that is read on request. Created by
+/// `OmFileReader::slice`. Slices can be sliced further without any IO, so
+/// selections can be composed before data is read.
+pub struct VariableSlice<'a, Backend: OmFileReaderBackend> {
+    reader: &'a OmFileReader<Backend>,
+    ranges: Vec<Range<u64>>,
+}
+
+impl<'a, Backend: OmFileReaderBackend> VariableSlice<'a, Backend> {
+    pub(crate) fn new(
+        reader: &'a OmFileReader<Backend>,
+        ranges: &[Range<u64>],
+    ) -> Result<Self, OmFilesRsError> {
+        check_ranges(ranges, reader.get_dimensions())?;
+        Ok(Self {
+            reader,
+            ranges: ranges.to_vec(),
+        })
+    }
+
+    /// Selected ranges in coordinates of the variable
+    pub fn ranges(&self) -> &[Range<u64>] {
+        &self.ranges
+    }
+
+    pub fn shape(&self) -> Vec<u64> {
+        self.ranges.iter().map(|r| r.end - r.start).collect()
+    }
+
+    /// Select a part of this slice. `ranges` are relative to the slice.
+    pub fn slice(&self, ranges: &[Range<u64>]) -> Result<Self, OmFilesRsError> {
+        check_ranges(ranges, &self.shape())?;
+        let ranges = ranges
+            .iter()
+            .zip(self.ranges.iter())
+            .map(|(range, outer)| outer.start + range.start..outer.start + range.end)
+            .collect();
+        Ok(Self {
+            reader: self.reader,
+            ranges,
+        })
+    }
+
+    /// Read the selection into a new array
+    pub fn to_array<T: OmFileArrayDataType + Clone + Default>(
+        &self,
+    ) -> Result<ArrayD<T>, OmFilesRsError> {
+        self.reader.read(&self.ranges, None, None)
+    }
+
+    /// Read the selection into `into` at `into_cube_offset`, see `OmFileReader::read_into`.
+    pub fn read_into<T: OmFileArrayDataType>(
+        &self,
+        into: &mut ArrayD<T>,
+        into_cube_offset: &[u64],
+        into_cube_dimension: &[u64],
+    ) -> Result<(), OmFilesRsError> {
+        self.reader.read_into(
+            into,
+            &self.ranges,
+            into_cube_offset,
+            into_cube_dimension,
+            None,
+            None,
+        )
+    }
+}
+
+impl<Backend: OmFileReaderBackend> Clone for VariableSlice<'_, Backend> {
+    fn clone(&self) -> Self {
+        Self {
+            reader: self.reader,
+            ranges: self.ranges.clone(),
+        }
+    }
+}
+
+fn check_ranges(ranges: &[Range<u64>], dimensions: &[u64]) -> Result<(), OmFilesRsError> {
+    if ranges.len() != dimensions.len() {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    for (range, &dimension) in ranges.iter().zip(dimensions.iter()) {
+        if range.start > range.end || range.end > dimension {
+            return Err(OmFilesRsError::DimensionOutOfBounds {
+                range: range.start as usize..range.end as usize,
+                allowed: dimension as usize,
+            });
+        }
+    }
+    Ok(())
+}
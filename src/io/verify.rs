@@ -0,0 +1,139 @@
+//! Read-after-write verification of compressed chunks. A chunk is copied
+//! byte by byte into a small in-memory file, decoded with the regular reader
+//! and compared directly with the values that were passed to the encoder.
+
+use crate::backend::backends::InMemoryBackend;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::io::writer::OmFileWriter;
+use ndarray::{ArrayViewD, IxDyn, Slice};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Whether chunk `chunk_index` is verified if a `fraction` of all chunks is
+/// verified. Chunks are picked pseudo-randomly, but the same chunks are
+/// picked for every file, so failures can be reproduced.
+pub(crate) fn is_selected(chunk_index: u64, fraction: f64) -> bool {
+    // SplitMix64 of the chunk index
+    let mut z = chunk_index.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    ((z >> 11) as f64 / (1u64 << 53) as f64) < fraction
+}
+
+/// Decode the chunk in `compressed` and compare it with `region` of `values`,
+/// which has the shape `array_dimensions`. Values are compared after applying
+/// scale factor and offset, so quantized values may differ by one step.
+/// `PforDelta2dInt16Logarithmic` is compared after applying `log10(1 + x)`.
+/// Values that were clamped by the int16 codecs fail the comparison.
+pub(crate) fn verify_chunk<OmType: OmFileArrayDataType>(
+    compressed: &[u8],
+    values: &[OmType],
+    array_dimensions: &[u64],
+    region: &[Range<usize>],
+    compression: CompressionType,
+    scale_factor: f32,
+    add_offset: f32,
+) -> Result<(), OmFilesRsError> {
+    let shape: Vec<u64> = region.iter().map(|r| (r.end - r.start) as u64).collect();
+
+    let mut backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut backend, compressed.len() as u64 + 1024);
+        let mut written = file_writer.prepare_array::<OmType>(
+            shape.clone(),
+            shape.clone(),
+            compression,
+            scale_factor,
+            add_offset,
+        )?;
+        written.write_compressed_chunk(compressed)?;
+        let written_meta = written.finalize();
+        let written = file_writer.write_array(written_meta, "", &[])?;
+        file_writer.write_trailer(written)?;
+    }
+    let written = OmFileReader::new(Arc::new(backend))?;
+    let ranges: Vec<Range<u64>> = shape.iter().map(|&n| 0..n).collect();
+    let written_values = written.read_converted::<f64>(&ranges)?;
+
+    let array_shape: Vec<usize> = array_dimensions.iter().map(|&x| x as usize).collect();
+    let expected_values = ArrayViewD::from_shape(IxDyn(&array_shape), values)
+        .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)?;
+    let expected_values =
+        expected_values.slice_each_axis(|axis| Slice::from(region[axis.axis.index()].clone()));
+
+    let (quantized, logarithmic) = match compression {
+        CompressionType::PforDelta2dInt16 => (true, false),
+        CompressionType::PforDelta2dInt16Logarithmic => (true, true),
+        CompressionType::PforDelta2d => (
+            matches!(
+                OmType::DATA_TYPE_ARRAY,
+                DataType::FloatArray | DataType::DoubleArray
+            ),
+            false,
+        ),
+        _ => (false, false),
+    };
+    // Quantized values are compared in steps of the scale factor
+    let transform = |x: f64| {
+        let x = if logarithmic {
+            x.ln_1p() / std::f64::consts::LN_10
+        } else {
+            x
+        };
+        if quantized {
+            x * scale_factor as f64 + add_offset as f64
+        } else {
+            x
+        }
+    };
+    let tolerance = if quantized { 1.0 } else { 0.0 };
+    let mut count = 0;
+    let mut max_difference: f64 = 0.0;
+    for (a, &b) in expected_values.iter().zip(written_values.iter()) {
+        let a = to_f64(a);
+        if a.is_nan() && b.is_nan() {
+            continue;
+        }
+        let difference = if a.is_nan() || b.is_nan() {
+            f64::INFINITY
+        } else {
+            (transform(a) - transform(b)).abs()
+        };
+        if difference > tolerance {
+            count += 1;
+            max_difference = max_difference.max(difference);
+        }
+    }
+    if count > 0 {
+        return Err(OmFilesRsError::ChunkVerificationFailed {
+            count,
+            max_difference,
+        });
+    }
+    Ok(())
+}
+
+/// Numeric value of an array element. The data type of the array determines
+/// the memory layout of `OmType`, like for the encoder.
+fn to_f64<OmType: OmFileArrayDataType>(value: &OmType) -> f64 {
+    let pointer = value as *const OmType;
+    unsafe {
+        match OmType::DATA_TYPE_ARRAY {
+            DataType::Int8Array => *(pointer as *const i8) as f64,
+            DataType::Uint8Array => *(pointer as *const u8) as f64,
+            DataType::Int16Array => *(pointer as *const i16) as f64,
+            DataType::Uint16Array => *(pointer as *const u16) as f64,
+            DataType::Int32Array => *(pointer as *const i32) as f64,
+            DataType::Uint32Array => *(pointer as *const u32) as f64,
+            DataType::Int64Array => *(pointer as *const i64) as f64,
+            DataType::Uint64Array => *(pointer as *const u64) as f64,
+            DataType::FloatArray => *(pointer as *const f32) as f64,
+            DataType::DoubleArray => *(pointer as *const f64),
+            _ => f64::NAN,
+        }
+    }
+}
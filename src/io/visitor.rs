@@ -0,0 +1,89 @@
+//! Depth-first traversal of the variable tree of a file. Parents are visited
+//! before their children and children in the order they were written.
+//!
+//! Every variable is identified by a path with the names of all parents
+//! separated by `/`, starting with the name of the root variable. Children
+//! without a name are called `#<index>`.
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+
+/// How the traversal continues after a variable was visited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Traversal {
+    /// Visit the children of this variable next
+    Continue,
+    /// Do not visit the children of this variable
+    SkipChildren,
+    /// End the traversal
+    Stop,
+}
+
+/// Callback for `OmFileReader::visit`, e.g. for validators and exporters
+pub trait VariableVisitor {
+    fn visit<Backend: OmFileReaderBackend>(
+        &mut self,
+        path: &str,
+        reader: &OmFileReader<Backend>,
+    ) -> Result<Traversal, OmFilesRsError>;
+}
+
+/// Iterator over `(path, reader)` of all variables of a tree, returned by
+/// `OmFileReader::iter_variables`. Children with invalid metadata are skipped
+/// like in `OmFileReader::get_child`.
+pub struct VariableIter<Backend: OmFileReaderBackend> {
+    /// Variables that are yet to be visited, the next one is last
+    stack: Vec<(String, OmFileReader<Backend>)>,
+}
+
+impl<Backend: OmFileReaderBackend> VariableIter<Backend> {
+    pub(crate) fn new(root: &OmFileReader<Backend>) -> Self {
+        let path = root.get_name().unwrap_or_default();
+        Self {
+            stack: vec![(path, root.clone())],
+        }
+    }
+
+    fn push_children(&mut self, path: &str, reader: &OmFileReader<Backend>) {
+        for index in (0..reader.number_of_children()).rev() {
+            if let Some(child) = reader.get_child(index) {
+                let name = child.get_name().unwrap_or_else(|| format!("#{}", index));
+                self.stack.push((format!("{}/{}", path, name), child));
+            }
+        }
+    }
+}
+
+impl<Backend: OmFileReaderBackend> Iterator for VariableIter<Backend> {
+    type Item = (String, OmFileReader<Backend>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, reader) = self.stack.pop()?;
+        self.push_children(&path, &reader);
+        Some((path, reader))
+    }
+}
+
+impl<Backend: OmFileReaderBackend> OmFileReader<Backend> {
+    /// All variables of the tree starting at this variable, depth first.
+    /// Children are only read once the iterator reaches their parent.
+    pub fn iter_variables(&self) -> VariableIter<Backend> {
+        VariableIter::new(self)
+    }
+
+    /// Call `visitor` for all variables of the tree starting at this variable,
+    /// depth first. Children are skipped or the traversal is ended depending
+    /// on the returned `Traversal`. Errors of the visitor end the traversal.
+    pub fn visit(&self, visitor: &mut impl VariableVisitor) -> Result<(), OmFilesRsError> {
+        let mut variables = self.iter_variables();
+        while let Some((path, reader)) = variables.stack.pop() {
+            match visitor.visit(&path, &reader)? {
+                Traversal::Continue => variables.push_children(&path, &reader),
+                Traversal::SkipChildren => {}
+                Traversal::Stop => break,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Hot reload of files that are replaced while they are being read, based on
+//! file system notifications of the `notify` crate.
+//!
+//! Directories are watched instead of files. Replacing a file with a rename,
+//! e.g. by `OmFileWriter::create_atomic`, creates a new inode and a watch on
+//! the old inode would never see further changes.
+
+use crate::backend::mmapfile::MmapFile;
+use crate::errors::OmFilesRsError;
+use crate::io::file_manager::{open_file, FileIdentity};
+use crate::io::reader::OmFileReader;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Calls `on_change` with the path of a watched file whenever it is created,
+/// modified, renamed or removed.
+pub(crate) struct PathWatcher {
+    watcher: RecommendedWatcher,
+    /// Paths passed to `watch` by the path reported in events. The same file
+    /// might be watched under several paths.
+    files: Arc<Mutex<HashMap<PathBuf, HashSet<PathBuf>>>>,
+    directories: HashSet<PathBuf>,
+}
+
+impl PathWatcher {
+    pub(crate) fn new(on_change: impl Fn(&Path) + Send + 'static) -> Result<Self, OmFilesRsError> {
+        let files: Arc<Mutex<HashMap<PathBuf, HashSet<PathBuf>>>> = Arc::default();
+        let watched = files.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in &event.paths {
+                let changed = watched.lock().unwrap().get(path).cloned();
+                for changed in changed.into_iter().flatten() {
+                    on_change(&changed);
+                }
+            }
+        })
+        .map_err(watch_error)?;
+        Ok(Self {
+            watcher,
+            files,
+            directories: HashSet::new(),
+        })
+    }
+
+    /// Report changes of `path` from now on. The file does not need to exist.
+    pub(crate) fn watch(&mut self, path: &Path) -> Result<(), OmFilesRsError> {
+        let Some(name) = path.file_name() else {
+            return Err(OmFilesRsError::WatchError(format!(
+                "'{}' is not a file",
+                path.display()
+            )));
+        };
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        // Events report canonical paths on some platforms
+        let directory = directory
+            .canonicalize()
+            .map_err(|e| OmFilesRsError::WatchError(format!("{}: {}", directory.display(), e)))?;
+        if !self.directories.contains(&directory) {
+            self.watcher
+                .watch(&directory, RecursiveMode::NonRecursive)
+                .map_err(watch_error)?;
+            self.directories.insert(directory.clone());
+        }
+        self.files
+            .lock()
+            .unwrap()
+            .entry(directory.join(name))
+            .or_default()
+            .insert(path.to_path_buf());
+        Ok(())
+    }
+}
+
+fn watch_error(error: notify::Error) -> OmFilesRsError {
+    OmFilesRsError::WatchError(error.to_string())
+}
+
+struct CurrentFile {
+    reader: OmFileReader<MmapFile>,
+    identity: FileIdentity,
+}
+
+/// Reader of a single file that switches to the new file as soon as the file
+/// is replaced. Every call to `reader` returns the latest file, readers that
+/// were returned earlier keep their mapping of the previous file, so reads in
+/// flight are never affected by a reload.
+///
+/// If the new file cannot be opened, e.g. while it is still being written in
+/// place, the previous file stays in use until the next change.
+pub struct WatchedReader {
+    path: PathBuf,
+    current: Arc<RwLock<CurrentFile>>,
+    reloads: Arc<AtomicU64>,
+    _watcher: PathWatcher,
+}
+
+impl WatchedReader {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, OmFilesRsError> {
+        let path = path.as_ref().to_path_buf();
+        let (reader, identity) = open_file(&path)?;
+        let current = Arc::new(RwLock::new(CurrentFile { reader, identity }));
+        let reloads = Arc::new(AtomicU64::new(0));
+
+        let mut watcher = {
+            let current = current.clone();
+            let reloads = reloads.clone();
+            PathWatcher::new(move |path| {
+                if let Ok(true) = reload(path, &current) {
+                    reloads.fetch_add(1, Ordering::Relaxed);
+                }
+            })?
+        };
+        watcher.watch(&path)?;
+
+        // The file might have been replaced before the watch was set up
+        let reader = Self {
+            path,
+            current,
+            reloads,
+            _watcher: watcher,
+        };
+        reader.reload()?;
+        Ok(reader)
+    }
+
+    /// Reader for the root variable of the latest file
+    pub fn reader(&self) -> OmFileReader<MmapFile> {
+        self.current.read().unwrap().reader.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of times a new file was loaded since creation
+    pub fn reloads(&self) -> u64 {
+        self.reloads.load(Ordering::Relaxed)
+    }
+
+    /// Load the file at `path` if it is not the current file anymore, without
+    /// waiting for a notification. Returns whether a new file was loaded.
+    pub fn reload(&self) -> Result<bool, OmFilesRsError> {
+        let reloaded = reload(&self.path, &self.current)?;
+        if reloaded {
+            self.reloads.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(reloaded)
+    }
+}
+
+fn reload(path: &Path, current: &RwLock<CurrentFile>) -> Result<bool, OmFilesRsError> {
+    if FileIdentity::of_path(path).as_ref() == Some(&current.read().unwrap().identity) {
+        return Ok(false);
+    }
+    let (reader, identity) = open_file(path)?;
+    *current.write().unwrap() = CurrentFile { reader, identity };
+    Ok(true)
+}
@@ -2,9 +2,24 @@ use crate::backend::backends::OmFileWriterBackend;
 use crate::core::c_defaults::{c_error_string, create_uninit_encoder};
 use crate::core::compression::CompressionType;
 use crate::core::data_types::{DataType, OmFileArrayDataType, OmFileScalarDataType};
+use crate::core::variable_metadata::{string_scalar_size, write_string_scalar};
 use crate::errors::OmFilesRsError;
-use crate::io::buffered_writer::OmBufferedWriter;
-use ndarray::ArrayViewD;
+use crate::io::buffered_writer::{OmBufferedWriter, SyncPolicy};
+use crate::io::checkpoint::OmFileWriterCheckpoint;
+use crate::io::chunking::ChunkSpec;
+use crate::io::geo::GridDefinition;
+use crate::io::progress::{ProgressSink, WriteProgress};
+use crate::io::quantization::{estimate_scale_factor, DoubleLinearFilter, QuantizationFilter};
+use crate::io::statistics::{
+    ArrayStatistics, ChunkStatistics, ChunkStatisticsAccumulator, SaturationAccumulator,
+    SaturationStatistics, StatisticsAccumulator,
+};
+use crate::io::time::TimeAxis;
+use crate::io::verify::{is_selected, verify_chunk};
+use crate::utils::divide_rounded_up;
+use futures::{Stream, StreamExt};
+use ndarray::{ArrayD, ArrayView2, ArrayViewD, IxDyn, Slice};
+use num_traits::ToPrimitive;
 use om_file_format_sys::{
     om_encoder_chunk_buffer_size, om_encoder_compress_chunk, om_encoder_compress_lut,
     om_encoder_compressed_chunk_buffer_size, om_encoder_count_chunks,
@@ -14,10 +29,20 @@ use om_file_format_sys::{
     OmEncoder_t, OmError_t_ERROR_OK,
 };
 use std::borrow::BorrowMut;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::os::raw::c_void;
+use std::path::PathBuf;
+use std::pin::pin;
 
+/// Location of the metadata of a variable in the file. With the `serde`
+/// feature it can be stored outside of the file, e.g. in a cache, and passed
+/// to `OmFileReader::open_variable` later.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OmOffsetSize {
     pub offset: u64,
     pub size: u64,
@@ -27,19 +52,189 @@ impl OmOffsetSize {
     pub fn new(offset: u64, size: u64) -> Self {
         Self { offset, size }
     }
+
+    /// Fails if the range is empty or ends beyond `file_size`
+    pub(crate) fn check_within(&self, file_size: u64) -> Result<(), OmFilesRsError> {
+        match self.offset.checked_add(self.size) {
+            Some(end) if self.size > 0 && end <= file_size => Ok(()),
+            _ => Err(OmFilesRsError::InvalidOffsetSize {
+                offset: self.offset,
+                size: self.size,
+                file_size,
+            }),
+        }
+    }
 }
 
+/// Name of the scalar child variable with the number of dimension names of an
+/// array. Its string children `0`, `1`, ... hold the name of each dimension.
+pub const DIMENSION_NAMES_VARIABLE: &str = "_dimension_names";
+/// Name of the scalar child variable that stores the fill value of an array
+pub const FILL_VALUE_VARIABLE: &str = "_fill_value";
+/// Name of the scalar child variable with the number of valid values of an
+/// array. Its children `min`, `max`, `mean` and `nan_count` hold the remaining
+/// statistics.
+pub const STATISTICS_VARIABLE: &str = "_statistics";
+/// Name of the child array with the minimum of every chunk, shaped like the chunk grid
+pub const CHUNK_MIN_VARIABLE: &str = "_chunk_min";
+/// Name of the child array with the maximum of every chunk, shaped like the chunk grid
+pub const CHUNK_MAX_VARIABLE: &str = "_chunk_max";
+/// Name of the string scalar child with the identifier of the quantization filter
+pub const QUANTIZATION_FILTER_VARIABLE: &str = "_quantization_filter";
+/// Name of the scalar child with the unix timestamp of the first time step.
+/// Its children `step`, `length` and `calendar` describe the rest of the axis.
+pub const TIME_AXIS_VARIABLE: &str = "_time_axis";
+/// Name of the string scalar child with the projection of the grid. Its
+/// children `lat_min`, `lat_max`, `lon_min`, `lon_max`, `dx` and `dy` hold
+/// the extent and resolution.
+pub const GRID_VARIABLE: &str = "_grid";
+
+/// Maximum number of dimensions of an array, the same limit as in numpy
+pub const MAX_DIMENSIONS: usize = 32;
+
+/// Size of the header of legacy (version 2) files
+const LEGACY_HEADER_SIZE: usize = 40;
+
 pub struct OmFileWriter<Backend: OmFileWriterBackend> {
     buffer: OmBufferedWriter<Backend>,
+    /// Temporary file that is moved to its destination by `write_trailer`
+    atomic_rename: Option<AtomicRename>,
+    /// Every written variable by offset, to write it again with late attributes
+    written_variables: HashMap<u64, WrittenVariable>,
+    /// Attributes added with `add_attribute`, by offset of their variable
+    late_attributes: HashMap<u64, Vec<OmOffsetSize>>,
+    /// Only accept codecs with identical output on all platforms
+    deterministic: bool,
+    /// Allow string arrays, see `set_string_arrays`
+    string_arrays: bool,
+    /// Write a copy of the trailer before the trailer, see `set_backup_trailer`
+    backup_trailer: bool,
+    /// Fraction of chunks of new arrays that are verified, see `set_verification`
+    verify_fraction: f64,
+}
+
+/// Everything needed to write a variable block again with more children
+#[derive(Clone)]
+struct WrittenVariable {
+    name: String,
+    children: Vec<OmOffsetSize>,
+    kind: WrittenVariableKind,
+}
+
+#[derive(Clone)]
+enum WrittenVariableKind {
+    Array(ArrayHeader),
+    Scalar { data_type: DataType, value: Vec<u8> },
+    String(Vec<u8>),
+}
+
+/// Fields of a numeric array variable without its children
+#[derive(Clone)]
+struct ArrayHeader {
+    scale_factor: f32,
+    add_offset: f32,
+    compression: CompressionType,
+    data_type: DataType,
+    dimensions: Vec<u64>,
+    chunks: Vec<u64>,
+    lut_size: u64,
+    lut_offset: u64,
+}
+
+struct AtomicRename {
+    temporary: PathBuf,
+    destination: PathBuf,
+    overwrite: bool,
 }
 
 impl<Backend: OmFileWriterBackend> OmFileWriter<Backend> {
     pub fn new(backend: Backend, initial_capacity: u64) -> Self {
         Self {
             buffer: OmBufferedWriter::new(backend, initial_capacity as usize),
+            atomic_rename: None,
+            written_variables: HashMap::new(),
+            late_attributes: HashMap::new(),
+            deterministic: false,
+            string_arrays: false,
+            backup_trailer: false,
+            verify_fraction: 0.0,
         }
     }
 
+    /// Sync data to disk while writing, see `SyncPolicy`. Defaults to
+    /// `SyncPolicy::Off`, which only syncs atomic writers when they finish.
+    pub fn set_sync_policy(&mut self, sync_policy: SyncPolicy) {
+        self.buffer.set_sync_policy(sync_policy);
+    }
+
+    /// Reject codecs whose output differs between platforms, see
+    /// `CompressionType::is_deterministic`. Files written in this mode have
+    /// the same bytes and checksum on every architecture. Helpers that pick
+    /// the codec themselves, like `write_mask`, `prepare_array_with_precision`,
+    /// copies and overviews, fall back to lossless `FpxXor2d` for floats and
+    /// `None` for other types instead.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    /// Allow writing string arrays. String arrays use an encoding
+    /// that is so far only read by omfiles-rs, other implementations of the
+    /// format fail to read them. Disabled by default.
+    pub fn set_string_arrays(&mut self, string_arrays: bool) {
+        self.string_arrays = string_arrays;
+    }
+
+    pub fn string_arrays(&self) -> bool {
+        self.string_arrays
+    }
+
+    /// Write a second copy of the trailer directly after the root variable,
+    /// before the regular trailer at the end of the file. If the end of the
+    /// file is lost, `OmFileReader::recover` finds the root variable through
+    /// the copy. Readers without recovery ignore it.
+    pub fn set_backup_trailer(&mut self, backup_trailer: bool) {
+        self.backup_trailer = backup_trailer;
+    }
+
+    /// Decode a `fraction` of the chunks of every array prepared afterwards
+    /// right after compressing them and compare them with the written values,
+    /// see `OmFileWriterArray::with_verification`. 0 disables verification,
+    /// 1 verifies every chunk.
+    pub fn set_verification(&mut self, fraction: f64) {
+        self.verify_fraction = fraction;
+    }
+
+    fn check_deterministic(&self, compression: CompressionType) -> Result<(), OmFilesRsError> {
+        if self.deterministic && !compression.is_deterministic() {
+            return Err(OmFilesRsError::InvalidCompressionType.context(format!(
+                "{:?} is not deterministic across platforms",
+                compression
+            )));
+        }
+        Ok(())
+    }
+
+    /// `compression`, or a deterministic codec for `data_type` if this writer
+    /// is deterministic and `compression` is not
+    pub(crate) fn deterministic_compression(
+        &self,
+        compression: CompressionType,
+        data_type: DataType,
+    ) -> CompressionType {
+        if !self.deterministic || compression.is_deterministic() {
+            return compression;
+        }
+        match data_type {
+            DataType::FloatArray | DataType::DoubleArray => CompressionType::FpxXor2d,
+            _ => CompressionType::None,
+        }
+    }
+
+    /// Output buffer for array writers that do not borrow the file writer
+    pub(crate) fn buffer_mut(&mut self) -> &mut OmBufferedWriter<Backend> {
+        &mut self.buffer
+    }
+
     pub fn write_header_if_required(&mut self) -> Result<(), OmFilesRsError> {
         if self.buffer.total_bytes_written > 0 {
             return Ok(());
@@ -64,7 +259,39 @@ impl<Backend: OmFileWriterBackend> OmFileWriter<Backend> {
         assert!(name.len() <= u16::MAX as usize);
         assert!(children.len() <= u32::MAX as usize);
 
-        let type_scalar = T::DATA_TYPE_SCALAR.to_c();
+        if let Some(bytes) = value.variable_length_bytes() {
+            return self.write_variable_length_scalar(bytes, name, children);
+        }
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&value as *const T as *const u8, std::mem::size_of::<T>())
+        };
+        self.write_fixed_size_scalar(T::DATA_TYPE_SCALAR, bytes, name, children)
+    }
+
+    /// Write the names of all dimensions as string scalars, which do not
+    /// require `set_string_arrays`.
+    pub(crate) fn write_dimension_names<S: AsRef<str>>(
+        &mut self,
+        names: &[S],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        let children = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| self.write_scalar(name.as_ref().to_string(), &i.to_string(), &[]))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.write_scalar(names.len() as u64, DIMENSION_NAMES_VARIABLE, &children)
+    }
+
+    /// Write a numeric scalar given as its native-endian bytes.
+    fn write_fixed_size_scalar(
+        &mut self,
+        data_type: DataType,
+        value: &[u8],
+        name: &str,
+        children: &[OmOffsetSize],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        let type_scalar = data_type.to_c();
 
         let size = unsafe {
             om_variable_write_scalar_size(name.len() as u16, children.len() as u32, type_scalar)
@@ -86,23 +313,72 @@ impl<Backend: OmFileWriterBackend> OmFileWriter<Backend> {
                 children_sizes.as_ptr(),
                 name.as_ptr() as *const ::std::os::raw::c_char,
                 type_scalar,
-                &value as *const T as *const c_void,
+                value.as_ptr() as *const c_void,
             )
         };
 
         self.buffer.increment_write_position(size);
+        self.record_variable(
+            offset,
+            name,
+            children,
+            WrittenVariableKind::Scalar {
+                data_type,
+                value: value.to_vec(),
+            },
+        );
+        Ok(OmOffsetSize::new(offset, size as u64))
+    }
+
+    /// Strings are not supported by the C library and are serialized in Rust.
+    fn write_variable_length_scalar(
+        &mut self,
+        value: &[u8],
+        name: &str,
+        children: &[OmOffsetSize],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        let size = string_scalar_size(name.len(), children.len(), value.len());
+
+        self.buffer.align_to_64_bytes()?;
+        let offset = self.buffer.total_bytes_written as u64;
+
+        self.buffer.reallocate(size)?;
+
+        let children_offsets: Vec<u64> = children.iter().map(|c| c.offset).collect();
+        let children_sizes: Vec<u64> = children.iter().map(|c| c.size).collect();
+        write_string_scalar(
+            &mut self.buffer.buffer_at_write_position()[..size],
+            name,
+            &children_offsets,
+            &children_sizes,
+            value,
+        );
+
+        self.buffer.increment_write_position(size);
+        self.record_variable(
+            offset,
+            name,
+            children,
+            WrittenVariableKind::String(value.to_vec()),
+        );
         Ok(OmOffsetSize::new(offset, size as u64))
     }
 
+    /// Prepare writing an array. `chunk_dimensions` are either explicit chunk
+    /// dimensions or `ChunkSpec::Auto` to pick them with `suggest_chunks`.
     pub fn prepare_array<T: OmFileArrayDataType>(
         &mut self,
         dimensions: Vec<u64>,
-        chunk_dimensions: Vec<u64>,
+        chunk_dimensions: impl Into<ChunkSpec>,
         compression: CompressionType,
         scale_factor: f32,
         add_offset: f32,
     ) -> Result<OmFileWriterArray<T, Backend>, OmFilesRsError> {
+        self.check_deterministic(compression)?;
         let _ = &self.write_header_if_required()?;
+        let chunk_dimensions = chunk_dimensions
+            .into()
+            .resolve(&dimensions, std::mem::size_of::<T>());
 
         let array_writer = OmFileWriterArray::new(
             dimensions,
@@ -114,25 +390,211 @@ impl<Backend: OmFileWriterBackend> OmFileWriter<Backend> {
             self.buffer.borrow_mut(),
         )?;
 
-        Ok(array_writer)
+        Ok(array_writer.with_verification(self.verify_fraction))
+    }
+
+    /// Prepare a float array for `PforDelta2dInt16` with scale factor and
+    /// offset derived by `estimate_scale_factor` from `sample`, e.g. the first
+    /// block of data. Values outside the range of the sample may be clipped.
+    /// The chosen values are stored like explicit ones.
+    pub fn prepare_array_with_precision(
+        &mut self,
+        dimensions: Vec<u64>,
+        chunk_dimensions: impl Into<ChunkSpec>,
+        required_precision: f32,
+        sample: ArrayViewD<f32>,
+    ) -> Result<OmFileWriterArray<f32, Backend>, OmFilesRsError> {
+        let estimate = estimate_scale_factor(sample, required_precision)?;
+        let compression =
+            self.deterministic_compression(CompressionType::PforDelta2dInt16, DataType::FloatArray);
+        self.prepare_array::<f32>(
+            dimensions,
+            chunk_dimensions,
+            compression,
+            estimate.scale_factor,
+            estimate.add_offset,
+        )
+    }
+
+    /// Continue writing an array from a checkpoint. The writer must have been
+    /// created with `OmFileWriter::resume` from the same checkpoint. Data has
+    /// to be written starting with chunk `checkpoint.chunk_index`.
+    pub fn resume_array<T: OmFileArrayDataType>(
+        &mut self,
+        checkpoint: &OmFileWriterCheckpoint,
+    ) -> Result<OmFileWriterArray<T, Backend>, OmFilesRsError> {
+        if checkpoint.bytes_written != self.buffer.total_bytes_written as u64 {
+            return Err(OmFilesRsError::InvalidCheckpoint(format!(
+                "Checkpoint was taken at byte {}, but the writer is at byte {}",
+                checkpoint.bytes_written, self.buffer.total_bytes_written
+            )));
+        }
+        let mut array_writer = OmFileWriterArray::new(
+            checkpoint.dimensions.clone(),
+            checkpoint.chunks.clone(),
+            checkpoint.compression,
+            checkpoint.data_type,
+            checkpoint.scale_factor,
+            checkpoint.add_offset,
+            self.buffer.borrow_mut(),
+        )?;
+        let written = checkpoint.chunk_index as usize + 1;
+        if checkpoint.look_up_table.len() != written || written > array_writer.look_up_table.len() {
+            return Err(OmFilesRsError::InvalidCheckpoint(
+                "Look up table does not match the number of chunks".to_string(),
+            ));
+        }
+        array_writer.look_up_table[..written].copy_from_slice(&checkpoint.look_up_table);
+        array_writer.chunk_index = checkpoint.chunk_index;
+        array_writer.dimension_names = checkpoint.dimension_names.clone();
+        array_writer.fill_value = checkpoint.fill_value.clone();
+        array_writer.time_axis = checkpoint.time_axis.clone();
+        array_writer.grid = checkpoint.grid.clone();
+        Ok(array_writer.with_verification(self.verify_fraction))
+    }
+
+    /// Prepare writing an array of variable-length UTF-8 strings. Requires
+    /// `set_string_arrays`.
+    pub fn prepare_string_array(
+        &mut self,
+        dimensions: Vec<u64>,
+    ) -> Result<OmFileWriterStringArray<Backend>, OmFilesRsError> {
+        if !self.string_arrays {
+            return Err(OmFilesRsError::InvalidDataType.context(
+                "string arrays are only readable by omfiles-rs, enable them with set_string_arrays",
+            ));
+        }
+        self.write_header_if_required()?;
+        if dimensions.is_empty() {
+            return Err(OmFilesRsError::DimensionMustBeLargerThan0);
+        }
+        validate_dimensions(&dimensions, &dimensions)?;
+        Ok(OmFileWriterStringArray::new(
+            dimensions,
+            self.buffer.borrow_mut(),
+        ))
     }
 
     pub fn write_array(
         &mut self,
-        array: OmFileWriterArrayFinalized,
+        mut array: OmFileWriterArrayFinalized,
         name: &str,
         children: &[OmOffsetSize],
     ) -> Result<OmOffsetSize, OmFilesRsError> {
+        self.check_deterministic(array.compression)?;
         self.write_header_if_required()?;
 
         debug_assert!(name.len() <= u16::MAX as usize);
-        debug_assert_eq!(array.dimensions.len(), array.chunks.len());
+        if array.dimensions.len() != array.chunks.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        validate_dimensions(&array.dimensions, &array.chunks)?;
 
+        let dimension_names_child = match array.dimension_names.take() {
+            Some(names) => Some(self.write_dimension_names(&names)?),
+            None => None,
+        };
+        let fill_value_child = match array.fill_value.take() {
+            Some(fill_value) => Some(self.write_fixed_size_scalar(
+                fill_value.data_type,
+                &fill_value.bytes,
+                FILL_VALUE_VARIABLE,
+                &[],
+            )?),
+            None => None,
+        };
+        let statistics_child = match array.statistics.take() {
+            Some(statistics) => {
+                let values = [
+                    self.write_scalar(statistics.min, "min", &[])?,
+                    self.write_scalar(statistics.max, "max", &[])?,
+                    self.write_scalar(statistics.mean, "mean", &[])?,
+                    self.write_scalar(statistics.nan_count, "nan_count", &[])?,
+                ];
+                Some(self.write_scalar(statistics.count, STATISTICS_VARIABLE, &values)?)
+            }
+            None => None,
+        };
+        let chunk_statistics_children = match array.chunk_statistics.take() {
+            Some(chunk_statistics) => {
+                let grid: Vec<u64> = array
+                    .dimensions
+                    .iter()
+                    .zip(array.chunks.iter())
+                    .map(|(&dim, &chunk)| divide_rounded_up(dim as usize, chunk as usize) as u64)
+                    .collect();
+                vec![
+                    self.write_chunk_grid(&grid, &chunk_statistics.min, CHUNK_MIN_VARIABLE)?,
+                    self.write_chunk_grid(&grid, &chunk_statistics.max, CHUNK_MAX_VARIABLE)?,
+                ]
+            }
+            None => vec![],
+        };
+        let quantization_filter_child = match array.quantization_filter.take() {
+            Some(id) => Some(self.write_scalar(id, QUANTIZATION_FILTER_VARIABLE, &[])?),
+            None => None,
+        };
+        let time_axis_child = match array.time_axis.take() {
+            Some(time_axis) => {
+                let values = [
+                    self.write_scalar(time_axis.step, "step", &[])?,
+                    self.write_scalar(time_axis.length, "length", &[])?,
+                    self.write_scalar(time_axis.calendar, "calendar", &[])?,
+                ];
+                Some(self.write_scalar(time_axis.start, TIME_AXIS_VARIABLE, &values)?)
+            }
+            None => None,
+        };
+        let grid_child = match array.grid.take() {
+            Some(grid) => {
+                let values = [
+                    self.write_scalar(grid.lat_min, "lat_min", &[])?,
+                    self.write_scalar(grid.lat_max, "lat_max", &[])?,
+                    self.write_scalar(grid.lon_min, "lon_min", &[])?,
+                    self.write_scalar(grid.lon_max, "lon_max", &[])?,
+                    self.write_scalar(grid.dx, "dx", &[])?,
+                    self.write_scalar(grid.dy, "dy", &[])?,
+                ];
+                Some(self.write_scalar(grid.projection, GRID_VARIABLE, &values)?)
+            }
+            None => None,
+        };
+        let children: Vec<OmOffsetSize> = children
+            .iter()
+            .cloned()
+            .chain(dimension_names_child)
+            .chain(fill_value_child)
+            .chain(statistics_child)
+            .chain(chunk_statistics_children)
+            .chain(quantization_filter_child)
+            .chain(time_axis_child)
+            .chain(grid_child)
+            .collect();
+
+        let header = ArrayHeader {
+            scale_factor: array.scale_factor,
+            add_offset: array.add_offset,
+            compression: array.compression,
+            data_type: array.data_type,
+            dimensions: array.dimensions,
+            chunks: array.chunks,
+            lut_size: array.lut_size,
+            lut_offset: array.lut_offset,
+        };
+        self.write_array_header(header, name, &children)
+    }
+
+    fn write_array_header(
+        &mut self,
+        header: ArrayHeader,
+        name: &str,
+        children: &[OmOffsetSize],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
         let size = unsafe {
             om_variable_write_numeric_array_size(
                 name.len() as u16,
                 children.len() as u32,
-                array.dimensions.len() as u64,
+                header.dimensions.len() as u64,
             )
         };
         self.buffer.align_to_64_bytes()?;
@@ -151,38 +613,343 @@ impl<Backend: OmFileWriterBackend> OmFileWriter<Backend> {
                 children_offsets.as_ptr(),
                 children_sizes.as_ptr(),
                 name.as_ptr() as *const ::std::os::raw::c_char,
-                array.data_type.to_c(),
-                array.compression.to_c(),
-                array.scale_factor,
-                array.add_offset,
-                array.dimensions.len() as u64,
-                array.dimensions.as_ptr(),
-                array.chunks.as_ptr(),
-                array.lut_size,
-                array.lut_offset,
+                header.data_type.to_c(),
+                header.compression.to_c(),
+                header.scale_factor,
+                header.add_offset,
+                header.dimensions.len() as u64,
+                header.dimensions.as_ptr(),
+                header.chunks.as_ptr(),
+                header.lut_size,
+                header.lut_offset,
             )
         };
 
         self.buffer.increment_write_position(size);
+        self.record_variable(offset, name, children, WrittenVariableKind::Array(header));
         Ok(OmOffsetSize::new(offset, size as u64))
     }
 
+    fn record_variable(
+        &mut self,
+        offset: u64,
+        name: &str,
+        children: &[OmOffsetSize],
+        kind: WrittenVariableKind,
+    ) {
+        self.written_variables.insert(
+            offset,
+            WrittenVariable {
+                name: name.to_string(),
+                children: children.to_vec(),
+                kind,
+            },
+        );
+    }
+
+    /// Add a scalar attribute to a variable that is already written, e.g. a
+    /// value that is only known after all data has been written. The variable
+    /// and all variables referencing it are written again with the additional
+    /// children by `write_trailer`, so offsets returned earlier can still be
+    /// used as children and as root variable.
+    pub fn add_attribute<T: OmFileScalarDataType>(
+        &mut self,
+        variable: &OmOffsetSize,
+        value: T,
+        name: &str,
+    ) -> Result<(), OmFilesRsError> {
+        if !self.written_variables.contains_key(&variable.offset) {
+            return Err(OmFilesRsError::VariableNotFound(format!(
+                "at offset {}",
+                variable.offset
+            )));
+        }
+        let attribute = self.write_scalar(value, name, &[])?;
+        self.late_attributes
+            .entry(variable.offset)
+            .or_default()
+            .push(attribute);
+        Ok(())
+    }
+
+    /// Write `variable` again if it or one of its descendants has late
+    /// attributes. Returns the new location, or the old one if unchanged.
+    fn apply_late_attributes(
+        &mut self,
+        variable: &OmOffsetSize,
+        rewritten: &mut HashMap<u64, OmOffsetSize>,
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        if let Some(new) = rewritten.get(&variable.offset) {
+            return Ok(new.clone());
+        }
+        let Some(written) = self.written_variables.get(&variable.offset).cloned() else {
+            return Ok(variable.clone());
+        };
+        let mut children = Vec::with_capacity(written.children.len());
+        for child in &written.children {
+            children.push(self.apply_late_attributes(child, rewritten)?);
+        }
+        let attributes = self.late_attributes.remove(&variable.offset);
+        if attributes.is_none() && children == written.children {
+            return Ok(variable.clone());
+        }
+        children.extend(attributes.into_iter().flatten());
+        let name = written.name.as_str();
+        let new = match written.kind {
+            WrittenVariableKind::Array(header) => {
+                self.write_array_header(header, name, &children)?
+            }
+            WrittenVariableKind::Scalar { data_type, value } => {
+                self.write_fixed_size_scalar(data_type, &value, name, &children)?
+            }
+            WrittenVariableKind::String(value) => {
+                self.write_variable_length_scalar(&value, name, &children)?
+            }
+        };
+        rewritten.insert(variable.offset, new.clone());
+        Ok(new)
+    }
+
+    /// Write one value per chunk as lossless double array shaped like the chunk grid.
+    fn write_chunk_grid(
+        &mut self,
+        grid: &[u64],
+        values: &[f64],
+        name: &str,
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        let shape: Vec<usize> = grid.iter().map(|&x| x as usize).collect();
+        let values = ArrayViewD::from_shape(IxDyn(&shape), values)
+            .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)?;
+        let chunks: Vec<u64> = grid.iter().map(|&x| x.min(32)).collect();
+        let mut writer =
+            self.prepare_array::<f64>(grid.to_vec(), chunks, CompressionType::FpxXor2d, 1.0, 0.0)?;
+        writer.write_data(values, None, None)?;
+        let variable_meta = writer.finalize();
+        self.write_array(variable_meta, name, &[])
+    }
+
+    /// Write a short vector, e.g. a bounding box or a list of levels, as an
+    /// attribute. The format has no inline vectors, so it is stored as an
+    /// uncompressed array of a single chunk right before its metadata, which
+    /// every reader can decode. Read it back with `OmFileReader::read_small_array`.
+    /// Arrays cannot have zero length, so `values` must not be empty.
+    pub fn write_small_array<T: OmFileArrayDataType>(
+        &mut self,
+        values: &[T],
+        name: &str,
+        children: &[OmOffsetSize],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        if values.is_empty() {
+            return Err(OmFilesRsError::ZeroDimension { index: 0 }
+                .context(format!("small array '{}' is empty", name)));
+        }
+        let length = values.len() as u64;
+        let mut writer =
+            self.prepare_array::<T>(vec![length], vec![length], CompressionType::None, 1.0, 0.0)?;
+        writer.write_data_flat(values, None, None, None)?;
+        let variable_meta = writer.finalize();
+        self.write_array(variable_meta, name, children)
+    }
+
+    /// Write a boolean mask, e.g. a land/sea mask or quality flags, as `u8`
+    /// array of zeros and ones. `PforDelta2d` packs these values into a few
+    /// bits per element. Read it back with `OmFileReader::read_mask`.
+    pub fn write_mask(
+        &mut self,
+        mask: ArrayViewD<bool>,
+        chunk_dimensions: Vec<u64>,
+        name: &str,
+        children: &[OmOffsetSize],
+    ) -> Result<OmOffsetSize, OmFilesRsError> {
+        let dimensions = mask.shape().iter().map(|&x| x as u64).collect();
+        let compression =
+            self.deterministic_compression(CompressionType::PforDelta2d, DataType::Uint8Array);
+        let mut writer =
+            self.prepare_array::<u8>(dimensions, chunk_dimensions, compression, 1.0, 0.0)?;
+        let values = mask.mapv(u8::from);
+        writer.write_data(values.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        self.write_array(variable_meta, name, children)
+    }
+
+    /// Write a complete legacy (version 2) file with one 2D float array for
+    /// consumers that cannot read trailer based files. Legacy files have no
+    /// names, children or attributes, so nothing else can be written to this
+    /// writer. Only `PforDelta2dInt16` and `FpxXor2d` are supported. The look
+    /// up table is written last with `write_at`.
+    pub fn write_legacy_array(
+        &mut self,
+        data: ArrayView2<f32>,
+        chunk_dimensions: [u64; 2],
+        compression: CompressionType,
+        scale_factor: f32,
+    ) -> Result<(), OmFilesRsError> {
+        if self.buffer.total_bytes_written > 0 {
+            return Err(OmFilesRsError::NotImplementedError(
+                "Legacy files can only contain a single array".to_string(),
+            ));
+        }
+        if !matches!(
+            compression,
+            CompressionType::PforDelta2dInt16 | CompressionType::FpxXor2d
+        ) {
+            return Err(OmFilesRsError::InvalidCompressionType);
+        }
+        self.check_deterministic(compression)?;
+        let dimensions = vec![data.nrows() as u64, data.ncols() as u64];
+        validate_dimensions(&dimensions, &chunk_dimensions)?;
+        let n_chunks = divide_rounded_up(dimensions[0] as usize, chunk_dimensions[0] as usize)
+            * divide_rounded_up(dimensions[1] as usize, chunk_dimensions[1] as usize);
+
+        // Header and a placeholder for the look up table
+        let mut header = Vec::with_capacity(LEGACY_HEADER_SIZE);
+        header.extend_from_slice(&[b'O', b'M', 2, compression as u8]);
+        header.extend_from_slice(&scale_factor.to_le_bytes());
+        for value in dimensions.iter().chain(chunk_dimensions.iter()) {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        let data_start = LEGACY_HEADER_SIZE + n_chunks * 8;
+        self.buffer.reallocate(data_start)?;
+        let destination = &mut self.buffer.buffer_at_write_position()[..data_start];
+        destination[..LEGACY_HEADER_SIZE].copy_from_slice(&header);
+        destination[LEGACY_HEADER_SIZE..].fill(0);
+        self.buffer.increment_write_position(data_start);
+
+        // Chunks directly follow the look up table, which stores the end of
+        // each chunk relative to the start of the data
+        let look_up_table = {
+            let mut writer = OmFileWriterArray::<f32, Backend>::new(
+                dimensions,
+                chunk_dimensions.to_vec(),
+                compression,
+                DataType::FloatArray,
+                scale_factor,
+                0.0,
+                &mut self.buffer,
+            )?;
+            writer.write_data(data.into_dyn(), None, None)?;
+            writer.look_up_table[1..]
+                .iter()
+                .flat_map(|end| (end - data_start as u64).to_le_bytes())
+                .collect::<Vec<u8>>()
+        };
+        self.buffer.write_to_file()?;
+        self.buffer
+            .backend
+            .write_at(&look_up_table, LEGACY_HEADER_SIZE)?;
+
+        if let Some(rename) = self.atomic_rename.take() {
+            self.buffer.backend.synchronize()?;
+            rename.commit()?;
+        }
+        Ok(())
+    }
+
     pub fn write_trailer(&mut self, root_variable: OmOffsetSize) -> Result<(), OmFilesRsError> {
         self.write_header_if_required()?;
+        let root_variable = if self.late_attributes.is_empty() {
+            root_variable
+        } else {
+            self.apply_late_attributes(&root_variable, &mut HashMap::new())?
+        };
         self.buffer.align_to_64_bytes()?;
 
         let size = unsafe { om_trailer_size() };
-        self.buffer.reallocate(size)?;
-        unsafe {
-            om_trailer_write(
-                self.buffer.buffer_at_write_position().as_mut_ptr() as *mut c_void,
-                root_variable.offset,
-                root_variable.size,
-            );
+        let copies = if self.backup_trailer { 2 } else { 1 };
+        for _ in 0..copies {
+            self.buffer.reallocate(size)?;
+            unsafe {
+                om_trailer_write(
+                    self.buffer.buffer_at_write_position().as_mut_ptr() as *mut c_void,
+                    root_variable.offset,
+                    root_variable.size,
+                );
+            }
+            self.buffer.increment_write_position(size);
         }
-        self.buffer.increment_write_position(size);
 
-        self.buffer.write_to_file()
+        self.buffer.write_to_file()?;
+
+        if let Some(rename) = self.atomic_rename.take() {
+            self.buffer.backend.synchronize()?;
+            rename.commit()?;
+        }
+        Ok(())
+    }
+}
+
+impl OmFileWriter<File> {
+    /// Reopen an output file at a checkpoint of `OmFileWriterArray::checkpoint`.
+    /// `file` must be opened for writing. Everything written after the
+    /// checkpoint is truncated. Continue with `resume_array`.
+    pub fn resume(
+        mut file: File,
+        checkpoint: &OmFileWriterCheckpoint,
+        initial_capacity: u64,
+    ) -> Result<Self, OmFilesRsError> {
+        let map_error = |e: std::io::Error| OmFilesRsError::FileWriterError {
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        };
+        let length = file.metadata().map_err(map_error)?.len();
+        if length < checkpoint.bytes_written {
+            return Err(OmFilesRsError::InvalidCheckpoint(format!(
+                "File has {} bytes, but {} bytes were written at the checkpoint",
+                length, checkpoint.bytes_written
+            )));
+        }
+        file.set_len(checkpoint.bytes_written).map_err(map_error)?;
+        file.seek(SeekFrom::End(0)).map_err(map_error)?;
+        let mut writer = Self::new(file, initial_capacity);
+        writer.buffer.total_bytes_written = checkpoint.bytes_written as usize;
+        Ok(writer)
+    }
+
+    /// Create a writer for `path` that writes to the temporary file `path~`.
+    /// `write_trailer` syncs the temporary file to disk and renames it to
+    /// `path`, so readers never observe a partially written file. Without
+    /// `overwrite`, an existing file at `path` is an error.
+    pub fn create_atomic(
+        path: &str,
+        overwrite: bool,
+        initial_capacity: u64,
+    ) -> Result<Self, OmFilesRsError> {
+        let destination = PathBuf::from(path);
+        if !overwrite && destination.exists() {
+            return Err(OmFilesRsError::FileExistsAlready {
+                filename: path.to_string(),
+            });
+        }
+        let temporary = PathBuf::from(format!("{}~", path));
+        // A left over temporary file from an earlier failed write is replaced
+        let file = File::create(&temporary).map_err(|e| OmFilesRsError::CannotOpenFile {
+            filename: temporary.display().to_string(),
+            errno: e.raw_os_error().unwrap_or(0),
+            error: e.to_string(),
+        })?;
+        let mut writer = Self::new(file, initial_capacity);
+        writer.atomic_rename = Some(AtomicRename {
+            temporary,
+            destination,
+            overwrite,
+        });
+        Ok(writer)
+    }
+}
+
+impl AtomicRename {
+    fn commit(self) -> Result<(), OmFilesRsError> {
+        if !self.overwrite && self.destination.exists() {
+            return Err(OmFilesRsError::FileExistsAlready {
+                filename: self.destination.display().to_string(),
+            });
+        }
+        std::fs::rename(&self.temporary, &self.destination).map_err(|e| {
+            OmFilesRsError::FileWriterError {
+                errno: e.raw_os_error().unwrap_or(0),
+                error: e.to_string(),
+            }
+        })
     }
 }
 
@@ -199,6 +966,24 @@ pub struct OmFileWriterArray<'a, OmType: OmFileArrayDataType, Backend: OmFileWri
     compressed_chunk_buffer_size: u64,
     chunk_buffer: Vec<u8>,
     buffer: &'a mut OmBufferedWriter<Backend>,
+    progress_sink: Option<Box<dyn ProgressSink + 'a>>,
+    dimension_names: Option<Vec<String>>,
+    fill_value: Option<FillValue>,
+    statistics: Option<StatisticsAccumulator<OmType>>,
+    chunk_statistics: Option<ChunkStatisticsAccumulator<OmType>>,
+    saturation: Option<SaturationAccumulator<OmType>>,
+    quantization_filter: Option<AppliedFilter<'a, OmType>>,
+    time_axis: Option<TimeAxis>,
+    grid: Option<GridDefinition>,
+    verify_fraction: f64,
+}
+
+type FilterFn<'a, OmType> = Box<dyn Fn(&[OmType]) -> Vec<OmType> + 'a>;
+
+/// Quantization filter together with its identifier
+struct AppliedFilter<'a, OmType> {
+    id: String,
+    forward: FilterFn<'a, OmType>,
 }
 
 impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
@@ -217,31 +1002,15 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
         if data_type != OmType::DATA_TYPE_ARRAY {
             return Err(OmFilesRsError::InvalidDataType);
         }
-        if dimensions.len() != chunk_dimensions.len() {
-            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
-        }
-
         let chunks = chunk_dimensions;
-
-        let mut encoder = unsafe { create_uninit_encoder() };
-        let error = unsafe {
-            om_encoder_init(
-                &mut encoder,
-                scale_factor,
-                add_offset,
-                compression.to_c(),
-                data_type.to_c(),
-                dimensions.as_ptr(),
-                chunks.as_ptr(),
-                dimensions.len() as u64,
-            )
-        };
-        if error != OmError_t_ERROR_OK {
-            return Err(OmFilesRsError::FileWriterError {
-                errno: error as i32,
-                error: c_error_string(error),
-            });
-        }
+        let encoder = init_encoder(
+            &dimensions,
+            &chunks,
+            compression,
+            data_type,
+            scale_factor,
+            add_offset,
+        )?;
 
         let n_chunks = unsafe { om_encoder_count_chunks(&encoder) } as usize;
         let compressed_chunk_buffer_size =
@@ -264,9 +1033,133 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
             compressed_chunk_buffer_size,
             chunk_buffer,
             buffer,
+            progress_sink: None,
+            dimension_names: None,
+            fill_value: None,
+            statistics: None,
+            chunk_statistics: None,
+            saturation: None,
+            quantization_filter: None,
+            time_axis: None,
+            grid: None,
+            verify_fraction: 0.0,
         })
     }
 
+    /// Attach names to all dimensions, e.g. `["time", "lat", "lon"]`. They are
+    /// written as child variables together with the array metadata and
+    /// returned by `OmFileReader::get_dimension_names`. Unlike string arrays,
+    /// they do not require `OmFileWriter::set_string_arrays`.
+    pub fn with_dimension_names<S: AsRef<str>>(
+        mut self,
+        names: &[S],
+    ) -> Result<Self, OmFilesRsError> {
+        if names.len() != self.dimensions.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        self.dimension_names = Some(names.iter().map(|n| n.as_ref().to_string()).collect());
+        Ok(self)
+    }
+
+    /// Describe the time axis of the array. It is written as child variables
+    /// and returned by `OmFileReader::get_time_axis`.
+    pub fn with_time_axis(mut self, time_axis: TimeAxis) -> Self {
+        self.time_axis = Some(time_axis);
+        self
+    }
+
+    /// Describe the spatial grid of the array. It is written as child
+    /// variables and returned by `OmFileReader::get_grid`.
+    pub fn with_grid(mut self, grid: GridDefinition) -> Self {
+        self.grid = Some(grid);
+        self
+    }
+
+    /// Mark elements equal to `value` as missing, e.g. for integer arrays that
+    /// cannot use NaN. The value is stored as child variable and used by
+    /// `OmFileReader::read_with_validity` and `read_replacing_fill_value`.
+    pub fn with_fill_value(mut self, value: OmType) -> Self
+    where
+        OmType: OmFileScalarDataType + Copy,
+    {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &value as *const OmType as *const u8,
+                std::mem::size_of::<OmType>(),
+            )
+        };
+        self.fill_value = Some(FillValue {
+            data_type: OmType::DATA_TYPE_SCALAR,
+            bytes: bytes.to_vec(),
+        });
+        self
+    }
+
+    /// Accumulate min, max, mean and NaN count of all written values. They are
+    /// stored as child variables and returned by `OmFileReader::get_statistics`.
+    pub fn with_statistics(mut self) -> Self
+    where
+        OmType: ToPrimitive,
+    {
+        self.statistics = Some(StatisticsAccumulator::new());
+        self
+    }
+
+    /// Store minimum and maximum of every chunk as child arrays, so that
+    /// `OmFileReader::read_where` can skip chunks outside of a value range.
+    pub fn with_chunk_statistics(mut self) -> Self
+    where
+        OmType: ToPrimitive,
+    {
+        let number_of_chunks = self.look_up_table.len() - 1;
+        self.chunk_statistics = Some(ChunkStatisticsAccumulator::new(number_of_chunks));
+        self
+    }
+
+    /// Count values per chunk that exceed the int16 range after scaling and
+    /// would be clamped by the int16 codecs. The counts are returned in
+    /// `OmFileWriterArrayFinalized::saturation`. If more than `max_saturated`
+    /// values saturate, `write_data` fails before compressing the chunk.
+    /// Other compression types are rejected.
+    pub fn with_saturation_check(
+        mut self,
+        max_saturated: Option<u64>,
+    ) -> Result<Self, OmFilesRsError>
+    where
+        OmType: ToPrimitive,
+    {
+        let logarithmic = match self.compression {
+            CompressionType::PforDelta2dInt16 => false,
+            CompressionType::PforDelta2dInt16Logarithmic => true,
+            _ => return Err(OmFilesRsError::InvalidCompressionType),
+        };
+        let number_of_chunks = self.look_up_table.len() - 1;
+        self.saturation = Some(SaturationAccumulator::new(
+            number_of_chunks,
+            self.scale_factor,
+            self.add_offset,
+            logarithmic,
+            max_saturated,
+        ));
+        Ok(self)
+    }
+
+    /// Decode a `fraction` of all chunks right after compressing them and
+    /// compare them with the values passed to `write_data`, to catch encoder
+    /// issues before a file is shipped. Quantized values may differ by one
+    /// step of the scale factor. Chunks are picked pseudo-randomly, but
+    /// reproducibly. A mismatch fails `write_data` with
+    /// `ChunkVerificationFailed`.
+    pub fn with_verification(mut self, fraction: f64) -> Self {
+        self.verify_fraction = fraction;
+        self
+    }
+
+    /// Report progress after every compressed chunk to `sink`.
+    pub fn set_progress_sink(&mut self, sink: impl ProgressSink + 'a) {
+        self.progress_sink = Some(Box::new(sink));
+    }
+
     /// Writes an ndarray to the file.
     pub fn write_data(
         &mut self,
@@ -283,6 +1176,74 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
         self.write_data_flat(array, Some(&array_dimensions), array_offset, array_count)
     }
 
+    /// Write the array from pieces without holding all of it in memory. Every
+    /// piece covers whole chunks and continues where the previous piece ended
+    /// in the order of chunks, e.g. slabs of `chunks[0]` time steps. Pieces
+    /// are compressed and released one after another.
+    pub fn write_data_from_iter(
+        &mut self,
+        pieces: impl IntoIterator<Item = ArrayD<OmType>>,
+    ) -> Result<(), OmFilesRsError> {
+        for piece in pieces {
+            self.write_piece(piece)?;
+        }
+        Ok(())
+    }
+
+    /// Like `write_data_from_iter` for pieces that are produced asynchronously,
+    /// e.g. downloaded or decoded from another format.
+    pub async fn write_data_from_stream(
+        &mut self,
+        pieces: impl Stream<Item = ArrayD<OmType>>,
+    ) -> Result<(), OmFilesRsError> {
+        let mut pieces = pin!(pieces);
+        while let Some(piece) = pieces.next().await {
+            self.write_piece(piece)?;
+        }
+        Ok(())
+    }
+
+    fn write_piece(&mut self, piece: ArrayD<OmType>) -> Result<(), OmFilesRsError> {
+        let shape: Vec<u64> = piece.shape().iter().map(|&x| x as u64).collect();
+        self.check_piece(&shape)
+            .map_err(|error| error.context(format!("piece at chunk {}", self.chunk_index)))?;
+        self.write_data(piece.view(), None, None)
+    }
+
+    /// Check that a piece of `shape` starts at the next chunk to be written
+    /// and covers a contiguous run of chunks.
+    fn check_piece(&self, shape: &[u64]) -> Result<(), OmFilesRsError> {
+        if shape.len() != self.dimensions.len() {
+            return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+        }
+        let mut remaining = self.chunk_index;
+        // All faster varying dimensions are covered entirely
+        let mut inner_complete = true;
+        for i in (0..self.dimensions.len()).rev() {
+            let (dimension, chunk) = (self.dimensions[i], self.chunks[i]);
+            let chunks_in_dimension = dimension.div_ceil(chunk);
+            let start = remaining % chunks_in_dimension * chunk;
+            remaining /= chunks_in_dimension;
+            if start + shape[i] > dimension {
+                return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                    offset: start,
+                    count: shape[i],
+                    dimension,
+                });
+            }
+            let aligned = shape[i] > 0 && (shape[i] % chunk == 0 || start + shape[i] == dimension);
+            let single_chunk = shape[i] == chunk.min(dimension - start);
+            if !aligned || (!inner_complete && !single_chunk) {
+                return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+            }
+            inner_complete &= start == 0 && shape[i] == dimension;
+        }
+        if remaining > 0 {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+        Ok(())
+    }
+
     /// Compresses data and writes it to file.
     pub fn write_data_flat(
         &mut self,
@@ -322,6 +1283,41 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
             }
         }
 
+        let view = if self.statistics.is_some() || self.chunk_statistics.is_some() {
+            let shape: Vec<usize> = array_dimensions.iter().map(|&x| x as usize).collect();
+            Some(
+                ArrayViewD::from_shape(IxDyn(&shape), array)
+                    .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)?,
+            )
+        } else {
+            None
+        };
+        if let (Some(statistics), Some(view)) = (self.statistics.as_mut(), view.as_ref()) {
+            let region = view.slice_each_axis(|axis| {
+                let offset = array_offset[axis.axis.index()] as usize;
+                let count = array_count[axis.axis.index()] as usize;
+                Slice::from(offset..offset + count)
+            });
+            region.iter().for_each(|value| statistics.add(value));
+        }
+
+        // Statistics describe the values before filtering
+        let filtered = self
+            .quantization_filter
+            .as_ref()
+            .map(|filter| (filter.forward)(array));
+        let encoded = filtered.as_deref().unwrap_or(array);
+        // Saturation is checked on the filtered values that are quantized
+        let encoded_view = if self.saturation.is_some() {
+            let shape: Vec<usize> = array_dimensions.iter().map(|&x| x as usize).collect();
+            Some(
+                ArrayViewD::from_shape(IxDyn(&shape), encoded)
+                    .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)?,
+            )
+        } else {
+            None
+        };
+
         self.buffer
             .reallocate(self.compressed_chunk_buffer_size as usize * 4)?;
 
@@ -340,10 +1336,41 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
             self.buffer
                 .reallocate(self.compressed_chunk_buffer_size as usize)?;
 
+            if let (Some(chunk_statistics), Some(view)) =
+                (self.chunk_statistics.as_mut(), view.as_ref())
+            {
+                let region = chunk_region(
+                    &self.dimensions,
+                    &self.chunks,
+                    array_offset,
+                    array_count,
+                    self.chunk_index,
+                    chunk_offset,
+                );
+                let chunk =
+                    view.slice_each_axis(|axis| Slice::from(region[axis.axis.index()].clone()));
+                chunk_statistics.add_chunk(self.chunk_index as usize, chunk.iter());
+            }
+            if let (Some(saturation), Some(view)) =
+                (self.saturation.as_mut(), encoded_view.as_ref())
+            {
+                let region = chunk_region(
+                    &self.dimensions,
+                    &self.chunks,
+                    array_offset,
+                    array_count,
+                    self.chunk_index,
+                    chunk_offset,
+                );
+                let chunk =
+                    view.slice_each_axis(|axis| Slice::from(region[axis.axis.index()].clone()));
+                saturation.add_chunk(self.chunk_index as usize, chunk.iter())?;
+            }
+
             let bytes_written = unsafe {
                 om_encoder_compress_chunk(
                     &mut self.encoder,
-                    array.as_ptr() as *const c_void,
+                    encoded.as_ptr() as *const c_void,
                     array_dimensions.as_ptr(),
                     array_offset.as_ptr(),
                     array_count.as_ptr(),
@@ -354,38 +1381,108 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
                 )
             };
 
+            if self.verify_fraction > 0.0 && is_selected(self.chunk_index, self.verify_fraction) {
+                let region = chunk_region(
+                    &self.dimensions,
+                    &self.chunks,
+                    array_offset,
+                    array_count,
+                    self.chunk_index,
+                    chunk_offset,
+                );
+                let compressed = &self.buffer.buffer_at_write_position()[..bytes_written as usize];
+                verify_chunk(
+                    compressed,
+                    encoded,
+                    array_dimensions,
+                    &region,
+                    self.compression,
+                    self.scale_factor,
+                    self.add_offset,
+                )
+                .map_err(|error| error.context(format!("verifying chunk {}", self.chunk_index)))?;
+            }
+
             self.buffer.increment_write_position(bytes_written as usize);
 
             self.look_up_table[(self.chunk_index + 1) as usize] =
                 self.buffer.total_bytes_written as u64;
             self.chunk_index += 1;
+
+            if let Some(sink) = self.progress_sink.as_mut() {
+                sink.on_progress(WriteProgress {
+                    chunks_written: self.chunk_index,
+                    total_chunks: self.look_up_table.len() as u64 - 1,
+                    bytes_written: self.look_up_table[self.chunk_index as usize]
+                        - self.look_up_table[0],
+                });
+            }
         }
 
         Ok(())
     }
 
+    /// Flush all compressed chunks to the backend, sync it and return the
+    /// current state to resume writing later. Arrays with statistics or a
+    /// quantization filter cannot be resumed, because the accumulated values
+    /// and the filter are not part of the checkpoint.
+    pub fn checkpoint(&mut self) -> Result<OmFileWriterCheckpoint, OmFilesRsError> {
+        if self.statistics.is_some() || self.chunk_statistics.is_some() || self.saturation.is_some()
+        {
+            return Err(OmFilesRsError::InvalidCheckpoint(
+                "Arrays with statistics cannot be resumed".to_string(),
+            ));
+        }
+        // Filters are closures and cannot be stored
+        if self.quantization_filter.is_some() {
+            return Err(OmFilesRsError::InvalidCheckpoint(
+                "Arrays with a quantization filter cannot be resumed".to_string(),
+            ));
+        }
+        self.buffer.write_to_file()?;
+        self.buffer.backend.synchronize()?;
+        Ok(OmFileWriterCheckpoint {
+            data_type: OmType::DATA_TYPE_ARRAY,
+            compression: self.compression,
+            scale_factor: self.scale_factor,
+            add_offset: self.add_offset,
+            dimensions: self.dimensions.clone(),
+            chunks: self.chunks.clone(),
+            chunk_index: self.chunk_index,
+            look_up_table: self.look_up_table[..=self.chunk_index as usize].to_vec(),
+            bytes_written: self.buffer.total_bytes_written as u64,
+            dimension_names: self.dimension_names.clone(),
+            fill_value: self.fill_value.clone(),
+            time_axis: self.time_axis.clone(),
+            grid: self.grid.clone(),
+        })
+    }
+
     /// Compress the lookup table and write it to the output buffer.
     pub fn write_lut(&mut self) -> u64 {
-        let buffer_size = unsafe {
-            om_encoder_lut_buffer_size(self.look_up_table.as_ptr(), self.look_up_table.len() as u64)
-        };
-
-        self.buffer
-            .reallocate(buffer_size as usize)
-            .expect("Failed to reallocate buffer");
-
-        let compressed_lut_size = unsafe {
-            om_encoder_compress_lut(
-                self.look_up_table.as_ptr(),
-                self.look_up_table.len() as u64,
-                self.buffer.buffer_at_write_position().as_mut_ptr(),
-                buffer_size,
-            )
-        };
+        write_lut(self.buffer, &self.look_up_table).expect("Failed to reallocate buffer")
+    }
 
-        self.buffer
-            .increment_write_position(compressed_lut_size as usize);
-        compressed_lut_size
+    /// Append a chunk that was compressed elsewhere with the same dimensions,
+    /// chunks and encoding. Chunks must be appended in the order of the
+    /// lookup table.
+    pub(crate) fn write_compressed_chunk(
+        &mut self,
+        compressed: &[u8],
+    ) -> Result<(), OmFilesRsError> {
+        let number_of_chunks = self.look_up_table.len() as u64 - 1;
+        if self.chunk_index >= number_of_chunks {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+        if self.chunk_index == 0 {
+            self.look_up_table[0] = self.buffer.total_bytes_written as u64;
+        }
+        self.buffer.reallocate(compressed.len())?;
+        self.buffer.buffer_at_write_position()[..compressed.len()].copy_from_slice(compressed);
+        self.buffer.increment_write_position(compressed.len());
+        self.chunk_index += 1;
+        self.look_up_table[self.chunk_index as usize] = self.buffer.total_bytes_written as u64;
+        Ok(())
     }
 
     /// Finalize the array and return the finalized struct.
@@ -402,10 +1499,158 @@ impl<'a, OmType: OmFileArrayDataType, Backend: OmFileWriterBackend>
             chunks: self.chunks.clone(),
             lut_size,
             lut_offset,
+            dimension_names: self.dimension_names.take(),
+            fill_value: self.fill_value.take(),
+            statistics: self.statistics.as_ref().map(|s| s.finish()),
+            chunk_statistics: self.chunk_statistics.take().map(|s| s.finish()),
+            saturation: self.saturation.take().map(|s| s.finish()),
+            quantization_filter: self.quantization_filter.take().map(|f| f.id),
+            time_axis: self.time_axis.take(),
+            grid: self.grid.take(),
+        }
+    }
+}
+
+impl<'a, Backend: OmFileWriterBackend> OmFileWriterArray<'a, f32, Backend> {
+    /// Apply `filter` to all values before they are quantized. Only the int16
+    /// codecs quantize values, other compression types are rejected. The
+    /// filter identifier is stored as child variable, so the reader can
+    /// invert it with `OmFileReader::read_dequantized`.
+    pub fn with_quantization_filter(
+        mut self,
+        filter: impl QuantizationFilter + 'a,
+    ) -> Result<Self, OmFilesRsError> {
+        match self.compression {
+            CompressionType::PforDelta2dInt16 | CompressionType::PforDelta2dInt16Logarithmic => {}
+            _ => return Err(OmFilesRsError::InvalidCompressionType),
+        }
+        self.quantization_filter = Some(AppliedFilter {
+            id: filter.id(),
+            forward: Box::new(move |values: &[f32]| {
+                values.iter().map(|&value| filter.forward(value)).collect()
+            }),
+        });
+        Ok(self)
+    }
+}
+
+impl<'a, Backend: OmFileWriterBackend> OmFileWriterArray<'a, f64, Backend> {
+    /// Store `value * scale_factor + add_offset` computed in double precision.
+    /// `PforDelta2d` rounds the result to 64 bit integers, so the precision is
+    /// `1 / scale_factor` even for large values. Arrays store scale and offset
+    /// as `f32`, therefore the array must be prepared with scale factor 1 and
+    /// offset 0. The parameters are stored as quantization filter and
+    /// inverted by `OmFileReader::read_dequantized_f64`.
+    pub fn with_double_scale(
+        mut self,
+        scale_factor: f64,
+        add_offset: f64,
+    ) -> Result<Self, OmFilesRsError> {
+        if self.compression != CompressionType::PforDelta2d
+            || self.scale_factor != 1.0
+            || self.add_offset != 0.0
+        {
+            return Err(OmFilesRsError::InvalidCompressionType);
+        }
+        let filter = DoubleLinearFilter {
+            scale: scale_factor,
+            offset: add_offset,
+        };
+        self.quantization_filter = Some(AppliedFilter {
+            id: filter.id(),
+            forward: Box::new(move |values: &[f64]| {
+                values.iter().map(|&value| filter.forward(value)).collect()
+            }),
+        });
+        Ok(self)
+    }
+}
+
+/// Writes an array of variable-length UTF-8 strings.
+///
+/// Strings are stored uncompressed as one contiguous block of bytes in
+/// row-major order. Instead of a compressed LUT, the variable references a
+/// table of `n + 1` little-endian u64 file offsets marking the start of each
+/// string and the end of the last one.
+pub struct OmFileWriterStringArray<'a, Backend: OmFileWriterBackend> {
+    dimensions: Vec<u64>,
+    offsets: Vec<u64>,
+    buffer: &'a mut OmBufferedWriter<Backend>,
+}
+
+impl<'a, Backend: OmFileWriterBackend> OmFileWriterStringArray<'a, Backend> {
+    pub fn new(dimensions: Vec<u64>, buffer: &'a mut OmBufferedWriter<Backend>) -> Self {
+        let offsets = vec![buffer.total_bytes_written as u64];
+        Self {
+            dimensions,
+            offsets,
+            buffer,
         }
     }
+
+    /// Append strings in row-major order. Can be called multiple times, e.g.
+    /// with one slice along the first dimension per call.
+    pub fn write_data<S: AsRef<str>>(
+        &mut self,
+        array: ArrayViewD<S>,
+    ) -> Result<(), OmFilesRsError> {
+        let total: u64 = self.dimensions.iter().product();
+        let written = self.offsets.len() as u64 - 1;
+        if written + array.len() as u64 > total {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+        for value in array.iter() {
+            let bytes = value.as_ref().as_bytes();
+            self.buffer.reallocate(bytes.len())?;
+            self.buffer.buffer_at_write_position()[..bytes.len()].copy_from_slice(bytes);
+            self.buffer.increment_write_position(bytes.len());
+            self.offsets.push(self.buffer.total_bytes_written as u64);
+        }
+        Ok(())
+    }
+
+    /// Write the offset table and return the finalized struct.
+    pub fn finalize(self) -> Result<OmFileWriterArrayFinalized, OmFilesRsError> {
+        let total: u64 = self.dimensions.iter().product();
+        if self.offsets.len() as u64 - 1 != total {
+            return Err(OmFilesRsError::ChunkHasWrongNumberOfElements);
+        }
+
+        self.buffer.align_to_64_bytes()?;
+        let lut_offset = self.buffer.total_bytes_written as u64;
+        let lut_size = self.offsets.len() * std::mem::size_of::<u64>();
+        self.buffer.reallocate(lut_size)?;
+        let destination = self.buffer.buffer_at_write_position();
+        for (i, offset) in self.offsets.iter().enumerate() {
+            destination[i * 8..(i + 1) * 8].copy_from_slice(&offset.to_le_bytes());
+        }
+        self.buffer.increment_write_position(lut_size);
+
+        Ok(OmFileWriterArrayFinalized {
+            scale_factor: 1.0,
+            add_offset: 0.0,
+            compression: CompressionType::None,
+            data_type: DataType::StringArray,
+            chunks: self.dimensions.clone(),
+            dimensions: self.dimensions,
+            lut_size: lut_size as u64,
+            lut_offset,
+            dimension_names: None,
+            fill_value: None,
+            statistics: None,
+            chunk_statistics: None,
+            saturation: None,
+            quantization_filter: None,
+            time_axis: None,
+            grid: None,
+        })
+    }
 }
 
+/// Layout and metadata of a written array, passed to `OmFileWriter::write_array`.
+/// More optional metadata may be added, so it can only be created by the
+/// array writers of this crate.
+#[non_exhaustive]
 pub struct OmFileWriterArrayFinalized {
     pub scale_factor: f32,
     pub add_offset: f32,
@@ -415,4 +1660,145 @@ pub struct OmFileWriterArrayFinalized {
     pub chunks: Vec<u64>,
     pub lut_size: u64,
     pub lut_offset: u64,
+    /// Optional names of all dimensions, stored as child variable
+    pub dimension_names: Option<Vec<String>>,
+    /// Optional fill value, stored as child variable
+    pub fill_value: Option<FillValue>,
+    /// Optional statistics of all values, stored as child variables
+    pub statistics: Option<ArrayStatistics>,
+    /// Optional minimum and maximum of every chunk, stored as child arrays
+    pub chunk_statistics: Option<ChunkStatistics>,
+    /// Saturated values per chunk if checked, not stored in the file
+    pub saturation: Option<SaturationStatistics>,
+    /// Optional identifier of the quantization filter, stored as child variable
+    pub quantization_filter: Option<String>,
+    /// Optional time axis, stored as child variables
+    pub time_axis: Option<TimeAxis>,
+    /// Optional grid definition, stored as child variables
+    pub grid: Option<GridDefinition>,
+}
+
+/// Fill value of an array, kept as scalar of the array element type
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillValue {
+    pub(crate) data_type: DataType,
+    pub(crate) bytes: Vec<u8>,
+}
+
+/// Compress `look_up_table` to the output buffer and return its size
+pub(crate) fn write_lut<Backend: OmFileWriterBackend>(
+    buffer: &mut OmBufferedWriter<Backend>,
+    look_up_table: &[u64],
+) -> Result<u64, OmFilesRsError> {
+    let buffer_size =
+        unsafe { om_encoder_lut_buffer_size(look_up_table.as_ptr(), look_up_table.len() as u64) };
+    buffer.reallocate(buffer_size as usize)?;
+
+    let compressed_lut_size = unsafe {
+        om_encoder_compress_lut(
+            look_up_table.as_ptr(),
+            look_up_table.len() as u64,
+            buffer.buffer_at_write_position().as_mut_ptr(),
+            buffer_size,
+        )
+    };
+    buffer.increment_write_position(compressed_lut_size as usize);
+    Ok(compressed_lut_size)
+}
+
+/// Validate the array layout and initialize an encoder for it. The encoder
+/// points to `dimensions` and `chunks`, which must outlive it.
+pub(crate) fn init_encoder(
+    dimensions: &[u64],
+    chunks: &[u64],
+    compression: CompressionType,
+    data_type: DataType,
+    scale_factor: f32,
+    add_offset: f32,
+) -> Result<OmEncoder_t, OmFilesRsError> {
+    if dimensions.len() != chunks.len() {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    validate_dimensions(dimensions, chunks)?;
+    if data_type == DataType::StringArray {
+        return Err(OmFilesRsError::InvalidDataType
+            .context("string arrays are written with prepare_string_array"));
+    }
+    // Doubles would silently lose most of their precision as int16
+    if data_type == DataType::DoubleArray
+        && matches!(
+            compression,
+            CompressionType::PforDelta2dInt16 | CompressionType::PforDelta2dInt16Logarithmic
+        )
+    {
+        return Err(OmFilesRsError::InvalidCompressionType);
+    }
+
+    let mut encoder = unsafe { create_uninit_encoder() };
+    let error = unsafe {
+        om_encoder_init(
+            &mut encoder,
+            scale_factor,
+            add_offset,
+            compression.to_c(),
+            data_type.to_c(),
+            dimensions.as_ptr(),
+            chunks.as_ptr(),
+            dimensions.len() as u64,
+        )
+    };
+    if error != OmError_t_ERROR_OK {
+        return Err(OmFilesRsError::EncoderError(c_error_string(error)));
+    }
+    Ok(encoder)
+}
+
+/// Check dimensions and chunks before they reach the encoder
+fn validate_dimensions(dimensions: &[u64], chunks: &[u64]) -> Result<(), OmFilesRsError> {
+    if dimensions.len() > MAX_DIMENSIONS {
+        return Err(OmFilesRsError::TooManyDimensions {
+            count: dimensions.len(),
+            max: MAX_DIMENSIONS,
+        });
+    }
+    for (index, (&dimension, &chunk)) in dimensions.iter().zip(chunks).enumerate() {
+        if dimension == 0 || chunk == 0 {
+            return Err(OmFilesRsError::ZeroDimension { index });
+        }
+        if chunk > dimension {
+            return Err(OmFilesRsError::ChunkExceedsDimension {
+                index,
+                chunk,
+                dimension,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Region of `array` that is compressed into chunk `chunk_index`, given as
+/// chunk number `chunk_offset` within the written part of the array.
+pub(crate) fn chunk_region(
+    dimensions: &[u64],
+    chunks: &[u64],
+    array_offset: &[u64],
+    array_count: &[u64],
+    chunk_index: u64,
+    chunk_offset: u64,
+) -> Vec<Range<usize>> {
+    let mut global = chunk_index;
+    let mut local = chunk_offset;
+    let mut region = vec![0..0; dimensions.len()];
+    for i in (0..dimensions.len()).rev() {
+        let chunks_global = divide_rounded_up(dimensions[i] as usize, chunks[i] as usize) as u64;
+        let chunks_local = divide_rounded_up(array_count[i] as usize, chunks[i] as usize) as u64;
+        let global_position = global % chunks_global;
+        let local_position = local % chunks_local;
+        global /= chunks_global;
+        local /= chunks_local;
+        let length = chunks[i].min(dimensions[i] - global_position * chunks[i]);
+        let start = array_offset[i] + local_position * chunks[i];
+        region[i] = start as usize..(start + length) as usize;
+    }
+    region
 }
@@ -3,8 +3,47 @@
 //! This library provides functionality for reading and writing Om file format.
 //!
 pub mod io {
+    #[cfg(feature = "arrow")]
+    pub mod arrow;
+    pub(crate) mod batch_reader;
+    pub mod bench;
     pub mod buffered_writer;
+    pub mod builder;
+    pub mod cancellation;
+    pub mod checkpoint;
+    pub(crate) mod chunk_cache;
+    pub mod chunking;
+    pub mod compare;
+    pub mod copy;
+    pub mod derive;
+    pub mod describe;
+    pub mod dynamic;
+    pub mod export;
+    pub mod file_manager;
+    pub mod geo;
+    pub mod grib;
+    pub mod histogram;
+    pub(crate) mod lut_cache;
+    pub mod multi_file_reader;
+    pub mod npy;
+    pub mod overview;
+    pub mod parallel_writer;
+    pub mod parse;
+    pub mod prefetch;
+    pub mod progress;
+    pub mod quantization;
+    pub mod read_stats;
     pub mod reader;
+    pub mod reader_async;
+    pub mod statistics;
+    pub mod tile;
+    pub mod time;
+    pub(crate) mod variable;
+    pub mod variable_slice;
+    pub(crate) mod verify;
+    pub mod visitor;
+    #[cfg(feature = "notify")]
+    pub mod watcher;
     pub mod writer;
 }
 
@@ -12,13 +51,44 @@ pub mod core {
     pub mod c_defaults;
     pub mod compression;
     pub mod data_types;
+    pub(crate) mod variable_metadata;
 }
 
 pub mod backend {
+    #[cfg(feature = "archive")]
+    pub mod archive;
     pub mod backends;
+    pub mod cached_backend;
+    #[cfg(all(target_os = "linux", feature = "direct_io"))]
+    pub mod direct_io;
+    #[cfg(feature = "encryption")]
+    pub mod encrypted;
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    pub mod fetch;
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    pub mod io_uring;
+    pub mod metrics;
     pub mod mmapfile;
+    pub mod pread;
+    pub mod retry;
+    #[cfg(feature = "tokio")]
+    pub mod tokio_file;
 }
 
+#[cfg(feature = "capi")]
+pub mod capi;
+
 pub mod errors;
 
+#[cfg(feature = "python")]
+pub mod python;
+
+pub mod service;
+
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+#[cfg(feature = "viz")]
+pub mod viz;
+
 mod utils;
@@ -0,0 +1,287 @@
+//! Python bindings, enabled with the `python` feature. The module is called
+//! `omfiles` and can be built with `maturin build --features python`.
+//!
+//! ```python
+//! import numpy as np
+//! import omfiles
+//!
+//! writer = omfiles.OmFileWriter("data.om")
+//! variable = writer.write_array(np.zeros((10, 20), dtype=np.float32), [5, 5], name="temperature")
+//! writer.close(variable)
+//!
+//! reader = omfiles.OmFileReader("data.om")
+//! data = reader.read([(0, 10), (0, 5)])
+//! ```
+
+use crate::backend::mmapfile::MmapFile;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::{ErrorKind, OmFilesRsError};
+use crate::io::reader::OmFileReader;
+use crate::io::writer::{OmFileWriter, OmOffsetSize};
+use ndarray::ArrayViewD;
+use numpy::{
+    IntoPyArray, PyArrayDescrMethods, PyArrayDyn, PyArrayMethods, PyUntypedArray,
+    PyUntypedArrayMethods,
+};
+use pyo3::exceptions::{PyIOError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::ops::Range;
+
+impl From<OmFilesRsError> for PyErr {
+    fn from(error: OmFilesRsError) -> Self {
+        match (error.kind(), error.root_cause()) {
+            (ErrorKind::Io, _) => PyIOError::new_err(error.to_string()),
+            (_, OmFilesRsError::InvalidDataType) => PyTypeError::new_err(error.to_string()),
+            _ => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+fn compression_from_name(name: &str) -> PyResult<CompressionType> {
+    match name {
+        "pfor_delta_2d_int16" => Ok(CompressionType::PforDelta2dInt16),
+        "fpx_xor_2d" => Ok(CompressionType::FpxXor2d),
+        "pfor_delta_2d" => Ok(CompressionType::PforDelta2d),
+        "pfor_delta_2d_int16_logarithmic" => Ok(CompressionType::PforDelta2dInt16Logarithmic),
+        "none" => Ok(CompressionType::None),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown compression '{}'",
+            name
+        ))),
+    }
+}
+
+/// Name of the numpy dtype for an array data type
+fn dtype_name(data_type: DataType) -> Option<&'static str> {
+    match data_type {
+        DataType::Int8Array => Some("int8"),
+        DataType::Uint8Array => Some("uint8"),
+        DataType::Int16Array => Some("int16"),
+        DataType::Uint16Array => Some("uint16"),
+        DataType::Int32Array => Some("int32"),
+        DataType::Uint32Array => Some("uint32"),
+        DataType::Int64Array => Some("int64"),
+        DataType::Uint64Array => Some("uint64"),
+        DataType::FloatArray => Some("float32"),
+        DataType::DoubleArray => Some("float64"),
+        _ => None,
+    }
+}
+
+/// Reader for om files. Opens the file with mmap.
+#[pyclass(name = "OmFileReader", unsendable)]
+pub struct PyOmFileReader {
+    reader: OmFileReader<MmapFile>,
+}
+
+#[pymethods]
+impl PyOmFileReader {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        Ok(Self {
+            reader: OmFileReader::from_file(path)?,
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.reader.get_name()
+    }
+
+    #[getter]
+    fn shape(&self) -> Vec<u64> {
+        self.reader.get_dimensions().to_vec()
+    }
+
+    #[getter]
+    fn chunks(&self) -> Vec<u64> {
+        self.reader.get_chunk_dimensions().to_vec()
+    }
+
+    /// Numpy dtype name of the array, `None` for scalars and strings
+    #[getter]
+    fn dtype(&self) -> Option<&'static str> {
+        dtype_name(self.reader.data_type())
+    }
+
+    fn number_of_children(&self) -> u32 {
+        self.reader.number_of_children()
+    }
+
+    fn get_child(&self, index: u32) -> Option<Self> {
+        Some(Self {
+            reader: self.reader.get_child(index)?,
+        })
+    }
+
+    /// Read `(start, end)` ranges for all dimensions into a numpy array
+    fn read<'py>(&self, py: Python<'py>, ranges: Vec<(u64, u64)>) -> PyResult<Bound<'py, PyAny>> {
+        let dim_read: Vec<Range<u64>> = ranges.iter().map(|&(start, end)| start..end).collect();
+        let array = match self.reader.data_type() {
+            DataType::Int8Array => self.read_array::<i8>(py, &dim_read)?,
+            DataType::Uint8Array => self.read_array::<u8>(py, &dim_read)?,
+            DataType::Int16Array => self.read_array::<i16>(py, &dim_read)?,
+            DataType::Uint16Array => self.read_array::<u16>(py, &dim_read)?,
+            DataType::Int32Array => self.read_array::<i32>(py, &dim_read)?,
+            DataType::Uint32Array => self.read_array::<u32>(py, &dim_read)?,
+            DataType::Int64Array => self.read_array::<i64>(py, &dim_read)?,
+            DataType::Uint64Array => self.read_array::<u64>(py, &dim_read)?,
+            DataType::FloatArray => self.read_array::<f32>(py, &dim_read)?,
+            DataType::DoubleArray => self.read_array::<f64>(py, &dim_read)?,
+            _ => return Err(OmFilesRsError::InvalidDataType.into()),
+        };
+        Ok(array)
+    }
+}
+
+impl PyOmFileReader {
+    fn read_array<'py, T>(
+        &self,
+        py: Python<'py>,
+        dim_read: &[Range<u64>],
+    ) -> PyResult<Bound<'py, PyAny>>
+    where
+        T: OmFileArrayDataType + numpy::Element + Clone + Default,
+    {
+        // Release the GIL while decoding, so other Python threads can run
+        let data = py.detach(|| self.reader.read::<T>(dim_read, None, None))?;
+        Ok(data.into_pyarray(py).into_any())
+    }
+}
+
+/// Location of a written variable, used as child or root variable.
+#[pyclass(name = "OmOffsetSize", frozen)]
+#[derive(Clone)]
+pub struct PyOmOffsetSize {
+    #[pyo3(get)]
+    offset: u64,
+    #[pyo3(get)]
+    size: u64,
+}
+
+impl From<OmOffsetSize> for PyOmOffsetSize {
+    fn from(value: OmOffsetSize) -> Self {
+        Self {
+            offset: value.offset,
+            size: value.size,
+        }
+    }
+}
+
+/// Writer for om files. Variables are written one after another, `close`
+/// writes the trailer with the root variable.
+#[pyclass(name = "OmFileWriter", unsendable)]
+pub struct PyOmFileWriter {
+    writer: Option<OmFileWriter<File>>,
+}
+
+#[pymethods]
+impl PyOmFileWriter {
+    #[new]
+    fn new(path: &str) -> PyResult<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            writer: Some(OmFileWriter::new(file, 8 * 1024)),
+        })
+    }
+
+    /// Write a numpy array with the given chunk dimensions. The data type of
+    /// the file is taken from the dtype of the array. Without `compression`,
+    /// floats are compressed losslessly with `fpx_xor_2d` and integers with
+    /// `pfor_delta_2d`.
+    #[pyo3(signature = (data, chunks, name = "data", compression = None, scale_factor = 1.0, add_offset = 0.0, children = Vec::new()))]
+    #[allow(clippy::too_many_arguments)]
+    fn write_array(
+        &mut self,
+        data: &Bound<'_, PyUntypedArray>,
+        chunks: Vec<u64>,
+        name: &str,
+        compression: Option<&str>,
+        scale_factor: f32,
+        add_offset: f32,
+        children: Vec<PyOmOffsetSize>,
+    ) -> PyResult<PyOmOffsetSize> {
+        let compression = match compression {
+            Some(name) => compression_from_name(name)?,
+            None if data.dtype().kind() == b'f' => CompressionType::FpxXor2d,
+            None => CompressionType::PforDelta2d,
+        };
+        let children: Vec<OmOffsetSize> = children
+            .iter()
+            .map(|c| OmOffsetSize::new(c.offset, c.size))
+            .collect();
+        let writer = self.writer()?;
+
+        macro_rules! write_typed {
+            ($($t:ty),*) => {
+                $(
+                    if let Ok(array) = data.cast::<PyArrayDyn<$t>>() {
+                        let array = array.readonly();
+                        return write_array(
+                            writer,
+                            array.as_array(),
+                            chunks,
+                            name,
+                            compression,
+                            scale_factor,
+                            add_offset,
+                            &children,
+                        );
+                    }
+                )*
+            };
+        }
+        write_typed!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+        Err(PyTypeError::new_err(format!(
+            "Unsupported dtype {}",
+            data.dtype()
+        )))
+    }
+
+    /// Write the trailer with the root variable and close the file
+    fn close(&mut self, root: PyOmOffsetSize) -> PyResult<()> {
+        let mut writer = self
+            .writer
+            .take()
+            .ok_or_else(|| PyValueError::new_err("Writer is already closed"))?;
+        writer.write_trailer(OmOffsetSize::new(root.offset, root.size))?;
+        Ok(())
+    }
+}
+
+impl PyOmFileWriter {
+    fn writer(&mut self) -> PyResult<&mut OmFileWriter<File>> {
+        self.writer
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("Writer is already closed"))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_array<T: OmFileArrayDataType + Clone>(
+    writer: &mut OmFileWriter<File>,
+    data: ArrayViewD<T>,
+    chunks: Vec<u64>,
+    name: &str,
+    compression: CompressionType,
+    scale_factor: f32,
+    add_offset: f32,
+    children: &[OmOffsetSize],
+) -> PyResult<PyOmOffsetSize> {
+    let dimensions = data.shape().iter().map(|&x| x as u64).collect();
+    let mut array_writer =
+        writer.prepare_array::<T>(dimensions, chunks, compression, scale_factor, add_offset)?;
+    array_writer.write_data(data.as_standard_layout().view(), None, None)?;
+    let variable_meta = array_writer.finalize();
+    Ok(writer.write_array(variable_meta, name, children)?.into())
+}
+
+#[pymodule]
+fn omfiles(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyOmFileReader>()?;
+    m.add_class::<PyOmFileWriter>()?;
+    m.add_class::<PyOmOffsetSize>()?;
+    Ok(())
+}
@@ -0,0 +1,344 @@
+//! Building blocks of services that extract selections of variables, e.g.
+//! forecast APIs over HTTP. Nothing here depends on a web framework: a
+//! handler parses the query into an `ExtractRequest`, calls `extract` and
+//! sends the body with the content type of the `ExtractResponse`. Errors are
+//! mapped to HTTP status codes with `status_code`.
+//!
+//! ```ignore
+//! let request = ExtractRequest::from_query("variable=temperature_2m&ranges=0:10,5&format=npy")?;
+//! let response = extract(&root, &request, 10_000_000)?;
+//! ```
+
+use crate::backend::backends::OmFileReaderBackend;
+use crate::errors::{ErrorKind, OmFilesRsError};
+use crate::io::dynamic::OmArray;
+use crate::io::npy::to_npy_bytes;
+use crate::io::reader::OmFileReader;
+use ndarray::{ArrayD, ArrayViewD};
+use num_traits::Float;
+use std::fmt::{Display, Write};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Encoding of the extracted values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// `{"shape": [...], "data": [...]}` with nested arrays in row-major
+    /// order. NaN and infinite values are `null`.
+    #[default]
+    Json,
+    /// Values in row-major order as little-endian bytes, without shape
+    Raw,
+    /// NumPy `.npy` file
+    Npy,
+}
+
+impl OutputFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "application/json",
+            OutputFormat::Raw => "application/octet-stream",
+            OutputFormat::Npy => "application/x-npy",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = OmFilesRsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "raw" => Ok(OutputFormat::Raw),
+            "npy" => Ok(OutputFormat::Npy),
+            _ => Err(OmFilesRsError::InvalidRequest(format!(
+                "unknown format '{}', expected json, raw or npy",
+                s
+            ))),
+        }
+    }
+}
+
+/// Selection of one variable of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractRequest {
+    /// Path of the variable below the root variable with names separated by
+    /// `/`. Children without a name are called `#<index>`. Empty for the
+    /// root variable.
+    pub variable: String,
+    /// Range of each dimension. `None` selects the whole variable.
+    pub ranges: Option<Vec<Range<u64>>>,
+    pub format: OutputFormat,
+}
+
+impl ExtractRequest {
+    /// Parse a URL query string with the keys `variable`, `ranges` and
+    /// `format`, e.g. `variable=temperature_2m&ranges=0:10,5&format=npy`.
+    /// Keys and values are percent-decoded. See `parse_ranges` for ranges.
+    pub fn from_query(query: &str) -> Result<Self, OmFilesRsError> {
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Ok((percent_decode(key)?, percent_decode(value)?))
+            })
+            .collect::<Result<Vec<_>, OmFilesRsError>>()?;
+        Self::from_params(params.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+    }
+
+    /// Like `from_query`, but with keys and values that were already decoded
+    /// by a web framework. Unknown keys are rejected.
+    pub fn from_params<'a>(
+        params: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, OmFilesRsError> {
+        let mut request = ExtractRequest::default();
+        for (key, value) in params {
+            match key {
+                "variable" => request.variable = value.to_string(),
+                "ranges" => request.ranges = Some(parse_ranges(value)?),
+                "format" => request.format = value.parse()?,
+                _ => {
+                    return Err(OmFilesRsError::InvalidRequest(format!(
+                        "unknown parameter '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// Parse comma separated ranges of all dimensions. Each range is either
+/// `start:end` with an exclusive end or a single index, e.g. `0:10,5`.
+pub fn parse_ranges(ranges: &str) -> Result<Vec<Range<u64>>, OmFilesRsError> {
+    let invalid =
+        |range: &str| OmFilesRsError::InvalidRequest(format!("invalid range '{}'", range));
+    ranges
+        .split(',')
+        .map(|range| {
+            let parse = |value: &str| value.trim().parse::<u64>().map_err(|_| invalid(range));
+            match range.split_once(':') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        return Err(invalid(range));
+                    }
+                    Ok(start..end)
+                }
+                None => {
+                    let index = parse(range)?;
+                    Ok(index..index.checked_add(1).ok_or_else(|| invalid(range))?)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Result of `extract`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractResponse {
+    pub content_type: &'static str,
+    /// Shape of the extracted selection
+    pub shape: Vec<u64>,
+    pub body: Vec<u8>,
+}
+
+/// Variable at `path` below `root`, see `ExtractRequest::variable`
+pub fn find_variable<Backend: OmFileReaderBackend>(
+    root: &OmFileReader<Backend>,
+    path: &str,
+) -> Result<OmFileReader<Backend>, OmFilesRsError> {
+    let mut variable = root.clone();
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        let mut found = None;
+        for index in 0..variable.number_of_children() {
+            let Some(child) = variable.get_child(index) else {
+                continue;
+            };
+            match child.get_name() {
+                Some(child_name) if child_name == name => {
+                    found = Some(child);
+                    break;
+                }
+                None if name == format!("#{}", index) => found = Some(child),
+                _ => {}
+            }
+        }
+        variable = found.ok_or_else(|| OmFilesRsError::VariableNotFound(path.to_string()))?;
+    }
+    Ok(variable)
+}
+
+/// Read the selection of `request` from the variable below `root` and
+/// encode it. Selections of more than `max_elements` elements are rejected
+/// before any data is read.
+pub fn extract<Backend: OmFileReaderBackend>(
+    root: &OmFileReader<Backend>,
+    request: &ExtractRequest,
+    max_elements: u64,
+) -> Result<ExtractResponse, OmFilesRsError> {
+    let reader = find_variable(root, &request.variable)?;
+    if !reader.data_type().is_array() {
+        return Err(OmFilesRsError::InvalidDataType
+            .context(format!("variable '{}' is not an array", request.variable)));
+    }
+    let dimensions = reader.get_dimensions();
+    let ranges = match &request.ranges {
+        Some(ranges) => ranges.clone(),
+        None => dimensions.iter().map(|&n| 0..n).collect(),
+    };
+    if ranges.len() != dimensions.len() {
+        return Err(OmFilesRsError::MismatchingCubeDimensionLength);
+    }
+    for (range, &dimension) in ranges.iter().zip(dimensions) {
+        if range.start > range.end || range.end > dimension {
+            return Err(OmFilesRsError::DimensionOutOfBounds {
+                range: range.start as usize..range.end as usize,
+                allowed: dimension as usize,
+            });
+        }
+    }
+    let shape: Vec<u64> = ranges.iter().map(|range| range.end - range.start).collect();
+    let elements = shape
+        .iter()
+        .try_fold(1u64, |product, &n| product.checked_mul(n))
+        .unwrap_or(u64::MAX);
+    if elements > max_elements {
+        return Err(OmFilesRsError::SelectionTooLarge {
+            elements,
+            max: max_elements,
+        });
+    }
+
+    let array = reader.read_dynamic(&ranges)?;
+    let body = match request.format {
+        OutputFormat::Json => to_json(&array).into_bytes(),
+        OutputFormat::Raw => array.to_le_bytes().ok_or_else(|| {
+            OmFilesRsError::InvalidDataType.context("strings cannot be encoded as raw bytes")
+        })?,
+        OutputFormat::Npy => to_npy_bytes(&array)?,
+    };
+    Ok(ExtractResponse {
+        content_type: request.format.content_type(),
+        shape,
+        body,
+    })
+}
+
+/// HTTP status code that corresponds to `error`
+pub fn status_code(error: &OmFilesRsError) -> u16 {
+    match error.root_cause() {
+        OmFilesRsError::SelectionTooLarge { .. } => return 413,
+        OmFilesRsError::RequestTimeout { .. } => return 504,
+        _ => {}
+    }
+    match error.kind() {
+        ErrorKind::InvalidArgument => 400,
+        ErrorKind::NotFound => 404,
+        ErrorKind::Unsupported => 501,
+        // Client closed request, as used by nginx
+        ErrorKind::Cancelled => 499,
+        ErrorKind::Io | ErrorKind::Format | ErrorKind::Decoder | ErrorKind::Encoder => 500,
+    }
+}
+
+fn to_json(array: &OmArray) -> String {
+    let shape: Vec<String> = array.shape().iter().map(|n| n.to_string()).collect();
+    let mut out = format!("{{\"shape\":[{}],\"data\":", shape.join(","));
+    match array {
+        OmArray::I8(a) => json_values(a, &mut out, &json_integer),
+        OmArray::U8(a) => json_values(a, &mut out, &json_integer),
+        OmArray::I16(a) => json_values(a, &mut out, &json_integer),
+        OmArray::U16(a) => json_values(a, &mut out, &json_integer),
+        OmArray::I32(a) => json_values(a, &mut out, &json_integer),
+        OmArray::U32(a) => json_values(a, &mut out, &json_integer),
+        OmArray::I64(a) => json_values(a, &mut out, &json_integer),
+        OmArray::U64(a) => json_values(a, &mut out, &json_integer),
+        OmArray::F32(a) => json_values(a, &mut out, &json_float),
+        OmArray::F64(a) => json_values(a, &mut out, &json_float),
+        OmArray::String(a) => json_values(a, &mut out, &json_string),
+    }
+    out.push('}');
+    out
+}
+
+/// Nested JSON arrays with one level per dimension
+fn json_values<T>(array: &ArrayD<T>, out: &mut String, value: &impl Fn(&T, &mut String)) {
+    fn nested<T>(view: ArrayViewD<T>, out: &mut String, value: &impl Fn(&T, &mut String)) {
+        if view.ndim() == 0 {
+            if let Some(v) = view.first() {
+                value(v, out);
+            }
+            return;
+        }
+        out.push('[');
+        for (i, row) in view.outer_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            nested(row, out, value);
+        }
+        out.push(']');
+    }
+    nested(array.view(), out, value)
+}
+
+fn json_integer<T: Display>(value: &T, out: &mut String) {
+    write!(out, "{}", value).expect("writing to a string cannot fail");
+}
+
+/// JSON has no representation of NaN and infinity
+fn json_float<T: Float + Display>(value: &T, out: &mut String) {
+    if value.is_finite() {
+        json_integer(value, out);
+    } else {
+        out.push_str("null");
+    }
+}
+
+fn json_string(value: &String, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a string cannot fail")
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Decode `%XX` escapes and `+` as space
+fn percent_decode(value: &str) -> Result<String, OmFilesRsError> {
+    let invalid = || OmFilesRsError::InvalidRequest(format!("invalid escape in '{}'", value));
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).ok_or_else(invalid)?;
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return Err(invalid());
+                }
+                let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
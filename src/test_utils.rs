@@ -0,0 +1,234 @@
+//! Helpers to property-test pipelines that write and read om files, e.g. in
+//! downstream crates. Random specs are generated from a seed, so a failing
+//! case can be reproduced from the seed alone.
+//!
+//! ```ignore
+//! for seed in 0..100 {
+//!     let mut rng = TestRng::new(seed);
+//!     let spec = RoundtripSpec::random::<f32>(&mut rng);
+//!     let data = random_array::<f32>(&mut rng, &spec);
+//!     roundtrip_check(data.view(), &spec).unwrap();
+//! }
+//! ```
+
+use crate::backend::backends::InMemoryBackend;
+use crate::core::compression::CompressionType;
+use crate::core::data_types::{DataType, OmFileArrayDataType};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use crate::io::writer::OmFileWriter;
+use ndarray::{ArrayD, ArrayViewD, Dimension};
+use num_traits::{NumCast, ToPrimitive};
+use std::sync::Arc;
+
+/// Small deterministic random number generator (SplitMix64). It is not
+/// suitable for anything but tests.
+#[derive(Debug, Clone)]
+pub struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in `min..=max`
+    pub fn range(&mut self, min: u64, max: u64) -> u64 {
+        assert!(min <= max, "min must not be larger than max");
+        match (max - min).checked_add(1) {
+            Some(count) => min + self.next_u64() % count,
+            None => self.next_u64(),
+        }
+    }
+
+    /// Uniformly distributed in `[0, 1)`
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn choose<'a, T>(&mut self, values: &'a [T]) -> &'a T {
+        &values[self.range(0, values.len() as u64 - 1) as usize]
+    }
+}
+
+/// Codecs that can be used for arrays of `data_type`
+pub fn compatible_compressions(data_type: DataType) -> &'static [CompressionType] {
+    match data_type {
+        DataType::FloatArray => &[
+            CompressionType::PforDelta2dInt16,
+            CompressionType::FpxXor2d,
+            CompressionType::PforDelta2d,
+            CompressionType::PforDelta2dInt16Logarithmic,
+            CompressionType::None,
+        ],
+        DataType::DoubleArray => &[
+            CompressionType::FpxXor2d,
+            CompressionType::PforDelta2d,
+            CompressionType::None,
+        ],
+        DataType::Int8Array
+        | DataType::Uint8Array
+        | DataType::Int16Array
+        | DataType::Uint16Array
+        | DataType::Int32Array
+        | DataType::Uint32Array
+        | DataType::Int64Array
+        | DataType::Uint64Array => &[CompressionType::PforDelta2d, CompressionType::None],
+        _ => &[],
+    }
+}
+
+/// Layout and codec of an array written by `roundtrip_check`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundtripSpec {
+    pub dimensions: Vec<u64>,
+    pub chunks: Vec<u64>,
+    pub compression: CompressionType,
+    pub scale_factor: f32,
+    pub add_offset: f32,
+}
+
+impl RoundtripSpec {
+    /// Random spec for arrays of `T` with up to 4 dimensions of up to 24
+    /// elements each
+    pub fn random<T: OmFileArrayDataType>(rng: &mut TestRng) -> Self {
+        Self::random_with_limits::<T>(rng, 4, 24)
+    }
+
+    pub fn random_with_limits<T: OmFileArrayDataType>(
+        rng: &mut TestRng,
+        max_dimensions: usize,
+        max_length: u64,
+    ) -> Self {
+        let count = rng.range(1, max_dimensions as u64) as usize;
+        let dimensions: Vec<u64> = (0..count).map(|_| rng.range(1, max_length)).collect();
+        let chunks = dimensions
+            .iter()
+            .map(|&dimension| rng.range(1, dimension))
+            .collect();
+        let compression = *rng.choose(compatible_compressions(T::DATA_TYPE_ARRAY));
+        Self {
+            dimensions,
+            chunks,
+            compression,
+            scale_factor: 100.0,
+            add_offset: 0.0,
+        }
+    }
+
+    /// Largest difference between written and read values that is expected
+    /// from quantization. Lossless codecs return 0.
+    pub fn tolerance(&self, data_type: DataType) -> f64 {
+        let quantized = match self.compression {
+            CompressionType::PforDelta2dInt16 | CompressionType::PforDelta2dInt16Logarithmic => {
+                true
+            }
+            CompressionType::PforDelta2d => {
+                matches!(data_type, DataType::FloatArray | DataType::DoubleArray)
+            }
+            CompressionType::FpxXor2d | CompressionType::None => false,
+        };
+        if quantized {
+            1.0 / self.scale_factor as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Random values for `spec` between -100 and 100. They stay within the range
+/// of the int16 codecs and are positive for unsigned types and logarithmic
+/// codecs.
+pub fn random_array<T: OmFileArrayDataType + NumCast>(
+    rng: &mut TestRng,
+    spec: &RoundtripSpec,
+) -> ArrayD<T> {
+    let shape: Vec<usize> = spec.dimensions.iter().map(|&x| x as usize).collect();
+    let float = matches!(
+        T::DATA_TYPE_ARRAY,
+        DataType::FloatArray | DataType::DoubleArray
+    );
+    let signed = matches!(
+        T::DATA_TYPE_ARRAY,
+        DataType::Int8Array
+            | DataType::Int16Array
+            | DataType::Int32Array
+            | DataType::Int64Array
+            | DataType::FloatArray
+            | DataType::DoubleArray
+    ) && spec.compression != CompressionType::PforDelta2dInt16Logarithmic;
+    // Fits into int16 for scale factors up to 300
+    let max = 100.0;
+    ArrayD::from_shape_simple_fn(shape, || {
+        let value = if signed {
+            (rng.unit() * 2.0 - 1.0) * max
+        } else {
+            rng.unit() * max
+        };
+        let value = if float { value } else { value.round() };
+        T::from(value).expect("random value fits into the data type")
+    })
+}
+
+/// Write `data` with `spec` to memory, read it back and compare all values
+/// within `RoundtripSpec::tolerance`. Errors of the writer or reader are
+/// returned.
+///
+/// # Panics
+///
+/// If a value read back differs from the written one, with the position and
+/// the spec in the message.
+pub fn roundtrip_check<T>(data: ArrayViewD<T>, spec: &RoundtripSpec) -> Result<(), OmFilesRsError>
+where
+    T: OmFileArrayDataType + Clone + Default + ToPrimitive,
+{
+    let mut backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut backend, 8);
+        let mut writer = file_writer.prepare_array::<T>(
+            spec.dimensions.clone(),
+            spec.chunks.clone(),
+            spec.compression,
+            spec.scale_factor,
+            spec.add_offset,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    let ranges: Vec<_> = spec.dimensions.iter().map(|&x| 0..x).collect();
+    let read = reader.read::<T>(&ranges, None, None)?;
+
+    let logarithmic = spec.compression == CompressionType::PforDelta2dInt16Logarithmic;
+    let tolerance = spec.tolerance(T::DATA_TYPE_ARRAY);
+    for ((index, expected), actual) in data.indexed_iter().zip(read.iter()) {
+        let (Some(mut expected), Some(mut actual)) = (expected.to_f64(), actual.to_f64()) else {
+            continue;
+        };
+        if logarithmic {
+            expected = expected.ln_1p() / std::f64::consts::LN_10;
+            actual = actual.ln_1p() / std::f64::consts::LN_10;
+        }
+        assert!(
+            (expected - actual).abs() <= tolerance,
+            "Value at {:?} was written as {} and read as {} with {:?}",
+            index.slice(),
+            expected,
+            actual,
+            spec
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Heatmaps of two dimensional slices of an array, e.g. for quick looks at a
+//! variable without external plotting tools.
+//!
+//! ```ignore
+//! let heatmap = HeatmapSource::new(&reader, 1, 2)?
+//!     .with_index(0, 12)
+//!     .with_flip_y(true)
+//!     .render()?;
+//! heatmap.write_ppm(&mut File::create("t2m.ppm")?)?;
+//! ```
+
+use crate::backend::backends::{map_io_error, OmFileReaderBackend};
+use crate::errors::OmFilesRsError;
+use crate::io::reader::OmFileReader;
+use ndarray::{Array2, Axis};
+use std::io::Write;
+use std::ops::Range;
+
+/// Maps values between 0 and 1 to colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Perceptually uniform from dark purple to yellow, like matplotlib
+    #[default]
+    Viridis,
+    Grayscale,
+}
+
+/// Viridis sampled at 9 evenly spaced positions
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [72, 40, 120],
+    [62, 73, 137],
+    [49, 104, 142],
+    [38, 130, 142],
+    [31, 158, 137],
+    [53, 183, 121],
+    [110, 206, 88],
+    [253, 231, 37],
+];
+
+impl Colormap {
+    /// Color of `t`, which is clamped to `0..=1`
+    pub fn color(self, t: f64) -> [u8; 3] {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Colormap::Viridis => {
+                let position = t * (VIRIDIS.len() - 1) as f64;
+                let i = (position.floor() as usize).min(VIRIDIS.len() - 2);
+                let fraction = position - i as f64;
+                let (a, b) = (VIRIDIS[i], VIRIDIS[i + 1]);
+                [0, 1, 2]
+                    .map(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * fraction).round() as u8)
+            }
+            Colormap::Grayscale => [(t * 255.0).round() as u8; 3],
+        }
+    }
+}
+
+/// Rendered heatmap with one RGBA pixel per element, row by row. NaN values
+/// are transparent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+    /// Values mapped to the first and last color of the colormap
+    pub value_range: Range<f64>,
+}
+
+impl Heatmap {
+    /// Write as binary PPM image, which most image viewers can open.
+    /// Transparent pixels are white.
+    pub fn write_ppm(&self, out: &mut impl Write) -> Result<(), OmFilesRsError> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height).map_err(map_io_error)?;
+        let rgb: Vec<u8> = self
+            .rgba
+            .chunks_exact(4)
+            .flat_map(|pixel| match pixel[3] {
+                0 => [255, 255, 255],
+                _ => [pixel[0], pixel[1], pixel[2]],
+            })
+            .collect();
+        out.write_all(&rgb).map_err(map_io_error)
+    }
+}
+
+/// Two dimensional slice of an array for rendering. Dimension `y_axis` maps
+/// to image rows and `x_axis` to image columns, all other dimensions are
+/// fixed at an index that is 0 unless set with `with_index`.
+pub struct HeatmapSource<'a, Backend: OmFileReaderBackend> {
+    reader: &'a OmFileReader<Backend>,
+    y_axis: usize,
+    x_axis: usize,
+    indices: Vec<u64>,
+    value_range: Option<Range<f64>>,
+    colormap: Colormap,
+    flip_y: bool,
+}
+
+impl<'a, Backend: OmFileReaderBackend> HeatmapSource<'a, Backend> {
+    pub fn new(
+        reader: &'a OmFileReader<Backend>,
+        y_axis: usize,
+        x_axis: usize,
+    ) -> Result<Self, OmFilesRsError> {
+        let n = reader.get_dimensions().len();
+        if y_axis >= n || x_axis >= n || y_axis == x_axis {
+            return Err(OmFilesRsError::InvalidAxesPermutation);
+        }
+        Ok(Self {
+            reader,
+            y_axis,
+            x_axis,
+            indices: vec![0; n],
+            value_range: None,
+            colormap: Colormap::default(),
+            flip_y: false,
+        })
+    }
+
+    /// Index of the slice in dimension `axis`, which is neither `y_axis` nor
+    /// `x_axis`
+    pub fn with_index(mut self, axis: usize, index: u64) -> Self {
+        self.indices[axis] = index;
+        self
+    }
+
+    /// Values mapped to the first and last color. By default the range of the
+    /// stored statistics, or of the values of the slice without statistics.
+    pub fn with_value_range(mut self, value_range: Range<f64>) -> Self {
+        self.value_range = Some(value_range);
+        self
+    }
+
+    pub fn with_colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Put the last index of `y_axis` in the first row, e.g. for latitudes
+    /// that increase to the north
+    pub fn with_flip_y(mut self, flip_y: bool) -> Self {
+        self.flip_y = flip_y;
+        self
+    }
+
+    /// Values of the slice with `y_axis` along rows and `x_axis` along
+    /// columns, as they are rendered
+    pub fn read_values(&self) -> Result<Array2<f32>, OmFilesRsError> {
+        let dimensions = self.reader.get_dimensions();
+        let ranges: Vec<Range<u64>> = (0..dimensions.len())
+            .map(|axis| {
+                if axis == self.y_axis || axis == self.x_axis {
+                    0..dimensions[axis]
+                } else {
+                    self.indices[axis]..self.indices[axis] + 1
+                }
+            })
+            .collect();
+        let (ny, nx) = (
+            dimensions[self.y_axis] as usize,
+            dimensions[self.x_axis] as usize,
+        );
+        let shape = if self.y_axis < self.x_axis {
+            (ny, nx)
+        } else {
+            (nx, ny)
+        };
+        let values = self
+            .reader
+            .read_converted::<f32>(&ranges)?
+            .into_shape_with_order(shape)
+            .map_err(|_| OmFilesRsError::ChunkHasWrongNumberOfElements)?;
+        let mut values = if self.y_axis < self.x_axis {
+            values
+        } else {
+            values.reversed_axes().as_standard_layout().into_owned()
+        };
+        if self.flip_y {
+            values.invert_axis(Axis(0));
+            values = values.as_standard_layout().into_owned();
+        }
+        Ok(values)
+    }
+
+    /// Read the slice and map it to colors
+    pub fn render(&self) -> Result<Heatmap, OmFilesRsError> {
+        let values = self.read_values()?;
+        let value_range = self.value_range.clone().unwrap_or_else(|| {
+            self.reader
+                .get_statistics()
+                .filter(|statistics| statistics.count > 0)
+                .map(|statistics| statistics.min..statistics.max)
+                .unwrap_or_else(|| finite_range(&values))
+        });
+        let span = value_range.end - value_range.start;
+        let rgba = values
+            .iter()
+            .flat_map(|&value| {
+                if value.is_nan() {
+                    return [0, 0, 0, 0];
+                }
+                let t = if span > 0.0 {
+                    (value as f64 - value_range.start) / span
+                } else {
+                    0.5
+                };
+                let [r, g, b] = self.colormap.color(t);
+                [r, g, b, 255]
+            })
+            .collect();
+        Ok(Heatmap {
+            width: values.ncols(),
+            height: values.nrows(),
+            rgba,
+            value_range,
+        })
+    }
+}
+
+/// Smallest and largest finite value, `0..1` if there are none
+fn finite_range(values: &Array2<f32>) -> Range<f64> {
+    let finite = values.iter().filter(|x| x.is_finite()).map(|&x| x as f64);
+    let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+        (min.min(x), max.max(x))
+    });
+    if min > max {
+        0.0..1.0
+    } else {
+        min..max
+    }
+}
@@ -1,9 +1,13 @@
 use ndarray::ArrayD;
-use omfiles_rs::backend::backends::InMemoryBackend;
+use omfiles_rs::backend::backends::{InMemoryBackend, OmFileReaderBackend};
 use omfiles_rs::core::compression::CompressionType;
-use omfiles_rs::errors::OmFilesRsError;
+use omfiles_rs::errors::{ErrorKind, OmFilesRsError};
+use omfiles_rs::io::copy::copy_transposed;
+use omfiles_rs::io::histogram::Histogram;
+use omfiles_rs::io::parse::validate_bytes;
+use omfiles_rs::io::quantization::LogFilter;
 use omfiles_rs::io::reader::OmFileReader;
-use omfiles_rs::io::writer::OmFileWriter;
+use omfiles_rs::io::writer::{OmFileWriter, OmOffsetSize};
 use std::borrow::BorrowMut;
 use std::sync::Arc;
 
@@ -107,6 +111,327 @@ fn test_mismatching_cube_dimension_length_for_read() {
     assert_eq!(error_string(result), "Mismatching cube dimension length");
 }
 
+#[test]
+fn test_invalid_axes_permutation() {
+    let mut backend = InMemoryBackend::new(vec![]);
+
+    {
+        let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+        let mut array_writer = writer
+            .prepare_array::<i32>(
+                vec![10, 10],
+                vec![5, 5],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )
+            .unwrap();
+
+        let array = ArrayD::from_elem(vec![10, 10], 1);
+        array_writer.write_data(array.view(), None, None).unwrap();
+
+        let variable_meta = array_writer.finalize();
+        let variable = writer.write_array(variable_meta, "data", &[]).unwrap();
+        writer.write_trailer(variable).unwrap();
+    }
+
+    let reader = OmFileReader::new(Arc::new(backend)).unwrap();
+    let mut copy_backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(copy_backend.borrow_mut(), 1024);
+    let result = copy_transposed(&reader, &mut writer, &[1, 1], None);
+
+    assert_eq!(error_string(result), "Invalid axes permutation");
+}
+
+#[test]
+fn test_invalid_histogram_bins() {
+    let result = Histogram::new(&[0.0, 10.0, 10.0]);
+
+    assert_eq!(
+        error_string(result),
+        "Histogram bins must be at least two increasing edges"
+    );
+}
+
+#[test]
+fn test_checkpoint_with_statistics() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let mut array_writer = writer
+        .prepare_array::<f32>(vec![10], vec![5], CompressionType::FpxXor2d, 1.0, 0.0)
+        .unwrap()
+        .with_statistics();
+
+    assert_eq!(
+        error_string(array_writer.checkpoint()),
+        "Invalid writer checkpoint: Arrays with statistics cannot be resumed"
+    );
+}
+
+#[test]
+fn test_quantization_filter_requires_int16_codec() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let result = writer
+        .prepare_array::<f32>(vec![10], vec![5], CompressionType::FpxXor2d, 1.0, 0.0)
+        .unwrap()
+        .with_quantization_filter(LogFilter);
+
+    assert_eq!(error_string(result), "Invalid compression type");
+}
+
+#[test]
+fn test_double_arrays_reject_int16_codecs() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let result = writer.prepare_array::<f64>(
+        vec![10],
+        vec![5],
+        CompressionType::PforDelta2dInt16,
+        1.0,
+        0.0,
+    );
+    assert_eq!(error_string(result), "Invalid compression type");
+
+    let result = writer
+        .prepare_array::<f64>(vec![10], vec![5], CompressionType::FpxXor2d, 1.0, 0.0)
+        .unwrap()
+        .with_double_scale(100.0, 0.0);
+    assert_eq!(error_string(result), "Invalid compression type");
+}
+
+#[test]
+fn test_too_many_saturated_values() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let mut array_writer = writer
+        .prepare_array::<f32>(
+            vec![4],
+            vec![4],
+            CompressionType::PforDelta2dInt16,
+            1000.0,
+            0.0,
+        )
+        .unwrap()
+        .with_saturation_check(Some(0))
+        .unwrap();
+    let data = ArrayD::from_shape_vec(vec![4], vec![1.0, 40.0, 2.0, -50.0]).unwrap();
+
+    assert_eq!(
+        error_string(array_writer.write_data(data.view(), None, None)),
+        "2 values exceed the int16 range after scaling, at most 0 are allowed"
+    );
+    drop(array_writer);
+
+    let result = writer
+        .prepare_array::<f32>(vec![4], vec![4], CompressionType::FpxXor2d, 1.0, 0.0)
+        .unwrap()
+        .with_saturation_check(None);
+    assert_eq!(error_string(result), "Invalid compression type");
+}
+
+#[test]
+fn test_error_kinds_and_context() {
+    use std::error::Error;
+
+    assert_eq!(OmFilesRsError::NotAnOmFile.kind(), ErrorKind::Format);
+    assert_eq!(
+        OmFilesRsError::VariableNotFound("temperature".to_string()).kind(),
+        ErrorKind::NotFound
+    );
+    assert_eq!(
+        OmFilesRsError::InvalidCompressionType.kind(),
+        ErrorKind::InvalidArgument
+    );
+
+    // Context is prepended to the message and the original error is the source
+    let error = OmFilesRsError::DecoderError("Invalid chunk".to_string())
+        .context("chunks 4..6")
+        .context("variable 'temperature'");
+    assert_eq!(
+        error.to_string(),
+        "variable 'temperature': chunks 4..6: Decoder error Invalid chunk"
+    );
+    assert_eq!(error.kind(), ErrorKind::Decoder);
+    assert_eq!(
+        error.root_cause(),
+        &OmFilesRsError::DecoderError("Invalid chunk".to_string())
+    );
+    let source = error.source().unwrap();
+    assert_eq!(
+        source.to_string(),
+        "chunks 4..6: Decoder error Invalid chunk"
+    );
+    assert_eq!(
+        source.source().unwrap().to_string(),
+        "Decoder error Invalid chunk"
+    );
+    assert!(source.source().unwrap().source().is_none());
+}
+
+#[test]
+fn test_validate_bytes() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    {
+        let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+        let mut array_writer = writer
+            .prepare_array::<i32>(
+                vec![10, 10],
+                vec![5, 5],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )
+            .unwrap();
+        let array = ArrayD::from_shape_fn(vec![10, 10], |x| (x[0] * 10 + x[1]) as i32);
+        array_writer.write_data(array.view(), None, None).unwrap();
+        let variable_meta = array_writer.finalize();
+        let units = writer
+            .write_scalar(String::from("K"), "units", &[])
+            .unwrap();
+        let variable = writer.write_array(variable_meta, "data", &[units]).unwrap();
+        writer.write_trailer(variable).unwrap();
+    }
+    let data = backend.into_inner();
+
+    let summary = validate_bytes(&data).unwrap();
+    assert!(!summary.legacy);
+    assert_eq!(summary.file_size, data.len() as u64);
+    assert_eq!(summary.variables, 2);
+    assert_eq!(summary.arrays, 1);
+    assert_eq!(summary.max_depth, 1);
+    assert!(summary.data_bytes > 0);
+
+    // Truncated files never parse
+    for length in 0..data.len() {
+        assert!(validate_bytes(&data[..length]).is_err());
+    }
+
+    // Corrupted bytes must not panic, whether they are detected or not
+    for position in 0..data.len() {
+        for value in [0x00, 0x7f, 0xff] {
+            let mut corrupted = data.clone();
+            corrupted[position] = value;
+            let _ = validate_bytes(&corrupted);
+        }
+    }
+}
+
+#[test]
+fn test_invalid_offset_size() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    {
+        let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+        let child = writer.write_scalar(1i32, "child", &[]).unwrap();
+        let root = writer.write_scalar(0i32, "root", &[child]).unwrap();
+        writer.write_trailer(root).unwrap();
+    }
+    let data = backend.into_inner();
+    let file_size = data.len() as u64;
+
+    // The root variable must end before the trailer
+    let mut corrupted = data.clone();
+    let trailer_offset = data.len() - 16;
+    corrupted[trailer_offset..trailer_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    let error = validate_bytes(&corrupted).unwrap_err();
+    assert!(matches!(
+        error,
+        OmFilesRsError::InvalidOffsetSize {
+            offset: u64::MAX,
+            ..
+        }
+    ));
+    assert_eq!(error.kind(), ErrorKind::Format);
+    assert!(OmFileReader::new(Arc::new(InMemoryBackend::new(corrupted))).is_err());
+
+    // Children outside of the file are reported, not fetched
+    let reader = OmFileReader::new(Arc::new(InMemoryBackend::new(data))).unwrap();
+    assert!(reader.get_child(0).is_some());
+    assert!(reader.get_child(1).is_none());
+    let result = reader.init_child_from_offset_size(OmOffsetSize::new(file_size - 4, 8));
+    assert_eq!(
+        error_string(result),
+        format!(
+            "Invalid offset {} and size 8 in a file of {} bytes",
+            file_size - 4,
+            file_size
+        )
+    );
+    let result = reader.init_child_from_offset_size(OmOffsetSize::new(u64::MAX, 2));
+    assert!(matches!(
+        result.err(),
+        Some(OmFilesRsError::InvalidOffsetSize { .. })
+    ));
+    assert_eq!(
+        reader.backend.get_bytes(u64::MAX, 2).err(),
+        Some(OmFilesRsError::OffsetAndCountExceedDimension {
+            offset: u64::MAX,
+            count: 2,
+            dimension: file_size,
+        })
+    );
+}
+
+#[test]
+fn test_chunk_exceeds_dimension() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let result =
+        writer.prepare_array::<i32>(vec![10, 10], vec![5, 11], CompressionType::None, 1.0, 0.0);
+
+    assert_eq!(
+        error_string(result),
+        "Chunk dimension 11 exceeds dimension 10 at index 1"
+    );
+}
+
+#[test]
+fn test_zero_dimension() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+    writer.set_string_arrays(true);
+
+    let result =
+        writer.prepare_array::<i32>(vec![0, 10], vec![1, 5], CompressionType::None, 1.0, 0.0);
+    assert_eq!(
+        error_string(result),
+        "Dimension and chunk dimension at index 0 must be larger than 0"
+    );
+
+    let result =
+        writer.prepare_array::<i32>(vec![10, 10], vec![5, 0], CompressionType::None, 1.0, 0.0);
+    assert_eq!(
+        error_string(result),
+        "Dimension and chunk dimension at index 1 must be larger than 0"
+    );
+
+    let result = writer.prepare_string_array(vec![3, 0]);
+    assert_eq!(
+        error_string(result),
+        "Dimension and chunk dimension at index 1 must be larger than 0"
+    );
+}
+
+#[test]
+fn test_too_many_dimensions() {
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut writer = OmFileWriter::new(backend.borrow_mut(), 1024);
+
+    let result =
+        writer.prepare_array::<i32>(vec![1; 33], vec![1; 33], CompressionType::None, 1.0, 0.0);
+
+    assert_eq!(
+        error_string(result),
+        "Too many dimensions: 33, at most 32 are supported"
+    );
+}
+
 fn error_string<T>(result: Result<T, OmFilesRsError>) -> String {
     match result {
         Ok(_) => {
@@ -2,13 +2,37 @@ use ndarray::{s, Array2, ArrayD, ArrayViewD};
 use om_file_format_sys::{fpxdec32, fpxenc32};
 use omfiles_rs::{
     backend::{
-        backends::{InMemoryBackend, OmFileReaderBackend},
+        backends::{
+            BlockingAdapter, DynOmFileReaderBackendAsync, InMemoryBackend, IoSizes,
+            OmFileReaderBackend, OmFileWriterBackend, StreamingBackend,
+        },
+        cached_backend::CachedBackend,
         mmapfile::{MmapFile, Mode},
+        pread::PreadFile,
+    },
+    core::{
+        compression::CompressionType,
+        data_types::{DataType, OmFileArrayDataType},
     },
-    core::compression::CompressionType,
     errors::OmFilesRsError,
     io::{
+        bench::{evaluate_compression, CodecCandidate},
+        cancellation::CancellationToken,
+        checkpoint::OmFileWriterCheckpoint,
+        chunking::{suggest_chunks, AccessPattern, ChunkSpec},
+        compare::{diff, Difference},
+        copy::{copy_transposed, copy_variable, rechunk},
+        describe::VariableDescription,
+        geo::GridDefinition,
+        histogram::Histogram,
+        multi_file_reader::MultiFileReader,
+        progress::WriteProgress,
+        quantization::{estimate_scale_factor, ClosureFilter, LogFilter, QuantizationFilter},
+        read_stats::ReadStats,
         reader::OmFileReader,
+        reader_async::OmFileReaderAsync,
+        time::TimeAxis,
+        visitor::{Traversal, VariableVisitor},
         writer::{OmFileWriter, OmOffsetSize},
     },
 };
@@ -678,6 +702,20 @@ fn test_hierarchical_variables() -> Result<(), Box<dyn std::error::Error>> {
 
         assert_eq!(all_children_meta, expected_metadata);
 
+        // Reopen a nested variable directly from its cached location
+        let subchild = reader.open_variable(&all_children_meta["subchild"])?;
+        assert_eq!(subchild.get_name().unwrap(), "subchild");
+        assert_eq!(subchild.get_dimensions(), &[4, 500]);
+        assert!(reader
+            .open_variable(&OmOffsetSize::new(1 << 20, 80))
+            .is_err());
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&all_children_meta)?;
+            let parsed: HashMap<String, OmOffsetSize> = serde_json::from_str(&json)?;
+            assert_eq!(parsed, all_children_meta);
+        }
+
         // Check parent data
         let parent = reader.read::<f32>(&[0..3, 0..3], None, None)?;
         let expected_parent = ArrayD::from_shape_vec(
@@ -1040,6 +1078,5551 @@ fn test_nan() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_read_vars() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![10, 20];
+    let chunks = vec![3, 7];
+    let u_data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 20 + x[1]) as f32
+    });
+    let v_data = u_data.mapv(|x| -x);
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+
+        let mut u_writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            chunks.clone(),
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        u_writer.write_data(u_data.view(), None, None)?;
+        let u_meta = u_writer.finalize();
+
+        let mut v_writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            chunks.clone(),
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        v_writer.write_data(v_data.view(), None, None)?;
+        let v_meta = v_writer.finalize();
+
+        let u_var = file_writer.write_array(u_meta, "u", &[])?;
+        let v_var = file_writer.write_array(v_meta, "v", &[])?;
+        let root_var = file_writer.write_scalar(1i32, "root", &[u_var, v_var])?;
+        file_writer.write_trailer(root_var)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let u_range = [2..5, 0..20];
+    let v_range = [0..10, 6..8];
+    let result = reader.read_vars::<f32>(&[("u", &u_range), ("v", &v_range)], None, None)?;
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0], u_data.slice(s![2..5, 0..20]).into_dyn());
+    assert_eq!(result[1], v_data.slice(s![0..10, 6..8]).into_dyn());
+
+    // Same variable twice with overlapping ranges
+    let result = reader.read_vars::<f32>(&[("u", &u_range), ("u", &[3..4, 1..19])], None, None)?;
+    assert_eq!(result[1], u_data.slice(s![3..4, 1..19]).into_dyn());
+
+    let missing = reader.read_vars::<f32>(&[("w", &u_range)], None, None);
+    assert_eq!(
+        missing.err().unwrap(),
+        OmFilesRsError::VariableNotFound("w".to_string())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_backend() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![20, 20];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 20 + x[1]) as f32
+    });
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![5, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let file_size = in_memory_backend.count() as u64;
+
+    let backend = Arc::new(CachedBackend::new(in_memory_backend, 64, 1000));
+    let reader = OmFileReader::new(backend.clone())?;
+
+    let first = reader.read::<f32>(&[0..20, 0..20], None, None)?;
+    assert_eq!(first, data);
+    let after_first_read = backend.statistics();
+    assert!(after_first_read.misses > 0);
+    assert!(after_first_read.bytes_fetched <= file_size);
+
+    // The second read is served entirely from the cache
+    let second = reader.read::<f32>(&[0..20, 0..20], None, None)?;
+    assert_eq!(second, data);
+    let after_second_read = backend.statistics();
+    assert_eq!(after_second_read.misses, after_first_read.misses);
+    assert!(after_second_read.hits > after_first_read.hits);
+
+    // A tiny cache has to evict blocks but still returns correct data
+    let small_backend = Arc::new(CachedBackend::new(
+        InMemoryBackend::new(backend.get_bytes_owned(0, file_size)?),
+        16,
+        2,
+    ));
+    let reader = OmFileReader::new(small_backend.clone())?;
+    let values = reader.read::<f32>(&[3..17, 2..9], None, None)?;
+    assert_eq!(values, data.slice(s![3..17, 2..9]).into_dyn());
+    assert!(small_backend.statistics().evictions > 0);
+    assert!(small_backend.statistics().cached_blocks <= 2);
+
+    // Ranges past the end of the file are rejected
+    assert!(small_backend.pre_read(file_size as usize, 1).is_err());
+    assert!(small_backend.pre_read(usize::MAX, 2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_backend_async() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::backends::OmFileReaderBackendAsync;
+
+    let bytes: Vec<u8> = (0..100).collect();
+    // Zero sizes are raised to 1 instead of panicking
+    let backend = CachedBackend::new(InMemoryBackend::new(bytes.clone()), 0, 0);
+    assert_eq!(backend.block_size(), 1);
+    assert_eq!(backend.get_bytes_owned(10, 3)?, bytes[10..13]);
+
+    let backend = CachedBackend::new(InMemoryBackend::new(bytes.clone()), 16, 2);
+    assert_eq!(backend.count_async(), 100);
+    futures::executor::block_on(async {
+        assert_eq!(backend.get_bytes_async(10, 20).await?, bytes[10..30]);
+        let first = backend.statistics();
+        assert_eq!(first.misses, 2);
+        assert_eq!(first.bytes_fetched, 32);
+
+        // Served from the cache, the last block is shorter than `block_size`
+        assert_eq!(backend.get_bytes_async(16, 10).await?, bytes[16..26]);
+        assert_eq!(backend.statistics().misses, 2);
+        assert_eq!(backend.get_bytes_async(96, 4).await?, bytes[96..100]);
+        assert_eq!(backend.statistics().evictions, 1);
+        assert!(backend.get_bytes_async(95, 10).await.is_err());
+        Ok::<_, OmFilesRsError>(())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_string_array() -> Result<(), Box<dyn std::error::Error>> {
+    let strings = ArrayD::from_shape_vec(
+        vec![2, 3],
+        vec!["Berlin", "", "Zürich", "東京", "a", "São Paulo"],
+    )
+    .unwrap();
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer.prepare_string_array(vec![2, 3])?;
+        // Write row by row
+        writer.write_data(strings.slice(s![0..1, ..]).into_dyn())?;
+        writer.write_data(strings.slice(s![1..2, ..]).into_dyn())?;
+        let variable_meta = writer.finalize()?;
+        let stations = file_writer.write_array(variable_meta, "stations", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[stations])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let child = reader.get_child(0).unwrap();
+    assert_eq!(child.data_type(), DataType::StringArray);
+    assert_eq!(child.get_name().unwrap(), "stations");
+    assert_eq!(child.get_dimensions(), &[2, 3]);
+
+    let all = child.read::<String>(&[0..2, 0..3], None, None)?;
+    assert_eq!(all, strings.mapv(|s| s.to_string()));
+
+    let column = child.read_string_array(&[0..2, 2..3])?;
+    assert_eq!(
+        column.into_raw_vec_and_offset().0,
+        vec!["Zürich".to_string(), "São Paulo".to_string()]
+    );
+
+    // Numeric reads of a string array are rejected
+    assert_eq!(
+        child.read::<f32>(&[0..2, 0..3], None, None).err().unwrap(),
+        OmFilesRsError::InvalidDataType
+    );
+    assert_eq!(
+        reader.read_string_array(&[0..1]).err().unwrap(),
+        OmFilesRsError::InvalidDataType
+    );
+
+    // Strings are only written if enabled, other implementations cannot read them
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(backend.borrow_mut(), 8);
+    assert_eq!(
+        file_writer
+            .prepare_string_array(vec![2])
+            .err()
+            .unwrap()
+            .root_cause(),
+        &OmFilesRsError::InvalidDataType
+    );
+    // Numeric writers do not encode strings
+    let result =
+        file_writer.prepare_array::<String>(vec![2], vec![2], CompressionType::None, 1.0, 0.0);
+    assert_eq!(
+        result.err().unwrap().root_cause(),
+        &OmFilesRsError::InvalidDataType
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_string_array_without_dimensions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let variable = {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer.prepare_string_array(vec![1])?;
+        writer.write_data(ArrayD::from_elem(vec![1], "a").view())?;
+        let variable_meta = writer.finalize()?;
+        let variable = file_writer.write_array(variable_meta, "", &[])?;
+        file_writer.write_trailer(variable.clone())?;
+        variable
+    };
+
+    // Metadata with a dimension count of 0 is rejected when the file is opened
+    let mut data = in_memory_backend.as_ref().to_vec();
+    let count = variable.offset as usize + 24;
+    data[count..count + 8].copy_from_slice(&0u64.to_le_bytes());
+    assert_eq!(
+        OmFileReader::new(Arc::new(InMemoryBackend::new(data)))
+            .err()
+            .unwrap(),
+        OmFilesRsError::NotAnOmFile
+    );
+    Ok(())
+}
+
+#[test]
+fn test_string_array_wrong_number_of_elements() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+    file_writer.set_string_arrays(true);
+    let mut writer = file_writer.prepare_string_array(vec![2])?;
+    let too_many = ArrayD::from_shape_vec(vec![3], vec!["a", "b", "c"]).unwrap();
+    assert_eq!(
+        writer.write_data(too_many.view()).err().unwrap(),
+        OmFilesRsError::ChunkHasWrongNumberOfElements
+    );
+    let too_few = ArrayD::from_shape_vec(vec![1], vec!["a"]).unwrap();
+    writer.write_data(too_few.view())?;
+    assert_eq!(
+        writer.finalize().err().unwrap(),
+        OmFilesRsError::ChunkHasWrongNumberOfElements
+    );
+    Ok(())
+}
+
+#[test]
+fn test_string_scalar() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let units = file_writer.write_scalar("°C".to_string(), "units", &[])?;
+        let empty = file_writer.write_scalar(String::new(), "empty", &[])?;
+        let with_null = file_writer.write_scalar("a\0b".to_string(), "with_null", &[])?;
+        let description = file_writer.write_scalar(
+            "Température à 2 m — 気温".to_string(),
+            "description",
+            &[units, empty, with_null],
+        )?;
+        file_writer.write_trailer(description)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.data_type(), DataType::String);
+    assert_eq!(reader.get_name().unwrap(), "description");
+    assert_eq!(
+        reader.read_scalar::<String>().unwrap(),
+        "Température à 2 m — 気温"
+    );
+    assert_eq!(reader.read_scalar::<f32>(), None);
+    assert_eq!(reader.number_of_children(), 3);
+
+    let units = reader.get_child(0).unwrap();
+    assert_eq!(units.get_name().unwrap(), "units");
+    assert_eq!(units.read_scalar::<String>().unwrap(), "°C");
+
+    let empty = reader.get_child(1).unwrap();
+    assert_eq!(empty.read_scalar::<String>().unwrap(), "");
+
+    // Strings are length prefixed, embedded null bytes are preserved
+    let with_null = reader.get_child(2).unwrap();
+    assert_eq!(with_null.read_scalar::<String>().unwrap(), "a\0b");
+
+    Ok(())
+}
+
+#[test]
+fn test_write_progress() -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress: Vec<WriteProgress> = Vec::new();
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![5, 5],
+            vec![2, 2],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.set_progress_sink(|p: WriteProgress| progress.push(p));
+
+        let data = ArrayD::from_shape_fn(vec![5, 5], |x| (x[0] * 5 + x[1]) as f32);
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    assert_eq!(progress.len(), 9);
+    for (i, p) in progress.iter().enumerate() {
+        assert_eq!(p.chunks_written, i as u64 + 1);
+        assert_eq!(p.total_chunks, 9);
+    }
+    assert!(progress
+        .windows(2)
+        .all(|w| w[0].bytes_written <= w[1].bytes_written));
+    assert_eq!(progress.last().unwrap().fraction(), 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_dimension_names() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![2, 3, 4],
+                vec![1, 3, 2],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&["time", "lat", "lon"])?;
+        let data = ArrayD::from_shape_fn(vec![2, 3, 4], |x| (x[0] * 12 + x[1] * 4 + x[2]) as f32);
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(
+        reader.get_dimension_names().unwrap(),
+        vec!["time".to_string(), "lat".to_string(), "lon".to_string()]
+    );
+    let data = reader.read::<f32>(&[0..2, 0..3, 0..4], None, None)?;
+    assert_eq!(data[[1, 2, 3]], 23.0);
+
+    // The number of names must match the number of dimensions
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+    let result = file_writer
+        .prepare_array::<f32>(vec![2, 3], vec![1, 3], CompressionType::FpxXor2d, 1.0, 0.0)?
+        .with_dimension_names(&["time"]);
+    assert_eq!(
+        result.err().unwrap(),
+        OmFilesRsError::MismatchingCubeDimensionLength
+    );
+    Ok(())
+}
+
+#[test]
+fn test_copy_dimension_names() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::overview::{copy_with_overviews, Aggregation};
+
+    let data = ArrayD::from_shape_fn(vec![2, 3, 4], |x| (x[0] * 12 + x[1] * 4 + x[2]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![2, 3, 4],
+                vec![1, 3, 2],
+                CompressionType::None,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&["time", "lat", "lon"])?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let names = vec!["time".to_string(), "lat".to_string(), "lon".to_string()];
+
+    // Dimension names do not require string arrays on the destination
+    let copy_names = |copy: &dyn Fn(
+        &mut OmFileWriter<&mut InMemoryBackend>,
+    ) -> Result<OmOffsetSize, OmFilesRsError>|
+     -> Result<Option<Vec<String>>, OmFilesRsError> {
+        let mut backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(&mut backend, 8);
+            let variable = copy(&mut file_writer)?;
+            file_writer.write_trailer(variable)?;
+        }
+        Ok(OmFileReader::new(Arc::new(backend))?.get_dimension_names())
+    };
+    assert_eq!(
+        copy_names(&|w| copy_variable(&reader, w))?,
+        Some(names.clone())
+    );
+    assert_eq!(
+        copy_names(&|w| reader.rechunk_to(w, &[2, 2, 2]))?,
+        Some(names.clone())
+    );
+    assert_eq!(
+        copy_names(&|w| copy_with_overviews(&reader, w, &[2], Aggregation::Mean))?,
+        Some(names.clone())
+    );
+    assert_eq!(
+        copy_names(&|w| copy_transposed(&reader, w, &[2, 0, 1], None))?,
+        Some(vec![
+            "lon".to_string(),
+            "time".to_string(),
+            "lat".to_string()
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pread_backend() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "test_pread_backend.om";
+    remove_file_if_exists(file);
+    let dims = vec![30, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![7, 9],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let backend = PreadFile::from_path(file)?;
+    let expected = fs::read(file)?;
+    assert_eq!(backend.count(), expected.len());
+    assert_eq!(backend.get_bytes_owned(3, 500)?, expected[3..503].to_vec());
+    assert_eq!(
+        backend
+            .get_bytes_owned(expected.len() as u64 - 2, 3)
+            .err()
+            .unwrap(),
+        OmFilesRsError::OffsetAndCountExceedDimension {
+            offset: expected.len() as u64 - 2,
+            count: 3,
+            dimension: expected.len() as u64
+        }
+    );
+
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None)?, data);
+
+    // Asynchronous backends can be used with the synchronous reader
+    let adapter = BlockingAdapter::new(InMemoryBackend::new(fs::read(file)?));
+    let reader = OmFileReader::new(Arc::new(adapter))?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None)?, data);
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+fn test_io_uring_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::backends::OmFileReaderBackendAsync;
+    use omfiles_rs::backend::io_uring::{AutoFileBackend, IoUringBackend, IoUringConfig};
+
+    let file = "test_io_uring_backend.om";
+    remove_file_if_exists(file);
+    let dims = vec![30, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![7, 9],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    // Tiny buffers force large reads to be split over several registered buffers
+    let config = IoUringConfig {
+        queue_depth: 4,
+        buffer_count: 4,
+        buffer_size: 64,
+        alignment: 64,
+    };
+    let backend = IoUringBackend::with_config(File::open(file)?, config.clone())?;
+    let expected = fs::read(file)?;
+    assert_eq!(backend.count(), expected.len());
+    assert_eq!(backend.get_bytes_owned(3, 500)?, expected[3..503].to_vec());
+    assert_eq!(
+        backend.read_with(10, 64, |bytes| bytes.to_vec())?,
+        expected[10..74].to_vec()
+    );
+
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None)?, data);
+
+    // Falls back to pread if io_uring is not available
+    let backend = AutoFileBackend::from_path(file)?;
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None)?, data);
+
+    // Invalid configurations are rejected, the automatic backend uses pread instead
+    let invalid = IoUringConfig {
+        alignment: 3,
+        ..config.clone()
+    };
+    assert_eq!(
+        IoUringBackend::with_config(File::open(file)?, invalid.clone())
+            .err()
+            .unwrap()
+            .kind(),
+        omfiles_rs::errors::ErrorKind::InvalidArgument
+    );
+    let backend = AutoFileBackend::with_config(File::open(file)?, invalid)?;
+    assert!(!backend.is_io_uring());
+
+    // Dropping a read in flight cancels it and the buffers are reused afterwards
+    let backend = IoUringBackend::with_config(File::open(file)?, config.clone())?;
+    {
+        let mut read = Box::pin(backend.get_bytes_async(0, 500));
+        let waker = futures::task::noop_waker();
+        let _ =
+            std::future::Future::poll(read.as_mut(), &mut std::task::Context::from_waker(&waker));
+    }
+    assert_eq!(backend.get_bytes_owned(3, 500)?, expected[3..503].to_vec());
+    drop(backend);
+
+    // Reads of the async reader are completed by the reaper thread, also with
+    // more concurrent requests than registered buffers
+    let backend = IoUringBackend::with_config(File::open(file)?, config)?;
+    futures::executor::block_on(async {
+        let reader = OmFileReaderAsync::new(Arc::new(backend)).await?;
+        assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None).await?, data);
+        let ranges: Vec<[std::ops::Range<u64>; 2]> = (0..8).map(|i| [i..i + 3, 0..40]).collect();
+        let reads = ranges
+            .iter()
+            .map(|ranges| reader.read::<f32>(ranges, None, None));
+        let results = futures::future::try_join_all(reads).await?;
+        for (i, read) in results.iter().enumerate() {
+            assert_eq!(read, &data.slice(s![i..i + 3, 0..40]).into_dyn());
+        }
+        Ok::<(), OmFilesRsError>(())
+    })?;
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "direct_io"))]
+fn test_direct_io_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::direct_io::DirectIoBackend;
+
+    let file = "test_direct_io_backend.om";
+    remove_file_if_exists(file);
+    let dims = vec![30, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![7, 9],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let backend = match DirectIoBackend::from_path(file) {
+        Ok(backend) => backend,
+        Err(OmFilesRsError::CannotOpenFile { errno: 22, .. }) => {
+            // EINVAL, the file system does not support O_DIRECT
+            remove_file_if_exists(file);
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    assert!(matches!(
+        DirectIoBackend::with_alignment(file, 3000).err(),
+        Some(OmFilesRsError::InvalidConfiguration(_))
+    ));
+
+    // Unaligned ranges, including the end of the file
+    let expected = fs::read(file)?;
+    assert_eq!(backend.count(), expected.len());
+    assert_eq!(backend.get_bytes_owned(3, 500)?, expected[3..503].to_vec());
+    let tail = expected.len() as u64 - 17;
+    assert_eq!(
+        backend.get_bytes_owned(tail, 17)?,
+        expected[tail as usize..].to_vec()
+    );
+    assert!(backend.get_bytes_owned(tail, 18).is_err());
+
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None)?, data);
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_async_reader() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![20, 30];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 30 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 7],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        let attribute = file_writer.write_scalar(42i32, "attribute", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[variable, attribute])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    futures::executor::block_on(async {
+        let reader = OmFileReaderAsync::new(Arc::new(in_memory_backend)).await?;
+        assert_eq!(reader.get_name().unwrap(), "root");
+        assert_eq!(reader.number_of_children(), 2);
+
+        let mut child = reader.get_child(0).await.unwrap();
+        assert_eq!(child.get_dimensions(), &[20, 30]);
+        assert_eq!(child.get_chunk_dimensions(), &[3, 7]);
+        assert_eq!(child.read::<f32>(&[0..20, 0..30], None, None).await?, data);
+        assert_eq!(
+            child.read::<f32>(&[5..6, 10..13], None, None).await?,
+            data.slice(s![5..6, 10..13]).into_dyn()
+        );
+
+        // Sequential requests return the same result
+        child.set_max_concurrency(1);
+        assert_eq!(child.read::<f32>(&[0..20, 0..30], None, None).await?, data);
+
+        let attribute = reader.get_child(1).await.unwrap();
+        assert_eq!(attribute.read_scalar::<i32>(), Some(42));
+        Ok(())
+    })
+}
+
+/// Async backend that counts requests in flight. Every request yields once
+/// before it completes, so concurrent requests overlap.
+struct InFlightBackend {
+    data: InMemoryBackend,
+    in_flight: std::sync::atomic::AtomicUsize,
+    peak: std::sync::atomic::AtomicUsize,
+}
+
+impl omfiles_rs::backend::backends::OmFileReaderBackendAsync for InFlightBackend {
+    fn count_async(&self) -> usize {
+        self.data.count()
+    }
+
+    async fn get_bytes_async(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        use std::sync::atomic::Ordering;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(in_flight, Ordering::SeqCst);
+        // Yield once without depending on a runtime
+        let mut yielded = false;
+        std::future::poll_fn(|cx| {
+            if yielded {
+                return std::task::Poll::Ready(());
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            std::task::Poll::Pending
+        })
+        .await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(self.data.get_bytes(offset, count)?.to_vec())
+    }
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn test_async_reader_pipeline() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![40, 50];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 50 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 4],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(InFlightBackend {
+        data: in_memory_backend,
+        in_flight: 0.into(),
+        peak: 0.into(),
+    });
+
+    let mut reader = OmFileReaderAsync::new(backend.clone()).await?;
+    reader.set_max_concurrency(3);
+    // Tiny IO sizes split the read into many index blocks and chunk requests
+    let read = reader
+        .read::<f32>(&[0..40, 0..50], Some(64), Some(0))
+        .await?;
+    assert_eq!(read, data);
+
+    // Requests overlap, but never exceed the concurrency limit
+    let peak = backend.peak.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(peak > 1, "requests did not overlap");
+    assert!(peak <= 3, "{} requests in flight", peak);
+
+    // Per-read options override the settings of the shared reader
+    backend.peak.store(0, std::sync::atomic::Ordering::SeqCst);
+    let options = omfiles_rs::io::reader_async::ReadOptions::default()
+        .with_max_concurrency(1)
+        .with_io_sizes(IoSizes {
+            io_size_max: 64,
+            io_size_merge: 0,
+        });
+    let read = reader
+        .read_with_options::<f32>(&[0..40, 0..50], &options)
+        .await?;
+    assert_eq!(read, data);
+    assert_eq!(backend.peak.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(reader.max_concurrency(), 3);
+
+    // A limit of 0 is raised to 1 instead of stalling the read
+    let options = omfiles_rs::io::reader_async::ReadOptions::default().with_max_concurrency(0);
+    assert_eq!(options.max_concurrency, Some(1));
+    reader.set_max_concurrency(0);
+    assert_eq!(reader.max_concurrency(), 1);
+    let options = omfiles_rs::io::reader_async::ReadOptions {
+        max_concurrency: Some(0),
+        io_sizes: None,
+    };
+    let read = reader
+        .read_with_options::<f32>(&[0..40, 0..50], &options)
+        .await?;
+    assert_eq!(read, data);
+    Ok(())
+}
+
+#[test]
+fn test_async_reader_concurrent_reads() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![40, 50], |x| (x[0] * 50 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![40, 50],
+            vec![3, 4],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(InFlightBackend {
+        data: in_memory_backend,
+        in_flight: 0.into(),
+        peak: 0.into(),
+    });
+
+    // Several reads of one shared reader run concurrently on a plain executor
+    // without any runtime or spawner
+    futures::executor::block_on(async {
+        let mut reader = OmFileReaderAsync::new(backend.clone()).await?;
+        reader.set_max_concurrency(2);
+        let ranges: Vec<[std::ops::Range<u64>; 2]> =
+            (0..4).map(|i| [i * 10..i * 10 + 10, 0..50]).collect();
+        let reads = ranges
+            .iter()
+            .map(|ranges| reader.read::<f32>(ranges, Some(64), Some(0)));
+        let results = futures::future::try_join_all(reads).await?;
+        for (i, read) in results.iter().enumerate() {
+            assert_eq!(read, &data.slice(s![i * 10..i * 10 + 10, ..]).into_dyn());
+        }
+        Ok::<(), OmFilesRsError>(())
+    })?;
+    // Each read keeps at most 2 requests in flight, so more than 2 requests
+    // at once means that the reads overlapped
+    let peak = backend.peak.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(peak > 2, "reads did not overlap");
+    assert!(peak <= 8, "{} requests in flight", peak);
+    Ok(())
+}
+
+/// Async backend whose requests never complete once `stall` is set
+struct StallingBackend {
+    data: InMemoryBackend,
+    stall: std::sync::atomic::AtomicBool,
+}
+
+impl omfiles_rs::backend::backends::OmFileReaderBackendAsync for StallingBackend {
+    fn count_async(&self) -> usize {
+        self.data.count()
+    }
+
+    async fn get_bytes_async(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        if self.stall.load(std::sync::atomic::Ordering::SeqCst) {
+            std::future::pending::<()>().await;
+        }
+        Ok(self.data.get_bytes(offset, count)?.to_vec())
+    }
+}
+
+#[test]
+fn test_async_reader_cancellation() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![10, 10];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 10 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![5, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(StallingBackend {
+        data: in_memory_backend,
+        stall: false.into(),
+    });
+
+    futures::executor::block_on(async {
+        let mut reader = OmFileReaderAsync::new(backend.clone()).await?;
+        let token = CancellationToken::new();
+        reader.set_cancellation_token(token.clone());
+        assert_eq!(reader.read::<f32>(&[0..10, 0..10], None, None).await?, data);
+
+        // A read waiting on a request that never completes is aborted
+        backend
+            .stall
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let (result, ()) = futures::join!(reader.read::<f32>(&[0..10, 0..10], None, None), async {
+            token.cancel()
+        });
+        assert!(matches!(result, Err(OmFilesRsError::Cancelled)));
+
+        // Reads with a cancelled token fail immediately
+        backend
+            .stall
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        assert!(token.is_cancelled());
+        let result = reader.read::<f32>(&[0..10, 0..10], None, None).await;
+        assert!(matches!(result, Err(OmFilesRsError::Cancelled)));
+        Ok(())
+    })
+}
+
+struct CountingWaker(std::sync::atomic::AtomicUsize);
+
+impl futures::task::ArcWake for CountingWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_cancelled_futures_of_one_task() {
+    let counter = Arc::new(CountingWaker(0.into()));
+    let waker = futures::task::waker(counter.clone());
+    let mut cx = std::task::Context::from_waker(&waker);
+    let token = CancellationToken::new();
+
+    // Both futures register the waker of the same task. Dropping the first
+    // must not unregister the second.
+    let mut first = Box::pin(token.cancelled());
+    let mut second = Box::pin(token.cancelled());
+    assert!(std::future::Future::poll(first.as_mut(), &mut cx).is_pending());
+    assert!(std::future::Future::poll(second.as_mut(), &mut cx).is_pending());
+    drop(first);
+    token.cancel();
+    assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert!(std::future::Future::poll(second.as_mut(), &mut cx).is_ready());
+}
+
+/// Async backend that fails the first `failures` requests, and never answers
+/// requests at offset 0
+struct FlakyBackend {
+    data: InMemoryBackend,
+    failures: std::sync::atomic::AtomicU64,
+}
+
+impl omfiles_rs::backend::backends::OmFileReaderBackendAsync for FlakyBackend {
+    fn count_async(&self) -> usize {
+        self.data.count()
+    }
+
+    async fn get_bytes_async(&self, offset: u64, count: u64) -> Result<Vec<u8>, OmFilesRsError> {
+        if offset == 0 {
+            std::future::pending::<()>().await;
+        }
+        let dimension = self.data.count() as u64;
+        if offset + count > dimension {
+            return Err(OmFilesRsError::OffsetAndCountExceedDimension {
+                offset,
+                count,
+                dimension,
+            });
+        }
+        let remaining = self.failures.load(std::sync::atomic::Ordering::SeqCst);
+        if remaining > 0 {
+            self.failures
+                .store(remaining - 1, std::sync::atomic::Ordering::SeqCst);
+            return Err(OmFilesRsError::FileReaderError {
+                errno: 0,
+                error: "Connection reset".to_string(),
+            });
+        }
+        Ok(self.data.get_bytes(offset, count)?.to_vec())
+    }
+}
+
+#[test]
+fn test_retry_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::backends::OmFileReaderBackendAsync;
+    use omfiles_rs::backend::retry::{RetryBackend, RetryPolicy};
+    use std::time::Duration;
+
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(250),
+        timeout: Some(Duration::from_secs(1)),
+    };
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(250));
+
+    let sleeps = std::sync::Mutex::new(Vec::new());
+    let sleep = |duration: Duration| {
+        sleeps.lock().unwrap().push(duration);
+        std::future::ready(())
+    };
+    let flaky = FlakyBackend {
+        data: InMemoryBackend::new((0..100).collect()),
+        failures: 2.into(),
+    };
+    let backend = RetryBackend::new(flaky, policy.clone(), sleep)?;
+
+    futures::executor::block_on(async {
+        // Two transient failures are retried with increasing backoff
+        assert_eq!(backend.get_bytes_async(10, 3).await?, vec![10, 11, 12]);
+        assert_eq!(backend.retries(), 2);
+        // The timeout timer is created for every attempt
+        assert_eq!(
+            sleeps.lock().unwrap().as_slice(),
+            &[
+                Duration::from_secs(1),
+                Duration::from_millis(100),
+                Duration::from_secs(1),
+                Duration::from_millis(200),
+                Duration::from_secs(1),
+            ]
+        );
+
+        // Requests that do not complete in time fail after all attempts. The
+        // timer completes immediately, so every attempt times out.
+        let result = backend.get_bytes_async(0, 3).await;
+        assert_eq!(
+            result,
+            Err(OmFilesRsError::RequestTimeout { timeout_ms: 1000 })
+        );
+        assert_eq!(backend.retries(), 4);
+
+        // Permanent errors are returned without retrying
+        let result = backend.get_bytes_async(99, 3).await;
+        assert!(matches!(
+            result,
+            Err(OmFilesRsError::OffsetAndCountExceedDimension { .. })
+        ));
+        assert_eq!(backend.retries(), 4);
+        Ok::<_, OmFilesRsError>(())
+    })?;
+
+    // A custom condition disables retries for all errors
+    let flaky = FlakyBackend {
+        data: InMemoryBackend::new((0..100).collect()),
+        failures: 1.into(),
+    };
+    let backend = RetryBackend::new(flaky, policy.clone(), |_| std::future::ready(()))?
+        .with_retry_condition(|_| false);
+    let result = futures::executor::block_on(backend.get_bytes_async(10, 3));
+    assert!(matches!(
+        result,
+        Err(OmFilesRsError::FileReaderError { .. })
+    ));
+    assert_eq!(backend.retries(), 0);
+
+    // At least one attempt is required
+    let no_attempts = RetryPolicy {
+        max_attempts: 0,
+        ..policy
+    };
+    let result = RetryBackend::new(InMemoryBackend::new(vec![]), no_attempts, |_: Duration| {
+        std::future::ready(())
+    });
+    assert!(matches!(
+        result.err(),
+        Some(OmFilesRsError::InvalidConfiguration(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_async_reader_merges_across_index_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    // One chunk per row, a point read touches every chunk
+    let dims = vec![500, 50];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| (x[0] + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![1, 50],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(in_memory_backend);
+    let expected = data.slice(s![.., 7..8]).into_dyn();
+
+    let reader = OmFileReader::new(backend.clone())?;
+    let mut into = ArrayD::<f32>::zeros(vec![500, 1]);
+    let mut sync_stats = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..500, 7..8],
+        &[0, 0],
+        &[500, 1],
+        Some(4096),
+        Some(4096),
+        &mut sync_stats,
+    )?;
+    assert_eq!(into, expected);
+
+    let async_stats = futures::executor::block_on(async {
+        let reader = OmFileReaderAsync::new(backend).await?;
+        let mut into = ArrayD::<f32>::zeros(vec![500, 1]);
+        let mut stats = ReadStats::default();
+        reader
+            .read_into_with_stats(
+                &mut into,
+                &[0..500, 7..8],
+                &[0, 0],
+                &[500, 1],
+                Some(4096),
+                Some(4096),
+                &mut stats,
+            )
+            .await?;
+        assert_eq!(into, expected);
+        Ok::<_, OmFilesRsError>(stats)
+    })?;
+
+    // The async reader merges requests of neighbouring index blocks
+    assert_eq!(async_stats.chunks_decoded, 500);
+    assert_eq!(async_stats.chunks_decoded, sync_stats.chunks_decoded);
+    assert!(async_stats.index_reads <= sync_stats.index_reads);
+    assert!(async_stats.data_reads <= sync_stats.data_reads);
+    assert!(async_stats.requests() <= sync_stats.requests());
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio")]
+async fn test_tokio_file_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::tokio_file::TokioFileBackend;
+
+    let file = "test_tokio_file_backend.om";
+    remove_file_if_exists(file);
+    let dims = vec![30, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![7, 9],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let backend = TokioFileBackend::open(file).await?;
+    let reader = OmFileReaderAsync::new(Arc::new(backend)).await?;
+    assert_eq!(reader.read::<f32>(&[0..30, 0..40], None, None).await?, data);
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_read_parallel() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![25, 10, 6];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 60 + x[1] * 6 + x[2]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 4, 6],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    assert_eq!(reader.read_parallel::<f32>(&[0..25, 0..10, 0..6], 4)?, data);
+    // Selections that do not start or end at chunk boundaries
+    let selection = [2..23, 1..9, 2..5];
+    let expected = reader.read::<f32>(&selection, None, None)?;
+    for n_threads in [1, 2, 3, 16] {
+        assert_eq!(
+            reader.read_parallel::<f32>(&selection, n_threads)?,
+            expected
+        );
+    }
+    assert_eq!(
+        reader
+            .read_parallel::<f32>(&[0..25, 0..10], 4)
+            .err()
+            .unwrap(),
+        OmFilesRsError::MismatchingCubeDimensionLength
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rechunk() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![25, 10, 6];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 60 + x[1] * 6 + x[2]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 4, 6],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let attribute = file_writer.write_scalar(1u8, "version", &[])?;
+        let variable = file_writer.write_array(variable_meta, "data", &[attribute])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // Small read limits force blocks spanning only a part of the inner dimensions
+    for (chunks, max_read_elements) in [
+        (vec![5, 5, 5], None),
+        (vec![25, 1, 1], None),
+        (vec![2, 3, 4], Some(1)),
+        (vec![4, 5, 3], Some(30)),
+        (vec![6, 2, 6], Some(100)),
+    ] {
+        let mut rechunked_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(rechunked_backend.borrow_mut(), 8);
+            let variable = rechunk(&reader, &mut file_writer, &chunks, max_read_elements)?;
+            file_writer.write_trailer(variable)?;
+        }
+        let rechunked = OmFileReader::new(Arc::new(rechunked_backend))?;
+        assert_eq!(rechunked.get_chunk_dimensions(), chunks.as_slice());
+        assert_eq!(rechunked.get_name().unwrap(), "data");
+        assert_eq!(rechunked.compression(), CompressionType::PforDelta2dInt16);
+        assert_eq!(
+            rechunked.read::<f32>(&[0..25, 0..10, 0..6], None, None)?,
+            data
+        );
+        assert_eq!(rechunked.get_child(0).unwrap().read_scalar::<u8>(), Some(1));
+    }
+
+    let mut rechunked_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(rechunked_backend.borrow_mut(), 8);
+    assert_eq!(
+        reader.rechunk_to(&mut file_writer, &[5, 5]).err().unwrap(),
+        OmFilesRsError::MismatchingCubeDimensionLength
+    );
+    Ok(())
+}
+
+#[test]
+fn test_copy_transposed() -> Result<(), Box<dyn std::error::Error>> {
+    // [lat, lon, time] to [time, lat, lon]
+    transpose_and_compare(&[6, 7, 20], &[3, 4, 5], &[2, 0, 1], None)?;
+    transpose_and_compare(&[6, 7, 20], &[3, 4, 5], &[2, 0, 1], Some(10))?;
+    transpose_and_compare(&[6, 7, 20], &[3, 4, 5], &[0, 1, 2], Some(50))?;
+    // 4D cube
+    transpose_and_compare(&[3, 4, 5, 6], &[2, 3, 2, 4], &[3, 1, 0, 2], None)?;
+    transpose_and_compare(&[3, 4, 5, 6], &[2, 3, 2, 4], &[1, 3, 2, 0], Some(7))?;
+    Ok(())
+}
+
+/// Write a cube with dimension names, copy it with `copy_transposed` and
+/// compare against the permuted ndarray.
+fn transpose_and_compare(
+    dims: &[u64],
+    chunks: &[u64],
+    axes: &[usize],
+    max_read_elements: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let names: Vec<String> = (0..dims.len()).map(|i| format!("dim{}", i)).collect();
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims.to_vec()), |x| {
+        (0..dims.len()).fold(0.0, |acc, i| acc * 100.0 + x[i] as f32)
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                dims.to_vec(),
+                chunks.to_vec(),
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&names)?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let mut transposed_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(transposed_backend.borrow_mut(), 8);
+        let variable = copy_transposed(&reader, &mut file_writer, axes, max_read_elements)?;
+        file_writer.write_trailer(variable)?;
+    }
+    let transposed = OmFileReader::new(Arc::new(transposed_backend))?;
+
+    let expected = data.permuted_axes(axes);
+    let new_dims: Vec<u64> = expected.shape().iter().map(|&d| d as u64).collect();
+    let new_chunks: Vec<u64> = axes.iter().map(|&a| chunks[a]).collect();
+    assert_eq!(transposed.get_dimensions(), new_dims.as_slice());
+    assert_eq!(transposed.get_chunk_dimensions(), new_chunks.as_slice());
+    let ranges: Vec<_> = new_dims.iter().map(|&d| 0..d).collect();
+    assert_eq!(transposed.read::<f32>(&ranges, None, None)?, expected);
+    let new_names: Vec<String> = axes.iter().map(|&a| names[a].clone()).collect();
+    assert_eq!(transposed.get_dimension_names().unwrap(), new_names);
+    Ok(())
+}
+
+#[test]
+fn test_read_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![20, 20];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 20 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![5, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let mut into = ArrayD::<f32>::zeros(vec![20, 20]);
+    let mut stats = ReadStats::default();
+    // Without merging, every chunk is requested separately
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..20, 0..20],
+        &[0, 0],
+        &[20, 20],
+        None,
+        Some(0),
+        &mut stats,
+    )?;
+    assert_eq!(into, data);
+    assert_eq!(stats.chunks_decoded, 16);
+    assert!(stats.index_reads >= 1);
+    assert!(stats.data_reads >= 1 && stats.data_reads <= 16);
+    assert_eq!(stats.requests(), stats.index_reads + stats.data_reads);
+    assert!(stats.bytes_fetched > 0);
+
+    // Merging nearby chunks reduces the number of data requests
+    let mut merged_stats = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..20, 0..20],
+        &[0, 0],
+        &[20, 20],
+        None,
+        None,
+        &mut merged_stats,
+    )?;
+    assert_eq!(merged_stats.chunks_decoded, 16);
+    assert!(merged_stats.data_reads <= stats.data_reads);
+
+    // Counters accumulate over several reads
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..5, 0..5],
+        &[0, 0],
+        &[20, 20],
+        None,
+        None,
+        &mut merged_stats,
+    )?;
+    assert_eq!(merged_stats.chunks_decoded, 17);
+    Ok(())
+}
+
+#[test]
+fn test_preferred_io_sizes() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![40, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![4, 4],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    assert_eq!(in_memory_backend.preferred_io_sizes(), IoSizes::default());
+
+    let backend = CachedBackend::new(in_memory_backend, 1 << 20, 4);
+    assert_eq!(
+        backend.preferred_io_sizes(),
+        IoSizes {
+            io_size_max: 1 << 20,
+            io_size_merge: 1 << 20
+        }
+    );
+    let reader = OmFileReader::new(Arc::new(backend))?;
+
+    // `None` uses the IO sizes of the backend
+    let mut into = ArrayD::<f32>::zeros(vec![40, 40]);
+    let mut preferred_stats = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..40, 0..40],
+        &[0, 0],
+        &[40, 40],
+        None,
+        None,
+        &mut preferred_stats,
+    )?;
+    assert_eq!(into, data);
+    let mut explicit_stats = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..40, 0..40],
+        &[0, 0],
+        &[40, 40],
+        Some(1 << 20),
+        Some(1 << 20),
+        &mut explicit_stats,
+    )?;
+    assert_eq!(preferred_stats.data_reads, explicit_stats.data_reads);
+    assert_eq!(preferred_stats.index_reads, explicit_stats.index_reads);
+    Ok(())
+}
+
+#[test]
+fn test_lut_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![40, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![2, 2],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(in_memory_backend);
+    let mut reader = OmFileReader::new(backend.clone())?;
+    reader.enable_lut_cache(1 << 20);
+
+    let mut into = ArrayD::<f32>::zeros(vec![3, 5]);
+    let mut first = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[10..13, 20..25],
+        &[0, 0],
+        &[3, 5],
+        None,
+        None,
+        &mut first,
+    )?;
+    assert_eq!(into, data.slice(s![10..13, 20..25]).into_dyn());
+    assert!(first.index_reads > 0);
+    assert_eq!(first.index_cache_hits, 0);
+
+    // The same selection again is served from the cache
+    let mut second = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[10..13, 20..25],
+        &[0, 0],
+        &[3, 5],
+        None,
+        None,
+        &mut second,
+    )?;
+    assert_eq!(into, data.slice(s![10..13, 20..25]).into_dyn());
+    assert_eq!(second.index_reads, 0);
+    assert!(second.index_cache_hits > 0);
+    assert_eq!(second.data_reads, first.data_reads);
+
+    // Cached entries do not depend on the IO sizes, and data reads are still
+    // merged like without the cache
+    let uncached = OmFileReader::new(backend)?;
+    for (io_size_max, io_size_merge) in [(0, 0), (4096, 512), (1 << 20, 1 << 20)] {
+        let mut cached_stats = ReadStats::default();
+        reader.read_into_with_stats(
+            &mut into,
+            &[10..13, 20..25],
+            &[0, 0],
+            &[3, 5],
+            Some(io_size_max),
+            Some(io_size_merge),
+            &mut cached_stats,
+        )?;
+        assert_eq!(into, data.slice(s![10..13, 20..25]).into_dyn());
+        assert_eq!(cached_stats.index_reads, 0);
+        let mut uncached_stats = ReadStats::default();
+        uncached.read_into_with_stats(
+            &mut into,
+            &[10..13, 20..25],
+            &[0, 0],
+            &[3, 5],
+            Some(io_size_max),
+            Some(io_size_merge),
+            &mut uncached_stats,
+        )?;
+        assert!(cached_stats.data_reads <= uncached_stats.data_reads);
+    }
+
+    // A part of the selection is served from the cache as well
+    let mut part = ArrayD::<f32>::zeros(vec![2, 2]);
+    let mut third = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut part,
+        &[11..13, 22..24],
+        &[0, 0],
+        &[2, 2],
+        None,
+        None,
+        &mut third,
+    )?;
+    assert_eq!(part, data.slice(s![11..13, 22..24]).into_dyn());
+    assert_eq!(third.index_reads, 0);
+
+    // Full reads still work with the cache enabled
+    assert_eq!(reader.read::<f32>(&[0..40, 0..40], None, None)?, data);
+    Ok(())
+}
+
+#[test]
+fn test_dyn_backends() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "test_dyn_backends.om";
+    remove_file_if_exists(file);
+    let dims = vec![10, 12];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 12 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let writer_backend: Box<dyn OmFileWriterBackend> = Box::new(file_handle);
+        let mut file_writer = OmFileWriter::new(writer_backend, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    // Select the backend at runtime
+    for use_mmap in [true, false] {
+        let backend: Box<dyn OmFileReaderBackend + Send + Sync> = if use_mmap {
+            Box::new(MmapFile::new(File::open(file)?, Mode::ReadOnly)?)
+        } else {
+            Box::new(InMemoryBackend::new(fs::read(file)?))
+        };
+        let reader = OmFileReader::new(Arc::new(backend))?;
+        assert_eq!(reader.read::<f32>(&[0..10, 0..12], None, None)?, data);
+    }
+
+    let backend: Box<dyn DynOmFileReaderBackendAsync + Send + Sync> =
+        Box::new(InMemoryBackend::new(fs::read(file)?));
+    futures::executor::block_on(async {
+        let reader = OmFileReaderAsync::new(Arc::new(backend)).await?;
+        assert_eq!(reader.read::<f32>(&[0..10, 0..12], None, None).await?, data);
+        Ok::<(), OmFilesRsError>(())
+    })?;
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_read_strided() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![30, 20];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 20 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![4, 6],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    for (selection, step) in [
+        ([0..30, 0..20], [1, 1]),
+        ([0..30, 0..20], [3, 2]),
+        ([1..29, 2..19], [5, 7]),
+        ([3..30, 0..20], [8, 13]),
+        ([0..30, 5..6], [30, 1]),
+        ([7..7, 0..20], [2, 2]),
+    ] {
+        let expected = data
+            .slice(s![
+                selection[0].start as usize..selection[0].end as usize;step[0],
+                selection[1].start as usize..selection[1].end as usize;step[1]
+            ])
+            .into_dyn();
+        assert_eq!(reader.read_strided::<f32>(&selection, &step)?, expected);
+    }
+    assert_eq!(
+        reader
+            .read_strided::<f32>(&[0..30, 0..20], &[1, 0])
+            .err()
+            .unwrap(),
+        OmFilesRsError::DimensionMustBeLargerThan0
+    );
+    Ok(())
+}
+
+#[test]
+fn test_mask() -> Result<(), Box<dyn std::error::Error>> {
+    let mask = ArrayD::from_shape_fn(vec![30, 40], |x| (x[0] * 40 + x[1]) % 7 < 3);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let variable = file_writer.write_mask(mask.view(), vec![10, 10], "land_sea_mask", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.data_type(), DataType::Uint8Array);
+    assert_eq!(reader.get_name().unwrap(), "land_sea_mask");
+    assert_eq!(reader.read_mask(&[0..30, 0..40])?, mask);
+    assert_eq!(
+        reader.read_mask(&[3..9, 15..16])?,
+        mask.slice(s![3..9, 15..16]).into_dyn()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fill_value() -> Result<(), Box<dyn std::error::Error>> {
+    let fill_value = i16::MIN;
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| {
+        if (x[0] + x[1]) % 5 == 0 {
+            fill_value
+        } else {
+            (x[0] * 8 + x[1]) as i16
+        }
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<i16>(
+                vec![6, 8],
+                vec![3, 3],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )?
+            .with_fill_value(fill_value);
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_fill_value::<i16>(), Some(fill_value));
+    assert_eq!(reader.get_fill_value::<i32>(), None);
+
+    let (values, validity) = reader.read_with_validity::<i16>(&[0..6, 0..8])?;
+    assert_eq!(values, data);
+    assert_eq!(validity, data.mapv(|v| v != fill_value));
+
+    let replaced = reader.read_replacing_fill_value::<i16>(&[0..6, 0..8], -1)?;
+    assert_eq!(
+        replaced,
+        data.mapv(|v| if v == fill_value { -1 } else { v })
+    );
+
+    // Copies keep the fill value
+    let mut copy_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(copy_backend.borrow_mut(), 8);
+        let variable = copy_variable(&reader, &mut file_writer)?;
+        file_writer.write_trailer(variable)?;
+    }
+    let copy = OmFileReader::new(Arc::new(copy_backend))?;
+    assert_eq!(copy.get_fill_value::<i16>(), Some(fill_value));
+
+    // A NaN fill value matches NaN elements
+    let floats = ArrayD::from_shape_fn(vec![6, 8], |x| {
+        if (x[0] + x[1]) % 5 == 0 {
+            f32::NAN
+        } else {
+            (x[0] * 8 + x[1]) as f32
+        }
+    });
+    let mut float_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(float_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(vec![6, 8], vec![3, 3], CompressionType::FpxXor2d, 1.0, 0.0)?
+            .with_fill_value(f32::NAN);
+        writer.write_data(floats.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(float_backend))?;
+    let (_, validity) = reader.read_with_validity::<f32>(&[0..6, 0..8])?;
+    assert_eq!(validity, floats.mapv(|v| !v.is_nan()));
+    let replaced = reader.read_replacing_fill_value::<f32>(&[0..6, 0..8], -1.0)?;
+    assert_eq!(replaced, floats.mapv(|v| if v.is_nan() { -1.0 } else { v }));
+    Ok(())
+}
+
+#[test]
+fn test_multi_file_reader() -> Result<(), Box<dyn std::error::Error>> {
+    // Three files with 5, 7 and 3 time steps along the second dimension
+    let full = ArrayD::from_shape_fn(vec![4, 15], |x| (x[0] * 15 + x[1]) as f32);
+    let mut readers = Vec::new();
+    for (start, end) in [(0, 5), (5, 12), (12, 15)] {
+        let part = full.slice(s![.., start..end]).to_owned().into_dyn();
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+            let mut writer = file_writer.prepare_array::<f32>(
+                vec![4, (end - start) as u64],
+                vec![2, 3],
+                CompressionType::PforDelta2dInt16,
+                1.0,
+                0.0,
+            )?;
+            writer.write_data(part.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+        }
+        readers.push(OmFileReader::new(Arc::new(in_memory_backend))?);
+    }
+    let reader = MultiFileReader::new(readers, 1)?;
+    assert_eq!(reader.get_dimensions(), &[4, 15]);
+    assert_eq!(reader.data_type(), DataType::FloatArray);
+
+    assert_eq!(reader.read::<f32>(&[0..4, 0..15], None, None)?, full);
+    for (rows, steps) in [(1..3, 3..9), (0..4, 11..13), (2..3, 6..7), (0..4, 4..4)] {
+        let expected = full
+            .slice(s![rows.start..rows.end, steps.start..steps.end])
+            .into_dyn();
+        assert_eq!(
+            reader.read::<f32>(
+                &[
+                    rows.start as u64..rows.end as u64,
+                    steps.start as u64..steps.end as u64
+                ],
+                None,
+                None
+            )?,
+            expected
+        );
+    }
+    assert_eq!(
+        reader
+            .read::<f32>(&[0..4, 10..16], None, None)
+            .err()
+            .unwrap(),
+        OmFilesRsError::DimensionOutOfBounds {
+            range: 10..16,
+            allowed: 15
+        }
+    );
+    // Other dimensions are validated before anything is allocated
+    let reversed = std::ops::Range { start: 3, end: 1 };
+    assert!(matches!(
+        reader.read::<f32>(&[reversed, 0..5], None, None),
+        Err(OmFilesRsError::DimensionOutOfBounds { allowed: 4, .. })
+    ));
+    assert!(reader.read::<f32>(&[0..5, 0..5], None, None).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "encryption")]
+fn test_encrypted_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::encrypted::EncryptedBackend;
+
+    let key = [7u8; 32];
+    assert!(matches!(
+        EncryptedBackend::create(&mut InMemoryBackend::new(vec![]), &key, 0),
+        Err(OmFilesRsError::InvalidConfiguration(_))
+    ));
+
+    let data = ArrayD::from_shape_fn(vec![20, 30], |x| (x[0] * 30 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut encrypted = EncryptedBackend::create(&mut in_memory_backend, &key, 100)?;
+        {
+            let mut file_writer = OmFileWriter::new(&mut encrypted, 8);
+            let mut writer = file_writer.prepare_array::<f32>(
+                vec![20, 30],
+                vec![5, 7],
+                CompressionType::PforDelta2dInt16,
+                1.0,
+                0.0,
+            )?;
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+        }
+        encrypted.finalize()?;
+    }
+    let stored = in_memory_backend
+        .get_bytes(0, in_memory_backend.count() as u64)?
+        .to_vec();
+    // Encrypted data is not a valid om file
+    assert!(OmFileReader::new(Arc::new(InMemoryBackend::new(stored.clone()))).is_err());
+
+    let backend = EncryptedBackend::open(InMemoryBackend::new(stored.clone()), &key)?;
+    assert_eq!(backend.get_bytes_owned(95, 10)?.len(), 10);
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.read::<f32>(&[0..20, 0..30], None, None)?, data);
+    assert_eq!(
+        reader.read::<f32>(&[3..9, 11..25], None, None)?,
+        data.slice(s![3..9, 11..25]).into_dyn()
+    );
+
+    // A wrong key fails authentication
+    let wrong_key = EncryptedBackend::open(InMemoryBackend::new(stored), &[8u8; 32])?;
+    assert_eq!(
+        wrong_key.get_bytes_owned(0, 10).err().unwrap(),
+        OmFilesRsError::DecryptionFailed
+    );
+    Ok(())
+}
+
+#[test]
+fn test_statistics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = ArrayD::from_shape_fn(vec![10, 12], |x| (x[0] * 12 + x[1]) as f32 - 20.0);
+    data[[3, 4]] = f32::NAN;
+    data[[7, 1]] = f32::NAN;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![10, 12],
+                vec![4, 5],
+                CompressionType::PforDelta2dInt16,
+                1.0,
+                0.0,
+            )?
+            .with_statistics();
+        // Written in two parts, the second one from an offset of a larger array
+        writer.write_data(data.slice(s![0..5, ..]).into_dyn(), None, None)?;
+        writer.write_data(data.view(), Some(&[5, 0]), Some(&[5, 12]))?;
+        let with_statistics = writer.finalize();
+        let with_statistics = file_writer.write_array(with_statistics, "data", &[])?;
+
+        let mut writer = file_writer.prepare_array::<i32>(
+            vec![3],
+            vec![3],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(ndarray::arr1(&[1, 2, 3]).into_dyn().view(), None, None)?;
+        let without_statistics = writer.finalize();
+        let without_statistics =
+            file_writer.write_array(without_statistics, "plain", &[with_statistics])?;
+        file_writer.write_trailer(without_statistics)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_statistics(), None);
+
+    let child = reader.get_child(0).unwrap();
+    let valid: Vec<f64> = data
+        .iter()
+        .filter(|v| !v.is_nan())
+        .map(|&v| v as f64)
+        .collect();
+    let statistics = child.get_statistics().unwrap();
+    assert_eq!(statistics.min, -20.0);
+    assert_eq!(statistics.max, 99.0);
+    assert_eq!(statistics.count, 118);
+    assert_eq!(statistics.nan_count, 2);
+    assert_eq!(
+        statistics.mean,
+        valid.iter().sum::<f64>() / valid.len() as f64
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_where() -> Result<(), Box<dyn std::error::Error>> {
+    // Mostly low values with a few events
+    let mut data = ArrayD::from_shape_fn(vec![12, 20], |x| ((x[0] + x[1]) % 5) as f32);
+    data[[2, 3]] = 50.0;
+    data[[9, 17]] = 42.0;
+    data[[10, 1]] = f32::NAN;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![12, 20],
+                vec![5, 6],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_chunk_statistics();
+        // Two writes with 5 and 7 rows
+        writer.write_data(data.slice(s![0..5, ..]).into_dyn(), None, None)?;
+        writer.write_data(data.view(), Some(&[5, 0]), Some(&[7, 20]))?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    for dim_read in [
+        [0..12, 0..20],
+        [1..11, 2..19],
+        [9..10, 15..20],
+        [0..4, 0..0],
+    ] {
+        let (values, mask) = reader.read_where::<f32>(&dim_read, 40.0..100.0)?;
+        let expected = data
+            .slice(s![
+                dim_read[0].start as usize..dim_read[0].end as usize,
+                dim_read[1].start as usize..dim_read[1].end as usize
+            ])
+            .into_dyn();
+        assert_eq!(mask, expected.map(|v| (40.0..100.0).contains(v)));
+        for (value, (expected, selected)) in values.iter().zip(expected.iter().zip(mask.iter())) {
+            if *selected {
+                assert_eq!(value, expected);
+            }
+        }
+    }
+
+    // Only the chunks with events are decoded
+    let (values, mask) = reader.read_where::<f32>(&[0..12, 0..20], 40.0..100.0)?;
+    assert_eq!(mask.iter().filter(|m| **m).count(), 2);
+    assert_eq!(values[[0, 0]], 0.0);
+    assert_eq!(values[[2, 4]], data[[2, 4]]);
+    assert_eq!(values[[7, 0]], 0.0);
+
+    // Chunks with matching values are decoded completely
+    let (values, mask) = reader.read_where::<f32>(&[0..12, 0..20], 3.0..4.0)?;
+    assert_eq!(mask, data.map(|v| *v == 3.0));
+    assert_eq!(values[[0, 3]], 3.0);
+    assert_eq!(values[[0, 4]], 4.0);
+    Ok(())
+}
+
+#[test]
+fn test_compute_histogram() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = ArrayD::from_shape_fn(vec![20, 25], |x| ((x[0] * 25 + x[1]) % 100) as f32);
+    data[[4, 4]] = f32::NAN;
+    data[[15, 3]] = -5.0;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![20, 25],
+            vec![6, 7],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let edges = [0.0, 10.0, 50.0, 90.0];
+    let histogram = reader.compute_histogram::<f32>(&[3..17, 2..24], &edges)?;
+    let selection = data.slice(s![3..17, 2..24]);
+    let expected: Vec<u64> = edges
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| {
+            selection
+                .iter()
+                .filter(|&&v| (v as f64) >= w[0] && ((v as f64) < w[1] || (i == 2 && v == 90.0)))
+                .count() as u64
+        })
+        .collect();
+    assert_eq!(histogram.counts, expected);
+    assert_eq!(histogram.below, 1);
+    assert_eq!(
+        histogram.above,
+        selection.iter().filter(|&&v| v > 90.0).count() as u64
+    );
+    assert_eq!(histogram.nan_count, 1);
+    assert_eq!(histogram.count(), 14 * 22 - 1);
+
+    // Values 0..100 are uniformly distributed over the full array
+    let histogram = reader
+        .compute_histogram::<f32>(&[0..20, 0..25], &Histogram::uniform(0.0, 100.0, 100)?.edges)?;
+    let median = histogram.quantile(0.5).unwrap();
+    assert!((median - 50.0).abs() <= 1.0, "median {}", median);
+    // -5.0 is below the first edge and clamped
+    assert_eq!(histogram.quantile(0.0), Some(0.0));
+    assert_eq!(histogram.quantile(1.5), None);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "capi")]
+fn test_capi_header() {
+    // Regenerate with `cbindgen --config cbindgen.toml --output include/omfiles_rs.h src/capi.rs`
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/omfiles_rs.h"));
+    let committed = include_str!("../include/omfiles_rs.h");
+    assert_eq!(generated, committed, "include/omfiles_rs.h is out of date");
+}
+
+#[test]
+#[cfg(feature = "capi")]
+fn test_capi_read() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::capi::*;
+    use std::ffi::{CStr, CString};
+
+    let file = "test_capi_read.om";
+    remove_file_if_exists(file);
+    let data = ArrayD::from_shape_fn(vec![10, 12], |x| (x[0] * 12 + x[1]) as f32);
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![10, 12],
+            vec![4, 5],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    unsafe {
+        let path = CString::new(file)?;
+        let reader = om_rs_open(path.as_ptr());
+        assert!(!reader.is_null());
+        assert_eq!(om_rs_dimension_count(reader), 2);
+        let mut dimensions = [0u64; 2];
+        assert_eq!(
+            om_rs_get_dimensions(reader, dimensions.as_mut_ptr(), 2),
+            OM_RS_OK
+        );
+        assert_eq!(dimensions, [10, 12]);
+        let mut chunks = [0u64; 1];
+        assert_eq!(
+            om_rs_get_chunk_dimensions(reader, chunks.as_mut_ptr(), 1),
+            OM_RS_ERROR
+        );
+
+        let offset = [2u64, 3];
+        let count = [4u64, 5];
+        let mut out = vec![0f32; 20];
+        let status = om_rs_read_f32(
+            reader,
+            offset.as_ptr(),
+            count.as_ptr(),
+            2,
+            out.as_mut_ptr(),
+            out.len() as u64,
+        );
+        assert_eq!(status, OM_RS_OK);
+        assert_eq!(
+            out,
+            data.slice(s![2..6, 3..8])
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+
+        // Out of bounds reads report an error message
+        let offset = [8u64, 3];
+        let status = om_rs_read_f32(
+            reader,
+            offset.as_ptr(),
+            count.as_ptr(),
+            2,
+            out.as_mut_ptr(),
+            out.len() as u64,
+        );
+        assert_eq!(status, OM_RS_ERROR);
+        assert!(!CStr::from_ptr(om_rs_last_error()).to_bytes().is_empty());
+
+        let offset = [u64::MAX, 3];
+        let status = om_rs_read_f32(
+            reader,
+            offset.as_ptr(),
+            count.as_ptr(),
+            2,
+            out.as_mut_ptr(),
+            out.len() as u64,
+        );
+        assert_eq!(status, OM_RS_ERROR);
+        assert_eq!(
+            CStr::from_ptr(om_rs_last_error()).to_str()?,
+            "offset + count overflows"
+        );
+        om_rs_close(reader);
+
+        let missing = CString::new("does_not_exist.om")?;
+        assert!(om_rs_open(missing.as_ptr()).is_null());
+    }
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_create_atomic() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "test_create_atomic.om";
+    let temporary = "test_create_atomic.om~";
+    remove_file_if_exists(file);
+    remove_file_if_exists(temporary);
+
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32);
+    let write = |overwrite: bool| -> Result<(), OmFilesRsError> {
+        let mut file_writer = OmFileWriter::create_atomic(file, overwrite, 8)?;
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        // The destination only exists from an earlier write
+        assert_eq!(fs::metadata(file).is_ok(), overwrite);
+        assert!(fs::metadata(temporary).is_ok());
+        file_writer.write_trailer(variable)
+    };
+
+    write(false)?;
+    assert!(fs::metadata(temporary).is_err());
+    let reader = OmFileReader::from_file(file)?;
+    assert_eq!(reader.read::<f32>(&[0..6, 0..8], None, None)?, data);
+
+    let error = write(false).err().unwrap();
+    assert_eq!(
+        error,
+        OmFilesRsError::FileExistsAlready {
+            filename: file.to_string()
+        }
+    );
+    write(true)?;
+    assert!(fs::metadata(temporary).is_err());
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_resume_writing() -> Result<(), Box<dyn std::error::Error>> {
+    let file = "test_resume_writing.om";
+    let checkpoint_file = "test_resume_writing.om.checkpoint";
+    remove_file_if_exists(file);
+    remove_file_if_exists(checkpoint_file);
+
+    let data = ArrayD::from_shape_fn(vec![8, 10], |x| (x[0] * 10 + x[1]) as f32);
+    let time_axis = TimeAxis::new(1704067200, 3600, 8);
+    let grid = GridDefinition::latlon(50.0, 50.0, 10.0, 14.5, 0.5, 0.5);
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(file_handle, 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![8, 10],
+                vec![2, 5],
+                CompressionType::PforDelta2dInt16,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&["time", "lon"])?
+            .with_fill_value(-999.0)
+            .with_time_axis(time_axis.clone())
+            .with_grid(grid.clone());
+        writer.write_data(data.slice(s![0..2, ..]).into_dyn(), None, None)?;
+        writer.write_data(data.slice(s![2..4, ..]).into_dyn(), None, None)?;
+        let checkpoint = writer.checkpoint()?;
+        assert_eq!(checkpoint.chunk_index, 4);
+        checkpoint.save(checkpoint_file)?;
+        // Crash after the checkpoint, this chunk is discarded
+        writer.write_data(data.slice(s![4..6, ..]).into_dyn(), None, None)?;
+    }
+
+    {
+        let checkpoint = OmFileWriterCheckpoint::load(checkpoint_file)?;
+        let file_handle = fs::OpenOptions::new().write(true).open(file)?;
+        let mut file_writer = OmFileWriter::resume(file_handle, &checkpoint, 8)?;
+        let mut writer = file_writer.resume_array::<f32>(&checkpoint)?;
+        writer.write_data(data.slice(s![4..6, ..]).into_dyn(), None, None)?;
+        writer.write_data(data.slice(s![6..8, ..]).into_dyn(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    // Metadata of the array is restored from the checkpoint
+    let reader = OmFileReader::from_file(file)?;
+    assert_eq!(reader.read::<f32>(&[0..8, 0..10], None, None)?, data);
+    assert_eq!(
+        reader.get_dimension_names(),
+        Some(vec!["time".to_string(), "lon".to_string()])
+    );
+    assert_eq!(reader.get_fill_value::<f32>(), Some(-999.0));
+    assert_eq!(reader.get_time_axis(), Some(time_axis));
+    assert_eq!(reader.get_grid(), Some(grid));
+
+    // Filters cannot be stored in a checkpoint
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+    let mut writer = file_writer
+        .prepare_array::<f32>(
+            vec![8, 10],
+            vec![2, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?
+        .with_quantization_filter(LogFilter)?;
+    assert!(matches!(
+        writer.checkpoint(),
+        Err(OmFilesRsError::InvalidCheckpoint(_))
+    ));
+
+    remove_file_if_exists(file);
+    remove_file_if_exists(checkpoint_file);
+    Ok(())
+}
+
+#[test]
+fn test_variable_slice() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![12, 15], |x| (x[0] * 15 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![12, 15],
+            vec![5, 4],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let slice = reader.slice(&[2..10, 3..15])?;
+    assert_eq!(slice.shape(), vec![8, 12]);
+    // Ranges of nested slices are relative to the outer slice
+    let inner = slice.slice(&[1..4, 5..12])?;
+    assert_eq!(inner.ranges(), &[3..6, 8..15]);
+    assert_eq!(inner.shape(), vec![3, 7]);
+    assert_eq!(
+        inner.to_array::<f32>()?,
+        data.slice(s![3..6, 8..15]).into_dyn()
+    );
+
+    let mut into = ArrayD::<f32>::from_elem(vec![5, 9], f32::NAN);
+    inner.read_into(&mut into, &[1, 2], &[5, 9])?;
+    assert_eq!(into.slice(s![1..4, 2..9]), data.slice(s![3..6, 8..15]));
+    assert!(into[[0, 0]].is_nan());
+
+    assert!(slice.slice(&[0..9, 0..1]).is_err());
+    assert!(reader.slice(&[0..12]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_read_into_flat() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![9, 11], |x| (x[0] * 11 + x[1]) as i32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<i32>(
+            vec![9, 11],
+            vec![4, 3],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // Read a 3x4 selection into the middle of a 5x6 buffer
+    let mut buffer = vec![-1i32; 30];
+    reader.read_into_flat(&mut buffer, &[2..5, 6..10], &[1, 1], &[5, 6], None, None)?;
+    let buffer = Array2::from_shape_vec((5, 6), buffer)?;
+    assert_eq!(buffer.slice(s![1..4, 1..5]), data.slice(s![2..5, 6..10]));
+    assert_eq!(buffer[[0, 0]], -1);
+    assert_eq!(buffer[[4, 5]], -1);
+
+    // The buffer has to match the cube dimensions and hold the selection
+    let mut small = vec![0i32; 29];
+    assert!(reader
+        .read_into_flat(&mut small, &[2..5, 6..10], &[1, 1], &[5, 6], None, None)
+        .is_err());
+    let mut buffer = vec![0i32; 30];
+    assert!(reader
+        .read_into_flat(&mut buffer, &[2..5, 6..10], &[3, 1], &[5, 6], None, None)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_quantization_filter() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![10, 10], |x| (x[0] * 10 + x[1]) as f32 * 0.5);
+    let write = |filter: Box<dyn QuantizationFilter>| -> Result<InMemoryBackend, OmFilesRsError> {
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+            let mut writer = file_writer
+                .prepare_array::<f32>(
+                    vec![10, 10],
+                    vec![5, 5],
+                    CompressionType::PforDelta2dInt16,
+                    1000.0,
+                    0.0,
+                )?
+                .with_quantization_filter(filter)?;
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+        }
+        Ok(in_memory_backend)
+    };
+
+    // Built-in filters are inverted automatically
+    let reader = OmFileReader::new(Arc::new(write(Box::new(LogFilter))?))?;
+    assert_eq!(reader.get_quantization_filter().as_deref(), Some("log10p1"));
+    let stored = reader.read::<f32>(&[0..10, 0..10], None, None)?;
+    assert!((stored[[9, 9]] - 50.5f32.log10()).abs() < 1e-3);
+    let values = reader.read_dequantized(&[0..10, 0..10], None)?;
+    for (value, expected) in values.iter().zip(data.iter()) {
+        assert!(
+            (value - expected).abs() <= 0.002 * (1.0 + expected),
+            "{}",
+            value
+        );
+    }
+
+    // Custom filters have to be passed to the reader
+    let sqrt = || ClosureFilter::new("sqrt", |x: f32| x.sqrt(), |x: f32| x * x);
+    let reader = OmFileReader::new(Arc::new(write(Box::new(sqrt()))?))?;
+    assert!(reader.read_dequantized(&[0..10, 0..10], None).is_err());
+    let values = reader.read_dequantized(&[2..4, 0..10], Some(&sqrt()))?;
+    for (value, expected) in values.iter().zip(data.slice(s![2..4, ..]).iter()) {
+        assert!((value - expected).abs() < 0.05, "{}", value);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_time_axis() -> Result<(), Box<dyn std::error::Error>> {
+    // Hourly data starting at 2024-01-01T00:00Z
+    let time_axis = TimeAxis::new(1704067200, 3600, 48);
+    let data = ArrayD::from_shape_fn(vec![4, 48], |x| (x[0] * 48 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![4, 48],
+                vec![2, 24],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_time_axis(time_axis.clone());
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let read_axis = reader.get_time_axis().unwrap();
+    assert_eq!(read_axis, time_axis);
+    assert_eq!(read_axis.calendar, "proleptic_gregorian");
+
+    assert_eq!(read_axis.time_at(24), 1704067200 + 24 * 3600);
+    assert_eq!(read_axis.index_of(1704067200 + 5 * 3600), Some(5));
+    assert_eq!(read_axis.index_of(1704067200 + 5 * 3600 + 1), None);
+    assert_eq!(read_axis.index_of(1704067200 + 48 * 3600), None);
+    // The second day, including a partial step at its start
+    let day = read_axis.index_range(1704153600 - 1800..1704240000);
+    assert_eq!(day, 24..48);
+    assert_eq!(read_axis.index_range(0..1704067200), 0..0);
+
+    #[cfg(feature = "chrono")]
+    {
+        let timestamps = read_axis.timestamps();
+        assert_eq!(timestamps.len(), 48);
+        assert_eq!(timestamps[1].to_rfc3339(), "2024-01-01T01:00:00+00:00");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_grid_definition() -> Result<(), Box<dyn std::error::Error>> {
+    // 1 degree grid over Europe
+    let grid = GridDefinition::latlon(35.0, 70.0, -10.0, 30.0, 1.0, 1.0);
+    let (ny, nx) = (grid.ny(), grid.nx());
+    assert_eq!((ny, nx), (36, 41));
+    let data = ArrayD::from_shape_fn(vec![ny as usize, nx as usize], |x| {
+        (x[0] * 100 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![ny, nx],
+                vec![10, 10],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_grid(grid.clone());
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_grid(), Some(grid));
+
+    // Berlin
+    let (y, x) = reader.latlon_to_index(52.52, 13.40).unwrap();
+    assert_eq!((y, x), (18, 23));
+    let value = reader.read::<f32>(&[y..y + 1, x..x + 1], None, None)?;
+    assert_eq!(value[[0, 0]], 1823.0);
+    // Longitudes are wrapped
+    assert_eq!(reader.latlon_to_index(52.52, 13.40 - 360.0), Some((18, 23)));
+    assert_eq!(reader.latlon_to_index(20.0, 13.40), None);
+    assert_eq!(reader.latlon_to_index(52.52, 45.0), None);
+
+    // Global grids wrap around at the antimeridian
+    let global = GridDefinition::latlon(-90.0, 90.0, -180.0, 179.75, 0.25, 0.25);
+    assert_eq!(global.latlon_to_index(0.0, 179.9), Some((360, 0)));
+    Ok(())
+}
+
+#[test]
+fn test_reader_can_be_moved_between_threads() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![6, 7], |x| (x[0] * 7 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 7],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let metadata_size = reader.variable_data().len();
+
+    // Metadata stays valid when the reader is moved
+    let readers = vec![reader];
+    let reader = std::thread::spawn(move || readers.into_iter().next().unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(reader.variable_data().len(), metadata_size);
+    assert_eq!(reader.get_dimensions(), &[6, 7]);
+    assert_eq!(reader.read::<f32>(&[0..6, 0..7], None, None)?, data);
+    Ok(())
+}
+
+#[test]
+fn test_read_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![10, 12, 20], |x| (x[0] * 240 + x[1] * 20 + x[2]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![10, 12, 20],
+            vec![3, 4, 8],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // Point time series, some of them in the same chunks, a duplicate, a
+    // larger window across chunks and an empty selection
+    let mut selections: Vec<Vec<std::ops::Range<u64>>> = [(0, 0), (1, 2), (2, 3), (9, 11), (1, 2)]
+        .iter()
+        .map(|&(x, y)| vec![x..x + 1, y..y + 1, 2..15])
+        .collect();
+    selections.push(vec![2..7, 3..9, 0..20]);
+    selections.push(vec![4..4, 0..12, 0..20]);
+
+    let results = reader.read_batch::<f32>(&selections, None, None)?;
+    assert_eq!(results.len(), selections.len());
+    for (result, selection) in results.iter().zip(selections.iter()).take(6) {
+        let expected = reader.read::<f32>(selection, None, None)?;
+        assert_eq!(result, &expected);
+    }
+    assert_eq!(results[1][[0, 0, 0]], (240 + 40 + 2) as f32);
+    assert_eq!(results[6].len(), 0);
+
+    assert!(reader
+        .read_batch::<f32>(&[vec![0..1, 0..1, 15..21]], None, None)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_sample_bilinear() -> Result<(), Box<dyn std::error::Error>> {
+    // Linear in y and x, so bilinear interpolation is exact
+    let data = ArrayD::from_shape_fn(vec![8, 9, 6], |x| {
+        (x[0] * 10 + x[1] * 2) as f32 + x[2] as f32 * 100.0
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![8, 9, 6],
+            vec![3, 3, 6],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let points = [(0.0, 0.0), (2.5, 2.5), (7.0, 8.0), (3.25, 5.75)];
+    let values = reader.sample_bilinear(&points, 1..4)?;
+    assert_eq!(values.shape(), &[4, 3]);
+    for (i, &(y, x)) in points.iter().enumerate() {
+        for t in 0..3 {
+            let expected = (y * 10.0 + x * 2.0) as f32 + (t + 1) as f32 * 100.0;
+            assert!((values[[i, t]] - expected).abs() < 1e-3, "{:?}", (y, x, t));
+        }
+    }
+
+    assert!(reader.sample_bilinear(&[(7.5, 0.0)], 0..6).is_err());
+    assert!(reader.sample_bilinear(&[(-0.1, 0.0)], 0..6).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_late_attributes() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![5, 5], |x| (x[0] * 5 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![5, 5],
+            vec![2, 2],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", std::slice::from_ref(&variable))?;
+        // Only known after the data has been written
+        file_writer.add_attribute(&variable, 12.5f32, "max")?;
+        file_writer.add_attribute(&variable, "K".to_string(), "units")?;
+        file_writer.add_attribute(&root, 42u64, "created")?;
+        let unknown = OmOffsetSize::new(1, 1);
+        assert!(file_writer.add_attribute(&unknown, 0u8, "x").is_err());
+        file_writer.write_trailer(root)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_name(), Some("root".to_string()));
+    assert_eq!(reader.read_scalar::<i32>(), Some(1));
+    assert_eq!(reader.number_of_children(), 2);
+    let created = reader.get_child(1).unwrap();
+    assert_eq!(created.get_name(), Some("created".to_string()));
+    assert_eq!(created.read_scalar::<u64>(), Some(42));
+
+    let variable = reader.get_child(0).unwrap();
+    assert_eq!(variable.get_name(), Some("data".to_string()));
+    assert_eq!(variable.read::<f32>(&[0..5, 0..5], None, None)?, data);
+    assert_eq!(variable.number_of_children(), 2);
+    let max = variable.get_child(0).unwrap();
+    assert_eq!(max.get_name(), Some("max".to_string()));
+    assert_eq!(max.read_scalar::<f32>(), Some(12.5));
+    let units = variable.get_child(1).unwrap();
+    assert_eq!(units.read_scalar::<String>(), Some("K".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_suggest_chunks() -> Result<(), Box<dyn std::error::Error>> {
+    // Global 0.25° grid with one year of hourly data
+    let dims = [721, 1440, 8760];
+    let target = 64 * 1024;
+    assert_eq!(
+        suggest_chunks(&dims, 4, target, AccessPattern::TimeSeries),
+        vec![1, 1, 8760]
+    );
+    assert_eq!(
+        suggest_chunks(&dims, 4, target, AccessPattern::Spatial),
+        vec![128, 128, 1]
+    );
+    assert_eq!(
+        suggest_chunks(&dims, 4, target, AccessPattern::Balanced),
+        vec![25, 25, 25]
+    );
+    // Short dimensions are taken completely
+    assert_eq!(
+        suggest_chunks(&[10, 1000, 24], 4, target, AccessPattern::Spatial),
+        vec![10, 1000, 1]
+    );
+    assert_eq!(
+        suggest_chunks(&[10, 20, 24], 4, target, AccessPattern::TimeSeries),
+        vec![10, 20, 24]
+    );
+
+    let data = ArrayD::from_shape_fn(vec![30, 40, 24], |x| (x[0] + x[1] + x[2]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let chunks = ChunkSpec::Auto {
+            target_chunk_bytes: 1024,
+            access_pattern: AccessPattern::TimeSeries,
+        };
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![30, 40, 24],
+            chunks,
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_chunk_dimensions(), &[3, 3, 24]);
+    assert_eq!(
+        reader.read::<f32>(&[0..30, 0..40, 0..24], None, None)?,
+        data
+    );
+    Ok(())
+}
+
+#[test]
+fn test_in_memory_reader_without_copy() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![5, 5], |x| (x[0] * 5 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    assert!(in_memory_backend.is_empty());
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![5, 5],
+            vec![2, 2],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    assert_eq!(in_memory_backend.len(), in_memory_backend.as_slice().len());
+    let bytes = in_memory_backend.into_inner();
+
+    // E.g. the body of a HTTP response
+    let shared: Arc<[u8]> = bytes.clone().into();
+    let reader = OmFileReader::new(Arc::new(shared.clone()))?;
+    assert_eq!(reader.read::<f32>(&[0..5, 0..5], None, None)?, data);
+
+    let reader = OmFileReader::new(Arc::new(InMemoryBackend::from(bytes.clone())))?;
+    assert_eq!(
+        reader.read::<f32>(&[1..3, 2..4], None, None)?,
+        data.slice(s![1..3, 2..4]).into_dyn()
+    );
+
+    #[cfg(feature = "bytes")]
+    {
+        let reader = OmFileReader::new(Arc::new(bytes::Bytes::from(bytes)))?;
+        assert_eq!(reader.read::<f32>(&[0..5, 0..5], None, None)?, data);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_metadata_without_copy() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let units = file_writer.write_scalar(String::from("m/s"), "units", &[])?;
+        let count = file_writer.write_scalar(42i64, "count", &[])?;
+        let root = file_writer.write_scalar(1.5f32, "root", &[units, count])?;
+        file_writer.write_trailer(root)?;
+    }
+    let bytes = in_memory_backend.into_inner();
+    let shared: Arc<[u8]> = bytes.clone().into();
+    let file = shared.as_ptr_range();
+    let is_view = |metadata: &[u8]| file.contains(&metadata.as_ptr());
+
+    let mut reader = OmFileReader::new(Arc::new(shared.clone()))?;
+    assert!(is_view(reader.variable_data()));
+    let units = reader.get_child(0).unwrap();
+    let count = reader.get_child(1).unwrap();
+    assert!(is_view(units.variable_data()));
+    assert!(is_view(count.variable_data()));
+    assert_eq!(units.read_scalar::<String>(), Some("m/s".to_string()));
+    assert_eq!(count.read_scalar::<i64>(), Some(42));
+
+    // The metadata keeps its backend alive, even if the reader switches backends
+    drop((units, count));
+    reader.backend = Arc::new(Arc::from(vec![0u8; 8]));
+    drop(shared);
+    assert_eq!(reader.get_name(), Some("root".to_string()));
+    assert_eq!(reader.read_scalar::<f32>(), Some(1.5));
+
+    // Backends without stable bytes are copied
+    let cached = OmFileReader::new(Arc::new(CachedBackend::new(
+        InMemoryBackend::new(bytes.clone()),
+        64,
+        16,
+    )))?;
+    assert_eq!(cached.variable_data(), reader.variable_data());
+    let copied = cached.variable_data().as_ptr_range();
+    assert!(!bytes.as_ptr_range().contains(&copied.start));
+
+    // Boxed backends do not expose an owner of their bytes and are copied as well
+    let shared: Arc<[u8]> = bytes.into();
+    let boxed: Box<dyn OmFileReaderBackend + Send + Sync> = Box::new(shared.clone());
+    let boxed = OmFileReader::new(Arc::new(boxed))?;
+    assert!(!shared
+        .as_ptr_range()
+        .contains(&boxed.variable_data().as_ptr()));
+    Ok(())
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_read_from_archive() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let om_file = in_memory_backend.into_inner();
+
+    let zip_path = "test_read_from_archive.zip";
+    remove_file_if_exists(zip_path);
+    {
+        let mut zip = zip::ZipWriter::new(File::create(zip_path)?);
+        zip.start_file("readme.txt", zip::write::SimpleFileOptions::default())?;
+        zip.write_all(b"Temperature")?;
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .with_alignment(64);
+        zip.start_file("data/temperature.om", stored)?;
+        zip.write_all(&om_file)?;
+        zip.finish()?;
+    }
+    let reader = OmFileReader::from_zip_entry(zip_path, "data/temperature.om")?;
+    assert_eq!(reader.read::<f32>(&[0..6, 0..8], None, None)?, data);
+    let error = OmFileReader::from_zip_entry(zip_path, "missing.om").err();
+    assert_eq!(
+        error,
+        Some(OmFilesRsError::ArchiveEntryNotFound(
+            "missing.om".to_string()
+        ))
+    );
+    remove_file_if_exists(zip_path);
+
+    let tar_path = "test_read_from_archive.tar";
+    remove_file_if_exists(tar_path);
+    {
+        let mut tar = tar::Builder::new(File::create(tar_path)?);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(om_file.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "temperature.om", om_file.as_slice())?;
+        tar.finish()?;
+    }
+    let reader = OmFileReader::from_tar_entry(tar_path, "temperature.om")?;
+    assert_eq!(
+        reader.read::<f32>(&[2..5, 1..7], None, None)?,
+        data.slice(s![2..5, 1..7]).into_dyn()
+    );
+    remove_file_if_exists(tar_path);
+
+    // Offsets from corrupt headers must not overflow
+    use omfiles_rs::backend::archive::RangeBackend;
+    assert!(RangeBackend::new(InMemoryBackend::new(om_file), u64::MAX, 2).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_streaming_writer_backend() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32);
+    // Any `std::io::Write`, e.g. stdout or a TCP stream
+    let mut output: Vec<u8> = Vec::new();
+    {
+        let mut file_writer = OmFileWriter::new(StreamingBackend::new(&mut output), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(InMemoryBackend::from(output)))?;
+    assert_eq!(reader.read::<f32>(&[0..6, 0..8], None, None)?, data);
+
+    let mut sink = Vec::new();
+    let mut backend = StreamingBackend::new(&mut sink);
+    assert!(backend.write_at(&[1, 2], 0).is_err());
+    backend.write(&[1, 2])?;
+    assert_eq!(sink, vec![1, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_describe() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 4],
+            CompressionType::PforDelta2dInt16,
+            20.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let units = file_writer.write_scalar("K".to_string(), "units", &[])?;
+        let variable = file_writer.write_array(variable_meta, "temperature", &[units])?;
+        let version = file_writer.write_scalar(3i32, "version", &[variable])?;
+        file_writer.write_trailer(version)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let description: VariableDescription = reader.describe();
+    assert_eq!(description.count_variables(), 3);
+    assert_eq!(description.name.as_deref(), Some("version"));
+    assert_eq!(description.data_type, DataType::Int32);
+    assert_eq!(description.value.as_deref(), Some("3"));
+    assert_eq!(description.array, None);
+    assert_eq!(
+        description.metadata_bytes,
+        reader.variable_data().len() as u64
+    );
+
+    let temperature = &description.children[0];
+    assert_eq!(temperature.data_type, DataType::FloatArray);
+    let array = temperature.array.as_ref().unwrap();
+    assert_eq!(array.dimensions, vec![6, 8]);
+    assert_eq!(array.chunks, vec![3, 4]);
+    assert_eq!(array.compression, CompressionType::PforDelta2dInt16);
+    assert_eq!(array.scale_factor, 20.0);
+    assert_eq!(array.uncompressed_bytes, Some(6 * 8 * 4));
+    assert_eq!(temperature.children[0].value.as_deref(), Some("K"));
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_string(&description)?;
+        assert!(json.contains("\"compression\":\"PforDelta2dInt16\""));
+        let parsed: VariableDescription = serde_json::from_str(&json)?;
+        assert_eq!(parsed, description);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_diff() -> Result<(), Box<dyn std::error::Error>> {
+    let write = |data: &ArrayD<f32>,
+                 compression: CompressionType,
+                 version: i32|
+     -> Result<OmFileReader<InMemoryBackend>, OmFilesRsError> {
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+            let mut writer = file_writer.prepare_array::<f32>(
+                vec![6, 8],
+                vec![3, 4],
+                compression,
+                100.0,
+                0.0,
+            )?;
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+            let root = file_writer.write_scalar(version, "version", &[variable])?;
+            file_writer.write_trailer(root)?;
+        }
+        OmFileReader::new(Arc::new(in_memory_backend))
+    };
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32 / 10.0);
+    let lossless = write(&data, CompressionType::FpxXor2d, 1)?;
+    assert!(diff(&lossless, &lossless, 0.0)?.is_empty());
+
+    // Quantized to 0.01, differences are within the tolerance
+    let quantized = write(&data, CompressionType::PforDelta2dInt16, 1)?;
+    let report = diff(&lossless, &quantized, 0.01)?;
+    assert_eq!(
+        report.differences,
+        vec![Difference::Metadata {
+            path: "version/temperature".to_string(),
+            field: "compression".to_string(),
+            first: "FpxXor2d".to_string(),
+            second: "PforDelta2dInt16".to_string(),
+        }]
+    );
+
+    let mut changed = data.clone();
+    changed[[4, 5]] += 1.0;
+    changed[[5, 7]] = f32::NAN;
+    let changed = write(&changed, CompressionType::FpxXor2d, 2)?;
+    let report = diff(&lossless, &changed, 0.01)?;
+    assert_eq!(
+        report.differences,
+        vec![
+            Difference::Metadata {
+                path: "version".to_string(),
+                field: "value".to_string(),
+                first: "1".to_string(),
+                second: "2".to_string(),
+            },
+            Difference::Data {
+                path: "version/temperature".to_string(),
+                region: vec![3..6, 4..8],
+                count: 2,
+                max_difference: f64::INFINITY,
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_write_legacy_array() -> Result<(), Box<dyn std::error::Error>> {
+    let data = Array2::from_shape_fn((5, 7), |(i, j)| (i * 7 + j) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        file_writer.write_legacy_array(data.view(), [2, 3], CompressionType::FpxXor2d, 1.0)?;
+        // Legacy files contain exactly one array
+        let result =
+            file_writer.write_legacy_array(data.view(), [2, 3], CompressionType::FpxXor2d, 1.0);
+        assert!(result.is_err());
+    }
+    let bytes = in_memory_backend.as_slice();
+    assert_eq!(
+        &bytes[..4],
+        &[b'O', b'M', 2, CompressionType::FpxXor2d as u8]
+    );
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_dimensions(), &[5, 7]);
+    assert_eq!(reader.get_chunk_dimensions(), &[2, 3]);
+    assert_eq!(reader.get_name(), None);
+    let read = reader.read::<f32>(&[0..5, 0..7], None, None)?;
+    assert_eq!(read, data.clone().into_dyn());
+    assert_eq!(
+        reader.read::<f32>(&[1..4, 2..6], None, None)?,
+        read.slice(s![1..4, 2..6]).into_dyn()
+    );
+
+    let mut other_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(other_backend.borrow_mut(), 8);
+    let result =
+        file_writer.write_legacy_array(data.view(), [2, 3], CompressionType::PforDelta2d, 1.0);
+    assert!(matches!(
+        result,
+        Err(OmFilesRsError::InvalidCompressionType)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_chunk_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![9, 10, 11];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 110 + x[1] * 11 + x[2]) as f64
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f64>(
+            dims.clone(),
+            vec![4, 3, 5],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let backend = Arc::new(in_memory_backend);
+    let mut reader = OmFileReader::new(backend.clone())?;
+    reader.enable_chunk_cache(1 << 20);
+
+    let mut into = ArrayD::<f64>::zeros(vec![4, 6, 7]);
+    let mut first = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &[2..5, 3..8, 1..7],
+        &[1, 1, 1],
+        &[4, 6, 7],
+        None,
+        None,
+        &mut first,
+    )?;
+    assert_eq!(
+        into.slice(s![1.., 1.., 1..]),
+        data.slice(s![2..5, 3..8, 1..7])
+    );
+    assert_eq!(first.chunk_cache_hits, 0);
+    assert_eq!(first.chunks_decoded, 2 * 2 * 2);
+
+    // An overlapping selection only decodes chunks that were not read before
+    let mut second = ReadStats::default();
+    let mut into = ArrayD::<f64>::zeros(vec![3, 5, 8]);
+    reader.read_into_with_stats(
+        &mut into,
+        &[3..6, 4..9, 3..11],
+        &[0, 0, 0],
+        &[3, 5, 8],
+        None,
+        None,
+        &mut second,
+    )?;
+    assert_eq!(into, data.slice(s![3..6, 4..9, 3..11]).into_dyn());
+    assert_eq!(second.chunk_cache_hits, 2 * 2 * 2);
+    assert_eq!(second.chunks_decoded, 2 * 2 * 3 - 2 * 2 * 2);
+
+    // A cache smaller than one chunk still returns correct data
+    let mut reader = OmFileReader::new(backend)?;
+    reader.enable_chunk_cache(64);
+    assert_eq!(reader.read::<f64>(&[0..9, 0..10, 0..11], None, None)?, data);
+    assert!(reader
+        .read::<f32>(&[0..9, 0..10, 0..11], None, None)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_prefetch_plan() -> Result<(), Box<dyn std::error::Error>> {
+    let dims = vec![30, 40];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 40 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![4, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let dim_read = [5..17, 12..31];
+    let plan = reader.prefetch(&dim_read, Some(256), Some(16))?;
+    assert!(!plan.index_ranges.is_empty());
+    assert!(!plan.data_ranges.is_empty());
+
+    // The plan contains exactly the requests of a read with the same IO sizes
+    let mut into = ArrayD::<f32>::zeros(vec![12, 19]);
+    let mut stats = ReadStats::default();
+    reader.read_into_with_stats(
+        &mut into,
+        &dim_read,
+        &[0, 0],
+        &[12, 19],
+        Some(256),
+        Some(16),
+        &mut stats,
+    )?;
+    assert_eq!(into, data.slice(s![5..17, 12..31]).into_dyn());
+    assert_eq!(plan.index_ranges.len() as u64, stats.index_reads);
+    assert_eq!(plan.data_ranges.len() as u64, stats.data_reads);
+    assert_eq!(plan.total_bytes(), stats.bytes_fetched);
+    Ok(())
+}
+
+#[test]
+fn test_double_arrays() -> Result<(), Box<dyn std::error::Error>> {
+    // Values that need more precision than f32 offers
+    let data = ArrayD::from_shape_fn(vec![8, 12], |x| {
+        1.0e6 + (x[0] * 12 + x[1]) as f64 / 3.0 + 1.0e-7
+    });
+    let write = |compression: CompressionType,
+                 double_scale: Option<(f64, f64)>|
+     -> Result<InMemoryBackend, OmFilesRsError> {
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+            let mut writer =
+                file_writer.prepare_array::<f64>(vec![8, 12], vec![4, 5], compression, 1.0, 0.0)?;
+            if let Some((scale_factor, add_offset)) = double_scale {
+                writer = writer.with_double_scale(scale_factor, add_offset)?;
+            }
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+        }
+        Ok(in_memory_backend)
+    };
+
+    // Fpx is lossless for doubles
+    let reader = OmFileReader::new(Arc::new(write(CompressionType::FpxXor2d, None)?))?;
+    assert_eq!(reader.data_type(), DataType::DoubleArray);
+    assert_eq!(reader.read::<f64>(&[0..8, 0..12], None, None)?, data);
+    assert_eq!(reader.read_dequantized_f64(&[0..8, 0..12])?, data);
+
+    // Scale and offset are applied in double precision
+    let backend = write(CompressionType::PforDelta2d, Some((1.0e9, -1.0e15)))?;
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(reader.data_type(), DataType::DoubleArray);
+    assert_eq!(
+        reader.get_quantization_filter().as_deref(),
+        Some("linear64:1000000000:-1000000000000000")
+    );
+    let values = reader.read_dequantized_f64(&[0..8, 0..12])?;
+    for (value, expected) in values.iter().zip(data.iter()) {
+        assert!((value - expected).abs() <= 1.0e-9, "{} {}", value, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_uncompressed_arrays() -> Result<(), Box<dyn std::error::Error>> {
+    roundtrip_uncompressed::<i8>(|i| (i % 200) as i8 - 100)?;
+    roundtrip_uncompressed::<u8>(|i| (i % 250) as u8)?;
+    roundtrip_uncompressed::<i16>(|i| i as i16 - 300)?;
+    roundtrip_uncompressed::<u16>(|i| (i * 7) as u16)?;
+    roundtrip_uncompressed::<i32>(|i| i as i32 * -1000)?;
+    roundtrip_uncompressed::<u32>(|i| i as u32 * 100_000)?;
+    roundtrip_uncompressed::<i64>(|i| i as i64 * -1_000_000_000)?;
+    roundtrip_uncompressed::<u64>(|i| i as u64 * 1_000_000_000)?;
+    roundtrip_uncompressed::<f32>(|i| i as f32 / 3.0)?;
+    roundtrip_uncompressed::<f64>(|i| i as f64 / 3.0)?;
+    Ok(())
+}
+
+/// Write a 2D array of `T` without compression and verify that values are
+/// stored exactly with one raw value per element.
+fn roundtrip_uncompressed<T>(value: impl Fn(usize) -> T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: OmFileArrayDataType + Clone + Default + PartialEq + std::fmt::Debug,
+{
+    let data = ArrayD::from_shape_fn(vec![7, 9], |x| value(x[0] * 9 + x[1]));
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<T>(
+            vec![7, 9],
+            vec![3, 4],
+            CompressionType::None,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.compression(), CompressionType::None);
+    assert_eq!(reader.data_type(), T::DATA_TYPE_ARRAY);
+    assert_eq!(reader.read::<T>(&[0..7, 0..9], None, None)?, data);
+    assert_eq!(
+        reader.read::<T>(&[2..5, 3..8], None, None)?,
+        data.slice(s![2..5, 3..8]).into_dyn()
+    );
+
+    let plan = reader.prefetch(&[0..7, 0..9], None, None)?;
+    let data_bytes: u64 = plan.data_ranges.iter().map(|r| r.end - r.start).sum();
+    assert_eq!(data_bytes, (7 * 9 * std::mem::size_of::<T>()) as u64);
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_compression() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![20, 30], |x| (x[0] as f32 * 0.7).sin() + x[1] as f32);
+    let codecs = [
+        CodecCandidate::from(CompressionType::FpxXor2d),
+        CodecCandidate::new(CompressionType::PforDelta2dInt16, 100.0),
+    ];
+    let chunks = [vec![5, 5], vec![10, 30]];
+    let evaluations = evaluate_compression(data.view(), &codecs, &chunks)?;
+
+    assert_eq!(evaluations.len(), 4);
+    assert_eq!(evaluations[1].codec, codecs[0]);
+    assert_eq!(evaluations[1].chunks, chunks[1]);
+    for evaluation in &evaluations {
+        assert!(evaluation.compressed_bytes > 0);
+        assert!(evaluation.compression_ratio > 0.0);
+    }
+    // Fpx is lossless, int16 quantization is accurate to half a step
+    assert_eq!(evaluations[0].max_error, 0.0);
+    assert!(evaluations[2].max_error > 0.0);
+    assert!(evaluations[2].max_error <= 0.005 + 1e-4);
+
+    // Invalid combinations are reported as error
+    let result = evaluate_compression(data.view(), &codecs, &[vec![5, 31]]);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_estimate_scale_factor() {
+    let data = ArrayD::from_shape_vec(vec![3], vec![-10.0, f32::NAN, 30.0]).unwrap();
+    let estimate = estimate_scale_factor(data.view(), 0.01).unwrap();
+    assert_eq!(estimate.scale_factor, 50.0);
+    assert_eq!(estimate.add_offset, 0.0);
+    assert_eq!(estimate.precision, 0.01);
+
+    // Values far from zero are centered with an offset
+    let data = ArrayD::from_shape_vec(vec![2], vec![99_000.0, 101_000.0]).unwrap();
+    let estimate = estimate_scale_factor(data.view(), 0.1).unwrap();
+    assert_eq!(estimate.scale_factor, 5.0);
+    assert_eq!(estimate.add_offset, 100_000.0);
+
+    // A range too large for the precision reduces the scale factor
+    let data = ArrayD::from_shape_vec(vec![2], vec![0.0, 1.0e6]).unwrap();
+    let estimate = estimate_scale_factor(data.view(), 0.01).unwrap();
+    assert_eq!(estimate.add_offset, 500_000.0);
+    assert!((estimate.scale_factor - 32766.0 / 500_000.0).abs() < 1e-6);
+    assert!(estimate.precision > 7.0 && estimate.precision < 8.0);
+
+    let data = ArrayD::from_shape_vec(vec![1], vec![f32::NAN]).unwrap();
+    let estimate = estimate_scale_factor(data.view(), 0.5).unwrap();
+    assert_eq!(estimate.scale_factor, 1.0);
+    assert_eq!(estimate.add_offset, 0.0);
+
+    for precision in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            estimate_scale_factor(data.view(), precision),
+            Err(OmFilesRsError::InvalidConfiguration(_))
+        ));
+    }
+}
+
+#[test]
+fn test_prepare_array_with_precision() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![10, 12], |x| {
+        1000.0 + (x[0] * 12 + x[1]) as f32 * 0.0834
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array_with_precision(
+            vec![10, 12],
+            vec![5, 6],
+            0.05,
+            data.slice(s![0..5, ..]).into_dyn(),
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.compression(), CompressionType::PforDelta2dInt16);
+    assert_eq!(reader.scale_factor(), 10.0);
+    assert_eq!(reader.add_offset(), 0.0);
+    let values = reader.read::<f32>(&[0..10, 0..12], None, None)?;
+    for (value, expected) in values.iter().zip(data.iter()) {
+        assert!((value - expected).abs() <= 0.05 + 1e-3, "{}", value);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_saturation_check() -> Result<(), Box<dyn std::error::Error>> {
+    // With scale factor 100 only values up to 327.66 fit into int16
+    let data = ArrayD::from_shape_fn(vec![4, 6], |x| match (x[0], x[1]) {
+        (0, 0) => 500.0,
+        (3, 5) => -400.0,
+        (3, 4) => f32::NAN,
+        (i, j) => (i * 6 + j) as f32,
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+    let mut writer = file_writer
+        .prepare_array::<f32>(
+            vec![4, 6],
+            vec![2, 3],
+            CompressionType::PforDelta2dInt16,
+            100.0,
+            0.0,
+        )?
+        .with_saturation_check(None)?;
+    writer.write_data(data.view(), None, None)?;
+    let variable_meta = writer.finalize();
+    let saturation = variable_meta.saturation.clone().unwrap();
+    assert_eq!(saturation.saturated, vec![1, 0, 0, 1]);
+    assert_eq!(saturation.total(), 2);
+
+    // Writing fails once the threshold is exceeded
+    let mut writer = file_writer
+        .prepare_array::<f32>(
+            vec![4, 6],
+            vec![2, 3],
+            CompressionType::PforDelta2dInt16,
+            100.0,
+            0.0,
+        )?
+        .with_saturation_check(Some(1))?;
+    let result = writer.write_data(data.view(), None, None);
+    assert_eq!(
+        result,
+        Err(OmFilesRsError::TooManySaturatedValues { count: 2, max: 1 })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_mmap_advice() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::mmapfile::MAdvice;
+
+    let file = "test_mmap_advice.om";
+    remove_file_if_exists(file);
+    let dims = vec![100, 100];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 100 + x[1]) as f32
+    });
+    {
+        let file_handle = File::create(file)?;
+        let mut file_writer = OmFileWriter::new(&file_handle, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![10, 10],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let mut backend = MmapFile::new(File::open(file)?, Mode::ReadOnly)?;
+    assert_eq!(backend.prefetch_advice(), Some(MAdvice::WillNeed));
+    // Point reads without read-ahead
+    backend.advise(MAdvice::Random)?;
+    backend.set_prefetch_advice(None);
+    let reader = OmFileReader::new(Arc::new(backend))?;
+    assert_eq!(
+        reader.read::<f32>(&[42..43, 17..18], None, None)?,
+        data.slice(s![42..43, 17..18]).into_dyn()
+    );
+
+    // Sequential scan that releases processed regions
+    reader.backend.advise(MAdvice::Sequential)?;
+    assert_eq!(reader.read::<f32>(&[0..100, 0..100], None, None)?, data);
+    let count = reader.backend.count();
+    reader.backend.release(0, count)?;
+    // Released pages are loaded again, ranges past the end are ignored
+    assert_eq!(reader.read::<f32>(&[0..100, 0..100], None, None)?, data);
+    reader.backend.release(count - 10, 100)?;
+    reader.backend.release(count + 10, 100)?;
+
+    drop(reader);
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+/// Writer backend that counts how often data is synced
+#[derive(Default)]
+struct SyncCountingBackend {
+    data: Vec<u8>,
+    syncs: std::cell::Cell<u32>,
+}
+
+impl OmFileWriterBackend for &mut SyncCountingBackend {
+    fn write(&mut self, data: &[u8]) -> Result<(), OmFilesRsError> {
+        self.data.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn write_at(&mut self, data: &[u8], offset: usize) -> Result<(), OmFilesRsError> {
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn synchronize(&self) -> Result<(), OmFilesRsError> {
+        self.syncs.set(self.syncs.get() + 1);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_sync_policy() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::buffered_writer::SyncPolicy;
+    use std::time::Duration;
+
+    let dims = vec![200, 200];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        ((x[0] * 7 + x[1] * 13) % 1000) as f32
+    });
+    let write = |policy: SyncPolicy| -> Result<SyncCountingBackend, OmFilesRsError> {
+        let mut backend = SyncCountingBackend::default();
+        {
+            // A small buffer forces many flushes
+            let mut file_writer = OmFileWriter::new(&mut backend, 1024);
+            file_writer.set_sync_policy(policy);
+            let mut writer = file_writer.prepare_array::<f32>(
+                dims.clone(),
+                vec![10, 10],
+                CompressionType::PforDelta2dInt16,
+                1.0,
+                0.0,
+            )?;
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+        }
+        Ok(backend)
+    };
+
+    let off = write(SyncPolicy::Off)?;
+    assert_eq!(off.syncs.get(), 0);
+
+    // At most one sync per 4 KB written
+    let bytes = write(SyncPolicy::Bytes(4096))?;
+    assert!(bytes.syncs.get() >= 1);
+    assert!(bytes.syncs.get() <= off.data.len() as u32 / 4096);
+    assert_eq!(bytes.data, off.data);
+
+    // A zero interval syncs on every flush, a long interval never
+    let every_flush = write(SyncPolicy::Interval(Duration::ZERO))?;
+    assert!(every_flush.syncs.get() > bytes.syncs.get());
+    let never = write(SyncPolicy::Interval(Duration::from_secs(3600)))?;
+    assert_eq!(never.syncs.get(), 0);
+    assert_eq!(never.data, off.data);
+
+    let reader = OmFileReader::new(Arc::new(InMemoryBackend::new(every_flush.data)))?;
+    assert_eq!(reader.read::<f32>(&[0..200, 0..200], None, None)?, data);
+    Ok(())
+}
+
+#[test]
+fn test_builders() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::buffered_writer::SyncPolicy;
+    use omfiles_rs::io::builder::{OmFileReaderBuilder, OmFileWriterBuilder};
+
+    let dims = vec![20, 20];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 20 + x[1]) as f32
+    });
+    let mut backend = SyncCountingBackend::default();
+    {
+        let mut file_writer = OmFileWriterBuilder::new()
+            .initial_capacity(256)
+            .sync_policy(SyncPolicy::Bytes(1))
+            .build(&mut backend);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![5, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    assert!(backend.syncs.get() > 0);
+    let backend = Arc::new(InMemoryBackend::new(backend.data));
+
+    let io_sizes = IoSizes {
+        io_size_max: 128,
+        io_size_merge: 0,
+    };
+    let reader = OmFileReaderBuilder::new()
+        .io_sizes(io_sizes)
+        .lut_cache(1024)
+        .chunk_cache(1024 * 1024)
+        .build(backend.clone())?;
+    assert_eq!(reader.io_sizes(), io_sizes);
+    let mut stats = ReadStats::default();
+    let mut into = ArrayD::<f32>::zeros(vec![20, 20]);
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..20, 0..20],
+        &[0, 0],
+        &[20, 20],
+        None,
+        None,
+        &mut stats,
+    )?;
+    assert_eq!(into, data);
+    // Reading again is served from the chunk cache
+    reader.read_into_with_stats(
+        &mut into,
+        &[0..20, 0..20],
+        &[0, 0],
+        &[20, 20],
+        None,
+        None,
+        &mut stats,
+    )?;
+    assert_eq!(stats.chunk_cache_hits, 16);
+
+    futures::executor::block_on(async {
+        let error = OmFileReaderBuilder::new()
+            .max_concurrency(0)
+            .build_async(backend.clone())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, OmFilesRsError::InvalidConfiguration(_)));
+
+        let reader = OmFileReaderBuilder::new()
+            .io_sizes(io_sizes)
+            .max_concurrency(2)
+            .cancellation_token(CancellationToken::new())
+            .build_async(backend)
+            .await?;
+        assert_eq!(reader.io_sizes(), io_sizes);
+        assert_eq!(reader.max_concurrency(), 2);
+        assert!(reader.cancellation_token().is_some());
+        assert_eq!(reader.read::<f32>(&[0..20, 0..20], None, None).await?, data);
+        Ok::<_, OmFilesRsError>(())
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_iter_and_visit_variables() -> Result<(), Box<dyn std::error::Error>> {
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer =
+            file_writer.prepare_array::<f32>(vec![4], vec![2], CompressionType::None, 1.0, 0.0)?;
+        writer.write_data(ArrayD::from_elem(vec![4], 1.0f32).view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let units = file_writer.write_scalar(String::from("K"), "units", &[])?;
+        let temperature = file_writer.write_array(variable_meta, "temperature", &[units])?;
+        let unnamed = file_writer.write_scalar(7i32, "", &[])?;
+        let root = file_writer.write_scalar(0i32, "root", &[temperature, unnamed])?;
+        file_writer.write_trailer(root)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let paths: Vec<String> = reader.iter_variables().map(|(path, _)| path).collect();
+    assert_eq!(
+        paths,
+        [
+            "root",
+            "root/temperature",
+            "root/temperature/units",
+            "root/#1"
+        ]
+    );
+    let (_, unnamed) = reader.iter_variables().last().unwrap();
+    assert_eq!(unnamed.read_scalar::<i32>(), Some(7));
+
+    struct Collect {
+        paths: Vec<String>,
+        arrays: usize,
+        stop_at: Option<&'static str>,
+    }
+    impl VariableVisitor for Collect {
+        fn visit<Backend: OmFileReaderBackend>(
+            &mut self,
+            path: &str,
+            reader: &OmFileReader<Backend>,
+        ) -> Result<Traversal, OmFilesRsError> {
+            self.paths.push(path.to_string());
+            if Some(path) == self.stop_at {
+                return Ok(Traversal::Stop);
+            }
+            if reader.data_type().is_array() {
+                self.arrays += 1;
+                return Ok(Traversal::SkipChildren);
+            }
+            Ok(Traversal::Continue)
+        }
+    }
+
+    let mut visitor = Collect {
+        paths: Vec::new(),
+        arrays: 0,
+        stop_at: None,
+    };
+    reader.visit(&mut visitor)?;
+    assert_eq!(visitor.paths, ["root", "root/temperature", "root/#1"]);
+    assert_eq!(visitor.arrays, 1);
+
+    let mut visitor = Collect {
+        paths: Vec::new(),
+        arrays: 0,
+        stop_at: Some("root"),
+    };
+    reader.visit(&mut visitor)?;
+    assert_eq!(visitor.paths, ["root"]);
+
+    // Errors of the visitor end the traversal
+    struct Fail;
+    impl VariableVisitor for Fail {
+        fn visit<Backend: OmFileReaderBackend>(
+            &mut self,
+            path: &str,
+            _reader: &OmFileReader<Backend>,
+        ) -> Result<Traversal, OmFilesRsError> {
+            Err(OmFilesRsError::VariableNotFound(path.to_string()))
+        }
+    }
+    assert_eq!(
+        reader.visit(&mut Fail),
+        Err(OmFilesRsError::VariableNotFound("root".to_string()))
+    );
+
+    let flat = reader.get_flat_variable_metadata();
+    assert_eq!(flat.len(), 3);
+    assert!(flat.contains_key("units"));
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_reads() -> Result<(), Box<dyn std::error::Error>> {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<OmFileReader<MmapFile>>();
+    assert_send_sync::<OmFileReader<InMemoryBackend>>();
+    assert_send_sync::<OmFileReader<Box<dyn OmFileReaderBackend + Send + Sync>>>();
+
+    let data = ArrayD::from_shape_fn(vec![20, 30], |x| (x[0] * 30 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![20, 30],
+            vec![4, 7],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let mut reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    reader.enable_chunk_cache(4096);
+
+    std::thread::scope(|scope| {
+        for thread in 0..8 {
+            // Half of the threads share the reader, the others use a clone
+            let reader = if thread % 2 == 0 {
+                std::borrow::Cow::Borrowed(&reader)
+            } else {
+                std::borrow::Cow::Owned(reader.clone())
+            };
+            let data = &data;
+            scope.spawn(move || {
+                for row in 0..20 {
+                    let start = (row + thread as usize) % 20;
+                    let read = reader
+                        .read::<f32>(&[start as u64..start as u64 + 1, 0..30], None, None)
+                        .unwrap();
+                    assert_eq!(read, data.slice(s![start..start + 1, ..]).into_dyn());
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[test]
+fn test_file_manager() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::file_manager::OmFileManager;
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    let files = [
+        "test_file_manager_0.om",
+        "test_file_manager_1.om",
+        "test_file_manager_2.om",
+    ];
+    let write = |file: &str, value: i32| -> Result<(), OmFilesRsError> {
+        let mut file_writer = OmFileWriter::create_atomic(file, true, 8)?;
+        let variable = file_writer.write_scalar(value, "value", &[])?;
+        file_writer.write_trailer(variable)
+    };
+    for (value, file) in files.iter().enumerate() {
+        write(file, value as i32)?;
+    }
+
+    let manager = OmFileManager::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(60));
+    assert_eq!(manager.get(files[0])?.read_scalar::<i32>(), Some(0));
+    assert_eq!(manager.get(files[0])?.read_scalar::<i32>(), Some(0));
+    assert_eq!(manager.len(), 1);
+
+    // Replacing the file is picked up, earlier readers keep the old file
+    let previous = manager.get(files[0])?;
+    write(files[0], 10)?;
+    assert_eq!(manager.get(files[0])?.read_scalar::<i32>(), Some(10));
+    assert_eq!(previous.read_scalar::<i32>(), Some(0));
+    assert_eq!(manager.len(), 1);
+
+    // The least recently used file is closed
+    manager.get(files[1])?;
+    manager.get(files[0])?;
+    manager.get(files[2])?;
+    assert_eq!(manager.len(), 2);
+    assert!(!manager.remove(files[1]));
+    assert!(manager.remove(files[2]));
+
+    remove_file_if_exists(files[0]);
+    assert!(manager.get(files[0]).is_err());
+    assert!(manager.is_empty());
+
+    let manager = OmFileManager::new(NonZeroUsize::new(2).unwrap(), Duration::ZERO);
+    manager.get(files[1])?;
+    manager.close_expired();
+    assert!(manager.is_empty());
+
+    for file in files {
+        remove_file_if_exists(file);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "notify")]
+fn test_watched_reader() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::{file_manager::OmFileManager, watcher::WatchedReader};
+    use std::num::NonZeroUsize;
+    use std::time::{Duration, Instant};
+
+    let file = "test_watched_reader.om";
+    let write = |value: i32| -> Result<(), OmFilesRsError> {
+        let mut file_writer = OmFileWriter::create_atomic(file, true, 8)?;
+        let variable = file_writer.write_scalar(value, "value", &[])?;
+        file_writer.write_trailer(variable)
+    };
+    // Notifications are delivered on a background thread
+    let wait_for = |condition: &dyn Fn() -> bool| {
+        let start = Instant::now();
+        while !condition() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        condition()
+    };
+    write(0)?;
+
+    let watched = WatchedReader::new(file)?;
+    let previous = watched.reader();
+    assert_eq!(previous.read_scalar::<i32>(), Some(0));
+    write(1)?;
+    assert!(wait_for(
+        &|| watched.reader().read_scalar::<i32>() == Some(1)
+    ));
+    assert!(watched.reloads() >= 1);
+    // Readers returned earlier still map the previous file
+    assert_eq!(previous.read_scalar::<i32>(), Some(0));
+    assert!(!watched.reload()?);
+
+    let manager =
+        OmFileManager::with_watcher(NonZeroUsize::new(4).unwrap(), Duration::from_secs(60))?;
+    assert_eq!(manager.get(file)?.read_scalar::<i32>(), Some(1));
+    write(2)?;
+    assert!(wait_for(&|| manager.is_empty()));
+    assert_eq!(manager.get(file)?.read_scalar::<i32>(), Some(2));
+
+    drop(watched);
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[test]
+fn test_write_data_from_iter() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![5, 6, 7], |x| (x[0] * 42 + x[1] * 7 + x[2]) as f32);
+    let slab = |range: std::ops::Range<usize>| data.slice(s![range, .., ..]).to_owned().into_dyn();
+    let write =
+        |pieces: Vec<ArrayD<f32>>, stream: bool| -> Result<InMemoryBackend, OmFilesRsError> {
+            let mut in_memory_backend = InMemoryBackend::new(vec![]);
+            let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+            let mut writer = file_writer.prepare_array::<f32>(
+                vec![5, 6, 7],
+                vec![2, 3, 7],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )?;
+            if stream {
+                futures::executor::block_on(
+                    writer.write_data_from_stream(futures::stream::iter(pieces)),
+                )?;
+            } else {
+                writer.write_data_from_iter(pieces)?;
+            }
+            let variable_meta = writer.finalize();
+            let variable = file_writer.write_array(variable_meta, "data", &[])?;
+            file_writer.write_trailer(variable)?;
+            drop(file_writer);
+            Ok(in_memory_backend)
+        };
+
+    // Slabs along the first dimension, the last one is shorter
+    let bytes = write(vec![slab(0..2), slab(2..4), slab(4..5)], false)?;
+    let reader = OmFileReader::new(Arc::new(bytes))?;
+    assert_eq!(reader.read::<f32>(&[0..5, 0..6, 0..7], None, None)?, data);
+
+    // Single chunks in the order of chunks
+    let mut chunks = Vec::new();
+    for i in (0..5).step_by(2) {
+        for j in (0..6).step_by(3) {
+            chunks.push(
+                data.slice(s![i..(i + 2).min(5), j..j + 3, ..])
+                    .to_owned()
+                    .into_dyn(),
+            );
+        }
+    }
+    let bytes = write(chunks, true)?;
+    let reader = OmFileReader::new(Arc::new(bytes))?;
+    assert_eq!(reader.read::<f32>(&[0..5, 0..6, 0..7], None, None)?, data);
+
+    // Pieces must start at a chunk boundary and cover a contiguous run of chunks
+    let error = write(vec![slab(0..3)], false).err().unwrap();
+    assert_eq!(
+        *error.root_cause(),
+        OmFilesRsError::ChunkHasWrongNumberOfElements
+    );
+    let error = write(
+        vec![data.slice(s![0..4, 0..3, ..]).to_owned().into_dyn()],
+        false,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        *error.root_cause(),
+        OmFilesRsError::ChunkHasWrongNumberOfElements
+    );
+    let error = write(vec![slab(0..2), slab(0..2), slab(0..2)], false)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.root_cause(),
+        OmFilesRsError::OffsetAndCountExceedDimension { .. }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_parallel_array_writer() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::parallel_writer::ParallelArrayWriter;
+
+    let data = ArrayD::from_shape_fn(vec![10, 12], |x| (x[0] * 12 + x[1]) as f32);
+    let new_writer = || {
+        ParallelArrayWriter::<f32>::new(
+            vec![10, 12],
+            vec![3, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )
+    };
+    let write = |array: ParallelArrayWriter<f32>| -> Result<InMemoryBackend, OmFilesRsError> {
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let variable_meta = array.finalize(&mut file_writer)?;
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+        drop(file_writer);
+        Ok(in_memory_backend)
+    };
+
+    // Time slices are produced by several threads in any order
+    let array = new_writer()?;
+    std::thread::scope(|scope| -> Result<(), OmFilesRsError> {
+        let threads: Vec<_> = [9, 3, 0, 6]
+            .into_iter()
+            .map(|start| {
+                let (array, data) = (&array, &data);
+                scope.spawn(move || {
+                    let end = (start + 3).min(10);
+                    let block = data.slice(s![start..end, ..]);
+                    array.write_block(&[start as u64, 0], block.into_dyn())
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap()?;
+        }
+        Ok(())
+    })?;
+    assert_eq!(array.chunks_written(), 12);
+    let parallel = write(array)?;
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![10, 12],
+            vec![3, 5],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    // Identical to writing the array sequentially
+    assert_eq!(parallel.count(), in_memory_backend.count());
+    assert_eq!(
+        parallel.get_bytes(0, parallel.count() as u64)?,
+        in_memory_backend.get_bytes(0, in_memory_backend.count() as u64)?
+    );
+    let reader = OmFileReader::new(Arc::new(parallel))?;
+    assert_eq!(reader.read::<f32>(&[0..10, 0..12], None, None)?, data);
+
+    // Blocks of single chunks in the middle of the array
+    let array = new_writer()?;
+    for i in (0..10).step_by(3) {
+        for j in (0..12).step_by(5) {
+            let block = data.slice(s![i..(i + 3).min(10), j..(j + 5).min(12)]);
+            array.write_block(&[i as u64, j as u64], block.into_dyn())?;
+        }
+    }
+    let reader = OmFileReader::new(Arc::new(write(array)?))?;
+    assert_eq!(reader.read::<f32>(&[0..10, 0..12], None, None)?, data);
+
+    let array = new_writer()?;
+    array.write_block(&[0, 0], data.slice(s![0..3, ..]).into_dyn())?;
+    let error = array
+        .write_block(&[0, 5], data.slice(s![0..3, 0..5]).into_dyn())
+        .err()
+        .unwrap();
+    assert_eq!(error, OmFilesRsError::ChunkWrittenTwice { chunk: 1 });
+    let error = array
+        .write_block(&[1, 0], data.slice(s![0..3, ..]).into_dyn())
+        .err()
+        .unwrap();
+    assert_eq!(
+        *error.root_cause(),
+        OmFilesRsError::ChunkHasWrongNumberOfElements
+    );
+    let error = write(array).err().unwrap();
+    assert_eq!(
+        error,
+        OmFilesRsError::MissingChunks {
+            missing: 9,
+            total: 12
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_deterministic_writer() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::builder::OmFileWriterBuilder;
+    use omfiles_rs::io::parallel_writer::ParallelArrayWriter;
+
+    assert!(CompressionType::FpxXor2d.is_deterministic());
+    assert!(CompressionType::None.is_deterministic());
+    assert!(!CompressionType::PforDelta2dInt16.is_deterministic());
+
+    let data = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] * 8 + x[1]) as f32 / 3.0);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriterBuilder::new()
+            .deterministic(true)
+            .build(&mut in_memory_backend);
+        let error = file_writer
+            .prepare_array::<f32>(
+                vec![6, 8],
+                vec![3, 3],
+                CompressionType::PforDelta2dInt16,
+                100.0,
+                0.0,
+            )
+            .err()
+            .unwrap();
+        assert_eq!(*error.root_cause(), OmFilesRsError::InvalidCompressionType);
+
+        // Arrays that are not prepared by the file writer are checked as well
+        let parallel = ParallelArrayWriter::<f32>::new(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::PforDelta2d,
+            100.0,
+            0.0,
+        )?;
+        parallel.write_block(&[0, 0], data.view())?;
+        let variable_meta = parallel.finalize(&mut file_writer)?;
+        let error = file_writer
+            .write_array(variable_meta, "data", &[])
+            .err()
+            .unwrap();
+        assert_eq!(*error.root_cause(), OmFilesRsError::InvalidCompressionType);
+    }
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriterBuilder::new()
+            .deterministic(true)
+            .build(&mut in_memory_backend);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.read::<f32>(&[0..6, 0..8], None, None)?, data);
+
+    // Helpers that choose the codec themselves fall back to deterministic
+    // codecs and write the same bytes as these codecs chosen explicitly
+    let mask = ArrayD::from_shape_fn(vec![6, 8], |x| (x[0] + x[1]) % 3 == 0);
+    let mut helper_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriterBuilder::new()
+            .deterministic(true)
+            .build(&mut helper_backend);
+        let mask_variable = file_writer.write_mask(mask.view(), vec![3, 3], "mask", &[])?;
+        let mut writer =
+            file_writer.prepare_array_with_precision(vec![6, 8], vec![3, 3], 0.05, data.view())?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[mask_variable])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let estimate = estimate_scale_factor(data.view(), 0.05)?;
+    let mut explicit_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriterBuilder::new()
+            .deterministic(true)
+            .build(&mut explicit_backend);
+        let mut writer = file_writer.prepare_array::<u8>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::None,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(mask.mapv(u8::from).view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let mask_variable = file_writer.write_array(variable_meta, "mask", &[])?;
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![6, 8],
+            vec![3, 3],
+            CompressionType::FpxXor2d,
+            estimate.scale_factor,
+            estimate.add_offset,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[mask_variable])?;
+        file_writer.write_trailer(variable)?;
+    }
+    assert_eq!(
+        helper_backend.get_bytes(0, helper_backend.count() as u64)?,
+        explicit_backend.get_bytes(0, explicit_backend.count() as u64)?
+    );
+    let reader = OmFileReader::new(Arc::new(helper_backend))?;
+    assert_eq!(reader.compression(), CompressionType::FpxXor2d);
+    assert_eq!(reader.read::<f32>(&[0..6, 0..8], None, None)?, data);
+    let mask_reader = reader.get_child(0).unwrap();
+    assert_eq!(mask_reader.compression(), CompressionType::None);
+    assert_eq!(mask_reader.read_mask(&[0..6, 0..8])?, mask);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "test-utils")]
+fn test_roundtrip_random_specs() -> Result<(), Box<dyn std::error::Error>> {
+    use num_traits::{NumCast, ToPrimitive};
+    use omfiles_rs::test_utils::{random_array, roundtrip_check, RoundtripSpec, TestRng};
+
+    fn check<T: OmFileArrayDataType + Clone + Default + NumCast + ToPrimitive>(
+        seed: u64,
+    ) -> Result<(), OmFilesRsError> {
+        let mut rng = TestRng::new(seed);
+        let spec = RoundtripSpec::random::<T>(&mut rng);
+        let data = random_array::<T>(&mut rng, &spec);
+        roundtrip_check(data.view(), &spec)
+    }
+
+    for seed in 0..50 {
+        check::<f32>(seed)?;
+        check::<f64>(seed)?;
+        check::<i8>(seed)?;
+        check::<u16>(seed)?;
+        check::<i32>(seed)?;
+        check::<u64>(seed)?;
+    }
+
+    // The same seed always produces the same spec
+    let spec = RoundtripSpec::random::<f32>(&mut TestRng::new(7));
+    assert_eq!(spec, RoundtripSpec::random::<f32>(&mut TestRng::new(7)));
+    assert!(spec.dimensions.len() <= 4);
+    for (dimension, chunk) in spec.dimensions.iter().zip(&spec.chunks) {
+        assert!(*chunk >= 1 && chunk <= dimension);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_small_array_attributes() -> Result<(), Box<dyn std::error::Error>> {
+    let bbox = [5.5f64, 47.0, 15.5, 55.0];
+    let levels = [1000i32, 850, 700, 500, 300];
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let bbox_attribute = file_writer.write_small_array(&bbox, "bbox", &[])?;
+        let levels_attribute = file_writer.write_small_array(&levels, "levels", &[])?;
+        assert_eq!(
+            file_writer
+                .write_small_array::<f32>(&[], "empty", &[])
+                .err()
+                .unwrap()
+                .root_cause(),
+            &OmFilesRsError::ZeroDimension { index: 0 }
+        );
+        let root = file_writer.write_scalar(1i32, "root", &[bbox_attribute, levels_attribute])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let bbox_reader = reader.get_child(0).unwrap();
+    assert_eq!(bbox_reader.data_type(), DataType::DoubleArray);
+    assert_eq!(bbox_reader.read_small_array::<f64>()?, bbox.to_vec());
+    let levels_reader = reader.get_child(1).unwrap();
+    assert_eq!(levels_reader.get_dimensions(), &[5]);
+    assert_eq!(levels_reader.read_small_array::<i32>()?, levels.to_vec());
+    assert_eq!(
+        levels_reader.read_small_array::<f32>().err().unwrap(),
+        OmFilesRsError::InvalidDataType
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_dynamic() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::dynamic::OmArray;
+
+    let data = ArrayD::from_shape_fn(vec![4, 5], |x| (x[0] * 5 + x[1]) as i16);
+    let names = ArrayD::from_shape_fn(vec![3], |x| format!("name {}", x[0]));
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer.prepare_array::<i16>(
+            vec![4, 5],
+            vec![2, 2],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let data_variable = file_writer.write_array(variable_meta, "data", &[])?;
+        let mut writer = file_writer.prepare_string_array(vec![3])?;
+        writer.write_data(names.view())?;
+        let variable_meta = writer.finalize()?;
+        let names_variable = file_writer.write_array(variable_meta, "names", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[data_variable, names_variable])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let array = reader.get_child(0).unwrap().read_dynamic(&[1..3, 0..5])?;
+    assert_eq!(array.data_type(), DataType::Int16Array);
+    assert_eq!(array.shape(), &[2, 5]);
+    assert_eq!(
+        array,
+        OmArray::I16(data.slice(s![1..3, ..]).to_owned().into_dyn())
+    );
+    assert_eq!(
+        array.to_f64(),
+        Some(data.slice(s![1..3, ..]).mapv(f64::from).into_dyn())
+    );
+
+    let array = reader.get_child(1).unwrap().read_dynamic(&[0..3])?;
+    assert_eq!(array, OmArray::String(names));
+    assert_eq!(array.to_f64(), None);
+
+    assert_eq!(
+        reader.read_dynamic(&[]).err().unwrap(),
+        OmFilesRsError::InvalidDataType
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_converted() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![4, 5], |x| (x[0] * 100 + x[1]) as i16 - 150);
+    let temperature = ArrayD::from_shape_fn(vec![3], |x| x[0] as f32 * 0.5 - 1.0);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<i16>(
+            vec![4, 5],
+            vec![2, 2],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let data_variable = file_writer.write_array(variable_meta, "data", &[])?;
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![3],
+            vec![3],
+            CompressionType::PforDelta2dInt16,
+            10.0,
+            0.0,
+        )?;
+        writer.write_data(temperature.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let temperature_variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        let mut writer =
+            file_writer.prepare_array::<f32>(vec![2], vec![2], CompressionType::None, 1.0, 0.0)?;
+        writer.write_data(
+            ndarray::arr1(&[1.0f32, f32::NAN]).into_dyn().view(),
+            None,
+            None,
+        )?;
+        let variable_meta = writer.finalize();
+        let nan_variable = file_writer.write_array(variable_meta, "nan", &[])?;
+        let root = file_writer.write_scalar(
+            1i32,
+            "root",
+            &[data_variable, temperature_variable, nan_variable],
+        )?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let data_reader = reader.get_child(0).unwrap();
+    assert_eq!(
+        data_reader.read_converted::<f32>(&[0..4, 0..5])?,
+        data.mapv(f32::from)
+    );
+    assert_eq!(
+        data_reader.read_converted::<f64>(&[1..3, 0..5])?,
+        data.slice(s![1..3, ..]).mapv(f64::from).into_dyn()
+    );
+    assert_eq!(data_reader.read_converted::<i16>(&[0..4, 0..5])?, data);
+    assert_eq!(
+        data_reader
+            .read_converted::<i8>(&[0..4, 0..5])
+            .err()
+            .unwrap(),
+        OmFilesRsError::ConversionOutOfRange {
+            from: DataType::Int16Array,
+            to: DataType::Int8Array
+        }
+    );
+
+    let temperature_reader = reader.get_child(1).unwrap();
+    assert_eq!(
+        temperature_reader.read_converted::<f64>(&[0..3])?,
+        temperature.mapv(f64::from)
+    );
+
+    let nan_reader = reader.get_child(2).unwrap();
+    assert!(nan_reader.read_converted::<f64>(&[0..2])?[1].is_nan());
+    assert_eq!(
+        nan_reader.read_converted::<i32>(&[0..2]).err().unwrap(),
+        OmFilesRsError::ConversionOutOfRange {
+            from: DataType::FloatArray,
+            to: DataType::Int32Array
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_tiled() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![5, 7], |x| (x[0] * 7 + x[1]) as i32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<i32>(
+            vec![5, 7],
+            vec![2, 3],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // One row of chunks per tile
+    let mut offsets = Vec::new();
+    let mut copy = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut copy, 8);
+        let mut writer = file_writer.prepare_array::<i32>(
+            vec![5, 7],
+            vec![2, 3],
+            CompressionType::PforDelta2d,
+            1.0,
+            0.0,
+        )?;
+        reader.read_tiled::<i32>(2 * 7 * 4, |tile, offset| {
+            let (start, end) = (offset[0] as usize, offset[0] as usize + tile.shape()[0]);
+            assert_eq!(tile, data.slice(s![start..end, ..]).into_dyn());
+            offsets.push(offset.to_vec());
+            writer.write_data(tile.view(), None, None)
+        })?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    assert_eq!(offsets, vec![vec![0, 0], vec![2, 0], vec![4, 0]]);
+    let copy = OmFileReader::new(Arc::new(copy))?;
+    assert_eq!(copy.read::<i32>(&[0..5, 0..7], None, None)?, data);
+
+    // A budget smaller than a chunk still reads one chunk per tile
+    let mut tiles = 0;
+    reader.read_tiled::<i32>(0, |tile, offset| {
+        let (row, column) = (offset[0] as usize, offset[1] as usize);
+        let expected = data.slice(s![row..(row + 2).min(5), column..(column + 3).min(7)]);
+        assert_eq!(tile, expected.into_dyn());
+        tiles += 1;
+        Ok(())
+    })?;
+    assert_eq!(tiles, 9);
+    Ok(())
+}
+
+#[test]
+fn test_write_grib_messages() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::geo::GridDefinition;
+    use omfiles_rs::io::grib::{write_grib_messages, GribIngestOptions, GribMessage};
+    use omfiles_rs::io::time::TimeAxis;
+
+    struct Message {
+        time: i64,
+        grid: GridDefinition,
+    }
+
+    impl GribMessage for Message {
+        fn valid_time(&self) -> i64 {
+            self.time
+        }
+
+        fn grid(&self) -> GridDefinition {
+            self.grid.clone()
+        }
+
+        fn values(&self) -> Result<Vec<f32>, OmFilesRsError> {
+            Ok((0..12)
+                .map(|i| ((self.time - 1_700_000_000) / 3600 * 100 + i) as f32)
+                .collect())
+        }
+    }
+
+    let grid = GridDefinition::latlon(50.0, 51.0, 10.0, 11.5, 0.5, 0.5);
+    let message = |hour: i64| Message {
+        time: 1_700_000_000 + hour * 3600,
+        grid: grid.clone(),
+    };
+    let options = GribIngestOptions {
+        time_axis: TimeAxis::new(message(0).time, 3600, 4),
+        chunks: [2, 3, 4],
+        compression: CompressionType::FpxXor2d,
+        scale_factor: 1.0,
+        add_offset: 0.0,
+    };
+
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let messages = vec![message(3), message(0), message(1)];
+        let variable = write_grib_messages(&mut file_writer, "t2m", messages, &options)?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.get_name(), Some("t2m".to_string()));
+    assert_eq!(reader.get_dimensions(), &[4, 3, 4]);
+    assert_eq!(reader.get_time_axis(), Some(options.time_axis.clone()));
+    assert_eq!(reader.get_grid(), Some(grid.clone()));
+    assert_eq!(
+        reader.get_dimension_names(),
+        Some(vec![
+            "time".to_string(),
+            "lat".to_string(),
+            "lon".to_string()
+        ])
+    );
+    let data = reader.read::<f32>(&[0..4, 0..3, 0..4], None, None)?;
+    assert_eq!(data[[0, 0, 0]], 0.0);
+    assert_eq!(data[[1, 2, 3]], 111.0);
+    assert!(data.slice(s![2, .., ..]).iter().all(|v| v.is_nan()));
+    assert_eq!(data[[3, 1, 0]], 304.0);
+
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(&mut backend, 8);
+    let error = write_grib_messages(
+        &mut file_writer,
+        "t2m",
+        vec![message(1), message(1)],
+        &options,
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        OmFilesRsError::DuplicateGribMessage {
+            time: message(1).time
+        }
+    );
+    let mut other_grid = message(2);
+    other_grid.grid.dx = 0.25;
+    let error = write_grib_messages(
+        &mut file_writer,
+        "t2m",
+        vec![message(1), other_grid],
+        &options,
+    );
+    assert_eq!(
+        error.err().unwrap(),
+        OmFilesRsError::GribGridMismatch {
+            time: message(2).time
+        }
+    );
+    let error = write_grib_messages(&mut file_writer, "t2m", vec![message(4)], &options);
+    assert_eq!(
+        error.err().unwrap(),
+        OmFilesRsError::GribTimeNotOnAxis {
+            time: message(4).time
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_export_csv() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::export::write_csv;
+
+    let data = ArrayD::from_shape_fn(vec![2, 3], |x| (x[0] * 3 + x[1]) as f32 * 0.5);
+    let names = ArrayD::from_shape_vec(vec![2], vec!["plain", "with, comma"])?;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer
+            .prepare_array::<f32>(vec![2, 3], vec![1, 3], CompressionType::None, 1.0, 0.0)?
+            .with_dimension_names(&["lat", "lon"])?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let data_variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        let mut writer = file_writer.prepare_string_array(vec![2])?;
+        writer.write_data(names.view())?;
+        let variable_meta = writer.finalize()?;
+        let names_variable = file_writer.write_array(variable_meta, "names", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[data_variable, names_variable])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let mut csv = Vec::new();
+    write_csv(&reader.get_child(0).unwrap(), &[1..2, 0..3], &mut csv)?;
+    assert_eq!(
+        String::from_utf8(csv)?,
+        "lat,lon,temperature\n1,0,1.5\n1,1,2\n1,2,2.5\n"
+    );
+
+    let mut csv = Vec::new();
+    write_csv(&reader.get_child(1).unwrap(), &[0..2], &mut csv)?;
+    assert_eq!(
+        String::from_utf8(csv)?,
+        "dim0,names\n0,plain\n1,\"with, comma\"\n"
+    );
+    Ok(())
+}
+
+/// File with a `temperature` array of 2 × 3 floats and a `names` string array
+fn write_export_test_file() -> Result<InMemoryBackend, Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![2, 3], |x| (x[0] * 3 + x[1]) as f32 * 0.5);
+    let names = ArrayD::from_shape_vec(vec![2], vec!["a", "bcd"])?;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![2, 3],
+            vec![1, 3],
+            CompressionType::None,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let data_variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        let mut writer = file_writer.prepare_string_array(vec![2])?;
+        writer.write_data(names.view())?;
+        let variable_meta = writer.finalize()?;
+        let names_variable = file_writer.write_array(variable_meta, "names", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[data_variable, names_variable])?;
+        file_writer.write_trailer(root)?;
+    }
+    Ok(in_memory_backend)
+}
+
+/// Header dictionary and data of a `.npy` file
+fn split_npy(npy: &[u8]) -> (&str, &[u8]) {
+    assert!(npy.starts_with(b"\x93NUMPY\x01\x00"));
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+    (header, &npy[10 + header_len..])
+}
+
+#[test]
+fn test_export_npy() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::export::to_npy;
+
+    let reader = OmFileReader::new(Arc::new(write_export_test_file()?))?;
+    let mut npy = Vec::new();
+    to_npy(&reader.get_child(0).unwrap(), &[1..2, 0..3], &mut npy)?;
+    let (header, data) = split_npy(&npy);
+    assert_eq!(
+        header.trim_end(),
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (1, 3), }"
+    );
+    let expected: Vec<u8> = [1.5f32, 2.0, 2.5]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    assert_eq!(data, expected);
+
+    // Strings are padded to the longest string
+    let mut npy = Vec::new();
+    to_npy(&reader.get_child(1).unwrap(), &[0..2], &mut npy)?;
+    let (header, data) = split_npy(&npy);
+    assert!(header.contains("'descr': '<U3'"));
+    assert!(header.contains("'shape': (2,)"));
+    let expected: Vec<u8> = ['a', '\0', '\0', 'b', 'c', 'd']
+        .iter()
+        .flat_map(|&c| (c as u32).to_le_bytes())
+        .collect();
+    assert_eq!(data, expected);
+    Ok(())
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_export_npz() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::export::{to_npy, to_npz};
+    use std::io::{Cursor, Read};
+
+    let reader = OmFileReader::new(Arc::new(write_export_test_file()?))?;
+    let temperature = reader.get_child(0).unwrap();
+    let names = reader.get_child(1).unwrap();
+    let mut npz = Cursor::new(Vec::new());
+    to_npz(
+        [
+            ("temperature", &temperature, &[0..2, 1..3][..]),
+            ("names", &names, &[0..2][..]),
+        ],
+        &mut npz,
+    )?;
+
+    let mut archive = zip::ZipArchive::new(npz)?;
+    assert_eq!(archive.len(), 2);
+    for (name, variable, ranges) in [
+        ("temperature", &temperature, &[0..2, 1..3][..]),
+        ("names", &names, &[0..2][..]),
+    ] {
+        let mut entry = archive.by_name(&format!("{}.npy", name))?;
+        assert_eq!(entry.compression(), zip::CompressionMethod::Stored);
+        let mut npy = Vec::new();
+        entry.read_to_end(&mut npy)?;
+        let mut expected = Vec::new();
+        to_npy(variable, ranges, &mut expected)?;
+        assert_eq!(npy, expected);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_export_parquet() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::export::to_parquet;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let file = "test_export_parquet.parquet";
+    remove_file_if_exists(file);
+
+    let data = ArrayD::from_shape_fn(vec![4, 5], |x| (x[0] * 5 + x[1]) as i32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer
+            .prepare_array::<i32>(
+                vec![4, 5],
+                vec![2, 2],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&["time", "station"])?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "count", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    to_parquet(&reader, &[1..3, 0..5], file)?;
+
+    let parquet = SerializedFileReader::new(File::open(file)?)?;
+    let metadata = parquet.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), 10);
+    let columns: Vec<&str> = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name())
+        .collect();
+    assert_eq!(columns, vec!["time", "station", "count"]);
+
+    remove_file_if_exists(file);
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_read_arrow() -> Result<(), Box<dyn std::error::Error>> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Float32Type;
+    use omfiles_rs::io::arrow::{ARROW_DIMENSION_NAMES_KEY, ARROW_OFFSET_KEY, ARROW_SHAPE_KEY};
+
+    let data = ArrayD::from_shape_fn(vec![4, 5], |x| (x[0] * 5 + x[1]) as f32);
+    let names = ArrayD::from_shape_vec(vec![2], vec!["a", "b"])?;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        file_writer.set_string_arrays(true);
+        let mut writer = file_writer
+            .prepare_array::<f32>(vec![4, 5], vec![2, 2], CompressionType::FpxXor2d, 1.0, 0.0)?
+            .with_dimension_names(&["lat", "lon"])?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let data_variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        let mut writer = file_writer.prepare_string_array(vec![2])?;
+        writer.write_data(names.view())?;
+        let variable_meta = writer.finalize()?;
+        let names_variable = file_writer.write_array(variable_meta, "names", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[data_variable, names_variable])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let batch = reader.get_child(0).unwrap().read_arrow(&[1..3, 2..5])?;
+    let field = batch.schema().field(0).clone();
+    assert_eq!(field.name(), "temperature");
+    assert_eq!(field.data_type(), &arrow_schema::DataType::Float32);
+    assert_eq!(field.metadata()[ARROW_SHAPE_KEY], "2,3");
+    assert_eq!(field.metadata()[ARROW_OFFSET_KEY], "1,2");
+    assert_eq!(field.metadata()[ARROW_DIMENSION_NAMES_KEY], "lat,lon");
+    let values = batch.column(0).as_primitive::<Float32Type>();
+    assert_eq!(
+        values.values().to_vec(),
+        vec![7.0, 8.0, 9.0, 12.0, 13.0, 14.0]
+    );
+
+    let batch = reader.get_child(1).unwrap().read_arrow(&[0..2])?;
+    assert_eq!(batch.column(0).as_string::<i32>().value(1), "b");
+    assert!(!batch
+        .schema()
+        .field(0)
+        .metadata()
+        .contains_key(ARROW_DIMENSION_NAMES_KEY));
+
+    assert_eq!(
+        reader.read_arrow(&[]).err().unwrap(),
+        OmFilesRsError::InvalidDataType
+    );
+    Ok(())
+}
+
+#[test]
+fn test_derive_variable() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::derive::{derive_variable, DerivedArraySpec};
+
+    let u = ArrayD::from_shape_fn(vec![4, 6], |x| x[0] as f32 - 1.5);
+    let mut v = ArrayD::from_shape_fn(vec![4, 6], |x| x[1] as f32 * 0.5);
+    v[[2, 3]] = f32::NAN;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![4, 6],
+                vec![2, 3],
+                CompressionType::PforDelta2dInt16,
+                10.0,
+                0.0,
+            )?
+            .with_dimension_names(&["lat", "lon"])?;
+        writer.write_data(u.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let u_variable = file_writer.write_array(variable_meta, "u", &[])?;
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![4, 6],
+            vec![4, 2],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(v.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let v_variable = file_writer.write_array(variable_meta, "v", &[])?;
+        let root = file_writer.write_scalar(1i32, "root", &[u_variable, v_variable])?;
+        file_writer.write_trailer(root)?;
+    }
+
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    let inputs = [reader.get_child(0).unwrap(), reader.get_child(1).unwrap()];
+    let spec = DerivedArraySpec {
+        chunks: None,
+        compression: CompressionType::PforDelta2dInt16,
+        scale_factor: None,
+        add_offset: 0.0,
+    };
+    let mut derived = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut derived, 8);
+        let variable = derive_variable(
+            &inputs,
+            |x| (x[0] * x[0] + x[1] * x[1]).sqrt(),
+            &mut file_writer,
+            "wind_speed",
+            &spec,
+        )?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    let derived = OmFileReader::new(Arc::new(derived))?;
+    assert_eq!(derived.get_name(), Some("wind_speed".to_string()));
+    assert_eq!(derived.get_chunk_dimensions(), &[2, 3]);
+    assert_eq!(derived.scale_factor(), 10.0);
+    assert_eq!(
+        derived.get_dimension_names(),
+        Some(vec!["lat".to_string(), "lon".to_string()])
+    );
+    let speed = derived.read::<f32>(&[0..4, 0..6], None, None)?;
+    for ((index, &actual), (&u, &v)) in speed.indexed_iter().zip(u.iter().zip(v.iter())) {
+        if index == ndarray::IxDyn(&[2, 3]) {
+            assert!(actual.is_nan());
+        } else {
+            assert!(
+                (actual - (u * u + v * v).sqrt()).abs() <= 0.1,
+                "{:?}",
+                index
+            );
+        }
+    }
+
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(&mut backend, 8);
+    let mismatch = [reader.get_child(0).unwrap(), reader.clone()];
+    assert!(matches!(
+        derive_variable(&mismatch, |x| x[0], &mut file_writer, "x", &spec),
+        Err(OmFilesRsError::IncompatibleFiles(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_overviews() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::overview::{copy_with_overviews, write_overviews, Aggregation};
+
+    let mut data = ArrayD::from_shape_fn(vec![2, 5, 6], |x| (x[0] * 100 + x[1] * 6 + x[2]) as f32);
+    data[[0, 0, 1]] = f32::NAN;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![2, 5, 6],
+            vec![1, 3, 3],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let mut copy = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut copy, 8);
+        let variable = copy_with_overviews(&reader, &mut file_writer, &[4, 2], Aggregation::Max)?;
+        file_writer.write_trailer(variable)?;
+    }
+    let copy = OmFileReader::new(Arc::new(copy))?;
+    let read = copy.read::<f32>(&[0..2, 0..5, 0..6], None, None)?;
+    assert_eq!(read[[1, 4, 5]], data[[1, 4, 5]]);
+
+    let overviews = copy.get_overviews();
+    assert_eq!(
+        overviews
+            .iter()
+            .map(|(factor, _)| *factor)
+            .collect::<Vec<_>>(),
+        vec![2, 4]
+    );
+    let (_, overview) = &overviews[0];
+    assert_eq!(overview.get_dimensions(), &[2, 3, 3]);
+    let max = overview.read::<f32>(&[0..2, 0..3, 0..3], None, None)?;
+    assert_eq!(max[[0, 0, 0]], data[[0, 1, 1]]);
+    assert_eq!(max[[1, 2, 2]], data[[1, 4, 5]]);
+    assert_eq!(max[[1, 1, 0]], data[[1, 3, 1]]);
+
+    assert_eq!(copy.overview_for_resolution(2, 2).0, 4);
+    assert_eq!(copy.overview_for_resolution(3, 3).0, 2);
+    assert_eq!(copy.overview_for_resolution(5, 6).0, 1);
+
+    let mut backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut backend, 8);
+        let children = write_overviews(&reader, &mut file_writer, &[3], Aggregation::Mean)?;
+        let root = file_writer.write_scalar(1i32, "root", &children)?;
+        file_writer.write_trailer(root)?;
+    }
+    let overviews = OmFileReader::new(Arc::new(backend))?.get_overviews();
+    let mean = overviews[0]
+        .1
+        .read::<f32>(&[0..2, 0..2, 0..2], None, None)?;
+    // NaN at [0, 0, 1] is skipped
+    assert_eq!(
+        mean[[0, 0, 0]],
+        (0.0 + 2.0 + 6.0 + 7.0 + 8.0 + 12.0 + 13.0 + 14.0) / 8.0
+    );
+    assert_eq!(mean[[1, 1, 1]], 125.0);
+
+    let mut backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(&mut backend, 8);
+    assert_eq!(
+        write_overviews(&reader, &mut file_writer, &[1], Aggregation::Mean)
+            .err()
+            .unwrap(),
+        OmFilesRsError::InvalidOverviewFactor { factor: 1 }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_xyz_tile() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::io::geo::GridDefinition;
+    use omfiles_rs::io::tile::XyzTile;
+
+    let data = ArrayD::from_shape_fn(vec![2, 21, 41], |x| {
+        (x[0] * 10000 + x[1] * 100 + x[2]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer
+            .prepare_array::<f32>(
+                vec![2, 21, 41],
+                vec![1, 8, 8],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?
+            .with_grid(GridDefinition::latlon(-10.0, 10.0, -20.0, 20.0, 1.0, 1.0));
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // North-eastern quarter of the world covers latitudes 0..10 and longitudes 0..20
+    let tile = reader.read_xyz_tile(XyzTile::new(1, 1, 0)?, &[1], None)?;
+    assert_eq!(tile.dim(), (11, 21));
+    assert_eq!(tile[[0, 0]], data[[1, 10, 20]]);
+    assert_eq!(tile[[10, 20]], data[[1, 20, 40]]);
+
+    let tile = reader.read_xyz_tile(XyzTile::new(4, 0, 0)?, &[1], None)?;
+    assert_eq!(tile.dim(), (0, 0));
+
+    // Pixel centers at 19.3°, 13.9°, 8.4° and 2.8° north, 2.8°, 8.4°, 14.1° and 19.7° east
+    let tile = reader.read_xyz_tile(XyzTile::new(4, 8, 7)?, &[1], Some(4))?;
+    assert_eq!(tile.dim(), (4, 4));
+    assert!(tile
+        .row(0)
+        .iter()
+        .chain(tile.row(1).iter())
+        .all(|x| x.is_nan()));
+    assert_eq!(tile[[2, 1]], data[[1, 18, 28]]);
+    assert_eq!(tile[[3, 0]], data[[1, 13, 23]]);
+    assert_eq!(tile[[3, 3]], data[[1, 13, 40]]);
+
+    assert_eq!(
+        reader
+            .read_xyz_tile(XyzTile::new(1, 1, 0)?, &[], None)
+            .err()
+            .unwrap(),
+        OmFilesRsError::MismatchingCubeDimensionLength
+    );
+
+    for (z, x, y) in [(1, 2, 0), (1, 0, 2), (64, 0, 0)] {
+        assert!(matches!(
+            XyzTile::new(z, x, y),
+            Err(OmFilesRsError::InvalidConfiguration(_))
+        ));
+    }
+    assert_eq!(XyzTile::new(63, u32::MAX, 0)?.x(), u32::MAX);
+    Ok(())
+}
+
+#[cfg(feature = "viz")]
+#[test]
+fn test_heatmap() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::viz::{Colormap, HeatmapSource};
+
+    let mut data = ArrayD::from_shape_fn(vec![2, 3, 4], |x| (x[0] * 100 + x[1] * 10 + x[2]) as f32);
+    data[[1, 0, 0]] = f32::NAN;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![2, 3, 4],
+            vec![1, 2, 2],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    // Last dimension along rows, starting with the last index
+    let source = HeatmapSource::new(&reader, 2, 1)?
+        .with_index(0, 1)
+        .with_flip_y(true)
+        .with_value_range(100.0..123.0)
+        .with_colormap(Colormap::Grayscale);
+    let values = source.read_values()?;
+    assert_eq!(values.dim(), (4, 3));
+    assert_eq!(values[[0, 2]], 123.0);
+    assert_eq!(values[[1, 0]], 102.0);
+    assert!(values[[3, 0]].is_nan());
+
+    let heatmap = source.render()?;
+    assert_eq!((heatmap.width, heatmap.height), (3, 4));
+    assert_eq!(heatmap.rgba.len(), 4 * 3 * 4);
+    assert_eq!(&heatmap.rgba[2 * 4..3 * 4], &[255, 255, 255, 255]);
+    assert_eq!(
+        &heatmap.rgba[(3 * 3 + 1) * 4..(3 * 3 + 2) * 4],
+        &[111, 111, 111, 255]
+    );
+    assert_eq!(heatmap.rgba[3 * 3 * 4 + 3], 0);
+
+    let mut ppm = Vec::new();
+    heatmap.write_ppm(&mut ppm)?;
+    assert!(ppm.starts_with(b"P6\n3 4\n255\n"));
+    assert_eq!(ppm.len(), b"P6\n3 4\n255\n".len() + 3 * 4 * 3);
+
+    assert!(HeatmapSource::new(&reader, 1, 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_recover_truncated_file() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![10, 20], |x| (x[0] * 20 + x[1]) as f32);
+    let write = |backup_trailer: bool| -> Result<Vec<u8>, OmFilesRsError> {
+        let mut in_memory_backend = InMemoryBackend::new(vec![]);
+        {
+            let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+            file_writer.set_backup_trailer(backup_trailer);
+            let mut writer = file_writer.prepare_array::<f32>(
+                vec![10, 20],
+                vec![5, 5],
+                CompressionType::FpxXor2d,
+                1.0,
+                0.0,
+            )?;
+            writer.write_data(data.view(), None, None)?;
+            let variable_meta = writer.finalize();
+            let unit = file_writer.write_scalar(String::from("K"), "unit", &[])?;
+            let variable = file_writer.write_array(variable_meta, "temperature", &[unit])?;
+            file_writer.write_trailer(variable)?;
+        }
+        Ok(in_memory_backend.into_inner())
+    };
+
+    for backup_trailer in [true, false] {
+        let mut bytes = write(backup_trailer)?;
+        let intact = OmFileReader::new(Arc::new(InMemoryBackend::new(bytes.clone())))?;
+        let root = intact.offset_size().unwrap().clone();
+        bytes.truncate(bytes.len() - 10);
+
+        let backend = Arc::new(InMemoryBackend::new(bytes));
+        assert!(OmFileReader::new(backend.clone()).is_err());
+        let reader = OmFileReader::recover(backend)?;
+        assert_eq!(reader.offset_size().unwrap().offset, root.offset);
+        assert_eq!(reader.get_name().as_deref(), Some("temperature"));
+        assert_eq!(reader.read::<f32>(&[0..10, 0..20], None, None)?, data);
+        let unit = reader.get_child(0).unwrap();
+        assert_eq!(unit.read_scalar::<String>().as_deref(), Some("K"));
+    }
+
+    // Without the root variable the last complete variable is recovered
+    let mut bytes = write(false)?;
+    let intact = OmFileReader::new(Arc::new(InMemoryBackend::new(bytes.clone())))?;
+    let root = intact.offset_size().unwrap().clone();
+    bytes.truncate(root.offset as usize + 8);
+    let reader = OmFileReader::recover(Arc::new(InMemoryBackend::new(bytes)))?;
+    assert_eq!(reader.get_name().as_deref(), Some("unit"));
+
+    assert!(OmFileReader::recover(Arc::new(InMemoryBackend::new(b"OM\x03".to_vec()))).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_write_verification() -> Result<(), Box<dyn std::error::Error>> {
+    let data = ArrayD::from_shape_fn(vec![10, 20], |x| (x[0] * 20 + x[1]) as f32 / 10.0);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        file_writer.set_verification(1.0);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![10, 20],
+            vec![3, 7],
+            CompressionType::PforDelta2dInt16,
+            10.0,
+            0.0,
+        )?;
+        // Verified chunks of a piece in the middle of the array
+        writer.write_data(data.slice(s![0..3, ..]).into_dyn(), None, None)?;
+        writer.write_data(data.view(), Some(&[3, 0]), Some(&[7, 20]))?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.read::<f32>(&[0..10, 0..20], None, None)?, data);
+
+    // Values beyond the int16 range are clamped by the encoder
+    let mut saturated = data.clone();
+    saturated[[5, 5]] = 1e6;
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+    let mut writer = file_writer
+        .prepare_array::<f32>(
+            vec![10, 20],
+            vec![3, 7],
+            CompressionType::PforDelta2dInt16,
+            10.0,
+            0.0,
+        )?
+        .with_verification(1.0);
+    let error = writer.write_data(saturated.view(), None, None).unwrap_err();
+    assert!(matches!(
+        error.root_cause(),
+        OmFilesRsError::ChunkVerificationFailed { count: 1, .. }
+    ));
+    assert_eq!(error.kind(), omfiles_rs::errors::ErrorKind::Encoder);
+
+    // The parallel writer verifies chunks of every block
+    use omfiles_rs::io::parallel_writer::ParallelArrayWriter;
+    let writer = ParallelArrayWriter::<f32>::new(
+        vec![10, 20],
+        vec![3, 7],
+        CompressionType::PforDelta2dInt16,
+        10.0,
+        0.0,
+    )?
+    .with_verification(1.0);
+    writer.write_block(&[0, 0], data.slice(s![0..3, ..]).into_dyn())?;
+    let error = writer
+        .write_block(&[3, 0], saturated.slice(s![3..6, ..]).into_dyn())
+        .unwrap_err();
+    assert!(matches!(
+        error.root_cause(),
+        OmFilesRsError::ChunkVerificationFailed { count: 1, .. }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_metrics_backend() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::metrics::{CacheKind, Metrics, MetricsBackend};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Counters {
+        requests: AtomicU64,
+        bytes: AtomicU64,
+        errors: AtomicU64,
+        chunk_hits: AtomicU64,
+        chunk_misses: AtomicU64,
+    }
+    impl Metrics for Counters {
+        fn record_request(&self, bytes: u64, _latency: Duration) {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        }
+        fn record_error(&self) {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        fn record_cache_lookup(&self, cache: CacheKind, hit: bool) {
+            match (cache, hit) {
+                (CacheKind::Chunk, true) => self.chunk_hits.fetch_add(1, Ordering::Relaxed),
+                (CacheKind::Chunk, false) => self.chunk_misses.fetch_add(1, Ordering::Relaxed),
+                (CacheKind::Index, _) => 0,
+            };
+        }
+    }
+
+    let data = ArrayD::from_shape_fn(vec![10, 20], |x| (x[0] * 20 + x[1]) as f32);
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(&mut in_memory_backend, 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            vec![10, 20],
+            vec![5, 5],
+            CompressionType::FpxXor2d,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let file_size = in_memory_backend.as_slice().len() as u64;
+    let backend = Arc::new(MetricsBackend::new(in_memory_backend, Counters::default()));
+    let cache_metrics = Arc::new(Counters::default());
+    let mut reader = OmFileReader::new(backend.clone())?;
+    reader.set_metrics(cache_metrics.clone());
+    reader.enable_chunk_cache(1024 * 1024);
+
+    let requests = backend.metrics().requests.load(Ordering::Relaxed);
+    assert!(requests > 0);
+    assert_eq!(reader.read::<f32>(&[0..10, 0..20], None, None)?, data);
+    let metrics = backend.metrics();
+    assert!(metrics.requests.load(Ordering::Relaxed) > requests);
+    assert!(metrics.bytes.load(Ordering::Relaxed) > 0);
+    assert_eq!(metrics.errors.load(Ordering::Relaxed), 0);
+    assert_eq!(cache_metrics.chunk_misses.load(Ordering::Relaxed), 8);
+
+    // The second read is served from the chunk cache
+    reader.read::<f32>(&[0..5, 0..20], None, None)?;
+    assert_eq!(cache_metrics.chunk_hits.load(Ordering::Relaxed), 4);
+
+    assert!(backend.get_bytes(file_size, 8).is_err());
+    assert_eq!(backend.metrics().errors.load(Ordering::Relaxed), 1);
+    Ok(())
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn test_prometheus_metrics() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::backend::metrics::{CacheKind, Metrics, PrometheusMetrics};
+    use std::time::Duration;
+
+    let registry = prometheus::Registry::new();
+    let metrics = PrometheusMetrics::register(&registry)?;
+    assert!(PrometheusMetrics::register(&registry).is_err());
+
+    let file = metrics.for_file("t2m.om");
+    file.record_request(100, Duration::from_millis(2));
+    file.record_request(50, Duration::from_millis(3));
+    file.record_cache_lookup(CacheKind::Chunk, true);
+    metrics.for_file("other.om").record_error();
+
+    let families = registry.gather();
+    let value = |name: &str, file: &str| {
+        families
+            .iter()
+            .find(|family| family.name() == name)
+            .and_then(|family| {
+                family.get_metric().iter().find(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|label| label.name() == "file" && label.value() == file)
+                })
+            })
+            .map(|metric| metric.get_counter().get_value())
+    };
+    assert_eq!(value("omfiles_bytes_read_total", "t2m.om"), Some(150.0));
+    assert_eq!(value("omfiles_requests_total", "t2m.om"), Some(2.0));
+    assert_eq!(value("omfiles_request_errors_total", "other.om"), Some(1.0));
+    assert_eq!(value("omfiles_cache_lookups_total", "t2m.om"), Some(1.0));
+    Ok(())
+}
+
+#[test]
+fn test_async_reader_stream_tiles() -> Result<(), Box<dyn std::error::Error>> {
+    use futures::StreamExt;
+    use omfiles_rs::io::reader_async::TileSpec;
+
+    let dims = vec![20, 30];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 30 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![3, 7],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "data", &[])?;
+        file_writer.write_trailer(variable)?;
+    }
+
+    futures::executor::block_on(async {
+        let reader = OmFileReaderAsync::new(Arc::new(in_memory_backend)).await?;
+        let selection = [2..17, 5..29];
+        for tile_spec in [TileSpec::Shape(vec![4, 10]), TileSpec::MaxBytes(200)] {
+            let mut tiles = std::pin::pin!(reader.stream_tiles::<f32>(&selection, &tile_spec));
+            let mut assembled = ArrayD::<f32>::zeros(vec![15, 24]);
+            let mut count = 0;
+            while let Some(tile) = tiles.next().await {
+                let (offset, tile) = tile?;
+                let (x, y) = (offset[0] as usize, offset[1] as usize);
+                assert_eq!(
+                    tile,
+                    data.slice(s![x..x + tile.shape()[0], y..y + tile.shape()[1]])
+                        .into_dyn()
+                );
+                assembled
+                    .slice_mut(s![
+                        x - 2..x - 2 + tile.shape()[0],
+                        y - 5..y - 5 + tile.shape()[1]
+                    ])
+                    .assign(&tile);
+                count += 1;
+            }
+            assert!(count > 1, "{:?} returned a single tile", tile_spec);
+            assert_eq!(assembled, data.slice(s![2..17, 5..29]).into_dyn());
+        }
+
+        // Invalid selections yield one error
+        let results: Vec<_> = reader
+            .stream_tiles::<f32>(&[0..21, 0..30], &TileSpec::Shape(vec![4, 10]))
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            results[0],
+            Err(OmFilesRsError::DimensionOutOfBounds { .. })
+        ));
+        Ok(())
+    })
+}
+
+#[test]
+fn test_service_extract() -> Result<(), Box<dyn std::error::Error>> {
+    use omfiles_rs::service::{extract, status_code, ExtractRequest};
+
+    let dims = vec![3, 4];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        (x[0] * 4 + x[1]) as f32
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer.prepare_array::<f32>(
+            dims.clone(),
+            vec![2, 2],
+            CompressionType::PforDelta2dInt16,
+            1.0,
+            0.0,
+        )?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let variable = file_writer.write_array(variable_meta, "temperature", &[])?;
+        let group = file_writer.write_scalar(1i32, "surface", &[variable])?;
+        let root = file_writer.write_scalar(1i32, "root", &[group])?;
+        file_writer.write_trailer(root)?;
+    }
+    let root = OmFileReader::new(Arc::new(in_memory_backend))?;
+
+    let request =
+        ExtractRequest::from_query("variable=surface%2Ftemperature&ranges=1:3,2&format=json")?;
+    let response = extract(&root, &request, 1000)?;
+    assert_eq!(response.content_type, "application/json");
+    assert_eq!(response.shape, vec![2, 1]);
+    assert_eq!(
+        String::from_utf8(response.body)?,
+        r#"{"shape":[2,1],"data":[[6],[10]]}"#
+    );
+
+    let request =
+        ExtractRequest::from_query("variable=surface/temperature&ranges=0:1,0:2&format=raw")?;
+    let response = extract(&root, &request, 1000)?;
+    let expected: Vec<u8> = [0f32, 1f32].iter().flat_map(|v| v.to_le_bytes()).collect();
+    assert_eq!(response.body, expected);
+
+    let request = ExtractRequest::from_query("variable=surface/temperature&format=npy")?;
+    let response = extract(&root, &request, 1000)?;
+    let header_len = u16::from_le_bytes([response.body[8], response.body[9]]) as usize;
+    let header = std::str::from_utf8(&response.body[10..10 + header_len])?;
+    assert!(response.body.starts_with(b"\x93NUMPY\x01\x00"));
+    assert_eq!((10 + header_len) % 64, 0);
+    assert!(header.contains("'descr': '<f4'"));
+    assert!(header.contains("'shape': (3, 4)"));
+    assert_eq!(response.body.len(), 10 + header_len + 12 * 4);
+    assert_eq!(
+        &response.body[10 + header_len + 4..][..4],
+        &1f32.to_le_bytes()
+    );
+
+    // Errors map to HTTP status codes
+    let status = |query: &str, max_elements: u64| {
+        ExtractRequest::from_query(query)
+            .and_then(|request| extract(&root, &request, max_elements))
+            .map(|_| 200)
+            .unwrap_or_else(|error| status_code(&error))
+    };
+    assert_eq!(status("variable=surface/missing", 1000), 404);
+    assert_eq!(
+        status("variable=surface/temperature&ranges=0:4,0:4", 1000),
+        400
+    );
+    assert_eq!(status("variable=surface/temperature&ranges=0:1", 1000), 400);
+    assert_eq!(status("variable=surface/temperature&format=csv", 1000), 400);
+    assert_eq!(status("variable=surface", 1000), 400);
+    assert_eq!(status("variable=surface%+2Ftemperature", 1000), 400);
+    assert_eq!(status("variable=surface%2", 1000), 400);
+    assert_eq!(status("variable=surface/temperature", 5), 413);
+    assert_eq!(status("variable=surface/temperature", 12), 200);
+    Ok(())
+}
+
+#[test]
+fn test_integer_arrays_and_copy_variable() -> Result<(), Box<dyn std::error::Error>> {
+    roundtrip_and_copy::<i8>(|i| (i % 200) as i8 - 100)?;
+    roundtrip_and_copy::<u8>(|i| (i % 250) as u8)?;
+    roundtrip_and_copy::<i16>(|i| i as i16 - 300)?;
+    roundtrip_and_copy::<u16>(|i| (i * 7) as u16)?;
+    roundtrip_and_copy::<i32>(|i| i as i32 * -1000)?;
+    roundtrip_and_copy::<u32>(|i| i as u32 * 100_000)?;
+    roundtrip_and_copy::<i64>(|i| i as i64 * -1_000_000_000)?;
+    roundtrip_and_copy::<u64>(|i| i as u64 * 1_000_000_000)?;
+    Ok(())
+}
+
+/// Write a 3D array of `T`, read it back, copy the file with `copy_variable`
+/// and verify that data type and values are preserved.
+fn roundtrip_and_copy<T>(value: impl Fn(usize) -> T) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: OmFileArrayDataType + Clone + Default + PartialEq + std::fmt::Debug,
+{
+    let dims = vec![5, 6, 7];
+    let data = ArrayD::from_shape_fn(copy_vec_u64_to_vec_usize(&dims), |x| {
+        value(x[0] * 42 + x[1] * 7 + x[2])
+    });
+    let mut in_memory_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(in_memory_backend.borrow_mut(), 8);
+        let mut writer = file_writer
+            .prepare_array::<T>(
+                dims.clone(),
+                vec![2, 3, 7],
+                CompressionType::PforDelta2d,
+                1.0,
+                0.0,
+            )?
+            .with_dimension_names(&["time", "lat", "lon"])?;
+        writer.write_data(data.view(), None, None)?;
+        let variable_meta = writer.finalize();
+        let attribute = file_writer.write_scalar(String::from("K"), "unit", &[])?;
+        let variable = file_writer.write_array(variable_meta, "data", &[attribute])?;
+        file_writer.write_trailer(variable)?;
+    }
+    let reader = OmFileReader::new(Arc::new(in_memory_backend))?;
+    assert_eq!(reader.data_type(), T::DATA_TYPE_ARRAY);
+    assert_eq!(reader.read::<T>(&[0..5, 0..6, 0..7], None, None)?, data);
+
+    let mut copy_backend = InMemoryBackend::new(vec![]);
+    {
+        let mut file_writer = OmFileWriter::new(copy_backend.borrow_mut(), 8);
+        let variable = copy_variable(&reader, &mut file_writer)?;
+        file_writer.write_trailer(variable)?;
+    }
+    let copy = OmFileReader::new(Arc::new(copy_backend))?;
+    assert_eq!(copy.data_type(), T::DATA_TYPE_ARRAY);
+    assert_eq!(copy.get_name().unwrap(), "data");
+    assert_eq!(copy.get_chunk_dimensions(), &[2, 3, 7]);
+    assert_eq!(copy.compression(), CompressionType::PforDelta2d);
+    assert_eq!(copy.read::<T>(&[0..5, 0..6, 0..7], None, None)?, data);
+    assert_eq!(
+        copy.get_dimension_names().unwrap(),
+        vec!["time".to_string(), "lat".to_string(), "lon".to_string()]
+    );
+    let unit = copy.get_child(0).unwrap();
+    assert_eq!(unit.read_scalar::<String>().unwrap(), "K");
+    Ok(())
+}
+
 fn copy_vec_u64_to_vec_usize(input: &Vec<u64>) -> Vec<usize> {
     input.iter().map(|&x| x as usize).collect()
 }